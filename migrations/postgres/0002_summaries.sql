@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS summaries (
+    id BIGSERIAL PRIMARY KEY,
+    endpoint TEXT NOT NULL,
+    location TEXT NOT NULL,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    model TEXT NOT NULL,
+    prompt_version TEXT NOT NULL,
+    latency_ms BIGINT NOT NULL,
+    summary TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS summaries_coordinates ON summaries (latitude, longitude, created_at);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS sms_opt_outs (
+    phone TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL
+);
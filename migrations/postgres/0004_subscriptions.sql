@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS subscriptions (
+    id TEXT PRIMARY KEY,
+    location TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    cron TEXT,
+    style TEXT,
+    created_at TEXT NOT NULL
+);
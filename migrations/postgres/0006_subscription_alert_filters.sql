@@ -0,0 +1 @@
+ALTER TABLE subscriptions ADD COLUMN alert_filter TEXT;
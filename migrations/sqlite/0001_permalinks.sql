@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS permalinks (
+    id TEXT PRIMARY KEY,
+    summary_key TEXT NOT NULL UNIQUE,
+    endpoint TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    model TEXT NOT NULL,
+    verified BOOLEAN NOT NULL,
+    location TEXT NOT NULL,
+    generated_at TEXT NOT NULL,
+    periods TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS alert_notifications (
+    alert_id TEXT NOT NULL,
+    subscription_id TEXT NOT NULL,
+    expires TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (alert_id, subscription_id)
+);
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::geocode::Coordinates;
+
+const AIRNOW_URL: &str = "https://www.airnowapi.org";
+
+// how far away a reporting area's monitors can be and still describe the point
+const SEARCH_DISTANCE_MILES: u32 = 25;
+
+// categories are 1 (good) through 6 (hazardous), 7 is unavailable
+const MODERATE_CATEGORY: u8 = 2;
+const UNAVAILABLE_CATEGORY: u8 = 7;
+
+#[derive(Debug, Error)]
+pub enum AirNowError {
+    #[error("airnow is not configured")]
+    NotConfigured,
+    #[error("error requesting airnowapi.org: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding airnowapi.org response: {0}")]
+    Decode(#[source] reqwest::Error),
+}
+
+impl AirNowError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AirNowError::NotConfigured => "not_configured",
+            AirNowError::Request(_) => "request",
+            AirNowError::Decode(_) => "decode",
+        }
+    }
+}
+
+// raw observation struct, one pollutant's current reading for a reporting area
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawObservation {
+    date_observed: String,
+    hour_observed: u32,
+    local_time_zone: String,
+    reporting_area: String,
+    state_code: String,
+    parameter_name: String,
+    #[serde(rename = "AQI")]
+    aqi: i64,
+    category: RawCategory,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawCategory {
+    number: u8,
+    name: String,
+}
+
+// air quality struct, the reporting area's overall AQI, which is the highest of its pollutants
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirQuality {
+    pub reporting_area: String,
+    pub state: String,
+    pub observed: String,
+    pub aqi: i64,
+    pub category: String,
+    pub category_number: u8,
+    pub pollutant: String,
+    pub pollutant_name: String,
+    pub pollutants: Vec<PollutantReading>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollutantReading {
+    pub pollutant: String,
+    pub aqi: i64,
+    pub category: String,
+}
+
+impl AirQuality {
+    // good air isn't worth a sentence in a forecast summary
+    pub fn is_notable(&self) -> bool {
+        self.category_number >= MODERATE_CATEGORY
+    }
+}
+
+fn pollutant_name(parameter: &str) -> String {
+    match parameter {
+        "PM2.5" => "fine particulate matter".to_string(),
+        "PM10" => "coarse particulate matter".to_string(),
+        "O3" => "ozone".to_string(),
+        parameter => parameter.to_string(),
+    }
+}
+
+pub struct AirNowClient {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl AirNowClient {
+    pub fn new(client: reqwest::Client, api_key: String) -> Self {
+        Self { client, api_key }
+    }
+
+    // none when there is no reporting area near the point, which is common away from cities
+    pub async fn current(
+        &self,
+        coordinates: Coordinates,
+    ) -> Result<Option<AirQuality>, AirNowError> {
+        let response_result = self
+            .client
+            .get(format!("{}/aq/observation/latLong/current/", AIRNOW_URL))
+            .query(&[
+                ("format", "application/json".to_string()),
+                ("latitude", coordinates.latitude.to_string()),
+                ("longitude", coordinates.longitude.to_string()),
+                ("distance", SEARCH_DISTANCE_MILES.to_string()),
+                ("API_KEY", self.api_key.to_owned()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        // the api key is part of the url, so it's left out of errors that get logged
+        let response = match response_result {
+            Ok(body) => body,
+            Err(e) => return Err(AirNowError::Request(e.without_url())),
+        };
+
+        let observations = match response.json::<Vec<RawObservation>>().await {
+            Ok(observations) => observations,
+            Err(e) => return Err(AirNowError::Decode(e.without_url())),
+        };
+
+        // monitors that are offline report an AQI of -1
+        let mut observations: Vec<RawObservation> = observations
+            .into_iter()
+            .filter(|observation| {
+                observation.aqi >= 0 && observation.category.number != UNAVAILABLE_CATEGORY
+            })
+            .collect();
+
+        observations.sort_by_key(|observation| std::cmp::Reverse(observation.aqi));
+
+        let worst = match observations.first() {
+            Some(worst) => worst,
+            None => return Ok(None),
+        };
+
+        Ok(Some(AirQuality {
+            reporting_area: worst.reporting_area.to_owned(),
+            state: worst.state_code.to_owned(),
+            observed: format!(
+                "{} {:02}:00 {}",
+                worst.date_observed.trim(),
+                worst.hour_observed,
+                worst.local_time_zone
+            ),
+            aqi: worst.aqi,
+            category: worst.category.name.to_owned(),
+            category_number: worst.category.number,
+            pollutant: worst.parameter_name.to_owned(),
+            pollutant_name: pollutant_name(&worst.parameter_name),
+            pollutants: observations
+                .iter()
+                .map(|observation| PollutantReading {
+                    pollutant: observation.parameter_name.to_owned(),
+                    aqi: observation.aqi,
+                    category: observation.category.name.to_owned(),
+                })
+                .collect(),
+        }))
+    }
+}
@@ -0,0 +1,231 @@
+use async_stream::stream;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::llm::{self, ChatTurn, LlmBackend, LlmError, Role, SummaryStream};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+// anthropic backend, talks to the Anthropic Messages API
+pub struct AnthropicBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+    max_tokens: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest {
+    model: String,
+    max_tokens: u32,
+    system: String,
+    messages: Vec<Message>,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type", default)]
+    block_type: String,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    delta: Option<ContentBlock>,
+}
+
+impl AnthropicBackend {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: String,
+        api_key: String,
+        model: String,
+        max_tokens: u32,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+            max_tokens,
+        }
+    }
+
+    fn request(&self, model: &str, messages: Vec<ChatTurn>, stream: bool) -> MessagesRequest {
+        let mut system = Vec::new();
+        let mut chat = Vec::new();
+
+        // the system prompt is a top level field rather than a message
+        for message in messages {
+            match message.role {
+                Role::System => system.push(message.content),
+                Role::User => chat.push(Message {
+                    role: "user",
+                    content: message.content,
+                }),
+                Role::Assistant => chat.push(Message {
+                    role: "assistant",
+                    content: message.content,
+                }),
+            }
+        }
+
+        MessagesRequest {
+            model: model.to_string(),
+            max_tokens: self.max_tokens,
+            system: system.join("\n"),
+            messages: chat,
+            stream,
+        }
+    }
+
+    async fn send(&self, request: &MessagesRequest) -> Result<reqwest::Response, LlmError> {
+        let response = self
+            .client
+            .post(format!("{}/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(request)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+
+            return Err(LlmError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmBackend for AnthropicBackend {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    // the models listing needs a valid key, so it also catches a revoked one
+    async fn ready(&self) -> Result<(), LlmError> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+
+            return Err(LlmError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(())
+    }
+
+    // there is no JSON mode, so the reply is prefilled with an opening brace to keep it a bare object
+    async fn chat_json(&self, model: &str, messages: Vec<ChatTurn>) -> Result<String, LlmError> {
+        let mut request = self.request(model, messages, false);
+
+        request.messages.push(Message {
+            role: "assistant",
+            content: "{".to_string(),
+        });
+
+        let response = self
+            .send(&request)
+            .await?
+            .json::<MessagesResponse>()
+            .await?;
+
+        let text: String = response
+            .content
+            .into_iter()
+            .filter(|block| block.block_type == "text")
+            .filter_map(|block| block.text)
+            .collect();
+
+        Ok(format!("{{{}", text))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<ChatTurn>,
+    ) -> Result<SummaryStream, LlmError> {
+        let request = self.request(model, messages, true);
+
+        let response = self.send(&request).await?;
+
+        let mut data = Box::pin(llm::sse_data(response.bytes_stream()));
+
+        // text arrives in content_block_delta events until message_stop
+        let tokens = stream! {
+            while let Some(data) = data.next().await {
+                let data = match data {
+                    Ok(data) => data,
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                };
+
+                let event = match serde_json::from_str::<StreamEvent>(&data) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        debug!("error decoding message stream event: {}", e);
+                        continue;
+                    }
+                };
+
+                match event.event_type.as_str() {
+                    "content_block_delta" => {
+                        if let Some(text) = event.delta.and_then(|delta| delta.text) {
+                            yield Ok(text);
+                        }
+                    }
+                    "message_stop" => return,
+                    "error" => {
+                        yield Err(LlmError::Stream);
+                        return;
+                    }
+                    _ => continue,
+                }
+            }
+        };
+
+        Ok(Box::pin(tokens))
+    }
+}
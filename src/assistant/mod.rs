@@ -0,0 +1,452 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use openssl::{
+    asn1::Asn1Time,
+    error::ErrorStack,
+    hash::MessageDigest,
+    sign::Verifier,
+    stack::Stack,
+    x509::{store::X509Store, store::X509StoreBuilder, X509StoreContext, X509},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::render;
+
+pub const CERT_URL_HEADER: &str = "SignatureCertChainUrl";
+pub const SIGNATURE_HEADER: &str = "Signature-256";
+
+// the interaction model's intent and slots, an AMAZON.US_CITY city and an AMAZON.DATE day
+pub const FORECAST_INTENT: &str = "ForecastIntent";
+pub const CITY_SLOT: &str = "City";
+pub const DAY_SLOT: &str = "Day";
+
+const CERT_HOST: &str = "s3.amazonaws.com";
+const CERT_PATH_PREFIX: &str = "/echo.api/";
+const CERT_SUBJECT_NAME: &str = "echo-api.amazon.com";
+// amazon rejects skills that accept requests older than this
+const MAX_REQUEST_AGE_SECONDS: i64 = 150;
+
+#[derive(Debug, Error)]
+pub enum AssistantError {
+    #[error("assistant requests are not configured")]
+    NotConfigured,
+    #[error("invalid signing certificate url {0}")]
+    InvalidCertificateUrl(String),
+    #[error("error downloading signing certificate: {0}")]
+    Download(#[from] reqwest::Error),
+    #[error("error reading signing certificate: {0}")]
+    Certificate(#[from] ErrorStack),
+    #[error("untrusted signing certificate: {0}")]
+    UntrustedCertificate(String),
+    #[error("request signature is invalid")]
+    InvalidSignature,
+    #[error("request timestamp {0} is too old")]
+    StaleRequest(String),
+    #[error("request is for skill {0}")]
+    WrongSkill(String),
+    #[error("invalid assistant request: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+impl AssistantError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AssistantError::NotConfigured => "not_configured",
+            AssistantError::InvalidCertificateUrl(_) => "invalid_certificate_url",
+            AssistantError::Download(_) => "download",
+            AssistantError::Certificate(_) => "certificate",
+            AssistantError::UntrustedCertificate(_) => "untrusted_certificate",
+            AssistantError::InvalidSignature => "invalid_signature",
+            AssistantError::StaleRequest(_) => "stale_request",
+            AssistantError::WrongSkill(_) => "wrong_skill",
+            AssistantError::Parse(_) => "parse",
+        }
+    }
+}
+
+// alexa request struct, the parts of a skill request the forecast intent uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlexaRequest {
+    pub context: AlexaContext,
+    pub request: AlexaRequestBody,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlexaContext {
+    #[serde(rename = "System")]
+    pub system: AlexaSystem,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlexaSystem {
+    pub application: AlexaApplication,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlexaApplication {
+    pub application_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlexaRequestBody {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub timestamp: String,
+    pub intent: Option<AlexaIntent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlexaIntent {
+    pub name: String,
+    #[serde(default)]
+    pub slots: HashMap<String, AlexaSlot>,
+}
+
+impl AlexaIntent {
+    // slots the user didn't fill are sent without a value
+    pub fn slot(&self, name: &str) -> Option<&str> {
+        self.slots
+            .get(name)
+            .and_then(|slot| slot.value.as_deref())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlexaSlot {
+    pub value: Option<String>,
+}
+
+// alexa response struct, spoken as SSML with a card in the alexa app
+#[derive(Debug, Clone, Serialize)]
+pub struct AlexaResponse {
+    version: &'static str,
+    response: AlexaResponseBody,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlexaResponseBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_speech: Option<AlexaSpeech>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card: Option<AlexaCard>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reprompt: Option<AlexaReprompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    should_end_session: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AlexaSpeech {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    ssml: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AlexaCard {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: String,
+    content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlexaReprompt {
+    output_speech: AlexaSpeech,
+}
+
+impl AlexaResponse {
+    // speaks each sentence with a short pause between them and ends the session
+    pub fn speak(sentences: &[String], card_title: Option<&str>) -> Self {
+        Self::new(AlexaResponseBody {
+            output_speech: Some(speech(sentences)),
+            card: card_title.map(|title| AlexaCard {
+                kind: "Simple",
+                title: title.to_string(),
+                content: sentences.join("\n"),
+            }),
+            reprompt: None,
+            should_end_session: Some(true),
+        })
+    }
+
+    // keeps the session open for an answer, repeating the reprompt if there isn't one
+    pub fn ask(question: &str, reprompt: &str) -> Self {
+        Self::new(AlexaResponseBody {
+            output_speech: Some(speech(&[question.to_string()])),
+            card: None,
+            reprompt: Some(AlexaReprompt {
+                output_speech: speech(&[reprompt.to_string()]),
+            }),
+            should_end_session: Some(false),
+        })
+    }
+
+    // session ended requests can't be answered with speech
+    pub fn empty() -> Self {
+        Self::new(AlexaResponseBody {
+            output_speech: None,
+            card: None,
+            reprompt: None,
+            should_end_session: None,
+        })
+    }
+
+    fn new(response: AlexaResponseBody) -> Self {
+        Self {
+            version: "1.0",
+            response,
+        }
+    }
+}
+
+fn speech(sentences: &[String]) -> AlexaSpeech {
+    AlexaSpeech {
+        kind: "SSML",
+        ssml: ssml(sentences),
+    }
+}
+
+pub fn ssml(sentences: &[String]) -> String {
+    let sentences: Vec<String> = sentences
+        .iter()
+        .map(|sentence| render::escape(sentence))
+        .collect();
+
+    format!(
+        "<speak>{}</speak>",
+        sentences.join("<break time=\"400ms\"/>")
+    )
+}
+
+// alexa skill struct, verifies requests were signed by amazon for this skill before they're answered
+pub struct AlexaSkill {
+    client: reqwest::Client,
+    skill_id: String,
+    pub default_address: Option<String>,
+    store: X509Store,
+    // the chain url is stable between requests, so each leaf certificate is only downloaded once
+    certificates: Mutex<HashMap<String, X509>>,
+}
+
+impl AlexaSkill {
+    pub fn new(
+        client: reqwest::Client,
+        skill_id: String,
+        default_address: Option<String>,
+    ) -> Result<Self, AssistantError> {
+        let mut store = X509StoreBuilder::new()?;
+        store.set_default_paths()?;
+
+        Ok(Self {
+            client,
+            skill_id,
+            default_address,
+            store: store.build(),
+            certificates: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // the signature is over the raw body, so this runs before the request is parsed
+    pub async fn verify(
+        &self,
+        cert_url: &str,
+        signature: &str,
+        body: &[u8],
+    ) -> Result<(), AssistantError> {
+        let certificate = self.certificate(cert_url).await?;
+
+        let signature = match BASE64_STANDARD.decode(signature) {
+            Ok(signature) => signature,
+            Err(_) => return Err(AssistantError::InvalidSignature),
+        };
+
+        let public_key = certificate.public_key()?;
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &public_key)?;
+        verifier.update(body)?;
+
+        match verifier.verify(&signature) {
+            Ok(true) => Ok(()),
+            _ => Err(AssistantError::InvalidSignature),
+        }
+    }
+
+    pub fn check(&self, request: &AlexaRequest) -> Result<(), AssistantError> {
+        let application_id = &request.context.system.application.application_id;
+
+        if *application_id != self.skill_id {
+            return Err(AssistantError::WrongSkill(application_id.to_owned()));
+        }
+
+        let timestamp = &request.request.timestamp;
+
+        match chrono::DateTime::parse_from_rfc3339(timestamp) {
+            Ok(time)
+                if (chrono::Utc::now() - time.to_utc()).num_seconds().abs()
+                    <= MAX_REQUEST_AGE_SECONDS =>
+            {
+                Ok(())
+            }
+            _ => Err(AssistantError::StaleRequest(timestamp.to_owned())),
+        }
+    }
+
+    async fn certificate(&self, cert_url: &str) -> Result<X509, AssistantError> {
+        let url = match cert_url_valid(cert_url) {
+            true => cert_url,
+            false => return Err(AssistantError::InvalidCertificateUrl(cert_url.to_string())),
+        };
+
+        let cached = self.certificates.lock().unwrap().get(url).cloned();
+
+        // cached certificates are still checked against the clock, they only last about a year
+        if let Some(certificate) = cached {
+            if within_validity(&certificate)? {
+                return Ok(certificate);
+            }
+        }
+
+        let pem = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        let certificate = self.trusted_certificate(&pem)?;
+
+        self.certificates
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), certificate.clone());
+
+        Ok(certificate)
+    }
+
+    // the leaf has to be current, issued to alexa, and chain to a system root through the rest of the file
+    fn trusted_certificate(&self, pem: &[u8]) -> Result<X509, AssistantError> {
+        let certificates = X509::stack_from_pem(pem)?;
+
+        let (leaf, intermediates) = match certificates.split_first() {
+            Some((leaf, intermediates)) => (leaf, intermediates),
+            None => {
+                return Err(AssistantError::UntrustedCertificate(
+                    "no certificates in chain".to_string(),
+                ))
+            }
+        };
+
+        if !within_validity(leaf)? {
+            return Err(AssistantError::UntrustedCertificate(
+                "certificate is expired or not yet valid".to_string(),
+            ));
+        }
+
+        let issued_to_alexa = leaf.subject_alt_names().is_some_and(|names| {
+            names
+                .iter()
+                .any(|name| name.dnsname() == Some(CERT_SUBJECT_NAME))
+        });
+
+        if !issued_to_alexa {
+            return Err(AssistantError::UntrustedCertificate(format!(
+                "certificate isn't issued to {}",
+                CERT_SUBJECT_NAME
+            )));
+        }
+
+        let mut chain = Stack::new()?;
+        for intermediate in intermediates {
+            chain.push(intermediate.to_owned())?;
+        }
+
+        let failure = X509StoreContext::new()?.init(&self.store, leaf, &chain, |context| {
+            Ok(match context.verify_cert()? {
+                true => None,
+                false => Some(context.error().to_string()),
+            })
+        })?;
+
+        match failure {
+            Some(failure) => Err(AssistantError::UntrustedCertificate(failure)),
+            None => Ok(leaf.to_owned()),
+        }
+    }
+}
+
+// https on the default port, on amazon's bucket, under /echo.api/ once dot segments are resolved
+pub fn cert_url_valid(cert_url: &str) -> bool {
+    let url = match reqwest::Url::parse(cert_url) {
+        Ok(url) => url,
+        Err(_) => return false,
+    };
+
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .is_some_and(|host| host.eq_ignore_ascii_case(CERT_HOST))
+        && url.port_or_known_default() == Some(443)
+        && url.path().starts_with(CERT_PATH_PREFIX)
+}
+
+fn within_validity(certificate: &X509) -> Result<bool, AssistantError> {
+    let now = Asn1Time::days_from_now(0)?;
+
+    Ok(certificate.not_before() <= now && certificate.not_after() >= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // amazon's documented examples for validating the SignatureCertChainUrl header
+    #[test]
+    fn accepts_amazon_cert_urls() {
+        assert!(cert_url_valid(
+            "https://s3.amazonaws.com/echo.api/echo-api-cert.pem"
+        ));
+        assert!(cert_url_valid(
+            "https://s3.amazonaws.com:443/echo.api/echo-api-cert.pem"
+        ));
+        assert!(cert_url_valid(
+            "https://s3.amazonaws.com/echo.api/../echo.api/echo-api-cert.pem"
+        ));
+        assert!(cert_url_valid(
+            "HTTPS://S3.AmazonAWS.com/echo.api/echo-api-cert.pem"
+        ));
+    }
+
+    #[test]
+    fn rejects_other_cert_urls() {
+        assert!(!cert_url_valid(
+            "http://s3.amazonaws.com/echo.api/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid(
+            "https://notamazon.com/echo.api/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid(
+            "https://s3.amazonaws.com/EcHo.aPi/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid(
+            "https://s3.amazonaws.com/invalid.path/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid(
+            "https://s3.amazonaws.com:563/echo.api/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid(
+            "https://s3.amazonaws.com/echo.api/../invalid.path/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid(
+            "https://s3.amazonaws.com.evil.example/echo.api/echo-api-cert.pem"
+        ));
+        assert!(!cert_url_valid("not a url"));
+    }
+}
@@ -0,0 +1,1005 @@
+use chrono::{DateTime, Datelike, Months, TimeDelta, TimeZone, Utc};
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::geocode::Coordinates;
+
+const AVIATION_WEATHER_URL: &str = "https://aviationweather.gov/api/data";
+
+const KNOTS_PER_METER_PER_SECOND: f64 = 1.943844;
+const METERS_PER_MILE: f64 = 1609.344;
+const HECTOPASCALS_PER_INCH_OF_MERCURY: f64 = 33.8639;
+
+// intensity and proximity come first, then at most one descriptor and the phenomena
+const WEATHER_DESCRIPTORS: &[(&str, &str)] = &[
+    ("MI", "shallow"),
+    ("PR", "partial"),
+    ("BC", "patches of"),
+    ("DR", "low drifting"),
+    ("BL", "blowing"),
+    ("SH", "showers"),
+    ("TS", "thunderstorm"),
+    ("FZ", "freezing"),
+];
+const WEATHER_PHENOMENA: &[(&str, &str)] = &[
+    ("DZ", "drizzle"),
+    ("RA", "rain"),
+    ("SN", "snow"),
+    ("SG", "snow grains"),
+    ("IC", "ice crystals"),
+    ("PL", "ice pellets"),
+    ("GR", "hail"),
+    ("GS", "small hail"),
+    ("UP", "unknown precipitation"),
+    ("BR", "mist"),
+    ("FG", "fog"),
+    ("FU", "smoke"),
+    ("VA", "volcanic ash"),
+    ("DU", "dust"),
+    ("SA", "sand"),
+    ("HZ", "haze"),
+    ("PY", "spray"),
+    ("PO", "dust whirls"),
+    ("SQ", "squalls"),
+    ("FC", "funnel cloud"),
+    ("SS", "sandstorm"),
+    ("DS", "duststorm"),
+];
+const CLOUD_COVERS: &[(&str, &str)] = &[
+    ("FEW", "few"),
+    ("SCT", "scattered"),
+    ("BKN", "broken"),
+    ("OVC", "overcast"),
+    ("VV", "vertical visibility"),
+];
+const CLEAR_SKY: &[&str] = &["SKC", "CLR", "NSC", "NCD"];
+
+#[derive(Debug, Error)]
+pub enum AviationError {
+    #[error("error requesting aviationweather.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding aviationweather.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("no report is available for station {0}")]
+    NoReport(String),
+    #[error("error parsing report: {0}")]
+    Parse(String),
+}
+
+impl AviationError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AviationError::Request(_) => "request",
+            AviationError::Decode(_) => "decode",
+            AviationError::NoReport(_) => "no_report",
+            AviationError::Parse(_) => "parse",
+        }
+    }
+}
+
+// metar struct, a decoded routine or special observation
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metar {
+    pub raw: String,
+    pub station: String,
+    pub name: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    pub observed: Option<DateTime<Utc>>,
+    // no observer was present, the report came straight from the sensors
+    pub automated: bool,
+    #[serde(flatten)]
+    pub conditions: Conditions,
+    pub temperature_c: Option<i32>,
+    pub dewpoint_c: Option<i32>,
+    pub altimeter_inhg: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remarks: Option<String>,
+}
+
+// taf struct, a decoded terminal aerodrome forecast
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Taf {
+    pub raw: String,
+    pub station: String,
+    pub amended: bool,
+    pub issued: Option<DateTime<Utc>>,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub periods: Vec<TafPeriod>,
+}
+
+// taf period struct, the initial conditions or one change group.
+// change is initial, from, becoming, or temporary, with a probability for PROB groups
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TafPeriod {
+    pub change: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probability: Option<u32>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub conditions: Conditions,
+}
+
+// conditions struct, the groups observations and forecast periods share
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    pub wind: Option<Wind>,
+    pub visibility_miles: Option<f64>,
+    pub weather: Vec<String>,
+    pub clouds: Vec<CloudLayer>,
+    pub ceiling_feet: Option<u32>,
+    pub flight_category: Option<String>,
+    // groups the parser doesn't know, kept so nothing in the report is silently dropped
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unparsed: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Wind {
+    // none when the direction is variable
+    pub direction: Option<u32>,
+    pub speed_knots: u32,
+    pub gust_knots: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub varying_from: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub varying_to: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudLayer {
+    pub cover: String,
+    pub base_feet: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloud_type: Option<String>,
+}
+
+// metar record struct, only the raw text and station are read from the API's decoded JSON,
+// whose other fields vary between stations
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetarRecord {
+    raw_ob: String,
+    name: Option<String>,
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TafRecord {
+    #[serde(rename = "rawTAF")]
+    raw_taf: String,
+}
+
+// the latest report first, no content when the station has none
+async fn get_records<T: DeserializeOwned>(
+    client: reqwest::Client,
+    product: &str,
+    icao: &str,
+) -> Result<Vec<T>, AviationError> {
+    let records_url = format!("{}/{}", AVIATION_WEATHER_URL, product);
+
+    let records_response_result = client
+        .get(records_url)
+        .query(&[("ids", icao), ("format", "json")])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let records_response = match records_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(AviationError::Request(e)),
+    };
+
+    if records_response.status() == StatusCode::NO_CONTENT {
+        return Ok(Vec::new());
+    }
+
+    match records_response.json::<Vec<T>>().await {
+        Ok(records) => Ok(records),
+        Err(e) => Err(AviationError::Decode(e)),
+    }
+}
+
+pub async fn get_metar(client: reqwest::Client, icao: &str) -> Result<Metar, AviationError> {
+    let record = match get_records::<MetarRecord>(client, "metar", icao)
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(record) => record,
+        None => return Err(AviationError::NoReport(icao.to_string())),
+    };
+
+    let mut metar = parse_metar(&record.raw_ob, Utc::now())?;
+
+    metar.name = record.name;
+    metar.coordinates = Some(Coordinates {
+        latitude: record.lat,
+        longitude: record.lon,
+    });
+
+    Ok(metar)
+}
+
+// most small airports report a METAR but have no TAF
+pub async fn get_taf(client: reqwest::Client, icao: &str) -> Result<Option<Taf>, AviationError> {
+    match get_records::<TafRecord>(client, "taf", icao)
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(record) => Ok(Some(parse_taf(&record.raw_taf, Utc::now())?)),
+        None => Ok(None),
+    }
+}
+
+// reports only give the day of the month, now picks the month
+pub fn parse_metar(raw: &str, now: DateTime<Utc>) -> Result<Metar, AviationError> {
+    let (body, remarks) = match raw.split_once(" RMK ") {
+        Some((body, remarks)) => (body, Some(remarks.trim_end_matches('=').to_string())),
+        None => (raw, None),
+    };
+
+    let tokens = tokenize(body.trim_end_matches('='));
+    let mut tokens = tokens
+        .iter()
+        .map(|token| token.as_str())
+        .skip_while(|token| *token == "METAR" || *token == "SPECI")
+        .peekable();
+
+    let station = match tokens.next() {
+        Some(station) if is_station(station) => station.to_string(),
+        _ => return Err(AviationError::Parse(format!("no station in {}", raw))),
+    };
+
+    let observed = match tokens.next().and_then(|token| day_time(token, now)) {
+        Some(observed) => Some(observed),
+        None => {
+            return Err(AviationError::Parse(format!(
+                "no observation time in {}",
+                raw
+            )))
+        }
+    };
+
+    let mut metar = Metar {
+        raw: raw.to_string(),
+        station,
+        observed,
+        remarks,
+        ..Metar::default()
+    };
+
+    for token in tokens {
+        match token {
+            "AUTO" => metar.automated = true,
+            "COR" => {}
+            _ => {
+                if let Some((temperature, dewpoint)) = temperature_dewpoint(token) {
+                    metar.temperature_c = Some(temperature);
+                    metar.dewpoint_c = dewpoint;
+                } else if let Some(altimeter) = altimeter(token) {
+                    metar.altimeter_inhg = Some(altimeter);
+                } else if !apply_group(&mut metar.conditions, token) {
+                    metar.conditions.unparsed.push(token.to_string());
+                }
+            }
+        }
+    }
+
+    finish(&mut metar.conditions);
+
+    Ok(metar)
+}
+
+// TAFs are wrapped over several lines, the raw text is kept on one
+pub fn parse_taf(raw: &str, now: DateTime<Utc>) -> Result<Taf, AviationError> {
+    let raw = &raw.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    let body = match raw.split_once(" RMK ") {
+        Some((body, _)) => body,
+        None => raw,
+    };
+
+    let tokens = tokenize(body.trim_end_matches('='));
+    let mut tokens = tokens.iter().map(|token| token.as_str()).peekable();
+
+    let mut taf = Taf {
+        raw: raw.to_string(),
+        ..Taf::default()
+    };
+
+    while let Some(token) = tokens.peek() {
+        match *token {
+            "TAF" | "COR" => {}
+            "AMD" => taf.amended = true,
+            _ => break,
+        }
+        tokens.next();
+    }
+
+    taf.station = match tokens.next() {
+        Some(station) if is_station(station) => station.to_string(),
+        _ => return Err(AviationError::Parse(format!("no station in {}", raw))),
+    };
+
+    if let Some(issued) = tokens.peek().and_then(|token| day_time(token, now)) {
+        taf.issued = Some(issued);
+        tokens.next();
+    }
+
+    let reference = taf.issued.unwrap_or(now);
+
+    let (valid_from, valid_to) = match tokens.next().and_then(|token| day_range(token, reference)) {
+        Some(valid) => valid,
+        None => return Err(AviationError::Parse(format!("no valid period in {}", raw))),
+    };
+
+    taf.valid_from = Some(valid_from);
+    taf.valid_to = Some(valid_to);
+
+    let mut period = TafPeriod {
+        change: "initial".to_string(),
+        from: Some(valid_from),
+        to: Some(valid_to),
+        ..TafPeriod::default()
+    };
+
+    while let Some(token) = tokens.next() {
+        let next = if let Some(from) = token.strip_prefix("FM") {
+            day_time(&format!("{}Z", from), reference).map(|from| TafPeriod {
+                change: "from".to_string(),
+                from: Some(from),
+                to: Some(valid_to),
+                ..TafPeriod::default()
+            })
+        } else {
+            let (change, probability) = match token {
+                "BECMG" => ("becoming", None),
+                "TEMPO" => ("temporary", None),
+                _ => match token.strip_prefix("PROB").and_then(digits) {
+                    Some(probability) => {
+                        // PROB30 TEMPO is a chance of temporary conditions, it reads the same as PROB30
+                        if tokens.peek() == Some(&"TEMPO") {
+                            tokens.next();
+                        }
+                        ("temporary", Some(probability))
+                    }
+                    None => ("", None),
+                },
+            };
+
+            match change.is_empty() {
+                true => None,
+                false => {
+                    let range = tokens.peek().and_then(|token| day_range(token, reference));
+                    if range.is_some() {
+                        tokens.next();
+                    }
+
+                    Some(TafPeriod {
+                        change: change.to_string(),
+                        probability,
+                        from: range.map(|(from, _)| from),
+                        to: range.map(|(_, to)| to),
+                        ..TafPeriod::default()
+                    })
+                }
+            }
+        };
+
+        match next {
+            Some(next) => {
+                finish(&mut period.conditions);
+                taf.periods.push(std::mem::replace(&mut period, next));
+            }
+            None => {
+                if !apply_group(&mut period.conditions, token) {
+                    period.conditions.unparsed.push(token.to_string());
+                }
+            }
+        }
+    }
+
+    finish(&mut period.conditions);
+    taf.periods.push(period);
+
+    // FM groups run until the next one starts
+    for i in 1..taf.periods.len() {
+        if taf.periods[i].change == "from" {
+            let from = taf.periods[i].from;
+            if let Some(previous) = taf.periods[..i]
+                .iter_mut()
+                .rev()
+                .find(|period| period.change == "from" || period.change == "initial")
+            {
+                previous.to = from;
+            }
+        }
+    }
+
+    Ok(taf)
+}
+
+// fractional visibility is written as two groups, like 1 1/2SM
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+
+    for token in text.split_whitespace() {
+        if let Some(previous) = tokens.last_mut() {
+            if token.ends_with("SM") && token.contains('/') && digits(previous).is_some() {
+                *previous = format!("{} {}", previous, token);
+                continue;
+            }
+        }
+        tokens.push(token.to_string());
+    }
+
+    tokens
+}
+
+// wind, visibility, weather, and sky condition, false when the token is none of them
+fn apply_group(conditions: &mut Conditions, token: &str) -> bool {
+    if let Some(wind) = wind(token) {
+        conditions.wind = Some(wind);
+    } else if let Some((from, to)) = wind_variation(token) {
+        match &mut conditions.wind {
+            Some(wind) => {
+                wind.varying_from = Some(from);
+                wind.varying_to = Some(to);
+            }
+            None => return false,
+        }
+    } else if let Some(visibility) = visibility(token) {
+        conditions.visibility_miles = Some(visibility);
+    } else if token == "CAVOK" {
+        conditions.visibility_miles = Some(round(10_000.0 / METERS_PER_MILE, 1));
+        conditions.clouds.clear();
+    } else if CLEAR_SKY.contains(&token) || token == "NSW" {
+        // a clear sky and no significant weather are stated outright, nothing is added
+    } else if let Some(layer) = cloud_layer(token) {
+        conditions.clouds.push(layer);
+    } else if let Some(weather) = weather(token) {
+        conditions.weather.push(weather);
+    } else {
+        return false;
+    }
+
+    true
+}
+
+// the ceiling is the lowest broken, overcast, or obscured layer
+fn finish(conditions: &mut Conditions) {
+    conditions.ceiling_feet = conditions
+        .clouds
+        .iter()
+        .filter(|layer| {
+            matches!(
+                layer.cover.as_str(),
+                "broken" | "overcast" | "vertical visibility"
+            )
+        })
+        .filter_map(|layer| layer.base_feet)
+        .min();
+
+    conditions.flight_category =
+        flight_category(conditions.ceiling_feet, conditions.visibility_miles).map(str::to_string);
+}
+
+// the FAA categories, the worse of the ceiling and visibility decides
+fn flight_category(
+    ceiling_feet: Option<u32>,
+    visibility_miles: Option<f64>,
+) -> Option<&'static str> {
+    if ceiling_feet.is_none() && visibility_miles.is_none() {
+        return None;
+    }
+
+    let ceiling = ceiling_feet.unwrap_or(u32::MAX);
+    let visibility = visibility_miles.unwrap_or(f64::MAX);
+
+    Some(if ceiling < 500 || visibility < 1.0 {
+        "LIFR"
+    } else if ceiling < 1000 || visibility < 3.0 {
+        "IFR"
+    } else if ceiling <= 3000 || visibility <= 5.0 {
+        "MVFR"
+    } else {
+        "VFR"
+    })
+}
+
+fn wind(token: &str) -> Option<Wind> {
+    let (token, knots_per_unit) = match (token.strip_suffix("KT"), token.strip_suffix("MPS")) {
+        (Some(token), _) => (token, 1.0),
+        (None, Some(token)) => (token, KNOTS_PER_METER_PER_SECOND),
+        (None, None) => return None,
+    };
+
+    if token.len() < 5 || !token.is_char_boundary(3) {
+        return None;
+    }
+
+    let (direction, speeds) = token.split_at(3);
+
+    let direction = match direction {
+        "VRB" => None,
+        direction => Some(digits(direction)?),
+    };
+
+    let (speed, gust) = match speeds.split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust)),
+        None => (speeds, None),
+    };
+
+    let knots = |value: &str| -> Option<u32> {
+        Some((digits(value)? as f64 * knots_per_unit).round() as u32)
+    };
+
+    Some(Wind {
+        direction,
+        speed_knots: knots(speed)?,
+        gust_knots: match gust {
+            Some(gust) => Some(knots(gust)?),
+            None => None,
+        },
+        varying_from: None,
+        varying_to: None,
+    })
+}
+
+// like 180V240, the range the wind direction swings through
+fn wind_variation(token: &str) -> Option<(u32, u32)> {
+    let (from, to) = token.split_once('V')?;
+
+    match from.len() == 3 && to.len() == 3 {
+        true => Some((digits(from)?, digits(to)?)),
+        false => None,
+    }
+}
+
+// statute miles like 10SM, P6SM, M1/4SM, or 1 1/2SM, or meters like 9999
+fn visibility(token: &str) -> Option<f64> {
+    if token.len() == 4 {
+        if let Some(meters) = digits(token) {
+            // 9999 means 10 kilometers or more
+            let meters = match meters {
+                9999 => 10_000,
+                meters => meters,
+            };
+            return Some(round(meters as f64 / METERS_PER_MILE, 1));
+        }
+    }
+
+    let miles = token.strip_suffix("SM")?;
+    let miles = miles
+        .strip_prefix('P')
+        .or(miles.strip_prefix('M'))
+        .unwrap_or(miles);
+
+    let mut total = 0.0;
+
+    for part in miles.split(' ') {
+        total += match part.split_once('/') {
+            Some((numerator, denominator)) => {
+                digits(numerator)? as f64 / digits(denominator).filter(|d| *d > 0)? as f64
+            }
+            None => digits(part)? as f64,
+        };
+    }
+
+    Some(total)
+}
+
+fn cloud_layer(token: &str) -> Option<CloudLayer> {
+    let (code, cover) = CLOUD_COVERS
+        .iter()
+        .find(|(code, _)| token.starts_with(code))?;
+
+    let rest = &token[code.len()..];
+
+    if rest.len() < 3 || !rest.is_char_boundary(3) {
+        return None;
+    }
+
+    let (height, cloud_type) = rest.split_at(3);
+
+    // /// is a layer whose base the sensor couldn't measure
+    let base_feet = match height {
+        "///" => None,
+        height => Some(digits(height)? * 100),
+    };
+
+    let cloud_type = match cloud_type {
+        "" | "///" => None,
+        "CB" => Some("cumulonimbus".to_string()),
+        "TCU" => Some("towering cumulus".to_string()),
+        _ => return None,
+    };
+
+    Some(CloudLayer {
+        cover: cover.to_string(),
+        base_feet,
+        cloud_type,
+    })
+}
+
+// like -SHRA, +TSRA, VCFG, or FZDZ, decoded into words
+fn weather(token: &str) -> Option<String> {
+    let (intensity, rest) = match token.strip_prefix('-') {
+        Some(rest) => (Some("light"), rest),
+        None => match token.strip_prefix('+') {
+            Some(rest) => (Some("heavy"), rest),
+            None => (None, token),
+        },
+    };
+
+    let (vicinity, rest) = match rest.strip_prefix("VC") {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+
+    if rest.is_empty() || rest.len() % 2 != 0 || !rest.is_ascii() {
+        return None;
+    }
+
+    let codes: Vec<&str> = (0..rest.len())
+        .step_by(2)
+        .map(|i| &rest[i..i + 2])
+        .collect();
+
+    let lookup = |table: &[(&str, &'static str)], code: &str| -> Option<&'static str> {
+        table
+            .iter()
+            .find(|(entry, _)| *entry == code)
+            .map(|(_, words)| *words)
+    };
+
+    let (descriptor, codes) = match lookup(WEATHER_DESCRIPTORS, codes[0]) {
+        Some(descriptor) => (Some((codes[0], descriptor)), &codes[1..]),
+        None => (None, &codes[..]),
+    };
+
+    let phenomena = codes
+        .iter()
+        .map(|code| lookup(WEATHER_PHENOMENA, code))
+        .collect::<Option<Vec<&str>>>()?
+        .join(" and ");
+
+    let mut words = match (descriptor, phenomena.is_empty()) {
+        (None, true) => return None,
+        (None, false) => phenomena,
+        (Some((_, descriptor)), true) => descriptor.to_string(),
+        (Some(("SH", descriptor)), false) => format!("{} {}", phenomena, descriptor),
+        (Some(("TS", descriptor)), false) => format!("{} with {}", descriptor, phenomena),
+        (Some((_, descriptor)), false) => format!("{} {}", descriptor, phenomena),
+    };
+
+    if let Some(intensity) = intensity {
+        words = format!("{} {}", intensity, words);
+    }
+
+    if vicinity {
+        words = format!("{} in the vicinity", words);
+    }
+
+    Some(words)
+}
+
+// like 06/04 or M02/M05, the dewpoint can be missing
+fn temperature_dewpoint(token: &str) -> Option<(i32, Option<i32>)> {
+    let (temperature, dewpoint) = token.split_once('/')?;
+
+    let celsius = |value: &str| -> Option<i32> {
+        match value.strip_prefix('M') {
+            Some(value) if value.len() == 2 => Some(-(digits(value)? as i32)),
+            None if value.len() == 2 => Some(digits(value)? as i32),
+            _ => None,
+        }
+    };
+
+    let dewpoint = match dewpoint {
+        "" => None,
+        dewpoint => Some(celsius(dewpoint)?),
+    };
+
+    Some((celsius(temperature)?, dewpoint))
+}
+
+// A3002 in inches of mercury or Q1016 in hectopascals
+fn altimeter(token: &str) -> Option<f64> {
+    if token.len() != 5 {
+        return None;
+    }
+
+    if let Some(value) = token.strip_prefix('A') {
+        return Some(digits(value)? as f64 / 100.0);
+    }
+
+    token
+        .strip_prefix('Q')
+        .and_then(digits)
+        .map(|value| round(value as f64 / HECTOPASCALS_PER_INCH_OF_MERCURY, 2))
+}
+
+fn is_station(token: &str) -> bool {
+    token.len() == 4 && token.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+// DDHHMMZ
+fn day_time(token: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let token = token.strip_suffix('Z')?;
+
+    if token.len() != 6 || !token.is_ascii() {
+        return None;
+    }
+
+    resolve_time(
+        digits(&token[0..2])?,
+        digits(&token[2..4])?,
+        digits(&token[4..6])?,
+        now,
+    )
+}
+
+// DDHH/DDHH, the end can be hour 24
+fn day_range(token: &str, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (from, to) = token.split_once('/')?;
+
+    let time = |value: &str| -> Option<DateTime<Utc>> {
+        match value.len() {
+            4 if value.is_ascii() => {
+                resolve_time(digits(&value[0..2])?, digits(&value[2..4])?, 0, now)
+            }
+            _ => None,
+        }
+    };
+
+    Some((time(from)?, time(to)?))
+}
+
+// the month is whichever of last, this, or next puts the time closest to now
+fn resolve_time(day: u32, hour: u32, minute: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (hour, extra_day) = match hour {
+        24 => (0, TimeDelta::days(1)),
+        hour => (hour, TimeDelta::zero()),
+    };
+
+    let this_month = now.with_day(1)?;
+
+    [
+        this_month.checked_sub_months(Months::new(1)),
+        Some(this_month),
+        this_month.checked_add_months(Months::new(1)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|month| {
+        Utc.with_ymd_and_hms(month.year(), month.month(), day, hour, minute, 0)
+            .single()
+    })
+    .map(|time| time + extra_day)
+    .min_by_key(|time| (*time - now).num_seconds().abs())
+}
+
+fn digits(value: &str) -> Option<u32> {
+    match !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        true => value.parse().ok(),
+        false => None,
+    }
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let scale = 10_f64.powi(places);
+
+    (value * scale).round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn layer(cover: &str, base_feet: Option<u32>, cloud_type: Option<&str>) -> CloudLayer {
+        CloudLayer {
+            cover: cover.to_string(),
+            base_feet,
+            cloud_type: cloud_type.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_an_automated_metar() {
+        let metar = parse_metar(
+            "METAR KSEA 141753Z AUTO VRB03KT 1 1/2SM -SHRA BR BKN008 OVC015 M02/M05 A3002 RMK AO2 SLP170",
+            time("2026-10-14T18:30:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(metar.station, "KSEA");
+        assert_eq!(metar.observed, Some(time("2026-10-14T17:53:00Z")));
+        assert!(metar.automated);
+        assert_eq!(
+            metar.conditions.wind,
+            Some(Wind {
+                direction: None,
+                speed_knots: 3,
+                ..Wind::default()
+            })
+        );
+        assert_eq!(metar.conditions.visibility_miles, Some(1.5));
+        assert_eq!(metar.conditions.weather, vec!["light rain showers", "mist"]);
+        assert_eq!(
+            metar.conditions.clouds,
+            vec![
+                layer("broken", Some(800), None),
+                layer("overcast", Some(1500), None)
+            ]
+        );
+        assert_eq!(metar.conditions.ceiling_feet, Some(800));
+        assert_eq!(metar.conditions.flight_category.as_deref(), Some("IFR"));
+        assert_eq!(metar.temperature_c, Some(-2));
+        assert_eq!(metar.dewpoint_c, Some(-5));
+        assert_eq!(metar.altimeter_inhg, Some(30.02));
+        assert_eq!(metar.remarks.as_deref(), Some("AO2 SLP170"));
+        assert!(metar.conditions.unparsed.is_empty());
+    }
+
+    #[test]
+    fn parses_a_metric_metar() {
+        let metar = parse_metar(
+            "EGLL 141750Z 24008MPS 9999 VCFG FEW030CB SCT045TCU BKN/// 12/08 Q1016",
+            time("2026-10-14T18:00:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            metar.conditions.wind,
+            Some(Wind {
+                direction: Some(240),
+                speed_knots: 16,
+                ..Wind::default()
+            })
+        );
+        assert_eq!(metar.conditions.visibility_miles, Some(6.2));
+        assert_eq!(metar.conditions.weather, vec!["fog in the vicinity"]);
+        assert_eq!(
+            metar.conditions.clouds,
+            vec![
+                layer("few", Some(3000), Some("cumulonimbus")),
+                layer("scattered", Some(4500), Some("towering cumulus")),
+                layer("broken", None, None),
+            ]
+        );
+        // a broken layer without a base isn't a ceiling
+        assert_eq!(metar.conditions.ceiling_feet, None);
+        assert_eq!(metar.conditions.flight_category.as_deref(), Some("VFR"));
+        assert_eq!(metar.temperature_c, Some(12));
+        assert_eq!(metar.dewpoint_c, Some(8));
+        assert_eq!(metar.altimeter_inhg, Some(30.0));
+        assert!(metar.conditions.unparsed.is_empty());
+    }
+
+    #[test]
+    fn parses_gusts_and_thunderstorms() {
+        let metar = parse_metar(
+            "KDEN 141753Z 27015G25KT P6SM +TSRA SCT050CB 22/M01 A2992",
+            time("2026-10-14T18:00:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            metar.conditions.wind,
+            Some(Wind {
+                direction: Some(270),
+                speed_knots: 15,
+                gust_knots: Some(25),
+                ..Wind::default()
+            })
+        );
+        assert_eq!(metar.conditions.visibility_miles, Some(6.0));
+        assert_eq!(
+            metar.conditions.weather,
+            vec!["heavy thunderstorm with rain"]
+        );
+        assert_eq!(metar.temperature_c, Some(22));
+        assert_eq!(metar.dewpoint_c, Some(-1));
+        assert_eq!(metar.altimeter_inhg, Some(29.92));
+    }
+
+    #[test]
+    fn rejects_reports_without_a_time() {
+        assert!(parse_metar("KSEA 1é175Z 18010KT", time("2026-10-14T18:00:00Z")).is_err());
+        assert!(parse_taf("TAF KSEA 141720Z 1é1/1524", time("2026-10-14T18:00:00Z")).is_err());
+    }
+
+    #[test]
+    fn parses_taf_change_groups() {
+        let taf = parse_taf(
+            "TAF KSEA 141720Z 1418/1524 18010KT P6SM BKN050\n  FM150200 20015G25KT 5SM -RA OVC020\n  TEMPO 1506/1510 2SM BR OVC008",
+            time("2026-10-14T17:30:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(taf.station, "KSEA");
+        assert_eq!(taf.issued, Some(time("2026-10-14T17:20:00Z")));
+        assert_eq!(taf.valid_from, Some(time("2026-10-14T18:00:00Z")));
+        // hour 24 is midnight at the end of the day
+        assert_eq!(taf.valid_to, Some(time("2026-10-16T00:00:00Z")));
+        assert!(!taf.raw.contains('\n'));
+
+        let changes: Vec<&str> = taf
+            .periods
+            .iter()
+            .map(|period| period.change.as_str())
+            .collect();
+        assert_eq!(changes, vec!["initial", "from", "temporary"]);
+
+        let initial = &taf.periods[0];
+        assert_eq!(initial.to, Some(time("2026-10-15T02:00:00Z")));
+        assert_eq!(initial.conditions.ceiling_feet, Some(5000));
+        assert_eq!(initial.conditions.flight_category.as_deref(), Some("VFR"));
+
+        let from = &taf.periods[1];
+        assert_eq!(from.from, Some(time("2026-10-15T02:00:00Z")));
+        assert_eq!(from.to, Some(time("2026-10-16T00:00:00Z")));
+        assert_eq!(from.conditions.wind.as_ref().unwrap().gust_knots, Some(25));
+        assert_eq!(from.conditions.weather, vec!["light rain"]);
+        assert_eq!(from.conditions.flight_category.as_deref(), Some("MVFR"));
+
+        let temporary = &taf.periods[2];
+        assert_eq!(temporary.from, Some(time("2026-10-15T06:00:00Z")));
+        assert_eq!(temporary.to, Some(time("2026-10-15T10:00:00Z")));
+        assert_eq!(temporary.conditions.visibility_miles, Some(2.0));
+        assert_eq!(temporary.conditions.weather, vec!["mist"]);
+        assert_eq!(temporary.conditions.flight_category.as_deref(), Some("IFR"));
+    }
+
+    #[test]
+    fn flight_category_thresholds() {
+        assert_eq!(flight_category(None, None), None);
+        assert_eq!(flight_category(Some(499), None), Some("LIFR"));
+        assert_eq!(flight_category(Some(500), None), Some("IFR"));
+        assert_eq!(flight_category(Some(999), None), Some("IFR"));
+        assert_eq!(flight_category(Some(1000), None), Some("MVFR"));
+        assert_eq!(flight_category(Some(3000), None), Some("MVFR"));
+        assert_eq!(flight_category(Some(3100), None), Some("VFR"));
+        assert_eq!(flight_category(None, Some(0.75)), Some("LIFR"));
+        assert_eq!(flight_category(None, Some(1.0)), Some("IFR"));
+        assert_eq!(flight_category(None, Some(3.0)), Some("MVFR"));
+        assert_eq!(flight_category(None, Some(5.0)), Some("MVFR"));
+        assert_eq!(flight_category(None, Some(6.0)), Some("VFR"));
+        // the worse of the two decides
+        assert_eq!(flight_category(Some(5000), Some(2.0)), Some("IFR"));
+    }
+
+    #[test]
+    fn resolves_times_across_months() {
+        assert_eq!(
+            resolve_time(1, 2, 0, time("2026-10-31T23:00:00Z")),
+            Some(time("2026-11-01T02:00:00Z"))
+        );
+        assert_eq!(
+            resolve_time(31, 22, 0, time("2026-11-01T01:00:00Z")),
+            Some(time("2026-10-31T22:00:00Z"))
+        );
+        assert_eq!(
+            day_range("3118/3124", time("2026-10-31T12:00:00Z")),
+            Some((time("2026-10-31T18:00:00Z"), time("2026-11-01T00:00:00Z")))
+        );
+    }
+
+    #[test]
+    fn ignores_non_ascii_times() {
+        let now = time("2026-10-14T18:00:00Z");
+
+        assert_eq!(day_time("1é175Z", now), None);
+        assert_eq!(day_range("1é1/1524", now), None);
+    }
+}
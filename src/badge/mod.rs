@@ -0,0 +1,80 @@
+use crate::render;
+
+// approximate width of an 11px Verdana character, close enough to size the badge without font metrics
+const CHARACTER_WIDTH: f32 = 6.5;
+const PADDING: f32 = 10.0;
+const MAX_MESSAGE_LENGTH: usize = 48;
+
+// colors follow the temperature, from shields.io's palette
+pub fn color(temperature: Option<i32>) -> &'static str {
+    match temperature {
+        Some(temperature) if temperature <= 32 => "#007ec6",
+        Some(temperature) if temperature <= 50 => "#5ac8fa",
+        Some(temperature) if temperature <= 70 => "#4c1",
+        Some(temperature) if temperature <= 85 => "#fe7d37",
+        Some(_) => "#e05d44",
+        None => "#9f9f9f",
+    }
+}
+
+// the leading number of a temperature like "54F" or "-3F"
+pub fn temperature(temperature: &str) -> Option<i32> {
+    let end = temperature
+        .char_indices()
+        .find(|(index, c)| !(c.is_ascii_digit() || (*index == 0 && *c == '-')))
+        .map_or(temperature.len(), |(index, _)| index);
+
+    temperature[..end].parse().ok()
+}
+
+pub fn svg(label: &str, message: &str, color: &str) -> String {
+    let message = match message.chars().count() > MAX_MESSAGE_LENGTH {
+        true => format!(
+            "{}…",
+            message
+                .chars()
+                .take(MAX_MESSAGE_LENGTH - 1)
+                .collect::<String>()
+        ),
+        false => message.to_string(),
+    };
+
+    let label_width = width(label);
+    let message_width = width(&message);
+    let total_width = label_width + message_width;
+
+    let label = render::escape(label);
+    let message = render::escape(&message);
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{total_width}\" height=\"20\" role=\"img\" aria-label=\"{label}: {message}\">
+<title>{label}: {message}</title>
+<linearGradient id=\"s\" x2=\"0\" y2=\"100%\"><stop offset=\"0\" stop-color=\"#bbb\" stop-opacity=\".1\"/><stop offset=\"1\" stop-opacity=\".1\"/></linearGradient>
+<clipPath id=\"r\"><rect width=\"{total_width}\" height=\"20\" rx=\"3\" fill=\"#fff\"/></clipPath>
+<g clip-path=\"url(#r)\">
+<rect width=\"{label_width}\" height=\"20\" fill=\"#555\"/>
+<rect x=\"{label_width}\" width=\"{message_width}\" height=\"20\" fill=\"{color}\"/>
+<rect width=\"{total_width}\" height=\"20\" fill=\"url(#s)\"/>
+</g>
+<g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" font-size=\"11\">
+<text x=\"{label_x}\" y=\"15\" fill=\"#010101\" fill-opacity=\".3\">{label}</text>
+<text x=\"{label_x}\" y=\"14\">{label}</text>
+<text x=\"{message_x}\" y=\"15\" fill=\"#010101\" fill-opacity=\".3\">{message}</text>
+<text x=\"{message_x}\" y=\"14\">{message}</text>
+</g>
+</svg>
+",
+        total_width = total_width,
+        label_width = label_width,
+        message_width = message_width,
+        label_x = label_width / 2.0,
+        message_x = label_width + message_width / 2.0,
+        label = label,
+        message = message,
+        color = color,
+    )
+}
+
+fn width(text: &str) -> f32 {
+    (text.chars().count() as f32 * CHARACTER_WIDTH + 2.0 * PADDING).round()
+}
@@ -0,0 +1,231 @@
+use std::{
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, register_gauge_vec, CounterVec, GaugeVec};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{info, warn};
+
+lazy_static! {
+    pub static ref CENSUS: CircuitBreaker = CircuitBreaker::new("census");
+    pub static ref NWS: CircuitBreaker = CircuitBreaker::new("nws");
+    pub static ref OLLAMA: CircuitBreaker = CircuitBreaker::new("ollama");
+    pub static ref CIRCUIT_OPEN_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "circuit_breaker_open",
+            "whether calls to an upstream are failing fast"
+        ),
+        &["upstream"]
+    )
+    .unwrap();
+    pub static ref CIRCUIT_REJECTED_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "circuit_breaker_rejected_total",
+            "calls failed fast because the upstream's circuit breaker was open"
+        ),
+        &["upstream"]
+    )
+    .unwrap();
+}
+
+// how long a caller is told to wait while the one trial call is in flight
+const HALF_OPEN_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// settings struct, how many failures in a row open a breaker and how long it stays open
+struct Settings {
+    failure_threshold: u32,
+    open_for: Duration,
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("{upstream} is failing, calls are paused for {} seconds", self.retry_after_seconds())]
+pub struct CircuitOpen {
+    pub upstream: &'static str,
+    pub retry_after: Duration,
+}
+
+impl CircuitOpen {
+    // rounded up, so a client that waits this long finds the breaker ready for a trial
+    pub fn retry_after_seconds(&self) -> u64 {
+        self.retry_after.as_secs() + (self.retry_after.subsec_nanos() > 0) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    // one call is let through to see if the upstream is back, started is when
+    HalfOpen { started: Instant },
+}
+
+// calls struct, when the upstream last answered and when it last failed
+#[derive(Debug, Clone, Copy, Default)]
+struct Calls {
+    last_success: Option<DateTime<Utc>>,
+    last_failure: Option<DateTime<Utc>>,
+}
+
+// breaker status struct, where a breaker is and when its upstream last answered or failed. retry
+// after is only set while calls are being failed fast
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    pub upstream: &'static str,
+    pub state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<u64>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+}
+
+// circuit breaker struct, fails calls fast after an upstream fails too many times in a row
+pub struct CircuitBreaker {
+    name: &'static str,
+    state: Mutex<State>,
+    calls: Mutex<Calls>,
+}
+
+// the thresholds every breaker uses, set once at startup
+pub fn configure(failure_threshold: u32, open_for: Duration) {
+    let settings = Settings {
+        failure_threshold: failure_threshold.max(1),
+        open_for,
+    };
+
+    if SETTINGS.set(settings).is_err() {
+        warn!("circuit breakers are already configured");
+    }
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings {
+        failure_threshold: 5,
+        open_for: Duration::from_secs(30),
+    })
+}
+
+impl CircuitBreaker {
+    fn new(name: &'static str) -> CircuitBreaker {
+        CircuitBreaker {
+            name,
+            state: Mutex::new(State::Closed { failures: 0 }),
+            calls: Mutex::new(Calls::default()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    // an error while open. once it has been open long enough one call goes through as a trial,
+    // and another gets its turn if that one never reports back
+    pub fn check(&self) -> Result<(), CircuitOpen> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let retry_after = match *state {
+            State::Closed { .. } => return Ok(()),
+            State::Open { until } if now >= until => {
+                *state = State::HalfOpen { started: now };
+                return Ok(());
+            }
+            State::HalfOpen { started } if now - started >= settings().open_for => {
+                *state = State::HalfOpen { started: now };
+                return Ok(());
+            }
+            State::Open { until } => until - now,
+            State::HalfOpen { .. } => HALF_OPEN_RETRY_AFTER,
+        };
+
+        CIRCUIT_REJECTED_COUNTER
+            .with_label_values(&[self.name])
+            .inc();
+
+        Err(CircuitOpen {
+            upstream: self.name,
+            retry_after,
+        })
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        let state = *self.state.lock().unwrap();
+        let calls = *self.calls.lock().unwrap();
+        let now = Instant::now();
+
+        let (state, retry_after) = match state {
+            State::Closed { .. } => ("closed", None),
+            // the next call goes through as the trial
+            State::Open { until } if now >= until => ("half_open", None),
+            State::Open { until } => ("open", Some(until - now)),
+            State::HalfOpen { .. } => ("half_open", None),
+        };
+
+        BreakerStatus {
+            upstream: self.name,
+            state,
+            retry_after_seconds: retry_after.map(|retry_after| {
+                CircuitOpen {
+                    upstream: self.name,
+                    retry_after,
+                }
+                .retry_after_seconds()
+            }),
+            last_success: calls.last_success,
+            last_failure: calls.last_failure,
+        }
+    }
+
+    pub fn record(&self, success: bool) {
+        let mut calls = self.calls.lock().unwrap();
+
+        match success {
+            true => calls.last_success = Some(Utc::now()),
+            false => calls.last_failure = Some(Utc::now()),
+        }
+
+        drop(calls);
+
+        let mut state = self.state.lock().unwrap();
+        let settings = settings();
+
+        let next = match (*state, success) {
+            (_, true) => State::Closed { failures: 0 },
+            (State::Closed { failures }, false) if failures + 1 < settings.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            // a failure while already open is a call that started before it opened
+            (State::Open { until }, false) => State::Open { until },
+            (_, false) => State::Open {
+                until: Instant::now() + settings.open_for,
+            },
+        };
+
+        let was_open = !matches!(*state, State::Closed { .. });
+        let is_open = !matches!(next, State::Closed { .. });
+
+        match (was_open, is_open) {
+            (false, true) => warn!(
+                "{} failed {} times in a row, failing calls to it for {} seconds",
+                self.name,
+                settings.failure_threshold,
+                settings.open_for.as_secs()
+            ),
+            (true, false) => info!("{} is back, closing its circuit breaker", self.name),
+            _ => {}
+        }
+
+        CIRCUIT_OPEN_GAUGE
+            .with_label_values(&[self.name])
+            .set(if is_open { 1.0 } else { 0.0 });
+
+        *state = next;
+    }
+}
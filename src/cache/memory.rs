@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use moka::future::Cache as MokaCache;
+
+use super::Cache;
+
+// in-memory cache backend, entries are local to this instance
+pub struct MemoryCache {
+    entries: MokaCache<String, String>,
+}
+
+impl MemoryCache {
+    pub fn new(ttl: Duration, max_capacity: u64) -> Self {
+        let entries = MokaCache::builder()
+            .max_capacity(max_capacity)
+            .time_to_live(ttl)
+            .build();
+
+        Self { entries }
+    }
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        self.entries.get(key).await
+    }
+
+    async fn set(&self, key: String, value: String) {
+        self.entries.insert(key, value).await
+    }
+
+    async fn remove(&self, key: &str) {
+        self.entries.invalidate(key).await
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    // moka only updates its count as it gets around to pending inserts and evictions
+    async fn entry_count(&self) -> Option<u64> {
+        self.entries.run_pending_tasks().await;
+        Some(self.entries.entry_count())
+    }
+}
@@ -0,0 +1,123 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::debug;
+
+mod memory;
+mod redis;
+
+pub use self::memory::MemoryCache;
+pub use self::redis::RedisCache;
+
+lazy_static! {
+    pub static ref CACHE_HITS_COUNTER: CounterVec = register_counter_vec!(
+        opts!("cache_hits_total", "cache lookups that found an entry"),
+        &["cache"]
+    )
+    .unwrap();
+    pub static ref CACHE_MISSES_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "cache_misses_total",
+            "cache lookups that did not find an entry"
+        ),
+        &["cache"]
+    )
+    .unwrap();
+}
+
+// cache backend, stores serialized values by key
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get(&self, key: &str) -> Option<String>;
+    async fn set(&self, key: String, value: String);
+    async fn remove(&self, key: &str);
+
+    // whether the backend can be reached, the error is only reported so it's kept as text
+    async fn ping(&self) -> Result<(), String>;
+
+    // how many entries this instance holds, none when the backend is shared and can't tell which
+    // entries are this cache's without scanning for them
+    async fn entry_count(&self) -> Option<u64>;
+}
+
+// json cache, values are stored as serialized JSON so any response type can share it
+#[derive(Clone)]
+pub struct JsonCache {
+    name: &'static str,
+    backend: Arc<dyn Cache>,
+}
+
+impl JsonCache {
+    pub fn new(name: &'static str, backend: Arc<dyn Cache>) -> Self {
+        Self { name, backend }
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = match self.backend.get(key).await {
+            Some(value) => value,
+            None => {
+                CACHE_MISSES_COUNTER.with_label_values(&[self.name]).inc();
+                return None;
+            }
+        };
+
+        match serde_json::from_str(&value) {
+            Ok(value) => {
+                CACHE_HITS_COUNTER.with_label_values(&[self.name]).inc();
+                Some(value)
+            }
+            Err(e) => {
+                debug!("error decoding cached value for {}: {}", key, e);
+                CACHE_MISSES_COUNTER.with_label_values(&[self.name]).inc();
+                self.backend.remove(key).await;
+                None
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub async fn ping(&self) -> Result<(), String> {
+        self.backend.ping().await
+    }
+
+    pub async fn entry_count(&self) -> Option<u64> {
+        self.backend.entry_count().await
+    }
+
+    pub async fn insert<T: Serialize>(&self, key: String, value: &T) {
+        match serde_json::to_string(value) {
+            Ok(value) => self.backend.set(key, value).await,
+            Err(e) => debug!("error encoding value for {}: {}", key, e),
+        }
+    }
+}
+
+pub async fn connect(
+    backend: &str,
+    redis_url: Option<&str>,
+    name: &'static str,
+    ttl: Duration,
+    max_capacity: u64,
+) -> JsonCache {
+    let backend: Arc<dyn Cache> = match backend {
+        "memory" => Arc::new(MemoryCache::new(ttl, max_capacity)),
+        "redis" => {
+            let redis_url = redis_url.unwrap_or_else(|| panic!("REDIS_URL is not set"));
+
+            let redis_cache = RedisCache::connect(redis_url, name, ttl)
+                .await
+                .unwrap_or_else(|e| panic!("error connecting to redis: {}", e));
+
+            Arc::new(redis_cache)
+        }
+        _ => panic!("{} is not a valid cache backend", backend),
+    };
+
+    JsonCache::new(name, backend)
+}
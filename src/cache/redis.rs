@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use tracing::error;
+
+use super::Cache;
+
+// redis cache backend, entries are shared between instances
+pub struct RedisCache {
+    connection: ConnectionManager,
+    prefix: String,
+    ttl: Duration,
+}
+
+impl RedisCache {
+    pub async fn connect(url: &str, prefix: &str, ttl: Duration) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+
+        Ok(Self {
+            connection,
+            prefix: prefix.to_string(),
+            ttl,
+        })
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("nws-forecast-summarizer:{}:{}", self.prefix, key)
+    }
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        let mut connection = self.connection.clone();
+
+        match connection.get::<_, Option<String>>(self.key(key)).await {
+            Ok(value) => value,
+            Err(e) => {
+                error!("error reading {} from redis: {}", key, e);
+                None
+            }
+        }
+    }
+
+    async fn set(&self, key: String, value: String) {
+        let mut connection = self.connection.clone();
+
+        if let Err(e) = connection
+            .set_ex::<_, _, ()>(self.key(&key), value, self.ttl.as_secs())
+            .await
+        {
+            error!("error writing {} to redis: {}", key, e);
+        }
+    }
+
+    async fn remove(&self, key: &str) {
+        let mut connection = self.connection.clone();
+
+        if let Err(e) = connection.del::<_, ()>(self.key(key)).await {
+            error!("error removing {} from redis: {}", key, e);
+        }
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        let mut connection = self.connection.clone();
+
+        redis::cmd("PING")
+            .query_async::<_, ()>(&mut connection)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn entry_count(&self) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::geocode::Coordinates;
+
+const VTEC_PARAMETER: &str = "VTEC";
+
+#[derive(Debug, Error)]
+pub enum CapError {
+    #[error("error parsing CAP alert: {0}")]
+    Parse(#[from] quick_xml::DeError),
+    #[error("CAP alert has no info block")]
+    MissingInfo,
+}
+
+impl CapError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CapError::Parse(_) => "parse",
+            CapError::MissingInfo => "missing_info",
+        }
+    }
+}
+
+// cap alert struct, the parts of a CAP 1.2 document that the JSON API flattens or leaves out
+#[derive(Debug, Clone, Deserialize)]
+struct CapAlert {
+    #[serde(default)]
+    info: Vec<CapInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CapInfo {
+    language: Option<String>,
+    instruction: Option<String>,
+    #[serde(default)]
+    parameter: Vec<CapValue>,
+    #[serde(default)]
+    area: Vec<CapArea>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CapValue {
+    #[serde(rename = "valueName")]
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CapArea {
+    #[serde(default)]
+    polygon: Vec<String>,
+}
+
+// cap details struct, the official instruction text as NWS wrote it, its VTEC codes, and the warned polygons
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CapDetails {
+    pub instruction: Option<String>,
+    pub vtec: Vec<String>,
+    pub polygons: Vec<Vec<Coordinates>>,
+}
+
+// alerts can carry an info block per language, the english one is used when there is one
+pub fn parse(xml: &str) -> Result<CapDetails, CapError> {
+    let alert: CapAlert = quick_xml::de::from_str(xml)?;
+
+    let info = match alert
+        .info
+        .iter()
+        .find(|info| {
+            info.language
+                .as_deref()
+                .is_none_or(|language| language.starts_with("en"))
+        })
+        .or(alert.info.first())
+    {
+        Some(info) => info,
+        None => return Err(CapError::MissingInfo),
+    };
+
+    Ok(CapDetails {
+        instruction: info
+            .instruction
+            .as_deref()
+            .map(|instruction| instruction.trim().to_string())
+            .filter(|instruction| !instruction.is_empty()),
+        vtec: info
+            .parameter
+            .iter()
+            .filter(|parameter| parameter.name == VTEC_PARAMETER)
+            .map(|parameter| parameter.value.trim().to_string())
+            .collect(),
+        polygons: info
+            .area
+            .iter()
+            .flat_map(|area| &area.polygon)
+            .filter_map(|polygon| parse_polygon(polygon))
+            .collect(),
+    })
+}
+
+// CAP polygons are space separated "lat,lon" pairs, closed by repeating the first point
+fn parse_polygon(polygon: &str) -> Option<Vec<Coordinates>> {
+    let mut points = Vec::new();
+
+    for pair in polygon.split_whitespace() {
+        let (latitude, longitude) = pair.split_once(',')?;
+
+        points.push(Coordinates {
+            latitude: latitude.parse().ok()?,
+            longitude: longitude.parse().ok()?,
+        });
+    }
+
+    match points.len() >= 4 {
+        true => Some(points),
+        false => None,
+    }
+}
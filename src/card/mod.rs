@@ -0,0 +1,255 @@
+use std::{fs, path::Path};
+
+use fontdue::{Font, FontSettings};
+use thiserror::Error;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 418;
+const MARGIN: f32 = 32.0;
+const TITLE_SIZE: f32 = 30.0;
+const SUMMARY_SIZE: f32 = 20.0;
+const PERIOD_NAME_SIZE: f32 = 16.0;
+const PERIOD_TEMPERATURE_SIZE: f32 = 28.0;
+const PERIOD_FORECAST_SIZE: f32 = 14.0;
+const MAX_SUMMARY_LINES: usize = 6;
+
+pub const MAX_CARD_PERIODS: usize = 3;
+
+const BACKGROUND: (u8, u8, u8) = (24, 52, 88);
+const PANEL: (u8, u8, u8) = (38, 74, 118);
+const TEXT: (u8, u8, u8) = (255, 255, 255);
+const MUTED_TEXT: (u8, u8, u8) = (196, 214, 236);
+
+#[derive(Debug, Error)]
+pub enum CardError {
+    #[error("error reading card font {0}: {1}")]
+    Read(String, #[source] std::io::Error),
+    #[error("error loading card font {0}: {1}")]
+    Font(String, &'static str),
+    #[error("error encoding card: {0}")]
+    Encode(String),
+    #[error("no card font is configured")]
+    NotConfigured,
+}
+
+impl CardError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CardError::Read(_, _) => "read",
+            CardError::Font(_, _) => "font",
+            CardError::Encode(_) => "encode",
+            CardError::NotConfigured => "not_configured",
+        }
+    }
+}
+
+// card period struct, one of the key periods shown along the bottom of the card
+pub struct CardPeriod {
+    pub name: String,
+    pub temperature: String,
+    pub forecast: String,
+}
+
+// card renderer, draws forecast cards as PNG images with a single font
+pub struct CardRenderer {
+    font: Font,
+}
+
+impl CardRenderer {
+    pub fn load(path: &str) -> Result<Self, CardError> {
+        let bytes = match fs::read(Path::new(path)) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(CardError::Read(path.to_string(), e)),
+        };
+
+        match Font::from_bytes(bytes, FontSettings::default()) {
+            Ok(font) => Ok(Self { font }),
+            Err(e) => Err(CardError::Font(path.to_string(), e)),
+        }
+    }
+
+    pub fn render(
+        &self,
+        title: &str,
+        summary: &str,
+        periods: &[CardPeriod],
+    ) -> Result<Vec<u8>, CardError> {
+        let mut pixmap = match Pixmap::new(WIDTH, HEIGHT) {
+            Some(pixmap) => pixmap,
+            None => return Err(CardError::Encode("invalid card size".to_string())),
+        };
+
+        pixmap.fill(color(BACKGROUND));
+
+        let content_width = WIDTH as f32 - 2.0 * MARGIN;
+
+        let mut y = MARGIN + TITLE_SIZE;
+        let title = self.truncate(title, TITLE_SIZE, content_width);
+        self.draw_text(&mut pixmap, &title, MARGIN, y, TITLE_SIZE, TEXT);
+
+        y += SUMMARY_SIZE * 0.8;
+
+        for line in self
+            .wrap(summary, SUMMARY_SIZE, content_width)
+            .into_iter()
+            .take(MAX_SUMMARY_LINES)
+        {
+            y += SUMMARY_SIZE * 1.35;
+            self.draw_text(&mut pixmap, &line, MARGIN, y, SUMMARY_SIZE, TEXT);
+        }
+
+        // key periods sit in equal panels along the bottom edge
+        let count = periods.len().min(MAX_CARD_PERIODS);
+
+        if count > 0 {
+            let gap = 16.0;
+            let panel_height = 104.0;
+            let panel_width = (content_width - gap * (count as f32 - 1.0)) / count as f32;
+            let panel_top = HEIGHT as f32 - MARGIN - panel_height;
+
+            let mut paint = Paint::default();
+            paint.set_color(color(PANEL));
+
+            for (index, period) in periods.iter().take(count).enumerate() {
+                let left = MARGIN + index as f32 * (panel_width + gap);
+                let inner_width = panel_width - 24.0;
+
+                if let Some(rect) = Rect::from_xywh(left, panel_top, panel_width, panel_height) {
+                    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+                }
+
+                let name = self.truncate(&period.name, PERIOD_NAME_SIZE, inner_width);
+                let forecast = self.truncate(&period.forecast, PERIOD_FORECAST_SIZE, inner_width);
+
+                self.draw_text(
+                    &mut pixmap,
+                    &name,
+                    left + 12.0,
+                    panel_top + 26.0,
+                    PERIOD_NAME_SIZE,
+                    MUTED_TEXT,
+                );
+                self.draw_text(
+                    &mut pixmap,
+                    &period.temperature,
+                    left + 12.0,
+                    panel_top + 62.0,
+                    PERIOD_TEMPERATURE_SIZE,
+                    TEXT,
+                );
+                self.draw_text(
+                    &mut pixmap,
+                    &forecast,
+                    left + 12.0,
+                    panel_top + 88.0,
+                    PERIOD_FORECAST_SIZE,
+                    MUTED_TEXT,
+                );
+            }
+        }
+
+        pixmap
+            .encode_png()
+            .map_err(|e| CardError::Encode(e.to_string()))
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars()
+            .map(|c| self.font.metrics(c, size).advance_width)
+            .sum()
+    }
+
+    // greedy word wrap, a single word wider than the line is left to overflow
+    fn wrap(&self, text: &str, size: f32, max_width: f32) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = match line.is_empty() {
+                true => word.to_string(),
+                false => format!("{} {}", line, word),
+            };
+
+            if self.text_width(&candidate, size) > max_width && !line.is_empty() {
+                lines.push(line);
+                line = word.to_string();
+            } else {
+                line = candidate;
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    fn truncate(&self, text: &str, size: f32, max_width: f32) -> String {
+        if self.text_width(text, size) <= max_width {
+            return text.to_string();
+        }
+
+        let mut truncated = String::new();
+
+        for c in text.chars() {
+            if self.text_width(&format!("{}{}…", truncated, c), size) > max_width {
+                break;
+            }
+            truncated.push(c);
+        }
+
+        format!("{}…", truncated.trim_end())
+    }
+
+    // blends each glyph's coverage over the opaque background, y is the baseline
+    fn draw_text(
+        &self,
+        pixmap: &mut Pixmap,
+        text: &str,
+        x: f32,
+        y: f32,
+        size: f32,
+        (red, green, blue): (u8, u8, u8),
+    ) {
+        let width = pixmap.width() as i32;
+        let height = pixmap.height() as i32;
+        let data = pixmap.data_mut();
+
+        let mut pen = x;
+
+        for c in text.chars() {
+            let (metrics, coverage) = self.font.rasterize(c, size);
+
+            let left = (pen + metrics.xmin as f32).round() as i32;
+            let top = (y - metrics.height as f32 - metrics.ymin as f32).round() as i32;
+
+            for row in 0..metrics.height {
+                for column in 0..metrics.width {
+                    let px = left + column as i32;
+                    let py = top + row as i32;
+
+                    if px < 0 || py < 0 || px >= width || py >= height {
+                        continue;
+                    }
+
+                    let alpha = coverage[row * metrics.width + column] as u16;
+                    let offset = ((py * width + px) * 4) as usize;
+
+                    for (channel, value) in [red, green, blue].into_iter().enumerate() {
+                        let existing = data[offset + channel] as u16;
+                        data[offset + channel] =
+                            ((value as u16 * alpha + existing * (255 - alpha)) / 255) as u8;
+                    }
+                }
+            }
+
+            pen += metrics.advance_width;
+        }
+    }
+}
+
+fn color((red, green, blue): (u8, u8, u8)) -> Color {
+    Color::from_rgba8(red, green, blue, 255)
+}
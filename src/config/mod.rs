@@ -6,33 +6,86 @@ pub struct Config {
     pub api_port: u16,
     pub metrics_host: String,
     pub metrics_port: u16,
-    pub ollama_host: String,
-    pub ollama_port: u16,
+    pub llm_backend: String,
+    pub llm_model_allowlist: Vec<String>,
+    pub llm_max_input_tokens: usize,
+    pub llm_max_attempts: usize,
+    pub prompt_path: Option<String>,
+    pub examples_dir: Option<String>,
+    pub ollama_hosts: Vec<String>,
+    pub ollama_health_check_interval: u64,
+    pub ollama_pull_model: bool,
+    pub ollama_keep_alive: Option<i64>,
+    pub ollama_warm_up: bool,
+    pub ollama_temperature: Option<f32>,
+    pub ollama_top_p: Option<f32>,
+    pub ollama_seed: Option<i32>,
+    pub ollama_num_predict: Option<i32>,
     pub ollama_model: String,
-}
-
-impl Config {
-    pub fn new(
-        log_level: String,
-        api_host: String,
-        api_port: u16,
-        metrics_host: String,
-        metrics_port: u16,
-        ollama_host: String,
-        ollama_port: u16,
-        ollama_model: String,
-    ) -> Self {
-        Self {
-            log_level,
-            api_host,
-            api_port,
-            metrics_host,
-            metrics_port,
-            ollama_host,
-            ollama_port,
-            ollama_model,
-        }
-    }
+    pub forecast_cache_ttl: u64,
+    pub geocode_cache_ttl: u64,
+    pub summary_cache_ttl: u64,
+    pub last_summary_cache_ttl: u64,
+    pub summary_fallbacks: Vec<String>,
+    pub summarize_by_default: bool,
+    pub include_alerts_by_default: bool,
+    pub degree_day_metrics: bool,
+    pub summary_tone: String,
+    pub default_units: String,
+    pub nws_si_passthrough: bool,
+    pub cache_backend: String,
+    pub redis_url: Option<String>,
+    pub openai_api_key: Option<String>,
+    pub openai_base_url: String,
+    pub openai_model: String,
+    pub openai_compatible_base_url: Option<String>,
+    pub openai_compatible_model: Option<String>,
+    pub openai_compatible_api_key: Option<String>,
+    pub openai_compatible_json_mode: bool,
+    pub anthropic_api_key: Option<String>,
+    pub anthropic_base_url: String,
+    pub anthropic_model: String,
+    pub anthropic_max_tokens: u32,
+    pub database_url: Option<String>,
+    pub alexa_skill_id: Option<String>,
+    pub alexa_default_address: Option<String>,
+    pub self_check_address: String,
+    pub static_dir: Option<String>,
+    pub badge_max_age: u64,
+    pub stale_forecast_age: u64,
+    pub forecast_revalidate_after: u64,
+    pub nws_requests_per_second: f32,
+    pub nws_request_burst: u32,
+    pub outbound_requests_per_second: f32,
+    pub outbound_request_burst: u32,
+    pub circuit_breaker_failures: u32,
+    pub circuit_breaker_open_seconds: u64,
+    pub http_connect_timeout: u64,
+    pub http_read_timeout: u64,
+    pub http_timeout: u64,
+    pub llm_read_timeout: u64,
+    pub summary_deadline: u64,
+    pub request_deadline: u64,
+    pub llm_concurrency: usize,
+    pub llm_max_queue: usize,
+    pub llm_max_scheduled_queue: usize,
+    pub card_font_path: Option<String>,
+    pub schedule_path: Option<String>,
+    pub rules_path: Option<String>,
+    pub subscriptions_admin_token: Option<String>,
+    pub subscriptions_create_token: Option<String>,
+    pub alert_poll_interval: u64,
+    pub twilio_auth_token: Option<String>,
+    pub twilio_webhook_url: Option<String>,
+    pub feed_locations: Option<String>,
+    pub feed_data_dir: Option<String>,
+    pub feed_refresh_interval: u64,
+    pub tts_backend: Option<String>,
+    pub tts_base_url: Option<String>,
+    pub tts_api_key: Option<String>,
+    pub tts_model: String,
+    pub tts_voice: String,
+    pub airnow_api_key: Option<String>,
 }
 
 pub fn load() -> Config {
@@ -41,20 +94,204 @@ pub fn load() -> Config {
     let api_port = u16(get("API_PORT"));
     let metrics_host = get("METRICS_HOST");
     let metrics_port = u16(get("METRICS_PORT"));
-    let ollama_host = get("OLLAMA_HOST");
-    let ollama_port = u16(get("OLLAMA_PORT"));
-    let ollama_model = get("OLLAMA_MODEL");
+    let llm_backend = get_or("LLM_BACKEND", "ollama");
+    let llm_model_allowlist = env::var("LLM_MODEL_ALLOWLIST")
+        .map(list)
+        .unwrap_or_default();
+    let llm_max_input_tokens = usize(get_or("LLM_MAX_INPUT_TOKENS", "4096"));
+    let llm_max_attempts = usize(get_or("LLM_MAX_ATTEMPTS", "3")).max(1);
+    let prompt_path = env::var("PROMPT_PATH").ok();
+    let examples_dir = env::var("EXAMPLES_DIR").ok();
+    let ollama_host = get_or("OLLAMA_HOST", "http://localhost");
+    let ollama_port = u16(get_or("OLLAMA_PORT", "11434"));
+    let ollama_hosts = list(get_or(
+        "OLLAMA_HOSTS",
+        &format!("{}:{}", ollama_host, ollama_port),
+    ));
+    let ollama_health_check_interval = u64(get_or("OLLAMA_HEALTH_CHECK_INTERVAL_SECONDS", "30"));
+    let ollama_model = get_or("OLLAMA_MODEL", "llama3");
+    let ollama_pull_model = bool(get_or("OLLAMA_PULL_MODEL", "false"));
+    let ollama_keep_alive = env::var("OLLAMA_KEEP_ALIVE_SECONDS").ok().map(i64);
+    let ollama_warm_up = bool(get_or("OLLAMA_WARM_UP", "false"));
+    let ollama_temperature = env::var("OLLAMA_TEMPERATURE").ok().map(f32);
+    let ollama_top_p = env::var("OLLAMA_TOP_P").ok().map(f32);
+    let ollama_seed = env::var("OLLAMA_SEED").ok().map(i32);
+    let ollama_num_predict = env::var("OLLAMA_NUM_PREDICT").ok().map(i32);
+    let forecast_cache_ttl = u64(get_or("FORECAST_CACHE_TTL_SECONDS", "1800"));
+    let geocode_cache_ttl = u64(get_or("GEOCODE_CACHE_TTL_SECONDS", "2592000"));
+    let summary_cache_ttl = u64(get_or("SUMMARY_CACHE_TTL_SECONDS", "86400"));
+    // the last summary for each location, served when the llm fails after the forecast changed
+    let last_summary_cache_ttl = u64(get_or("LAST_SUMMARY_CACHE_TTL_SECONDS", "172800"));
+    // tried in order when the llm fails, none returns the error instead
+    let summary_fallbacks = list(get_or("SUMMARY_FALLBACKS", "cached,template,periods"));
+    let summarize_by_default = bool(get_or("SUMMARIZE_BY_DEFAULT", "true"));
+    // off by default, it's a second request to NWS for every summary that isn't cached
+    let include_alerts_by_default = bool(get_or("INCLUDE_ALERTS_BY_DEFAULT", "false"));
+    let degree_day_metrics = bool(get_or("DEGREE_DAY_METRICS", "false"));
+    let summary_tone = get_or("SUMMARY_TONE", "neutral");
+    let default_units = get_or("DEFAULT_UNITS", "us");
+    // off by default, si forecasts are converted from the cached us one instead of fetched again
+    let nws_si_passthrough = bool(get_or("NWS_SI_PASSTHROUGH", "false"));
+    let cache_backend = get_or("CACHE_BACKEND", "memory");
+    let redis_url = env::var("REDIS_URL").ok();
+    let openai_api_key = env::var("OPENAI_API_KEY").ok();
+    let openai_base_url = get_or("OPENAI_BASE_URL", "https://api.openai.com/v1");
+    let openai_model = get_or("OPENAI_MODEL", "gpt-4o-mini");
+    let openai_compatible_base_url = env::var("OPENAI_COMPATIBLE_BASE_URL").ok();
+    let openai_compatible_model = env::var("OPENAI_COMPATIBLE_MODEL").ok();
+    let openai_compatible_api_key = env::var("OPENAI_COMPATIBLE_API_KEY").ok();
+    let openai_compatible_json_mode = bool(get_or("OPENAI_COMPATIBLE_JSON_MODE", "true"));
+    let anthropic_api_key = env::var("ANTHROPIC_API_KEY").ok();
+    let anthropic_base_url = get_or("ANTHROPIC_BASE_URL", "https://api.anthropic.com/v1");
+    let anthropic_model = get_or("ANTHROPIC_MODEL", "claude-3-5-haiku-latest");
+    let anthropic_max_tokens = u32(get_or("ANTHROPIC_MAX_TOKENS", "1024"));
+    let database_url = env::var("DATABASE_URL").ok();
+    let alexa_skill_id = env::var("ALEXA_SKILL_ID").ok();
+    let alexa_default_address = env::var("ALEXA_DEFAULT_ADDRESS").ok();
+    // geocoded and looked up by --check, any address NWS covers works
+    let self_check_address = get_or(
+        "SELF_CHECK_ADDRESS",
+        "1600 Pennsylvania Ave NW, Washington, DC 20500",
+    );
+    let static_dir = env::var("STATIC_DIR").ok();
+    let badge_max_age = u64(get_or("BADGE_MAX_AGE_SECONDS", "1800"));
+    // offices update the grid every few hours, a cached forecast older than this is fetched again
+    let stale_forecast_age = u64(get_or("STALE_FORECAST_SECONDS", "21600"));
+    let forecast_revalidate_after = u64(get_or("FORECAST_REVALIDATE_SECONDS", "600"));
+    // NWS asks for modest request rates, the per host limit applies to each NOAA host and the
+    // outbound limit to all of them together. 0 is unlimited
+    let nws_requests_per_second = f32(get_or("NWS_REQUESTS_PER_SECOND", "5"));
+    let nws_request_burst = u32(get_or("NWS_REQUEST_BURST", "10"));
+    let outbound_requests_per_second = f32(get_or("OUTBOUND_REQUESTS_PER_SECOND", "10"));
+    let outbound_request_burst = u32(get_or("OUTBOUND_REQUEST_BURST", "20"));
+    // failures in a row before calls to the geocoder, NWS, or ollama fail fast, and for how long
+    let circuit_breaker_failures = u32(get_or("CIRCUIT_BREAKER_FAILURES", "5"));
+    let circuit_breaker_open_seconds = u64(get_or("CIRCUIT_BREAKER_OPEN_SECONDS", "30"));
+    let http_connect_timeout = u64(get_or("HTTP_CONNECT_TIMEOUT_SECONDS", "5"));
+    let http_read_timeout = u64(get_or("HTTP_READ_TIMEOUT_SECONDS", "30"));
+    let http_timeout = u64(get_or("HTTP_TIMEOUT_SECONDS", "60"));
+    // the longest an llm request can go without sending anything, it includes loading the model.
+    // pulling a large model at startup can need more
+    let llm_read_timeout = u64(get_or("LLM_READ_TIMEOUT_SECONDS", "120"));
+    // the most one summary can take, correction attempts included
+    let summary_deadline = u64(get_or("SUMMARY_DEADLINE_SECONDS", "90"));
+    // the most a forecast request can take, longer than one summary so a slow one can still fall back
+    let request_deadline = u64(get_or("REQUEST_DEADLINE_SECONDS", "120"));
+    // llm workers, the generations run at once, and how many requests can wait for one before
+    // they are turned away
+    let llm_concurrency = usize(get_or("LLM_CONCURRENCY", "2"));
+    let llm_max_queue = usize(get_or("LLM_MAX_QUEUE", "16"));
+    // the feed, scheduled jobs, and subscriptions are turned away once this many requests of any
+    // kind are waiting
+    let llm_max_scheduled_queue = usize(get_or("LLM_MAX_SCHEDULED_QUEUE", "4"));
+    let card_font_path = env::var("CARD_FONT_PATH").ok();
+    let schedule_path = env::var("SCHEDULE_PATH").ok();
+    let rules_path = env::var("RULES_PATH").ok();
+    let subscriptions_admin_token = env::var("SUBSCRIPTIONS_ADMIN_TOKEN").ok();
+    // creating a subscription sends mail, texts, and requests on the operator's behalf, so it
+    // needs this token or the admin one. with neither set nobody can create one
+    let subscriptions_create_token = env::var("SUBSCRIPTIONS_CREATE_TOKEN").ok();
+    // 0 turns the alert monitor off
+    let alert_poll_interval = u64(get_or("ALERT_POLL_INTERVAL_SECONDS", "300"));
+    let twilio_auth_token = env::var("TWILIO_AUTH_TOKEN").ok();
+    let twilio_webhook_url = env::var("TWILIO_WEBHOOK_URL").ok();
+    let feed_locations = env::var("FEED_LOCATIONS").ok();
+    let feed_data_dir = env::var("FEED_DATA_DIR").ok();
+    let feed_refresh_interval = u64(get_or("FEED_REFRESH_INTERVAL_SECONDS", "3600"));
+    let tts_backend = env::var("TTS_BACKEND").ok();
+    let tts_base_url = env::var("TTS_BASE_URL").ok();
+    let tts_api_key = env::var("TTS_API_KEY").ok();
+    let tts_model = get_or("TTS_MODEL", "tts-1");
+    let tts_voice = get_or("TTS_VOICE", "alloy");
+    let airnow_api_key = env::var("AIRNOW_API_KEY").ok();
 
-    Config::new(
+    Config {
         log_level,
         api_host,
         api_port,
         metrics_host,
         metrics_port,
-        ollama_host,
-        ollama_port,
+        llm_backend,
+        llm_model_allowlist,
+        llm_max_input_tokens,
+        llm_max_attempts,
+        prompt_path,
+        examples_dir,
+        ollama_hosts,
+        ollama_health_check_interval,
+        ollama_pull_model,
+        ollama_keep_alive,
+        ollama_warm_up,
+        ollama_temperature,
+        ollama_top_p,
+        ollama_seed,
+        ollama_num_predict,
         ollama_model,
-    )
+        forecast_cache_ttl,
+        geocode_cache_ttl,
+        summary_cache_ttl,
+        last_summary_cache_ttl,
+        summary_fallbacks,
+        summarize_by_default,
+        include_alerts_by_default,
+        degree_day_metrics,
+        summary_tone,
+        default_units,
+        nws_si_passthrough,
+        cache_backend,
+        redis_url,
+        openai_api_key,
+        openai_base_url,
+        openai_model,
+        openai_compatible_base_url,
+        openai_compatible_model,
+        openai_compatible_api_key,
+        openai_compatible_json_mode,
+        anthropic_api_key,
+        anthropic_base_url,
+        anthropic_model,
+        anthropic_max_tokens,
+        database_url,
+        alexa_skill_id,
+        alexa_default_address,
+        self_check_address,
+        static_dir,
+        badge_max_age,
+        stale_forecast_age,
+        forecast_revalidate_after,
+        nws_requests_per_second,
+        nws_request_burst,
+        outbound_requests_per_second,
+        outbound_request_burst,
+        circuit_breaker_failures,
+        circuit_breaker_open_seconds,
+        http_connect_timeout,
+        http_read_timeout,
+        http_timeout,
+        llm_read_timeout,
+        summary_deadline,
+        request_deadline,
+        llm_concurrency,
+        llm_max_queue,
+        llm_max_scheduled_queue,
+        card_font_path,
+        schedule_path,
+        rules_path,
+        subscriptions_admin_token,
+        subscriptions_create_token,
+        alert_poll_interval,
+        twilio_auth_token,
+        twilio_webhook_url,
+        feed_locations,
+        feed_data_dir,
+        feed_refresh_interval,
+        tts_backend,
+        tts_base_url,
+        tts_api_key,
+        tts_model,
+        tts_voice,
+        airnow_api_key,
+    }
 }
 
 fn get(key: &str) -> String {
@@ -63,7 +300,60 @@ fn get(key: &str) -> String {
         .to_string()
 }
 
+fn get_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_string())
+}
+
+fn list(key: String) -> Vec<String> {
+    let values: Vec<String> = key
+        .split(',')
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+
+    if values.is_empty() {
+        panic!("{} is not a valid list", key);
+    }
+
+    values
+}
+
 fn u16(key: String) -> u16 {
     key.parse::<u16>()
         .unwrap_or_else(|_| panic!("{} is not a valid u16", key))
 }
+
+fn u32(key: String) -> u32 {
+    key.parse::<u32>()
+        .unwrap_or_else(|_| panic!("{} is not a valid u32", key))
+}
+
+fn i32(key: String) -> i32 {
+    key.parse::<i32>()
+        .unwrap_or_else(|_| panic!("{} is not a valid i32", key))
+}
+
+fn f32(key: String) -> f32 {
+    key.parse::<f32>()
+        .unwrap_or_else(|_| panic!("{} is not a valid f32", key))
+}
+
+fn i64(key: String) -> i64 {
+    key.parse::<i64>()
+        .unwrap_or_else(|_| panic!("{} is not a valid i64", key))
+}
+
+fn usize(key: String) -> usize {
+    key.parse::<usize>()
+        .unwrap_or_else(|_| panic!("{} is not a valid usize", key))
+}
+
+fn u64(key: String) -> u64 {
+    key.parse::<u64>()
+        .unwrap_or_else(|_| panic!("{} is not a valid u64", key))
+}
+
+fn bool(key: String) -> bool {
+    key.parse::<bool>()
+        .unwrap_or_else(|_| panic!("{} is not a valid bool", key))
+}
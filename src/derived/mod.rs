@@ -0,0 +1,153 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+// the NWS formulas are fahrenheit and mph, heat index applies from 80F and wind chill
+// at 50F or below with wind over 3 mph
+const HEAT_INDEX_MIN_F: f64 = 80.0;
+const WIND_CHILL_MAX_F: f64 = 50.0;
+const WIND_CHILL_MIN_MPH: f64 = 3.0;
+
+// degree days are counted from 65F in the US
+pub const DEGREE_DAY_BASE_F: f64 = 65.0;
+
+const TROPICS_LATITUDE: f64 = 23.5;
+
+// the heat index or wind chill when either applies, none when it's the same as the temperature
+pub fn apparent_temperature(
+    temperature_f: f64,
+    relative_humidity: Option<f64>,
+    wind_mph: Option<f64>,
+) -> Option<f64> {
+    let apparent = match (relative_humidity, wind_mph) {
+        (Some(relative_humidity), _) if temperature_f >= HEAT_INDEX_MIN_F => {
+            heat_index(temperature_f, relative_humidity)
+        }
+        (_, Some(wind_mph))
+            if temperature_f <= WIND_CHILL_MAX_F && wind_mph > WIND_CHILL_MIN_MPH =>
+        {
+            wind_chill(temperature_f, wind_mph)
+        }
+        _ => return None,
+    };
+
+    match apparent.round() == temperature_f.round() {
+        true => None,
+        false => Some(apparent),
+    }
+}
+
+// the Rothfusz regression with the NWS adjustments for very dry and very humid air.
+// the simple formula is close enough below 80F, where the regression isn't valid
+pub fn heat_index(temperature_f: f64, relative_humidity: f64) -> f64 {
+    let t = temperature_f;
+    let rh = relative_humidity;
+
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+
+    if (simple + t) / 2.0 < HEAT_INDEX_MIN_F {
+        return simple;
+    }
+
+    let mut heat_index = -42.379 + 2.04901523 * t + 10.14333127 * rh
+        - 0.22475541 * t * rh
+        - 0.00683783 * t * t
+        - 0.05481717 * rh * rh
+        + 0.00122874 * t * t * rh
+        + 0.00085282 * t * rh * rh
+        - 0.00000199 * t * t * rh * rh;
+
+    if rh < 13.0 && (80.0..=112.0).contains(&t) {
+        heat_index -= ((13.0 - rh) / 4.0) * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+    }
+
+    if rh > 85.0 && (80.0..=87.0).contains(&t) {
+        heat_index += ((rh - 85.0) / 10.0) * ((87.0 - t) / 5.0);
+    }
+
+    heat_index
+}
+
+// the 2001 NWS wind chill formula
+pub fn wind_chill(temperature_f: f64, wind_mph: f64) -> f64 {
+    let wind = wind_mph.powf(0.16);
+
+    35.74 + 0.6215 * temperature_f - 35.75 * wind + 0.4275 * temperature_f * wind
+}
+
+// forecast wind speeds are text like "5 mph" or "10 to 25 mph", the highest number is used
+pub fn wind_speed_mph(wind_speed: &str) -> Option<f64> {
+    let kilometers = wind_speed.contains("km/h");
+
+    let speed = wind_speed
+        .split_whitespace()
+        .filter_map(|word| word.parse::<f64>().ok())
+        .max_by(|a, b| a.total_cmp(b))?;
+
+    match kilometers {
+        true => Some(speed / 1.609344),
+        false => Some(speed),
+    }
+}
+
+// degree days struct, how far a day's mean temperature is below (heating) or above (cooling) the base
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DegreeDays {
+    pub heating: f64,
+    pub cooling: f64,
+}
+
+// the mean is the average of the high and low, like NWS climate reports use
+pub fn degree_days(high_f: f64, low_f: f64) -> DegreeDays {
+    let mean = (high_f + low_f) / 2.0;
+
+    DegreeDays {
+        heating: (DEGREE_DAY_BASE_F - mean).max(0.0),
+        cooling: (mean - DEGREE_DAY_BASE_F).max(0.0),
+    }
+}
+
+// frost can form on plants with the air a few degrees above freezing
+pub const FROST_MAX_F: f64 = 36.0;
+pub const FREEZE_MAX_F: f64 = 32.0;
+
+// april through october in the north and october through april in the south. tropical gardens
+// grow all year, so a rare frost there always matters
+pub fn growing_season(date: NaiveDate, latitude: f64) -> bool {
+    if latitude.abs() < TROPICS_LATITUDE {
+        return true;
+    }
+
+    match latitude >= 0.0 {
+        true => (4..=10).contains(&date.month()),
+        false => date.month() >= 10 || date.month() <= 4,
+    }
+}
+
+// gusts are only in the text forecast, like "with gusts as high as 40 mph" or "64 km/h" in si
+pub fn wind_gust_mph(detailed_forecast: &str) -> Option<f64> {
+    let start = detailed_forecast.find("gusts")?;
+    let mut words = detailed_forecast[start..].split_whitespace();
+
+    let gust = words.find_map(|word| word.parse::<f64>().ok())?;
+
+    match words.next() {
+        Some(unit) if unit.starts_with("km/h") => Some(gust / 1.609344),
+        _ => Some(gust),
+    }
+}
+
+// accumulations are text like "1 to 3 inches" or "around an inch", or "2 to 4 cm" in si.
+// only numbers with a unit are read
+pub fn accumulation_inches(accumulation: &str) -> Option<f64> {
+    let scale = match (accumulation.contains("inch"), accumulation.contains(" cm")) {
+        (true, _) => 1.0,
+        (false, true) => 1.0 / 2.54,
+        (false, false) => return None,
+    };
+
+    accumulation
+        .split_whitespace()
+        .filter_map(|word| word.parse::<f64>().ok())
+        .max_by(|a, b| a.total_cmp(b))
+        .map(|accumulation| accumulation * scale)
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{geocode::Coordinates, geometry};
+
+// the map is redrawn every thursday, this is always the latest one
+const DROUGHT_MAP_URL: &str = "https://droughtmonitor.unl.edu/data/json/usdm_current.json";
+
+#[derive(Debug, Error)]
+pub enum DroughtError {
+    #[error("error requesting droughtmonitor.unl.edu: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding droughtmonitor.unl.edu response: {0}")]
+    Decode(#[source] reqwest::Error),
+}
+
+// drought map struct, the GeoJSON with one feature per drought category
+#[derive(Debug, Clone, Deserialize)]
+struct DroughtMap {
+    #[serde(default)]
+    features: Vec<DroughtFeature>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DroughtFeature {
+    geometry: Option<serde_json::Value>,
+    properties: DroughtProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DroughtProperties {
+    #[serde(rename = "DM")]
+    level: u8,
+}
+
+// drought area struct, one category's polygons
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroughtArea {
+    pub level: u8,
+    pub geometry: serde_json::Value,
+}
+
+// drought category struct, D0 (abnormally dry) through D4 (exceptional drought)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroughtCategory {
+    pub level: u8,
+    pub category: String,
+    pub description: String,
+}
+
+// drought lookup struct, what the map says about one point. category is none outside every area
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroughtLookup {
+    pub category: Option<DroughtCategory>,
+}
+
+impl DroughtCategory {
+    // D0 is a dry spell that could lead to drought, not a drought yet
+    pub fn is_drought(&self) -> bool {
+        self.level >= 1
+    }
+}
+
+fn description(level: u8) -> Option<&'static str> {
+    match level {
+        0 => Some("Abnormally Dry"),
+        1 => Some("Moderate Drought"),
+        2 => Some("Severe Drought"),
+        3 => Some("Extreme Drought"),
+        4 => Some("Exceptional Drought"),
+        _ => None,
+    }
+}
+
+pub async fn get_drought_map(client: reqwest::Client) -> Result<Vec<DroughtArea>, DroughtError> {
+    let response_result = client
+        .get(DROUGHT_MAP_URL)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(DroughtError::Request(e)),
+    };
+
+    let map = match response.json::<DroughtMap>().await {
+        Ok(map) => map,
+        Err(e) => return Err(DroughtError::Decode(e)),
+    };
+
+    Ok(map
+        .features
+        .into_iter()
+        .filter_map(|feature| {
+            Some(DroughtArea {
+                level: feature.properties.level,
+                geometry: feature.geometry?,
+            })
+        })
+        .collect())
+}
+
+// categories are drawn inside each other, so the point is in the highest one whose polygon contains it
+pub fn lookup(areas: &[DroughtArea], coordinates: Coordinates) -> DroughtLookup {
+    let category = areas
+        .iter()
+        .filter(|area| {
+            geometry::from_geojson(&area.geometry)
+                .is_some_and(|polygons| geometry::contains(&polygons, coordinates))
+        })
+        .max_by_key(|area| area.level)
+        .and_then(|area| {
+            Some(DroughtCategory {
+                level: area.level,
+                category: format!("D{}", area.level),
+                description: description(area.level)?.to_string(),
+            })
+        });
+
+    DroughtLookup { category }
+}
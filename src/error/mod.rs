@@ -0,0 +1,268 @@
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::error;
+
+use crate::{
+    airnow::AirNowError,
+    assistant::AssistantError,
+    aviation::AviationError,
+    card::CardError,
+    geocode::GeocodeError,
+    gridpoint::GridpointError,
+    llm::{self, LlmError},
+    nhc::NhcError,
+    notify::NotifyError,
+    nwps::NwpsError,
+    nws::NwsError,
+    prompts::PromptError,
+    storage::StorageError,
+    tides::TidesError,
+    tts::TtsError,
+};
+
+lazy_static! {
+    pub static ref ERRORS_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "errors_total",
+            "errors returned to clients by failure class"
+        ),
+        &["source", "kind"]
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("request took longer than {0} seconds")]
+    DeadlineExceeded(u64),
+    #[error("geocode failed: {0}")]
+    GeocodeFailed(#[from] GeocodeError),
+    #[error("nws unavailable: {0}")]
+    NwsUnavailable(#[from] NwsError),
+    #[error("aviation weather failed: {0}")]
+    AviationFailed(#[from] AviationError),
+    #[error("air quality failed: {0}")]
+    AirQualityFailed(#[from] AirNowError),
+    #[error("river gauges failed: {0}")]
+    RiversFailed(#[from] NwpsError),
+    #[error("tide predictions failed: {0}")]
+    TidesFailed(#[from] TidesError),
+    #[error("tropical outlook failed: {0}")]
+    NhcFailed(#[from] NhcError),
+    #[error("gridpoint data failed: {0}")]
+    GridpointFailed(#[from] GridpointError),
+    #[error("llm failed: {0}")]
+    LlmFailed(#[from] LlmError),
+    #[error("prompt failed: {0}")]
+    PromptFailed(#[from] PromptError),
+    #[error("tts failed: {0}")]
+    TtsFailed(#[from] TtsError),
+    #[error("card failed: {0}")]
+    CardFailed(#[from] CardError),
+    #[error("storage failed: {0}")]
+    StorageFailed(#[from] StorageError),
+    #[error("notify failed: {0}")]
+    NotifyFailed(#[from] NotifyError),
+    #[error("assistant request failed: {0}")]
+    AssistantFailed(#[from] AssistantError),
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+    status: u16,
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::DeadlineExceeded(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::GeocodeFailed(GeocodeError::NoMatches(_)) => StatusCode::NOT_FOUND,
+            AppError::GeocodeFailed(GeocodeError::CircuitOpen(_)) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            AppError::GeocodeFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::NwsUnavailable(NwsError::PointNotFound) => StatusCode::NOT_FOUND,
+            AppError::NwsUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::GridpointFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::AviationFailed(AviationError::NoReport(_)) => StatusCode::NOT_FOUND,
+            AppError::AviationFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::NhcFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::TidesFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::RiversFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::AirQualityFailed(AirNowError::NotConfigured) => StatusCode::NOT_IMPLEMENTED,
+            AppError::AirQualityFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::LlmFailed(LlmError::CircuitOpen(_)) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::LlmFailed(LlmError::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::LlmFailed(LlmError::QueueFull) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::LlmFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::PromptFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::TtsFailed(TtsError::UnsupportedFormat(_)) => StatusCode::BAD_REQUEST,
+            AppError::TtsFailed(TtsError::NotConfigured) => StatusCode::NOT_IMPLEMENTED,
+            AppError::TtsFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::CardFailed(CardError::NotConfigured) => StatusCode::NOT_IMPLEMENTED,
+            AppError::CardFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::StorageFailed(StorageError::NotConfigured) => StatusCode::NOT_IMPLEMENTED,
+            AppError::StorageFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::NotifyFailed(NotifyError::NotConfigured) => StatusCode::NOT_IMPLEMENTED,
+            AppError::NotifyFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::AssistantFailed(AssistantError::NotConfigured) => StatusCode::NOT_IMPLEMENTED,
+            // amazon expects a 400 for every request that fails verification
+            AppError::AssistantFailed(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    // source and kind labels for metrics and logs
+    pub fn class(&self) -> (&'static str, &'static str) {
+        match self {
+            AppError::BadRequest(_) => ("request", "bad_request"),
+            AppError::NotFound(_) => ("request", "not_found"),
+            AppError::Forbidden(_) => ("request", "forbidden"),
+            AppError::DeadlineExceeded(_) => ("request", "deadline_exceeded"),
+            AppError::GeocodeFailed(e) => ("geocode", e.kind()),
+            AppError::NwsUnavailable(e) => ("nws", e.kind()),
+            AppError::GridpointFailed(e) => ("gridpoint", e.kind()),
+            AppError::AviationFailed(e) => ("aviation", e.kind()),
+            AppError::NhcFailed(e) => ("nhc", e.kind()),
+            AppError::TidesFailed(e) => ("tides", e.kind()),
+            AppError::RiversFailed(e) => ("nwps", e.kind()),
+            AppError::AirQualityFailed(e) => ("airnow", e.kind()),
+            AppError::LlmFailed(e) => ("llm", e.kind()),
+            AppError::PromptFailed(e) => ("prompt", e.kind()),
+            AppError::TtsFailed(e) => ("tts", e.kind()),
+            AppError::CardFailed(e) => ("card", e.kind()),
+            AppError::StorageFailed(e) => ("storage", e.kind()),
+            AppError::NotifyFailed(e) => ("notify", e.kind()),
+            AppError::AssistantFailed(e) => ("assistant", e.kind()),
+        }
+    }
+
+    // message returned to the client, upstream details are only logged
+    pub fn public_message(&self) -> String {
+        match self {
+            AppError::BadRequest(message) => message.to_owned(),
+            AppError::NotFound(message) => message.to_owned(),
+            AppError::Forbidden(message) => message.to_owned(),
+            AppError::DeadlineExceeded(_) => "request took too long".to_string(),
+            AppError::GeocodeFailed(GeocodeError::NoMatches(_)) => {
+                "no address matches found".to_string()
+            }
+            AppError::GeocodeFailed(GeocodeError::CircuitOpen(_)) => {
+                "the geocoder is unavailable, try again later".to_string()
+            }
+            AppError::GeocodeFailed(_) => "error geocoding address".to_string(),
+            AppError::NwsUnavailable(NwsError::PointNotFound) => {
+                "no NWS forecast is available for this location".to_string()
+            }
+            AppError::NwsUnavailable(NwsError::CircuitOpen(_)) => {
+                "NWS is unavailable, try again later".to_string()
+            }
+            AppError::NwsUnavailable(_) => "error getting forecast from NWS".to_string(),
+            AppError::GridpointFailed(_) => "error reading gridpoint data from NWS".to_string(),
+            AppError::AviationFailed(AviationError::NoReport(station)) => {
+                format!("no METAR is available for {}", station)
+            }
+            AppError::AviationFailed(_) => "error getting aviation weather".to_string(),
+            AppError::NhcFailed(_) => "error getting tropical outlook from NHC".to_string(),
+            AppError::TidesFailed(_) => "error getting tide predictions".to_string(),
+            AppError::RiversFailed(_) => "error getting river gauges from NWPS".to_string(),
+            AppError::AirQualityFailed(AirNowError::NotConfigured) => {
+                "air quality is not enabled".to_string()
+            }
+            AppError::AirQualityFailed(_) => "error getting air quality from AirNow".to_string(),
+            AppError::LlmFailed(LlmError::CircuitOpen(_)) => {
+                "summaries are unavailable, try again later".to_string()
+            }
+            AppError::LlmFailed(LlmError::Timeout(_)) => "summary took too long".to_string(),
+            AppError::LlmFailed(LlmError::QueueFull) => {
+                "too many summaries are being generated, try again later".to_string()
+            }
+            AppError::LlmFailed(_) => "error generating summary".to_string(),
+            AppError::PromptFailed(_) => "error building prompt".to_string(),
+            AppError::TtsFailed(TtsError::UnsupportedFormat(format)) => {
+                format!("{} audio is not supported", format)
+            }
+            AppError::TtsFailed(TtsError::NotConfigured) => {
+                "audio summaries are not enabled".to_string()
+            }
+            AppError::TtsFailed(_) => "error generating audio".to_string(),
+            AppError::CardFailed(CardError::NotConfigured) => {
+                "forecast cards are not enabled".to_string()
+            }
+            AppError::CardFailed(_) => "error drawing forecast card".to_string(),
+            AppError::StorageFailed(StorageError::NotConfigured) => {
+                "this feature needs a database, which is not configured".to_string()
+            }
+            AppError::StorageFailed(_) => "error reading stored summary".to_string(),
+            AppError::NotifyFailed(NotifyError::NotConfigured) => {
+                "sms replies are not enabled".to_string()
+            }
+            AppError::NotifyFailed(_) => "error sending notification".to_string(),
+            AppError::AssistantFailed(AssistantError::NotConfigured) => {
+                "assistant requests are not enabled".to_string()
+            }
+            AppError::AssistantFailed(AssistantError::Parse(_)) => {
+                "invalid assistant request".to_string()
+            }
+            AppError::AssistantFailed(_) => "request verification failed".to_string(),
+        }
+    }
+
+    // seconds until an upstream that's failing fast gets tried again
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            AppError::GeocodeFailed(GeocodeError::CircuitOpen(e))
+            | AppError::NwsUnavailable(NwsError::CircuitOpen(e))
+            | AppError::LlmFailed(LlmError::CircuitOpen(e)) => Some(e.retry_after_seconds()),
+            AppError::LlmFailed(LlmError::QueueFull) => Some(llm::QUEUE_FULL_RETRY_AFTER_SECONDS),
+            _ => None,
+        }
+    }
+
+    // counts the error and logs server side failures
+    pub fn record(&self) {
+        let (source, kind) = self.class();
+
+        ERRORS_COUNTER.with_label_values(&[source, kind]).inc();
+
+        if self.status_code().is_server_error() {
+            error!(source = source, kind = kind, "{}", self);
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+
+        self.record();
+
+        let body = ErrorBody {
+            error: self.public_message(),
+            status: status.as_u16(),
+        };
+
+        match self.retry_after() {
+            Some(seconds) => {
+                (status, [(RETRY_AFTER, seconds.to_string())], Json(body)).into_response()
+            }
+            None => (status, Json(body)).into_response(),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{derived, nws::Period};
+
+// NWS says chance at 30 to 50 percent and likely at 60 to 70
+const WET_PERCENT: i64 = 30;
+const LIKELY_PERCENT: i64 = 60;
+
+// the strongest wind under each speed, in mph, is light, moderate, and strong
+const LIGHT_WIND_MPH: f64 = 10.0;
+const MODERATE_WIND_MPH: f64 = 20.0;
+const STRONG_WIND_MPH: f64 = 30.0;
+
+// the order kinds are listed in when more than one is expected
+const KINDS: [&str; 3] = ["rain", "snow", "thunderstorms"];
+
+// today, tonight, and tomorrow, about what NWS puts in a short text forecast
+const RAW_PERIODS: usize = 3;
+
+// level enum, how far a forecast summary fell back. full is generated for this forecast, cached
+// is the last one generated for the location even if the forecast changed since, template is
+// built from the periods, and periods is the NWS text itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Full,
+    Cached,
+    Template,
+    Periods,
+}
+
+impl Level {
+    // the levels the llm can fall back to, full is always tried first
+    pub fn parse(name: &str) -> Option<Level> {
+        match name {
+            "cached" => Some(Level::Cached),
+            "template" => Some(Level::Template),
+            "periods" => Some(Level::Periods),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Full => "full",
+            Level::Cached => "cached",
+            Level::Template => "template",
+            Level::Periods => "periods",
+        }
+    }
+}
+
+// a one sentence summary built from the periods without the llm, like "Highs 61–74F, lows 45–52F,
+// chance of rain Friday–Saturday, light SW winds." none when there are no periods to build it from
+pub fn summary(periods: &[Period]) -> Option<String> {
+    if periods.is_empty() {
+        return None;
+    }
+
+    let unit = &periods[0].temperature_unit;
+    let highs: Vec<i64> = periods
+        .iter()
+        .filter(|period| period.is_daytime)
+        .map(|period| period.temperature)
+        .collect();
+    let lows: Vec<i64> = periods
+        .iter()
+        .filter(|period| !period.is_daytime)
+        .map(|period| period.temperature)
+        .collect();
+
+    let parts: Vec<String> = [
+        temperatures("highs", &highs, unit),
+        temperatures("lows", &lows, unit),
+        Some(precipitation(periods)),
+        wind(periods),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let sentence = parts.join(", ");
+    let mut chars = sentence.chars();
+
+    chars
+        .next()
+        .map(|first| format!("{}{}.", first.to_uppercase(), chars.as_str()))
+}
+
+// the first few periods as NWS wrote them, like "Today: Sunny, with a high near 62. Tonight: ..."
+pub fn periods(periods: &[Period]) -> Option<String> {
+    let sentences: Vec<String> = periods
+        .iter()
+        .take(RAW_PERIODS)
+        .map(|period| format!("{}: {}", period.name, period.detailed_forecast))
+        .collect();
+
+    match sentences.is_empty() {
+        true => None,
+        false => Some(sentences.join(" ")),
+    }
+}
+
+fn temperatures(label: &str, temperatures: &[i64], unit: &str) -> Option<String> {
+    let min = temperatures.iter().min()?;
+    let max = temperatures.iter().max()?;
+
+    match min == max {
+        true => Some(format!("{} near {}{}", label, min, unit)),
+        false => Some(format!("{} {}–{}{}", label, min, max, unit)),
+    }
+}
+
+fn chance(period: &Period) -> i64 {
+    period.probability_of_precipitation.value.unwrap_or(0)
+}
+
+// what the short forecast says is falling, rain when it only says showers or precipitation
+fn kind(short_forecast: &str) -> &'static str {
+    let short_forecast = short_forecast.to_lowercase();
+
+    if short_forecast.contains("thunder") {
+        return "thunderstorms";
+    }
+
+    if short_forecast.contains("snow") || short_forecast.contains("flurries") {
+        return "snow";
+    }
+
+    "rain"
+}
+
+fn precipitation(periods: &[Period]) -> String {
+    let wet: Vec<&Period> = periods
+        .iter()
+        .filter(|period| chance(period) >= WET_PERCENT)
+        .collect();
+
+    let most_likely = match wet.iter().map(|period| chance(period)).max() {
+        Some(most_likely) => most_likely,
+        None => return "dry".to_string(),
+    };
+
+    let kinds: Vec<&str> = KINDS
+        .into_iter()
+        .filter(|kind_name| {
+            wet.iter()
+                .any(|period| kind(&period.short_forecast) == *kind_name)
+        })
+        .collect();
+    let kinds = kinds.join(" and ");
+
+    let mut dates: Vec<NaiveDate> = wet
+        .iter()
+        .map(|period| period.start_time.date_naive())
+        .collect();
+    dates.dedup();
+
+    match most_likely >= LIKELY_PERCENT {
+        true => format!("{} likely {}", kinds, days(&dates)),
+        false => format!("chance of {} {}", kinds, days(&dates)),
+    }
+}
+
+// consecutive days are written as a range, like Friday–Saturday and Monday
+fn days(dates: &[NaiveDate]) -> String {
+    let mut runs: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+
+    for date in dates {
+        match runs.last_mut() {
+            Some((_, end)) if end.checked_add_days(Days::new(1)) == Some(*date) => *end = *date,
+            _ => runs.push((*date, *date)),
+        }
+    }
+
+    let runs: Vec<String> = runs
+        .iter()
+        .map(|(start, end)| match start == end {
+            true => start.format("%A").to_string(),
+            false => format!("{}–{}", start.format("%A"), end.format("%A")),
+        })
+        .collect();
+
+    runs.join(" and ")
+}
+
+// described by the strongest wind, from the direction it blows from most often
+fn wind(periods: &[Period]) -> Option<String> {
+    let strongest = periods
+        .iter()
+        .filter_map(|period| derived::wind_speed_mph(&period.wind_speed))
+        .max_by(|a, b| a.total_cmp(b))?;
+
+    let strength = match strongest {
+        mph if mph < LIGHT_WIND_MPH => "light",
+        mph if mph < MODERATE_WIND_MPH => "moderate",
+        mph if mph < STRONG_WIND_MPH => "strong",
+        _ => "very strong",
+    };
+
+    let mut directions: Vec<(&str, usize)> = Vec::new();
+
+    for period in periods {
+        if period.wind_direction.is_empty() {
+            continue;
+        }
+
+        match directions
+            .iter_mut()
+            .find(|(direction, _)| *direction == period.wind_direction)
+        {
+            Some((_, count)) => *count += 1,
+            None => directions.push((&period.wind_direction, 1)),
+        }
+    }
+
+    // the first one seen wins a tie
+    let direction = directions
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(direction, _)| *direction);
+
+    match direction {
+        Some(direction) => Some(format!("{} {} winds", strength, direction)),
+        None => Some(format!("{} winds", strength)),
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::render;
+
+// daily entries kept per location, older ones fall off the end of the feed
+const MAX_FEED_ENTRIES: usize = 30;
+
+// feed location struct, a slug and the request parameters used to look it up
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedLocation {
+    pub slug: String,
+    pub params: HashMap<String, String>,
+}
+
+// feed entry struct, the last summary generated on a given day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedEntry {
+    pub date: String,
+    pub updated: String,
+    pub summary: String,
+}
+
+// entries look like slug=address or slug=lat,lon and are separated by semicolons
+pub fn parse_locations(locations: &str) -> Vec<FeedLocation> {
+    locations
+        .split(';')
+        .map(|location| location.trim())
+        .filter(|location| !location.is_empty())
+        .map(|location| {
+            let (slug, value) = location
+                .split_once('=')
+                .unwrap_or_else(|| panic!("{} is not a valid feed location", location));
+
+            let slug = slug.trim();
+
+            if slug.is_empty()
+                || !slug
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                panic!("{} is not a valid feed slug", slug);
+            }
+
+            let value = value.trim();
+
+            let params = match value.split_once(',') {
+                Some((lat, lon))
+                    if lat.trim().parse::<f64>().is_ok() && lon.trim().parse::<f64>().is_ok() =>
+                {
+                    HashMap::from([
+                        ("lat".to_string(), lat.trim().to_string()),
+                        ("lon".to_string(), lon.trim().to_string()),
+                    ])
+                }
+                _ => HashMap::from([("address".to_string(), value.to_string())]),
+            };
+
+            FeedLocation {
+                slug: slug.to_string(),
+                params,
+            }
+        })
+        .collect()
+}
+
+// feed store, the entries for each configured location, written to disk when a directory is set
+pub struct FeedStore {
+    locations: Vec<FeedLocation>,
+    dir: Option<PathBuf>,
+    entries: RwLock<HashMap<String, Vec<FeedEntry>>>,
+}
+
+impl FeedStore {
+    pub fn load(locations: Vec<FeedLocation>, dir: Option<String>) -> Self {
+        let dir = dir.map(PathBuf::from);
+        let mut entries = HashMap::new();
+
+        if let Some(dir) = &dir {
+            for location in &locations {
+                if let Some(location_entries) = read(&path(dir, &location.slug)) {
+                    entries.insert(location.slug.to_owned(), location_entries);
+                }
+            }
+        }
+
+        Self {
+            locations,
+            dir,
+            entries: RwLock::new(entries),
+        }
+    }
+
+    pub fn locations(&self) -> &[FeedLocation] {
+        &self.locations
+    }
+
+    pub fn has_location(&self, slug: &str) -> bool {
+        self.locations.iter().any(|location| location.slug == slug)
+    }
+
+    // newest first
+    pub fn entries(&self, slug: &str) -> Vec<FeedEntry> {
+        self.entries
+            .read()
+            .unwrap()
+            .get(slug)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // replaces the entry for the same day, so each day keeps its latest summary
+    pub fn record(&self, slug: &str, entry: FeedEntry) {
+        let location_entries = {
+            let mut entries = self.entries.write().unwrap();
+            let location_entries = entries.entry(slug.to_string()).or_default();
+
+            location_entries.retain(|existing| existing.date != entry.date);
+            location_entries.push(entry);
+            location_entries.sort_by(|a, b| b.date.cmp(&a.date));
+            location_entries.truncate(MAX_FEED_ENTRIES);
+
+            location_entries.clone()
+        };
+
+        if let Some(dir) = &self.dir {
+            write(dir, slug, &location_entries);
+        }
+    }
+}
+
+fn path(dir: &Path, slug: &str) -> PathBuf {
+    dir.join(format!("{}.json", slug))
+}
+
+fn read(path: &Path) -> Option<Vec<FeedEntry>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return None,
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(entries) => {
+            info!("loaded feed entries from {}", path.display());
+            Some(entries)
+        }
+        Err(e) => {
+            warn!("error parsing feed file {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn write(dir: &Path, slug: &str, entries: &[FeedEntry]) {
+    if let Err(e) = fs::create_dir_all(dir) {
+        warn!("error creating feed directory {}: {}", dir.display(), e);
+        return;
+    }
+
+    let path = path(dir, slug);
+
+    if let Err(e) = fs::write(&path, serde_json::to_string(entries).unwrap()) {
+        warn!("error writing feed file {}: {}", path.display(), e);
+    }
+}
+
+pub fn atom(slug: &str, entries: &[FeedEntry]) -> String {
+    let updated = entries
+        .first()
+        .map(|entry| entry.updated.as_str())
+        .unwrap_or("1970-01-01T00:00:00Z");
+
+    let mut feed = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+<title>Forecast for {slug}</title>
+<id>urn:nws-forecast-summarizer:feed:{slug}</id>
+<updated>{updated}</updated>
+<author><name>nws-forecast-summarizer</name></author>
+",
+        slug = render::escape(slug),
+        updated = render::escape(updated),
+    );
+
+    for entry in entries {
+        feed.push_str(&format!(
+            "<entry>
+<title>Forecast for {slug} on {date}</title>
+<id>urn:nws-forecast-summarizer:feed:{slug}:{date}</id>
+<updated>{updated}</updated>
+<summary>{summary}</summary>
+</entry>
+",
+            slug = render::escape(slug),
+            date = render::escape(&entry.date),
+            updated = render::escape(&entry.updated),
+            summary = render::escape(&entry.summary),
+        ));
+    }
+
+    feed.push_str("</feed>\n");
+
+    feed
+}
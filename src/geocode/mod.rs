@@ -0,0 +1,135 @@
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    breaker::{self, CircuitOpen},
+    http_util,
+};
+
+#[derive(Debug, Error)]
+pub enum GeocodeError {
+    #[error("error requesting census geocoder: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding census geocoder response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("no address matches found for {0}")]
+    NoMatches(String),
+    #[error("error parsing coordinates: {0}")]
+    InvalidCoordinates(#[from] serde_json::Error),
+    #[error(transparent)]
+    CircuitOpen(#[from] CircuitOpen),
+}
+
+impl GeocodeError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GeocodeError::Request(_) => "request",
+            GeocodeError::Decode(_) => "decode",
+            GeocodeError::NoMatches(_) => "no_matches",
+            GeocodeError::InvalidCoordinates(_) => "invalid_coordinates",
+            GeocodeError::CircuitOpen(_) => "circuit_open",
+        }
+    }
+}
+
+// coordinate struct
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinates {
+    #[serde(alias = "y")]
+    pub latitude: f64,
+    #[serde(alias = "x")]
+    pub longitude: f64,
+}
+
+// location struct, the resolved address (if geocoded) and its coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub address: Option<String>,
+    pub coordinates: Coordinates,
+}
+
+const EARTH_RADIUS_MILES: f64 = 3958.8;
+
+// great circle distance with the haversine formula
+pub fn distance_miles(a: Coordinates, b: Coordinates) -> f64 {
+    let latitude_delta = (b.latitude - a.latitude).to_radians();
+    let longitude_delta = (b.longitude - a.longitude).to_radians();
+
+    let h = (latitude_delta / 2.0).sin().powi(2)
+        + a.latitude.to_radians().cos()
+            * b.latitude.to_radians().cos()
+            * (longitude_delta / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_MILES * h.sqrt().asin()
+}
+
+// the closest of the stations and its distance in miles, for any station with coordinates
+pub fn nearest<T>(
+    stations: &[T],
+    coordinates: Coordinates,
+    station_coordinates: fn(&T) -> Coordinates,
+) -> Option<(&T, f64)> {
+    stations
+        .iter()
+        .map(|station| {
+            (
+                station,
+                distance_miles(coordinates, station_coordinates(station)),
+            )
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+// addresses are geocoded with the Census Bureau geocoder
+pub const GEOCODER: &str = "census";
+
+pub async fn geocode_address(
+    client: reqwest::Client,
+    address: String,
+) -> Result<Location, GeocodeError> {
+    let census_geocode_url = format!(
+        "https://geocoding.geo.census.gov/geocoder/locations/onelineaddress?address={}&benchmark=2020&format=json",
+        urlencoding::encode(&address)
+    );
+
+    breaker::CENSUS.check()?;
+
+    let response_result = http_util::send(
+        &breaker::CENSUS,
+        client.get(census_geocode_url),
+        RequestBuilder::send,
+    )
+    .await;
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(GeocodeError::Request(e)),
+    };
+
+    let body_json_result = response.json::<serde_json::Value>().await;
+
+    let body_json = match body_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(GeocodeError::Decode(e)),
+    };
+
+    let address_matches_option = body_json["result"]["addressMatches"].as_array();
+
+    let address_match = match address_matches_option.and_then(|matches| matches.first()) {
+        Some(address_match) => address_match,
+        None => return Err(GeocodeError::NoMatches(address)),
+    };
+
+    let coordinates: Coordinates = serde_json::from_value(address_match["coordinates"].clone())?;
+
+    let matched_address = address_match["matchedAddress"]
+        .as_str()
+        .map(|matched_address| matched_address.to_string())
+        .unwrap_or(address);
+
+    Ok(Location {
+        address: Some(matched_address),
+        coordinates,
+    })
+}
@@ -0,0 +1,98 @@
+use geo::{Coord, Intersects, LineString, MultiPolygon, Point, Polygon};
+
+use crate::geocode::Coordinates;
+
+// alerts without a polygon are issued for whole zones or counties, which NWS only returns for points inside them.
+// the GeoJSON geometry is used when there is one, the CAP polygons otherwise
+pub fn applies_to_point(
+    geometry: Option<&serde_json::Value>,
+    cap_polygons: &[Vec<Coordinates>],
+    coordinates: Coordinates,
+) -> bool {
+    let area = match geometry.and_then(from_geojson) {
+        Some(area) => Some(area),
+        None => from_cap(cap_polygons),
+    };
+
+    match area {
+        Some(area) => contains(&area, coordinates),
+        None => true,
+    }
+}
+
+// points on the boundary count as inside, a warning drawn along a river applies to the riverbank
+pub fn contains(area: &MultiPolygon<f64>, coordinates: Coordinates) -> bool {
+    area.intersects(&Point::new(coordinates.longitude, coordinates.latitude))
+}
+
+// Polygon and MultiPolygon geometries, anything else has no area to test against
+pub fn from_geojson(geometry: &serde_json::Value) -> Option<MultiPolygon<f64>> {
+    let coordinates = geometry.get("coordinates")?;
+
+    match geometry.get("type")?.as_str()? {
+        "Polygon" => Some(MultiPolygon::new(vec![geojson_polygon(coordinates)?])),
+        "MultiPolygon" => Some(MultiPolygon::new(
+            coordinates
+                .as_array()?
+                .iter()
+                .map(geojson_polygon)
+                .collect::<Option<Vec<Polygon<f64>>>>()?,
+        )),
+        _ => None,
+    }
+}
+
+pub fn from_cap(polygons: &[Vec<Coordinates>]) -> Option<MultiPolygon<f64>> {
+    if polygons.is_empty() {
+        return None;
+    }
+
+    Some(MultiPolygon::new(
+        polygons
+            .iter()
+            .map(|points| {
+                let exterior: Vec<Coord<f64>> = points
+                    .iter()
+                    .map(|point| Coord {
+                        x: point.longitude,
+                        y: point.latitude,
+                    })
+                    .collect();
+
+                Polygon::new(LineString::new(exterior), Vec::new())
+            })
+            .collect(),
+    ))
+}
+
+// the first ring is the outline, any others are holes cut out of it
+fn geojson_polygon(rings: &serde_json::Value) -> Option<Polygon<f64>> {
+    let mut rings = rings
+        .as_array()?
+        .iter()
+        .map(geojson_ring)
+        .collect::<Option<Vec<LineString<f64>>>>()?
+        .into_iter();
+
+    let exterior = rings.next()?;
+
+    Some(Polygon::new(exterior, rings.collect()))
+}
+
+// GeoJSON positions are longitude first
+fn geojson_ring(positions: &serde_json::Value) -> Option<LineString<f64>> {
+    let coordinates = positions
+        .as_array()?
+        .iter()
+        .map(|position| {
+            let position = position.as_array()?;
+
+            Some(Coord {
+                x: position.first()?.as_f64()?,
+                y: position.get(1)?.as_f64()?,
+            })
+        })
+        .collect::<Option<Vec<Coord<f64>>>>()?;
+
+    Some(LineString::new(coordinates))
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GridpointError {
+    #[error("error parsing gridpoint data: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("invalid gridpoint time {0}")]
+    InvalidTime(String),
+}
+
+impl GridpointError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GridpointError::Parse(_) => "parse",
+            GridpointError::InvalidTime(_) => "invalid_time",
+        }
+    }
+}
+
+// raw grid struct, the forecastGridData layers this service reads, each value covers an interval
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawGrid {
+    update_time: String,
+    #[serde(default)]
+    temperature: RawLayer,
+    #[serde(default)]
+    sky_cover: RawLayer,
+    #[serde(default)]
+    quantitative_precipitation: RawLayer,
+    #[serde(default)]
+    wind_gust: RawLayer,
+    #[serde(default)]
+    snowfall_amount: RawLayer,
+}
+
+#[derive(Default, Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLayer {
+    #[serde(default)]
+    uom: String,
+    #[serde(default)]
+    values: Vec<RawValue>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawValue {
+    valid_time: String,
+    value: Option<f64>,
+}
+
+// grid data struct, the quantitative forecast layers for a gridpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridData {
+    pub updated: DateTime<Utc>,
+    pub temperature: GridLayer,
+    pub sky_cover: GridLayer,
+    pub quantitative_precipitation: GridLayer,
+    pub wind_gust: GridLayer,
+    // defaulted so grid data cached before it was read still deserializes
+    #[serde(default)]
+    pub snowfall_amount: GridLayer,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridLayer {
+    pub unit_code: String,
+    pub values: Vec<GridValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridValue {
+    #[serde(flatten)]
+    pub time: TimeRange,
+    pub value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+// takes the properties object of a forecastGridData response
+pub fn parse(properties: &serde_json::Value) -> Result<GridData, GridpointError> {
+    let raw = RawGrid::deserialize(properties)?;
+
+    let updated = match DateTime::parse_from_rfc3339(&raw.update_time) {
+        Ok(updated) => updated.to_utc(),
+        Err(_) => return Err(GridpointError::InvalidTime(raw.update_time)),
+    };
+
+    Ok(GridData {
+        updated,
+        temperature: layer(raw.temperature)?,
+        sky_cover: layer(raw.sky_cover)?,
+        quantitative_precipitation: layer(raw.quantitative_precipitation)?,
+        wind_gust: layer(raw.wind_gust)?,
+        snowfall_amount: layer(raw.snowfall_amount)?,
+    })
+}
+
+fn layer(raw: RawLayer) -> Result<GridLayer, GridpointError> {
+    let values = raw
+        .values
+        .into_iter()
+        .map(|value| {
+            Ok(GridValue {
+                time: parse_valid_time(&value.valid_time)?,
+                value: value.value,
+            })
+        })
+        .collect::<Result<Vec<GridValue>, GridpointError>>()?;
+
+    Ok(GridLayer {
+        unit_code: raw.uom,
+        values,
+    })
+}
+
+// the amount of an accumulating layer, like snowfall, that falls within the range.
+// values are spread evenly over their interval, so one partly inside the range counts in proportion
+pub fn total(layer: &GridLayer, range: TimeRange) -> Option<f64> {
+    let mut total = 0.0;
+    let mut overlapping = false;
+
+    for value in &layer.values {
+        let start = value.time.start.max(range.start);
+        let end = value.time.end.min(range.end);
+
+        if end <= start {
+            continue;
+        }
+
+        overlapping = true;
+
+        let interval = (value.time.end - value.time.start).num_seconds() as f64;
+        let overlap = (end - start).num_seconds() as f64;
+
+        if let (Some(amount), true) = (value.value, interval > 0.0) {
+            total += amount * overlap / interval;
+        }
+    }
+
+    overlapping.then_some(total)
+}
+
+// a start time and the ISO-8601 duration it lasts, like 2026-10-14T15:00:00+00:00/PT3H
+pub fn parse_valid_time(valid_time: &str) -> Result<TimeRange, GridpointError> {
+    let invalid = || GridpointError::InvalidTime(valid_time.to_string());
+
+    let (start, duration) = match valid_time.split_once('/') {
+        Some((start, duration)) => (start, duration),
+        None => return Err(invalid()),
+    };
+
+    let start = match DateTime::parse_from_rfc3339(start) {
+        Ok(start) => start.to_utc(),
+        Err(_) => return Err(invalid()),
+    };
+
+    match parse_duration(duration).and_then(|duration| start.checked_add_signed(duration)) {
+        Some(end) => Ok(TimeRange { start, end }),
+        None => Err(invalid()),
+    }
+}
+
+// weeks through seconds, months and years have no fixed length and NWS doesn't use them
+fn parse_duration(duration: &str) -> Option<TimeDelta> {
+    let duration = duration.strip_prefix('P')?;
+
+    let (date, time) = match duration.split_once('T') {
+        Some((date, time)) => (date, time),
+        None => (duration, ""),
+    };
+
+    let parts: [(&str, &[(char, i64)]); 2] = [
+        (date, &[('W', 604_800), ('D', 86_400)]),
+        (time, &[('H', 3_600), ('M', 60), ('S', 1)]),
+    ];
+
+    let mut seconds: i64 = 0;
+    let mut components = 0;
+
+    for (part, units) in parts {
+        let mut number = String::new();
+
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+
+            let (_, scale) = units.iter().find(|(unit, _)| *unit == c)?;
+            let value = number.parse::<i64>().ok()?;
+
+            seconds = seconds.checked_add(value.checked_mul(*scale)?)?;
+            components += 1;
+            number.clear();
+        }
+
+        if !number.is_empty() {
+            return None;
+        }
+    }
+
+    match components {
+        0 => None,
+        _ => TimeDelta::try_seconds(seconds),
+    }
+}
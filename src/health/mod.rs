@@ -0,0 +1,255 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_gauge_vec, GaugeVec};
+use serde::Serialize;
+
+use crate::{
+    breaker::{self, BreakerStatus},
+    cache::JsonCache,
+    geocode,
+    llm::{LlmBackend, PoolStatus, WorkerPool},
+    nws,
+    storage::Storage,
+};
+
+lazy_static! {
+    pub static ref READINESS_CHECK_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "readiness_check_passing",
+            "whether a dependency passed its last readiness check"
+        ),
+        &["check"]
+    )
+    .unwrap();
+}
+
+// a check that takes longer than this fails, so a hung dependency can't hang the probe
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// every NWS endpoint is on this host
+const NWS_HOST: &str = "api.weather.gov:443";
+
+// check struct, one dependency's result and how long it took to find out
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+// readiness struct, ready only when every check passed
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Readiness {
+    // one line per check, like "ok    llm (2 ms)" or "FAIL  nws_dns (1 ms): error resolving ..."
+    pub fn report(&self) -> String {
+        let lines: Vec<String> = self
+            .checks
+            .iter()
+            .map(|check| {
+                let status = if check.ok { "ok  " } else { "FAIL" };
+
+                match &check.error {
+                    Some(error) => format!(
+                        "{}  {} ({} ms): {}",
+                        status, check.name, check.duration_ms, error
+                    ),
+                    None => format!("{}  {} ({} ms)", status, check.name, check.duration_ms),
+                }
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
+
+// cache status struct, entries is none when the backend is shared, like redis
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStatus {
+    pub name: &'static str,
+    pub entries: Option<u64>,
+}
+
+// status struct, the readiness checks along with the llm's queues, each upstream's breaker, and
+// how full the caches are, everything an operator would otherwise piece together from metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub ready: bool,
+    pub checks: Vec<Check>,
+    pub llm: PoolStatus,
+    pub upstreams: Vec<BreakerStatus>,
+    pub caches: Vec<CacheStatus>,
+    pub generated_at: DateTime<Utc>,
+}
+
+// the check's result along with what the call returned, for checks that feed the next one
+async fn timed<F, T, E>(name: &'static str, call: F) -> (Check, Option<T>)
+where
+    F: Future<Output = Result<T, E>>,
+    E: ToString,
+{
+    let started = Instant::now();
+
+    let (value, error) = match tokio::time::timeout(CHECK_TIMEOUT, call).await {
+        Ok(Ok(value)) => (Some(value), None),
+        Ok(Err(e)) => (None, Some(e.to_string())),
+        Err(_) => (
+            None,
+            Some(format!(
+                "timed out after {} seconds",
+                CHECK_TIMEOUT.as_secs()
+            )),
+        ),
+    };
+
+    READINESS_CHECK_GAUGE
+        .with_label_values(&[name])
+        .set(if error.is_none() { 1.0 } else { 0.0 });
+
+    let check = Check {
+        name,
+        ok: error.is_none(),
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+
+    (check, value)
+}
+
+async fn check<F, E>(name: &'static str, call: F) -> Check
+where
+    F: Future<Output = Result<(), E>>,
+    E: ToString,
+{
+    timed(name, call).await.0
+}
+
+async fn resolve_nws() -> Result<(), String> {
+    let mut addresses = match tokio::net::lookup_host(NWS_HOST).await {
+        Ok(addresses) => addresses,
+        Err(e) => return Err(format!("error resolving {}: {}", NWS_HOST, e)),
+    };
+
+    match addresses.next() {
+        Some(_) => Ok(()),
+        None => Err(format!("{} has no addresses", NWS_HOST)),
+    }
+}
+
+async fn ping_caches(caches: &[&JsonCache]) -> Result<(), String> {
+    for cache in caches {
+        if let Err(e) = cache.ping().await {
+            return Err(format!("{} cache: {}", cache.name(), e));
+        }
+    }
+
+    Ok(())
+}
+
+// the llm answering with its model, NWS resolving, and the caches and storage connected. the
+// checks run at once, storage is only checked when one is configured
+pub async fn readiness(
+    llm: &dyn LlmBackend,
+    caches: &[&JsonCache],
+    storage: Option<&dyn Storage>,
+) -> Readiness {
+    let storage = async {
+        match storage {
+            Some(storage) => Some(check("storage", storage.ping()).await),
+            None => None,
+        }
+    };
+
+    let (llm, nws, cache, storage) = tokio::join!(
+        check("llm", llm.ready()),
+        check("nws_dns", resolve_nws()),
+        check("cache", ping_caches(caches)),
+        storage,
+    );
+
+    let mut checks = vec![llm, nws, cache];
+    checks.extend(storage);
+
+    Readiness {
+        ready: checks.iter().all(|check| check.ok),
+        checks,
+    }
+}
+
+// readiness plus a geocode and points lookup for a known address, what --check runs before an
+// instance takes traffic. config errors stop startup before this with their own message
+pub async fn self_check(
+    llm: &dyn LlmBackend,
+    caches: &[&JsonCache],
+    storage: Option<&dyn Storage>,
+    client: reqwest::Client,
+    address: String,
+) -> Readiness {
+    let mut readiness = readiness(llm, caches, storage).await;
+
+    let (geocode, location) =
+        timed("geocode", geocode::geocode_address(client.clone(), address)).await;
+
+    let points = match location {
+        Some(location) => {
+            check("nws_points", async {
+                nws::get_point(client, location.coordinates)
+                    .await
+                    .map(|_| ())
+            })
+            .await
+        }
+        None => Check {
+            name: "nws_points",
+            ok: false,
+            error: Some("skipped, the address didn't geocode".to_string()),
+            duration_ms: 0,
+        },
+    };
+
+    readiness.checks.push(geocode);
+    readiness.checks.push(points);
+    readiness.ready = readiness.checks.iter().all(|check| check.ok);
+
+    readiness
+}
+
+pub async fn status(
+    llm: &WorkerPool,
+    caches: &[&JsonCache],
+    storage: Option<&dyn Storage>,
+) -> Status {
+    let readiness = readiness(llm, caches, storage).await;
+    let mut cache_statuses = Vec::new();
+
+    for cache in caches {
+        cache_statuses.push(CacheStatus {
+            name: cache.name(),
+            entries: cache.entry_count().await,
+        });
+    }
+
+    Status {
+        ready: readiness.ready,
+        checks: readiness.checks,
+        llm: llm.status(),
+        upstreams: vec![
+            breaker::CENSUS.status(),
+            breaker::NWS.status(),
+            breaker::OLLAMA.status(),
+        ],
+        caches: cache_statuses,
+        generated_at: Utc::now(),
+    }
+}
@@ -0,0 +1,112 @@
+use std::{future::Future, time::Duration};
+
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tracing::warn;
+
+use crate::breaker::CircuitBreaker;
+
+lazy_static! {
+    pub static ref UPSTREAM_RETRIES_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "upstream_retries_total",
+            "upstream calls retried after a transient error"
+        ),
+        &["upstream"]
+    )
+    .unwrap();
+}
+
+// the delay doubles after each attempt, and each wait is somewhere between half and all of it
+// so requests that failed together don't retry together
+const MAX_ATTEMPTS: u32 = 3;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+// the statuses a proxy or an overloaded upstream returns, where the same request can work a moment later
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+pub fn is_transient_error(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.status().is_some_and(is_transient_status)
+}
+
+fn is_transient_response(result: &Result<Response, reqwest::Error>) -> bool {
+    match result {
+        Ok(response) => is_transient_status(response.status()),
+        Err(e) => is_transient_error(e),
+    }
+}
+
+fn jitter(delay: Duration) -> Duration {
+    rand::thread_rng().gen_range(delay / 2..=delay)
+}
+
+// calls until it succeeds, fails with an error that won't go away, or runs out of attempts, and
+// returns the last result. only a last result that's still transient counts against the breaker
+pub async fn retry<T, E, F, Fut>(
+    breaker: &CircuitBreaker,
+    is_transient: fn(&Result<T, E>) -> bool,
+    mut call: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let upstream = breaker.name();
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut attempt = 1;
+
+    loop {
+        let result = call().await;
+        let transient = is_transient(&result);
+
+        if attempt == MAX_ATTEMPTS || !transient {
+            breaker.record(!transient);
+            return result;
+        }
+
+        UPSTREAM_RETRIES_COUNTER
+            .with_label_values(&[upstream])
+            .inc();
+        warn!(
+            "transient error calling {}, retrying (attempt {} of {})",
+            upstream,
+            attempt + 1,
+            MAX_ATTEMPTS
+        );
+
+        tokio::time::sleep(jitter(delay)).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+// sends the request with the given sender, retrying connection errors, timeouts, and 502, 503, and
+// 504 responses. requests with a streaming body can't be sent twice, so they're sent once
+pub async fn send<F, Fut>(
+    breaker: &CircuitBreaker,
+    request: RequestBuilder,
+    send: F,
+) -> Result<Response, reqwest::Error>
+where
+    F: Fn(RequestBuilder) -> Fut,
+    Fut: Future<Output = Result<Response, reqwest::Error>>,
+{
+    if request.try_clone().is_none() {
+        let result = send(request).await;
+        breaker.record(!is_transient_response(&result));
+        return result;
+    }
+
+    // it cloned above, so it clones for every attempt
+    retry(breaker, is_transient_response, || {
+        send(request.try_clone().unwrap())
+    })
+    .await
+}
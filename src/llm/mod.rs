@@ -0,0 +1,301 @@
+use std::pin::Pin;
+
+use async_stream::stream;
+use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::breaker::CircuitOpen;
+
+mod pool;
+
+pub use self::pool::{with_priority, PoolStatus, Priority, WorkerPool};
+
+// how long a request turned away by a full queue is told to wait
+pub const QUEUE_FULL_RETRY_AFTER_SECONDS: u64 = 5;
+
+const CORRECTION: &str = "That response was not valid. Reply with only a JSON object with the key \"summary\" containing a non-empty summary.";
+
+lazy_static! {
+    pub static ref SUMMARIES_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "summaries_total",
+            "summaries requested from the llm backend"
+        ),
+        &["backend", "model"]
+    )
+    .unwrap();
+    pub static ref SUMMARY_RETRIES_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "summary_retries_total",
+            "summaries retried after the model returned invalid output"
+        ),
+        &["backend", "model", "reason"]
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Error)]
+pub enum LlmError {
+    #[error("error requesting ollama chat completion: {0}")]
+    Ollama(#[from] ollama_rs::error::OllamaError),
+    #[error("error requesting chat completion: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("chat completion API returned {status}: {message}")]
+    Api { status: u16, message: String },
+    #[error("error parsing summary: {0}")]
+    InvalidOutput(#[from] serde_json::Error),
+    #[error("model {0} is not available")]
+    ModelNotFound(String),
+    #[error("summary is empty")]
+    EmptySummary,
+    #[error("error reading chat stream")]
+    Stream,
+    #[error(transparent)]
+    CircuitOpen(#[from] CircuitOpen),
+    #[error("summary took longer than {0} seconds")]
+    Timeout(u64),
+    #[error("too many summaries are waiting to be generated")]
+    QueueFull,
+}
+
+impl LlmError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LlmError::Ollama(_) => "request",
+            LlmError::Http(_) => "request",
+            LlmError::Api { .. } => "api",
+            LlmError::InvalidOutput(_) => "invalid_output",
+            LlmError::ModelNotFound(_) => "model_not_found",
+            LlmError::EmptySummary => "empty_summary",
+            LlmError::Stream => "stream",
+            LlmError::CircuitOpen(_) => "circuit_open",
+            LlmError::Timeout(_) => "timeout",
+            LlmError::QueueFull => "queue_full",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NShotInOut {
+    pub input: String,
+    pub output: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryOutput {
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+// chat turn struct, a backend independent chat message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatTurn {
+    pub role: Role,
+    pub content: String,
+}
+
+// stream of summary tokens as they are generated
+pub type SummaryStream = Pin<Box<dyn Stream<Item = Result<String, LlmError>> + Send>>;
+
+// llm backend, anything that can turn a prompt and forecast input into a summary
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    // name of the backend, used in logs and metrics
+    fn name(&self) -> &'static str;
+
+    // model summaries are generated with when a request doesn't pick one
+    fn model(&self) -> &str;
+
+    // whether the backend answers and has the default model, for readiness checks
+    async fn ready(&self) -> Result<(), LlmError>;
+
+    // returns the raw JSON object produced by the model
+    async fn chat_json(&self, model: &str, messages: Vec<ChatTurn>) -> Result<String, LlmError>;
+
+    // streams plain text tokens as the model produces them
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<ChatTurn>,
+    ) -> Result<SummaryStream, LlmError>;
+}
+
+// output that isn't a valid SummaryOutput is sent back with a correction, up to max_attempts times in total
+pub async fn summarize(
+    backend: &dyn LlmBackend,
+    model: &str,
+    prompt: &str,
+    training: &[NShotInOut],
+    input: String,
+    max_attempts: usize,
+) -> Result<String, LlmError> {
+    let mut messages = build_messages(prompt, training, input, true);
+
+    SUMMARIES_COUNTER
+        .with_label_values(&[backend.name(), model])
+        .inc();
+
+    let mut attempt = 1;
+
+    loop {
+        let content = backend.chat_json(model, messages.clone()).await?;
+
+        let e = match parse_summary(&content) {
+            Ok(summary) => return Ok(summary),
+            Err(e) => e,
+        };
+
+        if attempt >= max_attempts {
+            return Err(e);
+        }
+
+        warn!(
+            "invalid summary from {} model {} on attempt {}: {}",
+            backend.name(),
+            model,
+            attempt,
+            e
+        );
+
+        SUMMARY_RETRIES_COUNTER
+            .with_label_values(&[backend.name(), model, e.kind()])
+            .inc();
+
+        messages.push(ChatTurn {
+            role: Role::Assistant,
+            content,
+        });
+        messages.push(ChatTurn {
+            role: Role::User,
+            content: CORRECTION.to_string(),
+        });
+
+        attempt += 1;
+    }
+}
+
+// streams a plain text summary, the n-shot outputs are unwrapped from their JSON form to match
+pub async fn summarize_stream(
+    backend: &dyn LlmBackend,
+    model: &str,
+    prompt: &str,
+    training: &[NShotInOut],
+    input: String,
+) -> Result<SummaryStream, LlmError> {
+    let messages = build_messages(prompt, training, input, false);
+
+    SUMMARIES_COUNTER
+        .with_label_values(&[backend.name(), model])
+        .inc();
+
+    backend.chat_stream(model, messages).await
+}
+
+fn build_messages(
+    prompt: &str,
+    training: &[NShotInOut],
+    input: String,
+    json: bool,
+) -> Vec<ChatTurn> {
+    let mut messages = vec![ChatTurn {
+        role: Role::System,
+        content: prompt.to_string(),
+    }];
+
+    for example in training {
+        let output = match json {
+            true => example.output.to_owned(),
+            false => match serde_json::from_str::<SummaryOutput>(&example.output) {
+                Ok(summary_output) => summary_output.summary,
+                Err(_) => example.output.to_owned(),
+            },
+        };
+
+        messages.push(ChatTurn {
+            role: Role::User,
+            content: example.input.to_owned(),
+        });
+        messages.push(ChatTurn {
+            role: Role::Assistant,
+            content: output,
+        });
+    }
+
+    messages.push(ChatTurn {
+        role: Role::User,
+        content: input,
+    });
+
+    messages
+}
+
+// splits a server-sent event response body into the payloads of its data lines
+pub fn sse_data<S, B, E>(mut bytes: S) -> impl Stream<Item = Result<String, LlmError>> + Send
+where
+    S: Stream<Item = Result<B, E>> + Send + Unpin + 'static,
+    B: AsRef<[u8]> + Send,
+    E: Send,
+{
+    stream! {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        while let Some(chunk) = bytes.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(_) => {
+                    yield Err(LlmError::Stream);
+                    return;
+                }
+            };
+
+            buffer.extend_from_slice(chunk.as_ref());
+
+            // lines are split on bytes so multibyte characters spanning chunks stay intact
+            while let Some(newline) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line).trim().to_string();
+
+                if let Some(data) = line.strip_prefix("data:") {
+                    yield Ok(data.trim().to_string());
+                }
+            }
+        }
+    }
+}
+
+// rough estimate of roughly four characters per token, close enough for budgeting across models
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+// tokens used by the prompt and n-shot examples before any forecast input is added
+pub fn context_tokens(prompt: &str, training: &[NShotInOut]) -> usize {
+    estimate_tokens(prompt)
+        + training
+            .iter()
+            .map(|example| estimate_tokens(&example.input) + estimate_tokens(&example.output))
+            .sum::<usize>()
+}
+
+fn parse_summary(content: &str) -> Result<String, LlmError> {
+    let summary_output: SummaryOutput = serde_json::from_str(content)?;
+
+    let summary = summary_output.summary.trim();
+
+    if summary.is_empty() {
+        return Err(LlmError::EmptySummary);
+    }
+
+    Ok(summary.to_string())
+}
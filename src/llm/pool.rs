@@ -0,0 +1,438 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use async_stream::stream;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use prometheus::{
+    histogram_opts, opts, register_counter_vec, register_gauge_vec, register_histogram_vec,
+    CounterVec, GaugeVec, HistogramVec,
+};
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot, Notify};
+
+use super::{ChatTurn, LlmBackend, LlmError, SummaryStream};
+
+lazy_static! {
+    pub static ref LLM_QUEUE_WAIT_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        histogram_opts!(
+            "llm_queue_wait_seconds",
+            "time llm requests waited for a free worker",
+            vec![0.0, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]
+        ),
+        &["backend", "priority"]
+    )
+    .unwrap();
+    pub static ref LLM_QUEUE_DEPTH_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!("llm_queue_depth", "llm requests waiting for a free worker"),
+        &["backend", "priority"]
+    )
+    .unwrap();
+    pub static ref LLM_QUEUE_REJECTED_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "llm_queue_rejected_total",
+            "llm requests turned away because the queue was full"
+        ),
+        &["backend", "priority"]
+    )
+    .unwrap();
+    pub static ref LLM_JOBS_CANCELLED_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "llm_jobs_cancelled_total",
+            "llm requests dropped because nobody was waiting for them anymore"
+        ),
+        &["backend", "priority"]
+    )
+    .unwrap();
+    pub static ref LLM_WORKERS_BUSY_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!("llm_workers_busy", "llm workers generating right now"),
+        &["backend"]
+    )
+    .unwrap();
+}
+
+// tokens a stream can get ahead of whoever is reading it
+const STREAM_BUFFER: usize = 32;
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+// interactive requests are people waiting on a response, scheduled ones are the feed, scheduled
+// jobs, and subscriptions, which can wait or be skipped until their next run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Interactive,
+    Scheduled,
+}
+
+// highest first, the order workers take jobs in
+const PRIORITIES: [Priority; 2] = [Priority::Interactive, Priority::Scheduled];
+
+impl Priority {
+    fn index(self) -> usize {
+        match self {
+            Priority::Interactive => 0,
+            Priority::Scheduled => 1,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Scheduled => "scheduled",
+        }
+    }
+}
+
+// llm calls made while running the future are queued at this priority, calls outside of one
+// are interactive
+pub async fn with_priority<F: Future>(priority: Priority, future: F) -> F::Output {
+    PRIORITY.scope(priority, future).await
+}
+
+fn current_priority() -> Priority {
+    PRIORITY
+        .try_with(|priority| *priority)
+        .unwrap_or(Priority::Interactive)
+}
+
+// what the requester is waiting for, the reply is dropped when the requester goes away
+enum Reply {
+    Json(oneshot::Sender<Result<String, LlmError>>),
+    Stream(oneshot::Sender<Result<SummaryStream, LlmError>>),
+}
+
+// job struct, one chat request waiting for a worker
+struct Job {
+    model: String,
+    messages: Vec<ChatTurn>,
+    priority: Priority,
+    queued_at: Instant,
+    reply: Reply,
+}
+
+impl Job {
+    fn is_cancelled(&self) -> bool {
+        match &self.reply {
+            Reply::Json(reply) => reply.is_closed(),
+            Reply::Stream(reply) => reply.is_closed(),
+        }
+    }
+}
+
+// queue struct, the jobs waiting at each priority, a signal for idle workers, how many workers are
+// idle, and when a generation last worked and last failed
+struct Queue {
+    jobs: Mutex<[VecDeque<Job>; 2]>,
+    ready: Notify,
+    idle: AtomicUsize,
+    last_success: Mutex<Option<DateTime<Utc>>>,
+    last_failure: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl Queue {
+    // jobs whose requester went away are dropped rather than counted
+    fn depth(jobs: &mut [VecDeque<Job>; 2], priority: Priority) -> usize {
+        let jobs = &mut jobs[priority.index()];
+        jobs.retain(|job| !job.is_cancelled());
+        jobs.len()
+    }
+
+    fn record_depth(backend: &'static str, jobs: &mut [VecDeque<Job>; 2]) {
+        for priority in PRIORITIES {
+            LLM_QUEUE_DEPTH_GAUGE
+                .with_label_values(&[backend, priority.label()])
+                .set(Queue::depth(jobs, priority) as f64);
+        }
+    }
+
+    fn pop(&self, backend: &'static str) -> Option<Job> {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        let job = PRIORITIES
+            .iter()
+            .find_map(|priority| jobs[priority.index()].pop_front());
+
+        Queue::record_depth(backend, &mut jobs);
+
+        job
+    }
+
+    fn record(&self, success: bool) {
+        let last = match success {
+            true => &self.last_success,
+            false => &self.last_failure,
+        };
+
+        *last.lock().unwrap() = Some(Utc::now());
+    }
+
+    async fn next(&self, backend: &'static str) -> Job {
+        loop {
+            if let Some(job) = self.pop(backend) {
+                return job;
+            }
+
+            self.idle.fetch_add(1, Ordering::SeqCst);
+            self.ready.notified().await;
+            self.idle.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+// pool status struct, what the pool is doing right now. busy workers are generating or about to
+// take a job, queued jobs are waiting for one
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStatus {
+    pub backend: &'static str,
+    pub model: String,
+    pub workers: usize,
+    pub busy_workers: usize,
+    pub queued_interactive: usize,
+    pub queued_scheduled: usize,
+    pub max_queue: usize,
+    pub max_scheduled_queue: usize,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+}
+
+// worker pool, runs chat requests on a fixed number of worker tasks since a single GPU slows down
+// for every generation it adds. interactive requests are taken first, and scheduled ones are
+// turned away sooner so they're shed before anyone waiting on a response. a request whose caller
+// went away, like a client that disconnected, is skipped or stopped mid generation
+pub struct WorkerPool {
+    name: &'static str,
+    model: String,
+    // only used for readiness checks, which skip the queue
+    backend: Arc<dyn LlmBackend>,
+    queue: Arc<Queue>,
+    workers: usize,
+    max_queue: [usize; 2],
+}
+
+impl WorkerPool {
+    pub fn spawn(
+        backend: Arc<dyn LlmBackend>,
+        workers: usize,
+        max_queue: usize,
+        max_scheduled_queue: usize,
+    ) -> Self {
+        let queue = Arc::new(Queue {
+            jobs: Mutex::new([VecDeque::new(), VecDeque::new()]),
+            ready: Notify::new(),
+            idle: AtomicUsize::new(0),
+            last_success: Mutex::new(None),
+            last_failure: Mutex::new(None),
+        });
+        let workers = workers.max(1);
+
+        for _ in 0..workers {
+            tokio::spawn(work(backend.clone(), queue.clone()));
+        }
+
+        WorkerPool {
+            name: backend.name(),
+            model: backend.model().to_string(),
+            backend,
+            queue,
+            workers,
+            max_queue: [max_queue, max_scheduled_queue],
+        }
+    }
+
+    pub fn status(&self) -> PoolStatus {
+        let mut jobs = self.queue.jobs.lock().unwrap();
+        let queued_interactive = Queue::depth(&mut jobs, Priority::Interactive);
+        let queued_scheduled = Queue::depth(&mut jobs, Priority::Scheduled);
+        drop(jobs);
+
+        let idle = self.queue.idle.load(Ordering::SeqCst);
+
+        PoolStatus {
+            backend: self.name,
+            model: self.model.clone(),
+            workers: self.workers,
+            busy_workers: self.workers.saturating_sub(idle),
+            queued_interactive,
+            queued_scheduled,
+            max_queue: self.max_queue[Priority::Interactive.index()],
+            max_scheduled_queue: self.max_queue[Priority::Scheduled.index()],
+            last_success: *self.queue.last_success.lock().unwrap(),
+            last_failure: *self.queue.last_failure.lock().unwrap(),
+        }
+    }
+
+    fn submit(&self, model: &str, messages: Vec<ChatTurn>, reply: Reply) -> Result<(), LlmError> {
+        let priority = current_priority();
+        let mut jobs = self.queue.jobs.lock().unwrap();
+
+        // scheduled requests count everyone waiting, interactive ones only each other. jobs an
+        // idle worker is about to take aren't waiting
+        let depth: usize = match priority {
+            Priority::Interactive => Queue::depth(&mut jobs, Priority::Interactive),
+            Priority::Scheduled => PRIORITIES
+                .iter()
+                .map(|priority| Queue::depth(&mut jobs, *priority))
+                .sum(),
+        };
+        let waiting = depth.saturating_sub(self.queue.idle.load(Ordering::SeqCst));
+
+        if waiting >= self.max_queue[priority.index()] {
+            LLM_QUEUE_REJECTED_COUNTER
+                .with_label_values(&[self.name, priority.label()])
+                .inc();
+            return Err(LlmError::QueueFull);
+        }
+
+        jobs[priority.index()].push_back(Job {
+            model: model.to_string(),
+            messages,
+            priority,
+            queued_at: Instant::now(),
+            reply,
+        });
+
+        Queue::record_depth(self.name, &mut jobs);
+        drop(jobs);
+
+        self.queue.ready.notify_one();
+
+        Ok(())
+    }
+}
+
+async fn work(backend: Arc<dyn LlmBackend>, queue: Arc<Queue>) {
+    let name = backend.name();
+
+    loop {
+        let job = queue.next(name).await;
+        let labels = [name, job.priority.label()];
+
+        LLM_QUEUE_WAIT_HISTOGRAM
+            .with_label_values(&labels)
+            .observe(job.queued_at.elapsed().as_secs_f64());
+
+        if job.is_cancelled() {
+            LLM_JOBS_CANCELLED_COUNTER.with_label_values(&labels).inc();
+            continue;
+        }
+
+        LLM_WORKERS_BUSY_GAUGE.with_label_values(&[name]).inc();
+
+        let finished = run(backend.as_ref(), &queue, job).await;
+
+        LLM_WORKERS_BUSY_GAUGE.with_label_values(&[name]).dec();
+
+        if !finished {
+            LLM_JOBS_CANCELLED_COUNTER.with_label_values(&labels).inc();
+        }
+
+        // a worker that was busy when a job arrived may have missed the signal for it
+        queue.ready.notify_one();
+    }
+}
+
+// false when the requester went away before it was done, which drops the generation. a stream
+// counts as working once the backend starts it
+async fn run(backend: &dyn LlmBackend, queue: &Queue, job: Job) -> bool {
+    match job.reply {
+        Reply::Json(mut reply) => {
+            let result = tokio::select! {
+                result = backend.chat_json(&job.model, job.messages) => result,
+                _ = reply.closed() => return false,
+            };
+
+            queue.record(result.is_ok());
+
+            reply.send(result).is_ok()
+        }
+        Reply::Stream(mut reply) => {
+            let tokens = tokio::select! {
+                tokens = backend.chat_stream(&job.model, job.messages) => tokens,
+                _ = reply.closed() => return false,
+            };
+
+            queue.record(tokens.is_ok());
+
+            let mut tokens = match tokens {
+                Ok(tokens) => tokens,
+                Err(e) => return reply.send(Err(e)).is_ok(),
+            };
+
+            let (sender, mut receiver) = mpsc::channel(STREAM_BUFFER);
+
+            let forwarded: SummaryStream = Box::pin(stream! {
+                while let Some(token) = receiver.recv().await {
+                    yield token;
+                }
+            });
+
+            if reply.send(Ok(forwarded)).is_err() {
+                return false;
+            }
+
+            // the worker stays busy until the stream is done, so streams count against the pool too
+            loop {
+                let token = tokio::select! {
+                    token = tokens.next() => token,
+                    _ = sender.closed() => return false,
+                };
+
+                match token {
+                    Some(token) => {
+                        if sender.send(token).await.is_err() {
+                            return false;
+                        }
+                    }
+                    None => return true,
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for WorkerPool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn ready(&self) -> Result<(), LlmError> {
+        self.backend.ready().await
+    }
+
+    async fn chat_json(&self, model: &str, messages: Vec<ChatTurn>) -> Result<String, LlmError> {
+        let (reply, result) = oneshot::channel();
+
+        self.submit(model, messages, Reply::Json(reply))?;
+
+        // workers only drop a reply without sending when the requester already went away
+        result.await.unwrap_or(Err(LlmError::Stream))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<ChatTurn>,
+    ) -> Result<SummaryStream, LlmError> {
+        let (reply, result) = oneshot::channel();
+
+        self.submit(model, messages, Reply::Stream(reply))?;
+
+        result.await.unwrap_or(Err(LlmError::Stream))
+    }
+}
@@ -1,13 +1,73 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use axum::{routing::get, Router};
-use tracing::info;
+use axum::{
+    error_handling::HandleErrorLayer,
+    routing::{get, post},
+    BoxError, Router,
+};
+use tower::ServiceBuilder;
+use tower_http::services::ServeDir;
+use tracing::{info, warn};
 
+mod airnow;
+mod anthropic;
+mod assistant;
+mod aviation;
+mod badge;
+mod breaker;
+mod cache;
+mod cap;
+mod card;
 mod config;
+mod derived;
+mod drought;
+mod error;
+mod fallback;
+mod feed;
+mod geocode;
+mod geometry;
+mod gridpoint;
+mod health;
+mod http_util;
+mod ics;
+mod llm;
 mod log;
 mod metrics;
+mod monitor;
+mod moon;
+mod nhc;
+mod normals;
+mod notify;
+mod nwps;
+mod nws;
+mod observation;
 mod ollama;
+mod openai;
+mod prompts;
+mod records;
+mod relative;
+mod render;
 mod routes;
+mod rules;
+mod sanitize;
+mod scheduler;
+mod spc;
+mod storage;
+mod sun;
+mod throttle;
+mod tides;
+mod timestamp;
+mod timezone;
+mod tts;
+mod ui;
+mod units;
+mod verify;
+
+// geocoded addresses are tiny and rarely change, so keep far more of them around
+const FORECAST_CACHE_CAPACITY: u64 = 10_000;
+const GEOCODE_CACHE_CAPACITY: u64 = 100_000;
+const SUMMARY_CACHE_CAPACITY: u64 = 10_000;
+const LAST_SUMMARY_CACHE_CAPACITY: u64 = 10_000;
 
 #[tokio::main]
 async fn main() {
@@ -17,21 +77,417 @@ async fn main() {
     // init log
     log::init(app_config.log_level);
 
-    // connect to ollama
-    let ollama_client = ollama::connect(app_config.ollama_host, app_config.ollama_port);
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(app_config.http_connect_timeout))
+        .read_timeout(Duration::from_secs(app_config.http_read_timeout))
+        .timeout(Duration::from_secs(app_config.http_timeout))
+        .build()
+        .unwrap_or_else(|e| panic!("error building http client: {}", e));
+
+    // subscribers' webhooks are checked for public addresses, a redirect would skip that check
+    let webhook_client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(app_config.http_connect_timeout))
+        .read_timeout(Duration::from_secs(app_config.http_read_timeout))
+        .timeout(Duration::from_secs(app_config.http_timeout))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_else(|e| panic!("error building webhook http client: {}", e));
+
+    // summaries stream for as long as the model takes, so only the gaps between reads are limited
+    let llm_client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(app_config.http_connect_timeout))
+        .read_timeout(Duration::from_secs(app_config.llm_read_timeout))
+        .build()
+        .unwrap_or_else(|e| panic!("error building llm http client: {}", e));
+
+    // limit requests to NWS so a busy instance doesn't get its IP blocked
+    throttle::configure(throttle::Throttle::new(
+        app_config.nws_requests_per_second,
+        app_config.nws_request_burst,
+        app_config.outbound_requests_per_second,
+        app_config.outbound_request_burst,
+    ));
+
+    breaker::configure(
+        app_config.circuit_breaker_failures,
+        Duration::from_secs(app_config.circuit_breaker_open_seconds),
+    );
+
+    // connect to the llm backend
+    let llm: Arc<dyn llm::LlmBackend> = match app_config.llm_backend.as_str() {
+        "ollama" => {
+            let models = model_allowlist(&app_config.ollama_model, &app_config.llm_model_allowlist);
+
+            let ollama_backend = ollama::OllamaBackend::new(
+                ollama::connect(&app_config.ollama_hosts, llm_client.clone()),
+                app_config.ollama_model,
+            )
+            .with_keep_alive(app_config.ollama_keep_alive.map(ollama::keep_alive))
+            .with_options(ollama::generation_options(
+                app_config.ollama_temperature,
+                app_config.ollama_top_p,
+                app_config.ollama_seed,
+                app_config.ollama_num_predict,
+            ));
+
+            ollama_backend
+                .ensure_models(&models, app_config.ollama_pull_model)
+                .await
+                .unwrap_or_else(|e| panic!("error verifying ollama model: {}", e));
+
+            if app_config.ollama_warm_up {
+                ollama_backend.warm_up().await;
+            }
+
+            ollama_backend
+                .spawn_health_checks(Duration::from_secs(app_config.ollama_health_check_interval));
+
+            Arc::new(ollama_backend)
+        }
+        "openai" => Arc::new(openai::OpenAiBackend::new(
+            llm_client.clone(),
+            app_config.openai_base_url,
+            app_config.openai_api_key,
+            app_config.openai_model,
+        )),
+        "openai_compatible" => Arc::new(openai::OpenAiBackend::compatible(
+            llm_client.clone(),
+            app_config
+                .openai_compatible_base_url
+                .unwrap_or_else(|| panic!("OPENAI_COMPATIBLE_BASE_URL is not set")),
+            app_config.openai_compatible_api_key,
+            app_config
+                .openai_compatible_model
+                .unwrap_or_else(|| panic!("OPENAI_COMPATIBLE_MODEL is not set")),
+            app_config.openai_compatible_json_mode,
+        )),
+        "anthropic" => Arc::new(anthropic::AnthropicBackend::new(
+            llm_client.clone(),
+            app_config.anthropic_base_url,
+            app_config
+                .anthropic_api_key
+                .unwrap_or_else(|| panic!("ANTHROPIC_API_KEY is not set")),
+            app_config.anthropic_model,
+            app_config.anthropic_max_tokens,
+        )),
+        backend => panic!("{} is not a valid llm backend", backend),
+    };
+
+    info!(
+        "using {} llm backend with model {}",
+        llm.name(),
+        llm.model()
+    );
+
+    // generations run on the pool's workers, so a handler that goes away stops its generation
+    let llm_pool = Arc::new(llm::WorkerPool::spawn(
+        llm,
+        app_config.llm_concurrency,
+        app_config.llm_max_queue,
+        app_config.llm_max_scheduled_queue,
+    ));
+    let llm: Arc<dyn llm::LlmBackend> = llm_pool.clone();
+
+    // text to speech is optional, the audio endpoint fails without it
+    let tts: Option<Arc<dyn tts::TtsBackend>> = match app_config.tts_backend.as_deref() {
+        None => None,
+        Some("piper") => Some(Arc::new(tts::PiperBackend::new(
+            client.clone(),
+            app_config
+                .tts_base_url
+                .unwrap_or_else(|| panic!("TTS_BASE_URL is not set")),
+        ))),
+        Some("openai") => Some(Arc::new(tts::OpenAiSpeechBackend::new(
+            client.clone(),
+            app_config
+                .tts_base_url
+                .unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
+            app_config.tts_api_key,
+            app_config.tts_model,
+            app_config.tts_voice,
+        ))),
+        Some(backend) => panic!("{} is not a valid tts backend", backend),
+    };
+
+    if let Some(tts) = &tts {
+        info!("using {} tts backend", tts.name());
+    }
+
+    // air quality is optional, forecasts leave it out and the air quality endpoint fails without a key
+    let airnow = app_config
+        .airnow_api_key
+        .map(|api_key| Arc::new(airnow::AirNowClient::new(client.clone(), api_key)));
+
+    // forecast cards need a font, the card endpoint fails without one
+    let card = app_config.card_font_path.as_deref().map(|path| {
+        Arc::new(
+            card::CardRenderer::load(path)
+                .unwrap_or_else(|e| panic!("error loading card renderer: {}", e)),
+        )
+    });
+
+    // load prompts and examples, reloaded on SIGHUP
+    let prompt_store = Arc::new(
+        prompts::PromptStore::load(app_config.prompt_path, app_config.examples_dir)
+            .unwrap_or_else(|e| panic!("error loading prompts: {}", e)),
+    );
+
+    prompts::spawn_reload_on_hangup(prompt_store.clone());
+
+    if prompts::tone_instructions(&app_config.summary_tone).is_none() {
+        panic!("{} is not a valid SUMMARY_TONE", app_config.summary_tone);
+    }
+
+    let default_units = units::parse(&app_config.default_units)
+        .unwrap_or_else(|| panic!("{} is not valid DEFAULT_UNITS", app_config.default_units));
+
+    let summary_fallbacks: Vec<fallback::Level> = app_config
+        .summary_fallbacks
+        .iter()
+        .filter(|name| name.as_str() != "none")
+        .map(|name| {
+            fallback::Level::parse(name)
+                .unwrap_or_else(|| panic!("{} is not a valid SUMMARY_FALLBACKS level", name))
+        })
+        .collect();
+
+    // summaries are only stored, and permalinks handed out, when a database is configured
+    let storage = match &app_config.database_url {
+        Some(database_url) => Some(
+            storage::connect(database_url)
+                .await
+                .unwrap_or_else(|e| panic!("error connecting to database: {}", e)),
+        ),
+        None => None,
+    };
+
+    if let Some(storage) = &storage {
+        info!("using {} storage", storage.name());
+    }
+
+    // alexa requests are only answered once they're verified as coming from amazon for this skill
+    let alexa = app_config.alexa_skill_id.map(|skill_id| {
+        Arc::new(
+            assistant::AlexaSkill::new(client.clone(), skill_id, app_config.alexa_default_address)
+                .unwrap_or_else(|e| panic!("error loading alexa skill: {}", e)),
+        )
+    });
+
+    // scheduled jobs pre-generate summaries on cron expressions, evaluated in the server's local time
+    let (scheduled_jobs, channels) = match &app_config.schedule_path {
+        Some(schedule_path) => {
+            let schedule = scheduler::load(
+                client.clone(),
+                storage.clone(),
+                std::path::Path::new(schedule_path),
+            )
+            .unwrap_or_else(|e| panic!("error loading schedule: {}", e));
+
+            (Some(schedule.jobs), schedule.channels)
+        }
+        None => (None, notify::ChannelTemplates::default()),
+    };
+
+    // advisories use the built-in thresholds unless a rules file replaces them
+    let rules = Arc::new(match &app_config.rules_path {
+        Some(rules_path) => rules::load(std::path::Path::new(rules_path))
+            .unwrap_or_else(|e| panic!("error loading rules: {}", e)),
+        None => rules::RuleSet::default(),
+    });
+
+    // feeds are regenerated on a schedule for a fixed set of locations
+    let feed_store = Arc::new(feed::FeedStore::load(
+        app_config
+            .feed_locations
+            .as_deref()
+            .map(feed::parse_locations)
+            .unwrap_or_default(),
+        app_config.feed_data_dir,
+    ));
 
     let forecast_state = Arc::new(routes::ForecastState {
-        client: reqwest::Client::new(),
-        ollama_connection: ollama_client,
-        ollama_model: app_config.ollama_model,
+        client,
+        model_allowlist: model_allowlist(llm.model(), &app_config.llm_model_allowlist),
+        llm,
+        llm_pool,
+        forecast_cache: cache::connect(
+            &app_config.cache_backend,
+            app_config.redis_url.as_deref(),
+            "forecast",
+            Duration::from_secs(app_config.forecast_cache_ttl),
+            FORECAST_CACHE_CAPACITY,
+        )
+        .await,
+        geocode_cache: cache::connect(
+            &app_config.cache_backend,
+            app_config.redis_url.as_deref(),
+            "geocode",
+            Duration::from_secs(app_config.geocode_cache_ttl),
+            GEOCODE_CACHE_CAPACITY,
+        )
+        .await,
+        summary_cache: cache::connect(
+            &app_config.cache_backend,
+            app_config.redis_url.as_deref(),
+            "summary",
+            Duration::from_secs(app_config.summary_cache_ttl),
+            SUMMARY_CACHE_CAPACITY,
+        )
+        .await,
+        last_summary_cache: cache::connect(
+            &app_config.cache_backend,
+            app_config.redis_url.as_deref(),
+            "last_summary",
+            Duration::from_secs(app_config.last_summary_cache_ttl),
+            LAST_SUMMARY_CACHE_CAPACITY,
+        )
+        .await,
+        summary_fallbacks,
+        summarize_by_default: app_config.summarize_by_default,
+        include_alerts_by_default: app_config.include_alerts_by_default,
+        degree_day_metrics: app_config.degree_day_metrics,
+        max_input_tokens: app_config.llm_max_input_tokens,
+        max_summary_attempts: app_config.llm_max_attempts,
+        default_tone: app_config.summary_tone,
+        default_units: default_units.to_string(),
+        nws_si_passthrough: app_config.nws_si_passthrough,
+        tts,
+        card,
+        airnow,
+        rules,
+        badge_max_age: app_config.badge_max_age,
+        stale_forecast_age: chrono::TimeDelta::seconds(app_config.stale_forecast_age as i64),
+        forecast_revalidate_after: app_config.forecast_revalidate_after as i64,
+        summary_deadline: Duration::from_secs(app_config.summary_deadline),
+        feed: feed_store,
+        storage,
+        channels,
+        subscriptions_admin_token: app_config.subscriptions_admin_token,
+        subscriptions_create_token: app_config.subscriptions_create_token,
+        webhook_client,
+        alexa,
+        twilio_auth_token: app_config.twilio_auth_token,
+        twilio_webhook_url: app_config.twilio_webhook_url,
+        prompts: prompt_store,
     });
 
+    // --check runs the dependency checks once and exits, so a bad deploy fails before it's ready
+    if std::env::args().any(|arg| arg == "--check") {
+        let report = health::self_check(
+            forecast_state.llm.as_ref(),
+            &[
+                &forecast_state.forecast_cache,
+                &forecast_state.geocode_cache,
+                &forecast_state.summary_cache,
+                &forecast_state.last_summary_cache,
+            ],
+            forecast_state.storage.as_deref(),
+            forecast_state.client.clone(),
+            app_config.self_check_address,
+        )
+        .await;
+
+        println!("{}", report.report());
+
+        std::process::exit(if report.ready { 0 } else { 1 });
+    }
+
+    if !forecast_state.feed.locations().is_empty() {
+        routes::spawn_feed_refresh(
+            forecast_state.clone(),
+            Duration::from_secs(app_config.feed_refresh_interval),
+        );
+    }
+
+    if let Some(jobs) = scheduled_jobs {
+        for job in &jobs {
+            info!("scheduled job {} runs at {}", job.name, job.cron);
+        }
+
+        routes::spawn_scheduler(forecast_state.clone(), jobs);
+    }
+
+    // subscriptions are read from the database every minute, so they need no schedule file
+    if forecast_state.storage.is_some() {
+        routes::spawn_subscriptions(forecast_state.clone());
+
+        if app_config.alert_poll_interval > 0 {
+            routes::spawn_alert_monitor(
+                forecast_state.clone(),
+                Duration::from_secs(app_config.alert_poll_interval),
+            );
+        }
+    }
+
     info!("welcome to rust-start!");
 
-    let app = Router::new().route("/", get(routes::root)).route(
-        "/api/v1/forecast",
-        get(routes::forecast).with_state(forecast_state),
-    );
+    // forecast requests give up after the deadline. dropping the handler drops its place in the
+    // llm queue, which stops a generation nobody is waiting for. streams and websockets are
+    // long lived, so they're left out
+    let request_deadline = app_config.request_deadline;
+    if request_deadline <= app_config.summary_deadline {
+        warn!(
+            "REQUEST_DEADLINE_SECONDS is not longer than SUMMARY_DEADLINE_SECONDS, slow summaries will time out instead of falling back"
+        );
+    }
+
+    let forecast_routes = Router::new()
+        .route("/api/v1/forecast", get(routes::forecast))
+        .route("/api/v1/forecast.ics", get(routes::forecast_ics))
+        .route("/api/v1/forecast/audio", get(routes::forecast_audio))
+        .route("/api/v1/forecast/short", get(routes::forecast_short))
+        .route("/api/v1/forecast/card.png", get(routes::forecast_card))
+        .route("/api/v1/forecast/ha", get(routes::forecast_home_assistant))
+        .route("/api/v1/forecast/hourly", get(routes::hourly_forecast))
+        .layer(
+            ServiceBuilder::new()
+                // the timeout is the only error the routes can return
+                .layer(HandleErrorLayer::new(move |_: BoxError| async move {
+                    error::AppError::DeadlineExceeded(request_deadline)
+                }))
+                .timeout(Duration::from_secs(request_deadline)),
+        );
+
+    let mut app = Router::new()
+        .route("/", get(routes::root))
+        .route("/livez", get(routes::livez))
+        .route("/readyz", get(routes::readyz))
+        .route("/api/v1/status", get(routes::status))
+        .route("/ui", get(ui::index))
+        .merge(forecast_routes)
+        .route("/api/v1/forecast/stream", get(routes::forecast_stream))
+        .route("/api/v1/alerts", get(routes::alerts))
+        .route("/api/v1/gridpoint", get(routes::gridpoint))
+        .route("/api/v1/current", get(routes::current))
+        .route("/api/v1/aviation", get(routes::aviation))
+        .route("/api/v1/fire", get(routes::fire_weather))
+        .route("/api/v1/tropical", get(routes::tropical))
+        .route("/api/v1/airquality", get(routes::air_quality))
+        .route("/api/v1/tides", get(routes::tides))
+        .route("/api/v1/rivers", get(routes::rivers))
+        .route("/api/v1/history", get(routes::history))
+        .route("/api/v1/history/export", get(routes::history_export))
+        .route("/api/v1/sms/twilio", post(routes::twilio_webhook))
+        .route("/api/v1/assistant", post(routes::assistant))
+        .route(
+            "/api/v1/subscriptions",
+            get(routes::subscriptions).post(routes::create_subscription),
+        )
+        .route(
+            "/api/v1/subscriptions/:id",
+            get(routes::subscription).delete(routes::delete_subscription),
+        )
+        .route("/ws", get(routes::forecast_ws))
+        .route("/badge", get(routes::badge))
+        .route("/s/:id", get(routes::permalink))
+        .route("/feed/:file", get(routes::feed))
+        .with_state(forecast_state);
+
+    // operators can ship their own frontend alongside the API
+    if let Some(static_dir) = app_config.static_dir {
+        info!("serving static files from {}", static_dir);
+        app = app.nest_service("/static", ServeDir::new(static_dir));
+    }
 
     tokio::spawn(async move {
         metrics::start_metrics_server(app_config.metrics_host, app_config.metrics_port).await;
@@ -43,3 +499,16 @@ async fn main() {
             .unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+// the default model is always allowed alongside any configured extras
+fn model_allowlist(default_model: &str, models: &[String]) -> Vec<String> {
+    let mut allowlist = vec![default_model.to_string()];
+
+    for model in models {
+        if !allowlist.contains(model) {
+            allowlist.push(model.to_owned());
+        }
+    }
+
+    allowlist
+}
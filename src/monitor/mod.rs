@@ -0,0 +1,55 @@
+use chrono::TimeDelta;
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+
+use crate::{geocode::Coordinates, nws::AlertProperties};
+
+lazy_static! {
+    pub static ref ALERT_POLLS_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "alert_monitor_polls_total",
+            "active alert requests made by the alert monitor"
+        ),
+        &["result"]
+    )
+    .unwrap();
+    pub static ref NEW_ALERTS_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "alert_monitor_new_alerts_total",
+            "warnings the alert monitor found that subscribers hadn't been notified of"
+        ),
+        &["severity"]
+    )
+    .unwrap();
+    pub static ref ALERT_NOTIFICATIONS_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "alert_monitor_notifications_total",
+            "alert notifications sent to subscribers"
+        ),
+        &["result"]
+    )
+    .unwrap();
+}
+
+// alerts with an unreadable expiry are remembered for this long, past any warning's lifetime
+const FALLBACK_RETENTION_DAYS: i64 = 7;
+
+// warnings are the alerts for hazards that are happening or imminent, watches and advisories aren't pushed
+pub fn is_warning(properties: &AlertProperties) -> bool {
+    properties.status == "Actual"
+        && properties.message_type != "Cancel"
+        && properties.event.ends_with("Warning")
+}
+
+// NWS matches alerts to a point at four decimal places, so subscriptions that round together share a request
+pub fn poll_key(coordinates: Coordinates) -> String {
+    format!("{:.4},{:.4}", coordinates.latitude, coordinates.longitude)
+}
+
+// NWS sends local offsets, the stored expiry is UTC so it compares as a string
+pub fn expires_utc(properties: &AlertProperties) -> String {
+    match chrono::DateTime::parse_from_rfc3339(&properties.expires) {
+        Ok(expires) => expires.to_utc().to_rfc3339(),
+        Err(_) => (chrono::Utc::now() + TimeDelta::days(FALLBACK_RETENTION_DAYS)).to_rfc3339(),
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::sun::{J2000, SECONDS_PER_DAY, UNIX_EPOCH_JULIAN};
+
+const DAYS_PER_CENTURY: f64 = 36525.0;
+
+const SYNODIC_MONTH_DAYS: f64 = 29.530588;
+
+// phases in order of elongation, each covers the 45 degrees centered on it
+const PHASES: [&str; 8] = [
+    "new moon",
+    "waxing crescent",
+    "first quarter",
+    "waxing gibbous",
+    "full moon",
+    "waning gibbous",
+    "last quarter",
+    "waning crescent",
+];
+
+// moon struct, the phase at local midnight at the end of a date, the middle of that night.
+// illumination is the percent of the disk that's lit and age is days since the last new moon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Moon {
+    pub date: NaiveDate,
+    pub phase: String,
+    pub illumination_percent: u8,
+    pub age_days: f64,
+}
+
+// the phase is the same everywhere at a given instant, the offset only decides which night a date means
+pub fn moon(date: NaiveDate, offset: FixedOffset) -> Moon {
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_local_timezone(offset)
+        .single()
+        .map(|midnight| midnight + TimeDelta::days(1));
+
+    let julian = match midnight {
+        Some(midnight) => julian_date(midnight),
+        None => J2000,
+    };
+
+    let elongation = elongation(julian);
+
+    // the fraction of the disk lit grows with the angle between the sun and moon as seen from earth
+    let illumination = (1.0 - elongation.to_radians().cos()) / 2.0;
+
+    let index = ((elongation + 22.5) / 45.0).floor() as usize % PHASES.len();
+
+    Moon {
+        date,
+        phase: PHASES[index].to_string(),
+        illumination_percent: (illumination * 100.0).round() as u8,
+        age_days: (elongation / 360.0 * SYNODIC_MONTH_DAYS * 10.0).round() / 10.0,
+    }
+}
+
+fn julian_date(time: DateTime<FixedOffset>) -> f64 {
+    time.timestamp() as f64 / SECONDS_PER_DAY + UNIX_EPOCH_JULIAN
+}
+
+// the moon's elongation east of the sun in degrees, 0 at new moon and 180 at full moon.
+// low precision terms from Meeus, good to a fraction of a degree
+fn elongation(julian: f64) -> f64 {
+    let centuries = (julian - J2000) / DAYS_PER_CENTURY;
+
+    let mean_elongation = 297.8501921 + 445267.1114034 * centuries;
+    let sun_anomaly = (357.5291092 + 35999.0502909 * centuries).to_radians();
+    let moon_anomaly = (134.9633964 + 477198.8675055 * centuries).to_radians();
+    let d = mean_elongation.rem_euclid(360.0).to_radians();
+
+    let phase_angle = 180.0 - mean_elongation - 6.289 * moon_anomaly.sin()
+        + 2.1 * sun_anomaly.sin()
+        - 1.274 * (2.0 * d - moon_anomaly).sin()
+        - 0.658 * (2.0 * d).sin()
+        - 0.214 * (2.0 * moon_anomaly).sin()
+        - 0.11 * d.sin();
+
+    (180.0 - phase_angle).rem_euclid(360.0)
+}
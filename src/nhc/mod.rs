@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    geocode::{self, Coordinates},
+    throttle,
+};
+
+const NHC_URL: &str = "https://www.nhc.noaa.gov";
+
+// points west of here are in the central pacific, covered from honolulu
+const CENTRAL_PACIFIC_LONGITUDE: f64 = -140.0;
+// points west of here face the pacific rather than the gulf or the atlantic
+const EASTERN_PACIFIC_LONGITUDE: f64 = -110.0;
+
+#[derive(Debug, Error)]
+pub enum NhcError {
+    #[error("error requesting nhc.noaa.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding nhc.noaa.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("error parsing tropical outlook: {0}")]
+    Parse(#[from] quick_xml::DeError),
+    #[error("tropical outlook for the {0} basin has no items")]
+    MissingOutlook(&'static str),
+}
+
+impl NhcError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NhcError::Request(_) => "request",
+            NhcError::Decode(_) => "decode",
+            NhcError::Parse(_) => "parse",
+            NhcError::MissingOutlook(_) => "missing_outlook",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Basin {
+    Atlantic,
+    EasternPacific,
+    CentralPacific,
+}
+
+impl Basin {
+    // the tropical outlook feed and the prefix of each storm's bin number
+    fn code(&self) -> &'static str {
+        match self {
+            Basin::Atlantic => "AT",
+            Basin::EasternPacific => "EP",
+            Basin::CentralPacific => "CP",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Basin::Atlantic => "atlantic",
+            Basin::EasternPacific => "eastern_pacific",
+            Basin::CentralPacific => "central_pacific",
+        }
+    }
+}
+
+// the basin whose storms reach the point's coastline, the gulf coast is part of the atlantic basin
+pub fn basin(coordinates: Coordinates) -> Basin {
+    if coordinates.longitude <= CENTRAL_PACIFIC_LONGITUDE {
+        Basin::CentralPacific
+    } else if coordinates.longitude <= EASTERN_PACIFIC_LONGITUDE {
+        Basin::EasternPacific
+    } else {
+        Basin::Atlantic
+    }
+}
+
+// rss struct, the feed the tropical weather outlook is published in
+#[derive(Debug, Clone, Deserialize)]
+struct Rss {
+    channel: RssChannel,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RssChannel {
+    #[serde(default)]
+    item: Vec<RssItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RssItem {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "pubDate", default)]
+    pub_date: String,
+}
+
+// outlook struct, where tropical cyclones may form over the next seven days
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Outlook {
+    pub title: String,
+    pub issued: String,
+    pub text: String,
+}
+
+// storm struct, an active tropical cyclone from CurrentStorms.json.
+// intensity is the maximum sustained wind in knots and pressure is in millibars
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Storm {
+    pub id: String,
+    pub bin_number: String,
+    pub name: String,
+    pub classification: String,
+    #[serde(default)]
+    pub intensity: String,
+    #[serde(default)]
+    pub pressure: String,
+    pub latitude_numeric: f64,
+    pub longitude_numeric: f64,
+    pub movement_dir: Option<i64>,
+    pub movement_speed: Option<i64>,
+    #[serde(default)]
+    pub last_update: String,
+    pub public_advisory: Option<Advisory>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Advisory {
+    #[serde(default)]
+    pub adv_num: String,
+    #[serde(default)]
+    pub issuance: String,
+    pub url: String,
+}
+
+impl Storm {
+    pub fn basin(&self) -> Option<Basin> {
+        [
+            Basin::Atlantic,
+            Basin::EasternPacific,
+            Basin::CentralPacific,
+        ]
+        .into_iter()
+        .find(|basin| self.bin_number.starts_with(basin.code()))
+    }
+
+    pub fn distance_miles(&self, coordinates: Coordinates) -> f64 {
+        geocode::distance_miles(
+            coordinates,
+            Coordinates {
+                latitude: self.latitude_numeric,
+                longitude: self.longitude_numeric,
+            },
+        )
+    }
+
+    pub fn classification_name(&self) -> &str {
+        match self.classification.as_str() {
+            "TD" => "Tropical Depression",
+            "STD" => "Subtropical Depression",
+            "TS" => "Tropical Storm",
+            "STS" => "Subtropical Storm",
+            "HU" => "Hurricane",
+            "TY" => "Typhoon",
+            "PTC" => "Post-Tropical Cyclone",
+            "PC" => "Potential Tropical Cyclone",
+            classification => classification,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurrentStorms {
+    #[serde(default)]
+    active_storms: Vec<Storm>,
+}
+
+async fn get_text(client: reqwest::Client, url: String) -> Result<String, NhcError> {
+    let response_result = throttle::send(client.get(url))
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NhcError::Request(e)),
+    };
+
+    match response.text().await {
+        Ok(body) => Ok(body),
+        Err(e) => Err(NhcError::Decode(e)),
+    }
+}
+
+pub async fn get_outlook(client: reqwest::Client, basin: Basin) -> Result<Outlook, NhcError> {
+    let xml = get_text(client, format!("{}/xml/TWO{}.xml", NHC_URL, basin.code())).await?;
+
+    let rss: Rss = quick_xml::de::from_str(&xml)?;
+
+    let item = match rss.channel.item.into_iter().next() {
+        Some(item) => item,
+        None => return Err(NhcError::MissingOutlook(basin.name())),
+    };
+
+    Ok(Outlook {
+        title: item.title,
+        issued: item.pub_date,
+        text: plain_text(&item.description),
+    })
+}
+
+pub async fn get_active_storms(client: reqwest::Client) -> Result<Vec<Storm>, NhcError> {
+    let response_result = throttle::send(client.get(format!("{}/CurrentStorms.json", NHC_URL)))
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NhcError::Request(e)),
+    };
+
+    match response.json::<CurrentStorms>().await {
+        Ok(current_storms) => Ok(current_storms.active_storms),
+        Err(e) => Err(NhcError::Decode(e)),
+    }
+}
+
+// advisories are html pages with the text product in a pre block
+pub async fn get_advisory_text(
+    client: reqwest::Client,
+    advisory: &Advisory,
+) -> Result<String, NhcError> {
+    let html = get_text(client, advisory.url.to_owned()).await?;
+
+    Ok(plain_text(&html))
+}
+
+// the text of the first pre block when there is one, with tags removed and entities decoded
+fn plain_text(html: &str) -> String {
+    let lowercase = html.to_ascii_lowercase();
+
+    let body = match (lowercase.find("<pre"), lowercase.find("</pre>")) {
+        (Some(start), Some(end)) if start < end => {
+            let start = html[start..end].find('>').map(|offset| start + offset + 1);
+            &html[start.unwrap_or(end)..end]
+        }
+        _ => html,
+    };
+
+    let mut text = String::new();
+    let mut in_tag = false;
+
+    for c in body.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
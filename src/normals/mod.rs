@@ -0,0 +1,196 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::geocode::{self, Coordinates};
+
+const SEARCH_URL: &str = "https://www.ncei.noaa.gov/access/services/search/v1/data";
+const DATA_URL: &str = "https://www.ncei.noaa.gov/access/services/data/v1";
+
+const DATASET: &str = "normals-daily-1991-2020";
+
+const MILES_PER_DEGREE_LATITUDE: f64 = 69.0;
+
+// ncei fills values it can't compute with -7777, -8888, or -9999
+const MISSING_VALUE: f64 = -7777.0;
+
+#[derive(Debug, Error)]
+pub enum NormalsError {
+    #[error("error requesting ncei.noaa.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding ncei.noaa.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawSearch {
+    #[serde(default)]
+    results: Vec<RawResult>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawResult {
+    #[serde(default)]
+    stations: Vec<RawStation>,
+    location: Option<RawLocation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawStation {
+    id: String,
+    name: String,
+}
+
+// geojson point, longitude first
+#[derive(Debug, Clone, Deserialize)]
+struct RawLocation {
+    coordinates: (f64, f64),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawNormal {
+    #[serde(rename = "DATE")]
+    date: String,
+    #[serde(rename = "DLY-TMAX-NORMAL")]
+    high: Option<String>,
+    #[serde(rename = "DLY-TMIN-NORMAL")]
+    low: Option<String>,
+}
+
+// normals station struct, a station with 1991-2020 daily normals
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalsStation {
+    pub id: String,
+    pub name: String,
+    pub coordinates: Coordinates,
+}
+
+// daily normal struct, the normal high and low in fahrenheit for a day of the year like 06-15
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyNormal {
+    pub day: String,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+}
+
+// stations in a box around the point, the caller picks the nearest
+pub async fn get_stations(
+    client: reqwest::Client,
+    coordinates: Coordinates,
+    radius_miles: f64,
+) -> Result<Vec<NormalsStation>, NormalsError> {
+    let latitude_delta = radius_miles / MILES_PER_DEGREE_LATITUDE;
+    let longitude_delta = radius_miles
+        / (MILES_PER_DEGREE_LATITUDE * coordinates.latitude.to_radians().cos().max(0.01));
+
+    // the box is north, west, south, east
+    let response_result = client
+        .get(SEARCH_URL)
+        .query(&[
+            ("dataset", DATASET.to_string()),
+            (
+                "bbox",
+                format!(
+                    "{:.4},{:.4},{:.4},{:.4}",
+                    coordinates.latitude + latitude_delta,
+                    coordinates.longitude - longitude_delta,
+                    coordinates.latitude - latitude_delta,
+                    coordinates.longitude + longitude_delta
+                ),
+            ),
+            ("dataTypes", "DLY-TMAX-NORMAL,DLY-TMIN-NORMAL".to_string()),
+            ("limit", "100".to_string()),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NormalsError::Request(e)),
+    };
+
+    let raw = match response.json::<RawSearch>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(NormalsError::Decode(e)),
+    };
+
+    Ok(raw
+        .results
+        .into_iter()
+        .filter_map(|result| {
+            let location = result.location?;
+            let station = result.stations.into_iter().next()?;
+
+            Some(NormalsStation {
+                id: station.id,
+                name: station.name,
+                coordinates: Coordinates {
+                    latitude: location.coordinates.1,
+                    longitude: location.coordinates.0,
+                },
+            })
+        })
+        .collect())
+}
+
+pub fn nearest(
+    stations: &[NormalsStation],
+    coordinates: Coordinates,
+) -> Option<(&NormalsStation, f64)> {
+    geocode::nearest(stations, coordinates, |station| station.coordinates)
+}
+
+// the whole year at once, normals don't change until the next thirty year period
+pub async fn get_daily_normals(
+    client: reqwest::Client,
+    station_id: &str,
+) -> Result<Vec<DailyNormal>, NormalsError> {
+    let response_result = client
+        .get(DATA_URL)
+        .query(&[
+            ("dataset", DATASET),
+            ("stations", station_id),
+            ("dataTypes", "DLY-TMAX-NORMAL,DLY-TMIN-NORMAL"),
+            ("units", "standard"),
+            ("format", "json"),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NormalsError::Request(e)),
+    };
+
+    let raw = match response.json::<Vec<RawNormal>>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(NormalsError::Decode(e)),
+    };
+
+    let value = |value: Option<String>| {
+        value
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| *value > MISSING_VALUE)
+    };
+
+    Ok(raw
+        .into_iter()
+        .map(|normal| DailyNormal {
+            day: normal.date,
+            high: value(normal.high),
+            low: value(normal.low),
+        })
+        .collect())
+}
+
+// normals have no february 29th, the 28th stands in for it
+pub fn for_date(normals: &[DailyNormal], date: NaiveDate) -> Option<&DailyNormal> {
+    let day = match (date.month(), date.day()) {
+        (2, 29) => "02-28".to_string(),
+        (month, day) => format!("{:02}-{:02}", month, day),
+    };
+
+    normals.iter().find(|normal| normal.day == day)
+}
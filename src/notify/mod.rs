@@ -0,0 +1,1553 @@
+use std::{collections::BTreeMap, net::IpAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use prometheus::{opts, register_counter_vec, CounterVec};
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS, TlsConfiguration, Transport};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::Sha256;
+use tera::{Context, Tera};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{
+    geocode::Location,
+    render,
+    routes::SimplifiedForecastPeriod,
+    storage::{self, Storage, StorageError},
+};
+
+lazy_static! {
+    pub static ref NOTIFICATIONS_COUNTER: CounterVec = register_counter_vec!(
+        opts!("notifications_total", "summary notifications delivered"),
+        &["notifier", "result"]
+    )
+    .unwrap();
+}
+
+const MAX_DELIVERY_ATTEMPTS: u32 = 4;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+pub const TWILIO_SIGNATURE_HEADER: &str = "X-Twilio-Signature";
+
+const DEFAULT_EMAIL_SUBJECT: &str = "{{ title }}";
+const SUBJECT_TEMPLATE: &str = "subject";
+const SLACK_API_URL: &str = "https://slack.com/api/chat.postMessage";
+const MAX_SLACK_TEXT_LENGTH: usize = 3000;
+// slack allows ten fields per section, six periods covers the next three days
+const MAX_SLACK_PERIODS: usize = 6;
+const MAX_DISCORD_PERIODS: usize = 6;
+const MAX_DISCORD_FIELD_LENGTH: usize = 1024;
+
+// embed colors, the service's blue for summaries and the usual warning scale for alerts
+const DISCORD_SUMMARY_COLOR: u32 = 0x184c88;
+const DISCORD_EXTREME_COLOR: u32 = 0x8b0000;
+const DISCORD_SEVERE_COLOR: u32 = 0xe05d44;
+const DISCORD_MODERATE_COLOR: u32 = 0xfe7d37;
+const DISCORD_MINOR_COLOR: u32 = 0xdfb317;
+
+const TWILIO_API_URL: &str = "https://api.twilio.com/2010-04-01";
+// two concatenated GSM segments of 153 characters, jobs texting summaries should use the brief
+// style to stay well under it
+const MAX_SMS_LENGTH: usize = 306;
+// the GSM 7 bit alphabet, one character outside it sends the whole text as UCS-2 at 70 a segment
+const GSM_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+// in the alphabet's extension table, each is sent as an escape and the character so counts twice
+const GSM_EXTENDED: &str = "^{}\\[~]|€";
+
+const NTFY_SERVER: &str = "https://ntfy.sh";
+const PUSHOVER_API_URL: &str = "https://api.pushover.net/1/messages.json";
+// pushover rejects longer messages instead of truncating them
+const MAX_PUSHOVER_LENGTH: usize = 1024;
+
+const MQTT_PORT: u16 = 1883;
+const MQTTS_PORT: u16 = 8883;
+const MQTT_KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("error sending notification: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("notification endpoint returned {status}: {message}")]
+    Api { status: u16, message: String },
+    #[error("error sending email: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("error building email: {0}")]
+    Email(#[from] lettre::error::Error),
+    #[error("invalid email address {0}: {1}")]
+    Address(String, #[source] lettre::address::AddressError),
+    #[error("error in subject template: {0}")]
+    Template(#[from] tera::Error),
+    #[error("invalid notifier config: {0}")]
+    Config(String),
+    #[error("error checking sms opt-outs: {0}")]
+    Storage(#[from] StorageError),
+    #[error("sms webhooks are not configured")]
+    NotConfigured,
+    #[error("webhook host is not allowed: {0}")]
+    BlockedHost(String),
+    #[error("error publishing to mqtt: {0}")]
+    MqttClient(#[from] rumqttc::ClientError),
+    // boxed, the connection error is several times the size of every other variant
+    #[error("error connecting to mqtt broker: {0}")]
+    MqttConnection(#[from] Box<rumqttc::ConnectionError>),
+}
+
+impl NotifyError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NotifyError::Http(_) => "http",
+            NotifyError::Api { .. } => "api",
+            NotifyError::Smtp(_) => "smtp",
+            NotifyError::Email(_) => "email",
+            NotifyError::Address(_, _) => "address",
+            NotifyError::Template(_) => "template",
+            NotifyError::Config(_) => "config",
+            NotifyError::Storage(_) => "storage",
+            NotifyError::NotConfigured => "not_configured",
+            NotifyError::BlockedHost(_) => "blocked_host",
+            NotifyError::MqttClient(_) => "mqtt_client",
+            NotifyError::MqttConnection(_) => "mqtt_connection",
+        }
+    }
+
+    // connection failures, rate limits, and server errors may succeed later, other rejections won't
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            NotifyError::Http(_) => true,
+            NotifyError::Api { status, .. } => *status == 429 || *status >= 500,
+            NotifyError::Smtp(e) => !e.is_permanent(),
+            NotifyError::Storage(_) => true,
+            NotifyError::MqttClient(_) | NotifyError::MqttConnection(_) => true,
+            NotifyError::Email(_)
+            | NotifyError::Address(_, _)
+            | NotifyError::Template(_)
+            | NotifyError::Config(_)
+            | NotifyError::NotConfigured
+            | NotifyError::BlockedHost(_) => false,
+        }
+    }
+}
+
+// notification struct, a generated summary pushed to a notifier, and the webhook payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub event: &'static str,
+    pub source: String,
+    pub summary: String,
+    pub model: String,
+    pub verified: bool,
+    pub location: Location,
+    pub generated_at: String,
+    pub permalink: Option<String>,
+    // NWS alert event, severity, and official instructions, only set for alert driven notifications.
+    // the instructions are NWS's own words, kept apart from the generated summary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub periods: Vec<SimplifiedForecastPeriod>,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError>;
+}
+
+// notifier config struct, one [[notifiers]] table, jobs refer to notifiers by name
+#[derive(Debug, Deserialize)]
+pub struct NotifierConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: NotifierKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierKind {
+    Webhook {
+        url: String,
+        secret: Option<String>,
+    },
+    Email {
+        host: String,
+        port: Option<u16>,
+        #[serde(default)]
+        tls: SmtpTls,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+        subject: Option<String>,
+    },
+    // either an incoming webhook, which is tied to one channel, or a bot token and the channel to post in
+    Slack {
+        webhook_url: Option<String>,
+        token: Option<String>,
+        channel: Option<String>,
+    },
+    Discord {
+        webhook_url: String,
+        username: Option<String>,
+    },
+    // numbers are E.164, like +12065550100
+    Twilio {
+        account_sid: String,
+        auth_token: String,
+        from: String,
+        to: Vec<String>,
+        api_url: Option<String>,
+    },
+    // the server defaults to ntfy.sh, the token is only needed for protected topics
+    Ntfy {
+        topic: String,
+        server: Option<String>,
+        token: Option<String>,
+    },
+    Pushover {
+        token: String,
+        user: String,
+        device: Option<String>,
+        api_url: Option<String>,
+    },
+    // publishes the webhook payload, retained so new subscribers like home assistant get the latest summary
+    Mqtt {
+        host: String,
+        port: Option<u16>,
+        #[serde(default)]
+        tls: bool,
+        username: Option<String>,
+        password: Option<String>,
+        topic: String,
+        #[serde(default = "default_retain")]
+        retain: bool,
+    },
+}
+
+fn default_retain() -> bool {
+    true
+}
+
+// subscription channel enum, where a subscription is delivered.
+// email, sms, and mqtt go through the server's configured channel notifiers, only the recipient is the subscriber's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubscriptionChannel {
+    Webhook {
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+    },
+    Email {
+        address: String,
+    },
+    Sms {
+        phone: String,
+    },
+    Mqtt {
+        topic: String,
+    },
+}
+
+impl SubscriptionChannel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubscriptionChannel::Webhook { .. } => "webhook",
+            SubscriptionChannel::Email { .. } => "email",
+            SubscriptionChannel::Sms { .. } => "sms",
+            SubscriptionChannel::Mqtt { .. } => "mqtt",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SubscriptionChannel::Webhook { url, .. } => match reqwest::Url::parse(url) {
+                Ok(url) if url.scheme() == "https" || url.scheme() == "http" => Ok(()),
+                _ => Err(format!("{} is not an http or https url", url)),
+            },
+            SubscriptionChannel::Email { address } => match mailbox(address) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(format!("{} is not a valid email address", address)),
+            },
+            SubscriptionChannel::Sms { phone } => match is_phone_number(phone) {
+                true => Ok(()),
+                false => Err(format!(
+                    "{} is not an E.164 phone number like +12065550100",
+                    phone
+                )),
+            },
+            SubscriptionChannel::Mqtt { topic } => {
+                match topic.is_empty() || topic.contains(['+', '#']) {
+                    true => Err(format!("{} is not an mqtt topic to publish to", topic)),
+                    false => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+// subscribers can only publish under this, so they can't reach the broker's other topics
+pub const DEFAULT_MQTT_TOPIC_PREFIX: &str = "nws-forecast-summarizer/subscriptions/";
+
+// channel templates struct, the notifiers named in the schedule file's [channels] table, and the
+// prefix every mqtt subscription's topic has to start with
+#[derive(Debug, Clone, Default)]
+pub struct ChannelTemplates {
+    pub email: Option<NotifierKind>,
+    pub sms: Option<NotifierKind>,
+    pub mqtt: Option<NotifierKind>,
+    pub mqtt_topic_prefix: Option<String>,
+}
+
+impl ChannelTemplates {
+    pub fn mqtt_topic_prefix(&self) -> &str {
+        self.mqtt_topic_prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_MQTT_TOPIC_PREFIX)
+    }
+
+    // the channel's own checks, and mqtt topics kept under the prefix
+    pub fn validate(&self, channel: &SubscriptionChannel) -> Result<(), String> {
+        channel.validate()?;
+
+        match channel {
+            SubscriptionChannel::Mqtt { topic } => {
+                let prefix = self.mqtt_topic_prefix();
+
+                match topic.strip_prefix(prefix) {
+                    Some(rest) if !rest.is_empty() => Ok(()),
+                    _ => Err(format!("{} is not an mqtt topic under {}", topic, prefix)),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // webhooks need nothing from the server
+    pub fn supports(&self, channel: &SubscriptionChannel) -> bool {
+        match channel {
+            SubscriptionChannel::Webhook { .. } => true,
+            SubscriptionChannel::Email { .. } => self.email.is_some(),
+            SubscriptionChannel::Sms { .. } => self.sms.is_some(),
+            SubscriptionChannel::Mqtt { .. } => self.mqtt.is_some(),
+        }
+    }
+
+    // the channel's template with the subscriber's recipient swapped in
+    pub fn build(
+        &self,
+        client: reqwest::Client,
+        storage: Option<Arc<dyn Storage>>,
+        name: String,
+        channel: &SubscriptionChannel,
+    ) -> Result<Arc<dyn Notifier>, NotifyError> {
+        let not_configured =
+            || NotifyError::Config(format!("no {} channel is configured", channel.name()));
+
+        let kind = match channel {
+            SubscriptionChannel::Webhook { url, secret } => NotifierKind::Webhook {
+                url: url.to_owned(),
+                secret: secret.to_owned(),
+            },
+            SubscriptionChannel::Email { address } => match self.email.clone() {
+                Some(NotifierKind::Email {
+                    host,
+                    port,
+                    tls,
+                    username,
+                    password,
+                    from,
+                    subject,
+                    ..
+                }) => NotifierKind::Email {
+                    host,
+                    port,
+                    tls,
+                    username,
+                    password,
+                    from,
+                    to: vec![address.to_owned()],
+                    subject,
+                },
+                _ => return Err(not_configured()),
+            },
+            SubscriptionChannel::Sms { phone } => match self.sms.clone() {
+                Some(NotifierKind::Twilio {
+                    account_sid,
+                    auth_token,
+                    from,
+                    api_url,
+                    ..
+                }) => NotifierKind::Twilio {
+                    account_sid,
+                    auth_token,
+                    from,
+                    to: vec![phone.to_owned()],
+                    api_url,
+                },
+                _ => return Err(not_configured()),
+            },
+            SubscriptionChannel::Mqtt { topic } => match self.mqtt.clone() {
+                Some(NotifierKind::Mqtt {
+                    host,
+                    port,
+                    tls,
+                    username,
+                    password,
+                    retain,
+                    ..
+                }) => NotifierKind::Mqtt {
+                    host,
+                    port,
+                    tls,
+                    username,
+                    password,
+                    topic: topic.to_owned(),
+                    retain,
+                },
+                _ => return Err(not_configured()),
+            },
+        };
+
+        build(client, storage, NotifierConfig { name, kind })
+    }
+}
+
+// smtp tls enum, starttls on the submission port unless told otherwise
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+// storage is only needed by notifiers that track opt-outs
+pub fn build(
+    client: reqwest::Client,
+    storage: Option<Arc<dyn Storage>>,
+    config: NotifierConfig,
+) -> Result<Arc<dyn Notifier>, NotifyError> {
+    match config.kind {
+        NotifierKind::Webhook { url, secret } => Ok(Arc::new(WebhookNotifier {
+            client,
+            name: config.name,
+            url,
+            secret,
+        })),
+        NotifierKind::Email {
+            host,
+            port,
+            tls,
+            username,
+            password,
+            from,
+            to,
+            subject,
+        } => {
+            let mut transport = match tls {
+                SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+                SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+                SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            };
+
+            if let Some(port) = port {
+                transport = transport.port(port);
+            }
+
+            if let (Some(username), Some(password)) = (username, password) {
+                transport = transport.credentials(Credentials::new(username, password));
+            }
+
+            let mut subject_template = Tera::default();
+            subject_template.add_raw_template(
+                SUBJECT_TEMPLATE,
+                subject.as_deref().unwrap_or(DEFAULT_EMAIL_SUBJECT),
+            )?;
+
+            Ok(Arc::new(EmailNotifier {
+                name: config.name,
+                transport: transport.build(),
+                from: mailbox(&from)?,
+                to: to
+                    .iter()
+                    .map(|to| mailbox(to))
+                    .collect::<Result<Vec<Mailbox>, NotifyError>>()?,
+                subject_template,
+            }))
+        }
+        NotifierKind::Slack {
+            webhook_url,
+            token,
+            channel,
+        } => {
+            let target = match (webhook_url, token, channel) {
+                (Some(webhook_url), None, None) => SlackTarget::Webhook(webhook_url),
+                (None, Some(token), Some(channel)) => SlackTarget::Bot { token, channel },
+                _ => {
+                    return Err(NotifyError::Config(format!(
+                        "slack notifier {} needs either webhook_url or token and channel",
+                        config.name
+                    )))
+                }
+            };
+
+            Ok(Arc::new(SlackNotifier {
+                client,
+                name: config.name,
+                target,
+            }))
+        }
+        NotifierKind::Discord {
+            webhook_url,
+            username,
+        } => Ok(Arc::new(DiscordNotifier {
+            client,
+            name: config.name,
+            webhook_url,
+            username,
+        })),
+        NotifierKind::Twilio {
+            account_sid,
+            auth_token,
+            from,
+            to,
+            api_url,
+        } => {
+            let storage = match storage {
+                Some(storage) => storage,
+                None => {
+                    return Err(NotifyError::Config(format!(
+                        "twilio notifier {} needs DATABASE_URL set to track opt-outs",
+                        config.name
+                    )))
+                }
+            };
+
+            if let Some(number) = to
+                .iter()
+                .chain([&from])
+                .find(|number| !is_phone_number(number))
+            {
+                return Err(NotifyError::Config(format!(
+                    "{} is not an E.164 phone number like +12065550100",
+                    number
+                )));
+            }
+
+            Ok(Arc::new(TwilioNotifier {
+                client,
+                storage,
+                name: config.name,
+                messages_url: format!(
+                    "{}/Accounts/{}/Messages.json",
+                    api_url.as_deref().unwrap_or(TWILIO_API_URL),
+                    account_sid
+                ),
+                account_sid,
+                auth_token,
+                from,
+                to,
+            }))
+        }
+        NotifierKind::Ntfy {
+            topic,
+            server,
+            token,
+        } => Ok(Arc::new(NtfyNotifier {
+            client,
+            name: config.name,
+            server: server.unwrap_or_else(|| NTFY_SERVER.to_string()),
+            topic,
+            token,
+        })),
+        NotifierKind::Pushover {
+            token,
+            user,
+            device,
+            api_url,
+        } => Ok(Arc::new(PushoverNotifier {
+            client,
+            name: config.name,
+            api_url: api_url.unwrap_or_else(|| PUSHOVER_API_URL.to_string()),
+            token,
+            user,
+            device,
+        })),
+        NotifierKind::Mqtt {
+            host,
+            port,
+            tls,
+            username,
+            password,
+            topic,
+            retain,
+        } => {
+            let port = port.unwrap_or(match tls {
+                true => MQTTS_PORT,
+                false => MQTT_PORT,
+            });
+
+            Ok(Arc::new(MqttNotifier {
+                name: config.name,
+                host,
+                port,
+                tls,
+                credentials: username.zip(password),
+                topic,
+                retain,
+            }))
+        }
+    }
+}
+
+// delivers in the background so a slow endpoint never holds up the caller
+pub fn dispatch(notifiers: &[Arc<dyn Notifier>], notification: Notification) {
+    let notification = Arc::new(notification);
+
+    for notifier in notifiers {
+        let notifier = notifier.clone();
+        let notification = notification.clone();
+
+        tokio::spawn(async move { deliver(notifier.as_ref(), &notification).await });
+    }
+}
+
+// retries with exponential backoff, giving up early on errors that won't go away. true once it's delivered
+pub async fn deliver(notifier: &dyn Notifier, notification: &Notification) -> bool {
+    let mut delay = INITIAL_RETRY_DELAY;
+
+    for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+        let e = match notifier.send(notification).await {
+            Ok(()) => {
+                NOTIFICATIONS_COUNTER
+                    .with_label_values(&[notifier.name(), "success"])
+                    .inc();
+                return true;
+            }
+            Err(e) => e,
+        };
+
+        if !e.is_retryable() || attempt == MAX_DELIVERY_ATTEMPTS {
+            NOTIFICATIONS_COUNTER
+                .with_label_values(&[notifier.name(), e.kind()])
+                .inc();
+            warn!(
+                "error notifying {} after {} attempts: {}",
+                notifier.name(),
+                attempt,
+                e
+            );
+            return false;
+        }
+
+        NOTIFICATIONS_COUNTER
+            .with_label_values(&[notifier.name(), "retry"])
+            .inc();
+
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+
+    false
+}
+
+// webhook notifier, POSTs the notification as JSON, signed with HMAC-SHA256 of the body when a secret is set
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    name: String,
+    url: String,
+    secret: Option<String>,
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let body = serde_json::to_vec(notification).unwrap();
+
+        let mut builder = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+
+        if let Some(secret) = &self.secret {
+            builder = builder.header(SIGNATURE_HEADER, signature(secret, &body));
+        }
+
+        let response = builder.body(body).send().await?;
+
+        success(response).await
+    }
+}
+
+// the same sha256=<hex> form GitHub uses, so existing verification code can be reused
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body);
+
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("sha256={}", digest)
+}
+
+// email notifier, sends one message to all recipients with plain text and html alternatives
+pub struct EmailNotifier {
+    name: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    subject_template: Tera,
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let mut context = Context::new();
+        context.insert("title", &title(notification));
+        context.insert("location", &render::title(&notification.location));
+        context.insert("alert", &notification.alert);
+        context.insert("source", &notification.source);
+        context.insert("summary", &notification.summary);
+        context.insert("generated_at", &notification.generated_at);
+
+        let subject = self.subject_template.render(SUBJECT_TEMPLATE, &context)?;
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(subject.trim());
+
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+
+        let message = builder.multipart(MultiPart::alternative_plain_html(
+            render::plain(
+                &notification.summary,
+                &notification.location,
+                &notification.periods,
+            ),
+            render::html(
+                &notification.summary,
+                &notification.location,
+                &notification.periods,
+            ),
+        ))?;
+
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
+
+// slack notifier, posts the summary with block kit formatting and a compact period table
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    name: String,
+    target: SlackTarget,
+}
+
+enum SlackTarget {
+    Webhook(String),
+    Bot { token: String, channel: String },
+}
+
+#[derive(Debug, Serialize)]
+struct SlackMessage<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<&'a str>,
+    // shown in notifications and by clients that can't render blocks
+    text: String,
+    blocks: Vec<SlackBlock>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SlackBlock {
+    Header {
+        text: SlackText,
+    },
+    Section {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<SlackText>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        fields: Vec<SlackText>,
+    },
+    Context {
+        elements: Vec<SlackText>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct SlackText {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+}
+
+impl SlackText {
+    fn plain(text: String) -> Self {
+        Self {
+            kind: "plain_text",
+            text,
+        }
+    }
+
+    fn markdown(text: String) -> Self {
+        Self {
+            kind: "mrkdwn",
+            text,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackResponse {
+    ok: bool,
+    error: Option<String>,
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let title = title(notification);
+
+        let mut blocks = vec![
+            SlackBlock::Header {
+                text: SlackText::plain(title.clone()),
+            },
+            SlackBlock::Section {
+                text: Some(SlackText::markdown(slack_escape(&notification.summary))),
+                fields: Vec::new(),
+            },
+        ];
+
+        if let Some(instruction) = &notification.instruction {
+            blocks.push(SlackBlock::Section {
+                text: Some(SlackText::markdown(truncate(
+                    &format!("*Official instructions*\n{}", slack_escape(instruction)),
+                    MAX_SLACK_TEXT_LENGTH,
+                ))),
+                fields: Vec::new(),
+            });
+        }
+
+        let fields: Vec<SlackText> = notification
+            .periods
+            .iter()
+            .take(MAX_SLACK_PERIODS)
+            .map(|period| {
+                SlackText::markdown(format!(
+                    "*{}*\n{}, {}",
+                    slack_escape(&period.name),
+                    slack_escape(&period.temperature),
+                    slack_escape(&period.short_forecast)
+                ))
+            })
+            .collect();
+
+        if !fields.is_empty() {
+            blocks.push(SlackBlock::Section { text: None, fields });
+        }
+
+        blocks.push(SlackBlock::Context {
+            elements: vec![SlackText::markdown(slack_escape(&format!(
+                "{} · {}",
+                notification.model, notification.generated_at
+            )))],
+        });
+
+        let (url, channel, token) = match &self.target {
+            SlackTarget::Webhook(webhook_url) => (webhook_url.as_str(), None, None),
+            SlackTarget::Bot { token, channel } => {
+                (SLACK_API_URL, Some(channel.as_str()), Some(token))
+            }
+        };
+
+        let mut builder = self.client.post(url).json(&SlackMessage {
+            channel,
+            text: slack_escape(&format!("{}: {}", title, notification.summary)),
+            blocks,
+        });
+
+        if let Some(token) = token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let response = builder.send().await?;
+
+        if token.is_none() {
+            return success(response).await;
+        }
+
+        // the web api answers 200 with ok set to false for most errors
+        let status = response.status();
+
+        if !status.is_success() {
+            return success(response).await;
+        }
+
+        let body: SlackResponse = response.json().await?;
+
+        match body.ok {
+            true => Ok(()),
+            false => Err(NotifyError::Api {
+                status: status.as_u16(),
+                message: body.error.unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+// discord notifier, posts one embed per summary to a channel webhook
+pub struct DiscordNotifier {
+    client: reqwest::Client,
+    name: String,
+    webhook_url: String,
+    username: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordMessage<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<&'a str>,
+    embeds: Vec<DiscordEmbed>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordEmbed {
+    title: String,
+    description: String,
+    color: u32,
+    fields: Vec<DiscordField>,
+    footer: DiscordFooter,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordField {
+    name: String,
+    value: String,
+    inline: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordFooter {
+    text: String,
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        // the first period's conditions stand in for the whole summary
+        let emoji = notification.periods.first().map_or("🌡️", |period| {
+            render::condition_emoji(&period.short_forecast)
+        });
+
+        let color = match notification.severity.as_deref() {
+            Some("Extreme") => DISCORD_EXTREME_COLOR,
+            Some("Severe") => DISCORD_SEVERE_COLOR,
+            Some("Moderate") => DISCORD_MODERATE_COLOR,
+            Some(_) => DISCORD_MINOR_COLOR,
+            None => DISCORD_SUMMARY_COLOR,
+        };
+
+        let mut fields: Vec<DiscordField> = notification
+            .periods
+            .iter()
+            .take(MAX_DISCORD_PERIODS)
+            .map(|period| DiscordField {
+                name: format!(
+                    "{} {}",
+                    render::condition_emoji(&period.short_forecast),
+                    period.name
+                ),
+                value: format!("{}, {}", period.temperature, period.short_forecast),
+                inline: true,
+            })
+            .collect();
+
+        if let Some(instruction) = &notification.instruction {
+            fields.push(DiscordField {
+                name: "Official instructions".to_string(),
+                value: truncate(instruction, MAX_DISCORD_FIELD_LENGTH),
+                inline: false,
+            });
+        }
+
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&DiscordMessage {
+                username: self.username.as_deref(),
+                embeds: vec![DiscordEmbed {
+                    title: format!("{} {}", emoji, title(notification)),
+                    description: notification.summary.to_owned(),
+                    color,
+                    fields,
+                    footer: DiscordFooter {
+                        text: format!("{} · {}", notification.model, notification.generated_at),
+                    },
+                }],
+            })
+            .send()
+            .await?;
+
+        success(response).await
+    }
+}
+
+// twilio notifier, texts the summary to each number that hasn't replied STOP
+pub struct TwilioNotifier {
+    client: reqwest::Client,
+    storage: Arc<dyn Storage>,
+    name: String,
+    messages_url: String,
+    account_sid: String,
+    auth_token: String,
+    from: String,
+    to: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct TwilioMessage<'a> {
+    to: &'a str,
+    from: &'a str,
+    body: &'a str,
+}
+
+#[async_trait]
+impl Notifier for TwilioNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // a failure part way through retries every number, so earlier numbers may get the text twice
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let body = sms_text(notification);
+
+        for to in &self.to {
+            if self.storage.sms_opted_out(to).await? {
+                continue;
+            }
+
+            let response = self
+                .client
+                .post(&self.messages_url)
+                .basic_auth(&self.account_sid, Some(&self.auth_token))
+                .form(&TwilioMessage {
+                    to,
+                    from: &self.from,
+                    body: &body,
+                })
+                .send()
+                .await?;
+
+            success(response).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// the text in the GSM alphabet, dashes, quotes, and ellipses are swapped for ones in it and
+// anything else outside it, like the degree sign, is dropped
+fn gsm_text(text: &str) -> String {
+    let mut gsm = String::new();
+
+    for c in text.chars() {
+        match c {
+            '–' | '—' | '−' => gsm.push('-'),
+            '…' => gsm.push_str("..."),
+            '‘' | '’' => gsm.push('\''),
+            '“' | '”' => gsm.push('"'),
+            '\u{a0}' | '\t' => gsm.push(' '),
+            c if GSM_BASIC.contains(c) || GSM_EXTENDED.contains(c) => gsm.push(c),
+            _ => {}
+        }
+    }
+
+    gsm
+}
+
+fn gsm_length(c: char) -> usize {
+    match GSM_EXTENDED.contains(c) {
+        true => 2,
+        false => 1,
+    }
+}
+
+// kept in the GSM alphabet, which fits 153 characters in each segment of a longer text
+fn sms_text(notification: &Notification) -> String {
+    let text = gsm_text(&format!(
+        "{}: {}",
+        title(notification),
+        notification.summary
+    ));
+
+    if text.chars().map(gsm_length).sum::<usize>() <= MAX_SMS_LENGTH {
+        return text;
+    }
+
+    let mut truncated = String::new();
+    let mut length = 0;
+
+    for c in text.chars() {
+        length += gsm_length(c);
+
+        if length > MAX_SMS_LENGTH - 3 {
+            break;
+        }
+
+        truncated.push(c);
+    }
+
+    format!("{}...", truncated.trim_end())
+}
+
+// addresses on the internet, not loopback, private, link-local like 169.254.169.254, shared, or
+// otherwise reserved ones a webhook could use to reach the server's own network
+fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let [first, second, ..] = address.octets();
+
+            !(address.is_private()
+                || address.is_loopback()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_documentation()
+                || address.is_multicast()
+                // 100.64.0.0/10, carrier grade NAT
+                || (first == 100 && second & 0xc0 == 64)
+                || first == 0
+                || first >= 240)
+        }
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(address) => is_public_address(IpAddr::V4(address)),
+            None => {
+                !(address.is_loopback()
+                    || address.is_unspecified()
+                    || address.is_multicast()
+                    || address.is_unique_local()
+                    || address.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+// a subscriber's webhook has to resolve to public addresses only. it's checked when the
+// subscription is made and before each delivery, since what a name resolves to can change
+pub async fn check_webhook_host(url: &str) -> Result<(), NotifyError> {
+    let url = match reqwest::Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => return Err(NotifyError::BlockedHost(format!("{} is not a url", url))),
+    };
+
+    let host = match url.host_str() {
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+        None => return Err(NotifyError::BlockedHost(format!("{} has no host", url))),
+    };
+
+    let addresses: Vec<IpAddr> = match host.parse::<IpAddr>() {
+        Ok(address) => vec![address],
+        Err(_) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+
+            match tokio::net::lookup_host((host, port)).await {
+                Ok(addresses) => addresses.map(|address| address.ip()).collect(),
+                Err(e) => {
+                    return Err(NotifyError::BlockedHost(format!(
+                        "error resolving {}: {}",
+                        host, e
+                    )))
+                }
+            }
+        }
+    };
+
+    match !addresses.is_empty() && addresses.into_iter().all(is_public_address) {
+        true => Ok(()),
+        false => Err(NotifyError::BlockedHost(format!(
+            "{} is not a public address",
+            host
+        ))),
+    }
+}
+
+pub fn is_phone_number(number: &str) -> bool {
+    match number.strip_prefix('+') {
+        Some(digits) => {
+            (8..=15).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+// sms keyword enum, the carrier style keywords an inbound text can be, matched against the whole message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmsKeyword {
+    OptOut,
+    OptIn,
+    Help,
+}
+
+impl SmsKeyword {
+    pub fn parse(body: &str) -> Option<Self> {
+        match body.trim().to_uppercase().as_str() {
+            "STOP" | "STOPALL" | "UNSUBSCRIBE" | "CANCEL" | "END" | "QUIT" | "OPTOUT"
+            | "REVOKE" => Some(SmsKeyword::OptOut),
+            "START" | "UNSTOP" | "YES" | "OPTIN" => Some(SmsKeyword::OptIn),
+            "HELP" | "INFO" => Some(SmsKeyword::Help),
+            _ => None,
+        }
+    }
+
+    pub fn reply(&self) -> &'static str {
+        match self {
+            SmsKeyword::OptOut => {
+                "You're unsubscribed from forecast texts and won't get any more. Reply START to resubscribe."
+            }
+            SmsKeyword::OptIn => {
+                "You're subscribed to forecast texts again. Reply STOP to unsubscribe."
+            }
+            SmsKeyword::Help => "Automated forecast texts. Reply STOP to unsubscribe.",
+        }
+    }
+}
+
+// twilio signs the webhook url followed by every form parameter's name and value, sorted by name
+pub fn twilio_signature_valid(
+    auth_token: &str,
+    url: &str,
+    params: &BTreeMap<String, String>,
+    signature: &str,
+) -> bool {
+    let signature = match BASE64_STANDARD.decode(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+
+    let mut mac = Hmac::<Sha1>::new_from_slice(auth_token.as_bytes()).unwrap();
+    mac.update(url.as_bytes());
+
+    for (name, value) in params {
+        mac.update(name.as_bytes());
+        mac.update(value.as_bytes());
+    }
+
+    mac.verify_slice(&signature).is_ok()
+}
+
+// ntfy notifier, publishes to a topic as JSON, severe alerts are sent at a higher priority
+pub struct NtfyNotifier {
+    client: reqwest::Client,
+    name: String,
+    server: String,
+    topic: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NtfyMessage<'a> {
+    topic: &'a str,
+    title: String,
+    message: &'a str,
+    // 1 (min) to 5 (max), 3 is the default
+    priority: u8,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<&'static str>,
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let (priority, tags) = match notification.severity.as_deref() {
+            Some("Extreme") => (5, vec!["rotating_light"]),
+            Some("Severe") => (4, vec!["warning"]),
+            Some(_) => (3, vec!["warning"]),
+            None => (3, Vec::new()),
+        };
+
+        let mut builder = self.client.post(&self.server).json(&NtfyMessage {
+            topic: &self.topic,
+            title: title(notification),
+            message: &notification.summary,
+            priority,
+            tags,
+        });
+
+        if let Some(token) = &self.token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let response = builder.send().await?;
+
+        success(response).await
+    }
+}
+
+// pushover notifier, sends to a user or group key, optionally a single device
+pub struct PushoverNotifier {
+    client: reqwest::Client,
+    name: String,
+    api_url: String,
+    token: String,
+    user: String,
+    device: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PushoverMessage<'a> {
+    token: &'a str,
+    user: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<&'a str>,
+    title: String,
+    message: String,
+    // -2 to 2, emergency priority needs acknowledgement settings so it isn't used
+    priority: i8,
+}
+
+#[async_trait]
+impl Notifier for PushoverNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let priority = match notification.severity.as_deref() {
+            Some("Extreme") | Some("Severe") => 1,
+            _ => 0,
+        };
+
+        let message = truncate(&notification.summary, MAX_PUSHOVER_LENGTH);
+
+        let response = self
+            .client
+            .post(&self.api_url)
+            .form(&PushoverMessage {
+                token: &self.token,
+                user: &self.user,
+                device: self.device.as_deref(),
+                title: title(notification),
+                message,
+                priority,
+            })
+            .send()
+            .await?;
+
+        success(response).await
+    }
+}
+
+// mqtt notifier, connects for each notification and waits for the broker to acknowledge the publish
+pub struct MqttNotifier {
+    name: String,
+    host: String,
+    port: u16,
+    tls: bool,
+    credentials: Option<(String, String)>,
+    topic: String,
+    retain: bool,
+}
+
+#[async_trait]
+impl Notifier for MqttNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let mut options = MqttOptions::new(
+            format!("nws-forecast-summarizer-{}", storage::short_id()),
+            &self.host,
+            self.port,
+        );
+        options.set_keep_alive(MQTT_KEEP_ALIVE);
+
+        if self.tls {
+            options.set_transport(Transport::tls_with_config(TlsConfiguration::Native));
+        }
+
+        if let Some((username, password)) = &self.credentials {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, 1);
+
+        client
+            .publish(
+                &self.topic,
+                QoS::AtLeastOnce,
+                self.retain,
+                serde_json::to_vec(notification).unwrap(),
+            )
+            .await?;
+
+        // the event loop does the connecting and sending, it's polled until the broker has the message
+        loop {
+            if let Event::Incoming(Packet::PubAck(_)) = event_loop.poll().await.map_err(Box::new)? {
+                break;
+            }
+        }
+
+        client.disconnect().await?;
+
+        Ok(())
+    }
+}
+
+// alerts are titled for the warning, like "Tornado Warning for Seattle, WA"
+fn title(notification: &Notification) -> String {
+    match &notification.alert {
+        Some(alert) => format!("{} for {}", alert, render::title(&notification.location)),
+        None => format!("Forecast for {}", render::title(&notification.location)),
+    }
+}
+
+// cuts text to a length limit counted in characters, ending it with an ellipsis
+fn truncate(text: &str, max_length: usize) -> String {
+    match text.chars().count() > max_length {
+        true => format!("{}…", text.chars().take(max_length - 1).collect::<String>()),
+        false => text.to_string(),
+    }
+}
+
+// the three characters slack's mrkdwn treats as control characters
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, NotifyError> {
+    address
+        .parse()
+        .map_err(|e| NotifyError::Address(address.to_string(), e))
+}
+
+async fn success(response: reqwest::Response) -> Result<(), NotifyError> {
+    let status = response.status();
+
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_default();
+
+        return Err(NotifyError::Api {
+            status: status.as_u16(),
+            message,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_texts_in_the_gsm_alphabet() {
+        assert_eq!(
+            gsm_text("Highs 61–74°F, “breezy”… café in Bogotá"),
+            "Highs 61-74F, \"breezy\"... café in Bogot"
+        );
+        assert_eq!(gsm_text("Ñandú €5 {x}"), "Ñand €5 {x}");
+        assert_eq!("€{".chars().map(gsm_length).sum::<usize>(), 4);
+    }
+
+    // the example from twilio's webhook security docs
+    fn twilio_params() -> BTreeMap<String, String> {
+        [
+            ("CallSid", "CA1234567890ABCDE"),
+            ("Caller", "+12349013030"),
+            ("Digits", "1234"),
+            ("From", "+12349013030"),
+            ("To", "+18005551212"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn accepts_twilio_signatures() {
+        assert!(twilio_signature_valid(
+            "12345",
+            "https://mycompany.com/myapp.php?foo=1&bar=2",
+            &twilio_params(),
+            "0/KCTR6DLpKmkAf8muzZqo1nDgQ="
+        ));
+    }
+
+    #[test]
+    fn rejects_other_twilio_signatures() {
+        let url = "https://mycompany.com/myapp.php?foo=1&bar=2";
+        let signature = "0/KCTR6DLpKmkAf8muzZqo1nDgQ=";
+
+        assert!(!twilio_signature_valid(
+            "54321",
+            url,
+            &twilio_params(),
+            signature
+        ));
+        assert!(!twilio_signature_valid(
+            "12345",
+            "https://mycompany.com/myapp.php?foo=1&bar=3",
+            &twilio_params(),
+            signature
+        ));
+
+        let mut params = twilio_params();
+        params.insert("Digits".to_string(), "4321".to_string());
+        assert!(!twilio_signature_valid("12345", url, &params, signature));
+
+        assert!(!twilio_signature_valid(
+            "12345",
+            url,
+            &twilio_params(),
+            "not base64!"
+        ));
+        assert!(!twilio_signature_valid("12345", url, &twilio_params(), ""));
+    }
+}
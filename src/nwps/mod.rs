@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    geocode::{self, Coordinates},
+    throttle,
+};
+
+const NWPS_URL: &str = "https://api.water.noaa.gov/nwps/v1";
+
+const MILES_PER_DEGREE_LATITUDE: f64 = 69.0;
+
+// nwps fills values it doesn't have with -999 or -9999
+const MISSING_VALUE: f64 = -999.0;
+
+#[derive(Debug, Error)]
+pub enum NwpsError {
+    #[error("error requesting api.water.noaa.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding api.water.noaa.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+}
+
+impl NwpsError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NwpsError::Request(_) => "request",
+            NwpsError::Decode(_) => "decode",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawGauges {
+    #[serde(default)]
+    gauges: Vec<RawGauge>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawGauge {
+    lid: String,
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    #[serde(default)]
+    status: RawStatus,
+}
+
+#[derive(Default, Debug, Clone, Deserialize)]
+struct RawStatus {
+    observed: Option<RawReading>,
+    forecast: Option<RawReading>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReading {
+    primary: Option<f64>,
+    #[serde(default)]
+    primary_unit: String,
+    #[serde(default)]
+    flood_category: String,
+    #[serde(default)]
+    valid_time: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawGaugeDetail {
+    #[serde(default)]
+    flood: RawFlood,
+}
+
+#[derive(Default, Debug, Clone, Deserialize)]
+struct RawFlood {
+    #[serde(default)]
+    categories: RawCategories,
+}
+
+#[derive(Default, Debug, Clone, Deserialize)]
+struct RawCategories {
+    action: Option<RawCategory>,
+    minor: Option<RawCategory>,
+    moderate: Option<RawCategory>,
+    major: Option<RawCategory>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawCategory {
+    stage: Option<f64>,
+}
+
+// gauge struct, a river gauge and its latest observed and forecast stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gauge {
+    pub id: String,
+    pub name: String,
+    pub coordinates: Coordinates,
+    pub observed: Option<Reading>,
+    pub forecast: Option<Reading>,
+}
+
+// reading struct, a stage and the flood category NWPS puts it in, like no_flooding, action, or minor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    pub stage: f64,
+    pub unit: String,
+    pub flood_category: String,
+    pub time: String,
+}
+
+// flood stages struct, the stages where each flood category begins, not every gauge has all of them
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FloodStages {
+    pub action: Option<f64>,
+    pub minor: Option<f64>,
+    pub moderate: Option<f64>,
+    pub major: Option<f64>,
+}
+
+impl Gauge {
+    pub fn distance_miles(&self, coordinates: Coordinates) -> f64 {
+        geocode::distance_miles(coordinates, self.coordinates)
+    }
+
+    // a forecast into flood from a lower category, a river already in flood isn't rising to it
+    pub fn rising_to_flood(&self) -> bool {
+        let observed = self
+            .observed
+            .as_ref()
+            .map(|reading| severity(&reading.flood_category))
+            .unwrap_or_default();
+
+        let forecast = self
+            .forecast
+            .as_ref()
+            .map(|reading| severity(&reading.flood_category))
+            .unwrap_or_default();
+
+        forecast >= severity("minor") && forecast > observed
+    }
+}
+
+// categories like not_defined and obs_not_current say nothing about flooding
+fn severity(flood_category: &str) -> u8 {
+    match flood_category {
+        "action" => 1,
+        "minor" => 2,
+        "moderate" => 3,
+        "major" => 4,
+        _ => 0,
+    }
+}
+
+fn value(value: Option<f64>) -> Option<f64> {
+    value.filter(|value| *value > MISSING_VALUE)
+}
+
+fn reading(raw: Option<RawReading>) -> Option<Reading> {
+    let raw = raw?;
+
+    Some(Reading {
+        stage: value(raw.primary)?,
+        unit: raw.primary_unit,
+        flood_category: raw.flood_category,
+        time: raw.valid_time,
+    })
+}
+
+// gauges in a box around the point, the caller filters by actual distance
+pub async fn get_gauges(
+    client: reqwest::Client,
+    coordinates: Coordinates,
+    radius_miles: f64,
+) -> Result<Vec<Gauge>, NwpsError> {
+    let latitude_delta = radius_miles / MILES_PER_DEGREE_LATITUDE;
+    let longitude_delta = radius_miles
+        / (MILES_PER_DEGREE_LATITUDE * coordinates.latitude.to_radians().cos().max(0.01));
+
+    let response_result = throttle::send(
+        client
+            .get(format!("{}/gauges", NWPS_URL))
+            .query(&[
+                ("bbox.xmin", coordinates.longitude - longitude_delta),
+                ("bbox.ymin", coordinates.latitude - latitude_delta),
+                ("bbox.xmax", coordinates.longitude + longitude_delta),
+                ("bbox.ymax", coordinates.latitude + latitude_delta),
+            ])
+            .query(&[("srid", "EPSG_4326")]),
+    )
+    .await
+    .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwpsError::Request(e)),
+    };
+
+    let raw = match response.json::<RawGauges>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(NwpsError::Decode(e)),
+    };
+
+    Ok(raw
+        .gauges
+        .into_iter()
+        .map(|gauge| Gauge {
+            id: gauge.lid,
+            name: gauge.name,
+            coordinates: Coordinates {
+                latitude: gauge.latitude,
+                longitude: gauge.longitude,
+            },
+            observed: reading(gauge.status.observed),
+            forecast: reading(gauge.status.forecast),
+        })
+        .collect())
+}
+
+pub async fn get_flood_stages(client: reqwest::Client, id: &str) -> Result<FloodStages, NwpsError> {
+    let response_result = throttle::send(client.get(format!("{}/gauges/{}", NWPS_URL, id)))
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwpsError::Request(e)),
+    };
+
+    let raw = match response.json::<RawGaugeDetail>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(NwpsError::Decode(e)),
+    };
+
+    let stage = |category: Option<RawCategory>| value(category.and_then(|category| category.stage));
+
+    Ok(FloodStages {
+        action: stage(raw.flood.categories.action),
+        minor: stage(raw.flood.categories.minor),
+        moderate: stage(raw.flood.categories.moderate),
+        major: stage(raw.flood.categories.major),
+    })
+}
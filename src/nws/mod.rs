@@ -0,0 +1,764 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED, USER_AGENT,
+    },
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use crate::{
+    breaker::{self, CircuitOpen},
+    geocode::Coordinates,
+    http_util, throttle,
+};
+
+// the CAP severities NWS alerts use, most severe first
+pub const ALERT_SEVERITIES: &[&str] = &["Extreme", "Severe", "Moderate", "Minor", "Unknown"];
+
+#[derive(Debug, Error)]
+pub enum NwsError {
+    #[error("error requesting api.weather.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding api.weather.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("no NWS forecast is available for this location")]
+    PointNotFound,
+    #[error("api.weather.gov response is missing {0}")]
+    MissingField(&'static str),
+    #[error("error parsing api.weather.gov response: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error(transparent)]
+    CircuitOpen(#[from] CircuitOpen),
+}
+
+impl NwsError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NwsError::Request(_) => "request",
+            NwsError::Decode(_) => "decode",
+            NwsError::PointNotFound => "point_not_found",
+            NwsError::MissingField(_) => "missing_field",
+            NwsError::Parse(_) => "parse",
+            NwsError::CircuitOpen(_) => "circuit_open",
+        }
+    }
+}
+
+// point struct, holds the gridpoint and forecast URLs for a location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub grid_id: String,
+    pub grid_x: i64,
+    pub grid_y: i64,
+    pub forecast: String,
+    pub forecast_hourly: String,
+    pub forecast_grid_data: String,
+    pub observation_stations: String,
+    // the zone fire weather forecasts are issued for, some offices don't issue them
+    pub fire_weather_zone: Option<String>,
+    // an IANA zone like America/Los_Angeles, points cached before it was kept don't have one
+    #[serde(default)]
+    pub time_zone: Option<String>,
+}
+
+impl Point {
+    pub fn gridpoint(&self) -> String {
+        format!("{}/{},{}", self.grid_id, self.grid_x, self.grid_y)
+    }
+}
+
+// forecast data struct, the periods of a forecast and when it was generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForecastData<T> {
+    pub generated_at: String,
+    // when the forecaster last edited the grid, generated_at is when NWS last rendered it
+    #[serde(default)]
+    pub update_time: Option<String>,
+    // when this service fetched it, for telling how old a cached forecast is
+    #[serde(default)]
+    pub fetched_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub validators: Validators,
+    pub periods: Vec<T>,
+}
+
+// validators struct, the cache validators NWS sent with a forecast, sent back so an unchanged
+// forecast comes back as a 304 instead of the whole payload
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Period {
+    pub detailed_forecast: String,
+    #[serde(with = "crate::timestamp")]
+    pub end_time: DateTime<FixedOffset>,
+    pub icon: String,
+    pub is_daytime: bool,
+    pub name: String,
+    pub number: i64,
+    pub dewpoint: Option<Dewpoint>,
+    pub relative_humidity: Option<RelativeHumidity>,
+    pub probability_of_precipitation: ProbabilityOfPrecipitation,
+    pub short_forecast: String,
+    #[serde(with = "crate::timestamp")]
+    pub start_time: DateTime<FixedOffset>,
+    pub temperature: i64,
+    pub temperature_trend: Option<String>,
+    pub temperature_unit: String,
+    pub wind_direction: String,
+    pub wind_speed: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HourlyPeriod {
+    pub dewpoint: Dewpoint,
+    #[serde(with = "crate::timestamp")]
+    pub end_time: DateTime<FixedOffset>,
+    pub icon: String,
+    pub is_daytime: bool,
+    pub number: i64,
+    pub probability_of_precipitation: ProbabilityOfPrecipitation,
+    pub relative_humidity: RelativeHumidity,
+    pub short_forecast: String,
+    #[serde(with = "crate::timestamp")]
+    pub start_time: DateTime<FixedOffset>,
+    pub temperature: i64,
+    pub temperature_unit: String,
+    pub wind_direction: String,
+    pub wind_speed: String,
+}
+
+// forecast period trait, what a cached forecast needs from its periods to tell whether it's stale
+pub trait ForecastPeriod {
+    fn end_time(&self) -> Option<DateTime<FixedOffset>>;
+}
+
+impl ForecastPeriod for Period {
+    fn end_time(&self) -> Option<DateTime<FixedOffset>> {
+        Some(self.end_time)
+    }
+}
+
+impl ForecastPeriod for HourlyPeriod {
+    fn end_time(&self) -> Option<DateTime<FixedOffset>> {
+        Some(self.end_time)
+    }
+}
+
+// zone periods are named, like Tonight, and have no times
+impl ForecastPeriod for ZonePeriod {
+    fn end_time(&self) -> Option<DateTime<FixedOffset>> {
+        None
+    }
+}
+
+impl<T: ForecastPeriod> ForecastData<T> {
+    // stale when the forecaster hasn't updated it in max_age, or its first period is already over
+    pub fn is_stale(&self, max_age: chrono::TimeDelta, now: DateTime<Utc>) -> bool {
+        let outdated = self
+            .update_time
+            .as_deref()
+            .and_then(|update_time| DateTime::parse_from_rfc3339(update_time).ok())
+            .is_some_and(|update_time| now - update_time.to_utc() > max_age);
+
+        let over = self
+            .periods
+            .first()
+            .and_then(|period| period.end_time())
+            .is_some_and(|end_time| end_time.to_utc() <= now);
+
+        outdated || over
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dewpoint {
+    pub unit_code: String,
+    pub value: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbabilityOfPrecipitation {
+    pub unit_code: String,
+    pub value: Option<i64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelativeHumidity {
+    pub unit_code: String,
+    pub value: i64,
+}
+
+// zone period struct, one period of a zone's text forecast
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZonePeriod {
+    pub number: i64,
+    pub name: String,
+    #[serde(alias = "detailedForecast")]
+    pub detailed_forecast: String,
+}
+
+// station struct, an observation station near a point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Station {
+    pub id: String,
+    pub name: String,
+    pub coordinates: Coordinates,
+}
+
+// observation struct, a station's reading, anything the station didn't measure has a null value
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Observation {
+    pub timestamp: String,
+    #[serde(default)]
+    pub text_description: String,
+    #[serde(default)]
+    pub temperature: Measurement,
+    #[serde(default)]
+    pub dewpoint: Measurement,
+    #[serde(default)]
+    pub relative_humidity: Measurement,
+    #[serde(default)]
+    pub wind_direction: Measurement,
+    #[serde(default)]
+    pub wind_speed: Measurement,
+    #[serde(default)]
+    pub wind_gust: Measurement,
+    #[serde(default)]
+    pub barometric_pressure: Measurement,
+    #[serde(default)]
+    pub visibility: Measurement,
+    #[serde(default)]
+    pub heat_index: Measurement,
+    #[serde(default)]
+    pub wind_chill: Measurement,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Measurement {
+    #[serde(default)]
+    pub unit_code: String,
+    pub value: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertFeature {
+    // the GeoJSON polygon for storm based warnings, null for zone and county alerts
+    #[serde(default)]
+    pub geometry: Option<serde_json::Value>,
+    pub properties: AlertProperties,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertProperties {
+    pub id: String,
+    pub area_desc: String,
+    pub onset: Option<String>,
+    pub ends: Option<String>,
+    pub expires: String,
+    pub status: String,
+    pub message_type: String,
+    pub category: String,
+    pub severity: String,
+    pub certainty: String,
+    pub urgency: String,
+    pub event: String,
+    pub sender_name: String,
+    pub headline: Option<String>,
+    pub description: String,
+    pub instruction: Option<String>,
+    // earlier versions of this alert, updates and cancellations point back at what they replace
+    #[serde(default)]
+    pub references: Vec<AlertReference>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertReference {
+    pub identifier: String,
+}
+
+// alert filter struct, the severities and event names to keep, an empty list keeps everything
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub severity: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub event: Vec<String>,
+}
+
+impl AlertFilter {
+    // severities are matched case insensitively and kept in the NWS spelling, since the API's filter is case sensitive
+    pub fn new(severity: &[String], event: &[String]) -> Result<Self, String> {
+        let mut severities = Vec::new();
+
+        for name in severity
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+        {
+            match ALERT_SEVERITIES
+                .iter()
+                .find(|severity| severity.eq_ignore_ascii_case(name))
+            {
+                Some(severity) => severities.push(severity.to_string()),
+                None => {
+                    return Err(format!(
+                        "severity {} must be one of {}",
+                        name,
+                        ALERT_SEVERITIES.join(", ").to_lowercase()
+                    ))
+                }
+            }
+        }
+
+        let events = event
+            .iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        Ok(Self {
+            severity: severities,
+            event: events,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.severity.is_empty() && self.event.is_empty()
+    }
+
+    pub fn matches(&self, properties: &AlertProperties) -> bool {
+        (self.severity.is_empty() || self.severity.contains(&properties.severity))
+            && (self.event.is_empty()
+                || self
+                    .event
+                    .iter()
+                    .any(|event| event.eq_ignore_ascii_case(&properties.event)))
+    }
+}
+
+pub async fn get_point(
+    client: reqwest::Client,
+    coordinates: Coordinates,
+) -> Result<Point, NwsError> {
+    let point_url = format!(
+        "https://api.weather.gov/points/{:.5},{:.5}",
+        coordinates.latitude, coordinates.longitude
+    );
+
+    breaker::NWS.check()?;
+
+    let point_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(point_url).headers(nws_headers()),
+        throttle::send,
+    )
+    .await;
+
+    let point_response = match point_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    if point_response.status() == StatusCode::NOT_FOUND {
+        return Err(NwsError::PointNotFound);
+    }
+
+    let point_json_result = point_response.json::<serde_json::Value>().await;
+
+    let point_json = match point_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Decode(e)),
+    };
+
+    let properties = &point_json["properties"];
+
+    let grid_id = match properties["gridId"].as_str() {
+        Some(grid_id) => grid_id.to_string(),
+        None => return Err(NwsError::MissingField("grid ID")),
+    };
+
+    let (grid_x, grid_y) = match (properties["gridX"].as_i64(), properties["gridY"].as_i64()) {
+        (Some(grid_x), Some(grid_y)) => (grid_x, grid_y),
+        _ => return Err(NwsError::MissingField("grid coordinates")),
+    };
+
+    let forecast_url = match properties["forecast"].as_str() {
+        Some(forecast_url) => forecast_url.to_string(),
+        None => return Err(NwsError::MissingField("forecast URL")),
+    };
+
+    let forecast_hourly_url = match properties["forecastHourly"].as_str() {
+        Some(forecast_hourly_url) => forecast_hourly_url.to_string(),
+        None => return Err(NwsError::MissingField("hourly forecast URL")),
+    };
+
+    let forecast_grid_data_url = match properties["forecastGridData"].as_str() {
+        Some(forecast_grid_data_url) => forecast_grid_data_url.to_string(),
+        None => return Err(NwsError::MissingField("forecast grid data URL")),
+    };
+
+    let observation_stations_url = match properties["observationStations"].as_str() {
+        Some(observation_stations_url) => observation_stations_url.to_string(),
+        None => return Err(NwsError::MissingField("observation stations URL")),
+    };
+
+    let fire_weather_zone_url = properties["fireWeatherZone"]
+        .as_str()
+        .map(|fire_weather_zone_url| fire_weather_zone_url.to_string());
+
+    let time_zone = properties["timeZone"]
+        .as_str()
+        .map(|time_zone| time_zone.to_string());
+
+    info!("forecast URL: {}", forecast_url);
+
+    Ok(Point {
+        grid_id,
+        grid_x,
+        grid_y,
+        forecast: forecast_url,
+        forecast_hourly: forecast_hourly_url,
+        forecast_grid_data: forecast_grid_data_url,
+        observation_stations: observation_stations_url,
+        fire_weather_zone: fire_weather_zone_url,
+        time_zone,
+    })
+}
+
+// forecasts are us units unless asked for si, which also changes the wording of the text forecast
+pub fn forecast_url(forecast_url: &str, units: &str) -> String {
+    match units {
+        "si" => format!("{}?units=si", forecast_url),
+        _ => forecast_url.to_string(),
+    }
+}
+
+// with a cached copy the request is conditional, and the cached copy is what comes back when
+// NWS says it hasn't changed
+pub async fn get_forecast<T: DeserializeOwned>(
+    client: reqwest::Client,
+    forecast_url: String,
+    cached: Option<ForecastData<T>>,
+) -> Result<ForecastData<T>, NwsError> {
+    let mut headers = nws_headers();
+
+    if let Some(cached) = &cached {
+        let validators = [
+            (IF_NONE_MATCH, &cached.validators.etag),
+            (IF_MODIFIED_SINCE, &cached.validators.last_modified),
+        ];
+
+        for (name, value) in validators {
+            if let Some(value) = value
+                .as_deref()
+                .and_then(|value| HeaderValue::from_str(value).ok())
+            {
+                headers.insert(name, value);
+            }
+        }
+    }
+
+    breaker::NWS.check()?;
+
+    let forecast_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(forecast_url).headers(headers),
+        throttle::send,
+    )
+    .await;
+
+    let forecast_response = match forecast_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    if let (StatusCode::NOT_MODIFIED, Some(cached)) = (forecast_response.status(), cached) {
+        return Ok(ForecastData {
+            fetched_at: Some(Utc::now()),
+            ..cached
+        });
+    }
+
+    let header = |name| {
+        forecast_response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+
+    let validators = Validators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+
+    let forecast_json_result = forecast_response.json::<serde_json::Value>().await;
+
+    let forecast_json = match forecast_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Decode(e)),
+    };
+
+    let periods_json = forecast_json["properties"]["periods"].clone();
+
+    if periods_json.is_null() {
+        return Err(NwsError::MissingField("forecast periods"));
+    }
+
+    let periods: Vec<T> = serde_json::from_value(periods_json)?;
+
+    // zone forecasts call it updated
+    let generated_at = forecast_json["properties"]["generatedAt"]
+        .as_str()
+        .or(forecast_json["properties"]["updated"].as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let update_time = forecast_json["properties"]["updateTime"]
+        .as_str()
+        .map(|update_time| update_time.to_string());
+
+    Ok(ForecastData {
+        generated_at,
+        update_time,
+        fetched_at: Some(Utc::now()),
+        validators,
+        periods,
+    })
+}
+
+// the properties of the raw gridpoint forecast, parsed by the gridpoint module
+pub async fn get_grid_data(
+    client: reqwest::Client,
+    grid_data_url: String,
+) -> Result<serde_json::Value, NwsError> {
+    breaker::NWS.check()?;
+
+    let grid_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(grid_data_url).headers(nws_headers()),
+        throttle::send,
+    )
+    .await;
+
+    let grid_response = match grid_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    let grid_json_result = grid_response.json::<serde_json::Value>().await;
+
+    let grid_json = match grid_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Decode(e)),
+    };
+
+    match grid_json.get("properties") {
+        Some(properties) => Ok(properties.to_owned()),
+        None => Err(NwsError::MissingField("gridpoint properties")),
+    }
+}
+
+// stations come back nearest first, though callers shouldn't rely on it
+pub async fn get_stations(
+    client: reqwest::Client,
+    stations_url: String,
+) -> Result<Vec<Station>, NwsError> {
+    breaker::NWS.check()?;
+
+    let stations_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(stations_url).headers(nws_headers()),
+        throttle::send,
+    )
+    .await;
+
+    let stations_response = match stations_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    let stations_json_result = stations_response.json::<serde_json::Value>().await;
+
+    let stations_json = match stations_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Decode(e)),
+    };
+
+    let features = match stations_json["features"].as_array() {
+        Some(features) => features,
+        None => return Err(NwsError::MissingField("station features")),
+    };
+
+    // GeoJSON points are longitude first, stations without one can't be ranked and are skipped
+    let stations = features
+        .iter()
+        .filter_map(|feature| {
+            let position = feature["geometry"]["coordinates"].as_array()?;
+
+            Some(Station {
+                id: feature["properties"]["stationIdentifier"]
+                    .as_str()?
+                    .to_string(),
+                name: feature["properties"]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                coordinates: Coordinates {
+                    latitude: position.get(1)?.as_f64()?,
+                    longitude: position.first()?.as_f64()?,
+                },
+            })
+        })
+        .collect();
+
+    Ok(stations)
+}
+
+pub async fn get_latest_observation(
+    client: reqwest::Client,
+    station_id: &str,
+) -> Result<Observation, NwsError> {
+    let observation_url = format!(
+        "https://api.weather.gov/stations/{}/observations/latest",
+        station_id
+    );
+
+    breaker::NWS.check()?;
+
+    let observation_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(observation_url).headers(nws_headers()),
+        throttle::send,
+    )
+    .await
+    .and_then(|response| response.error_for_status());
+
+    let observation_response = match observation_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    let observation_json_result = observation_response.json::<serde_json::Value>().await;
+
+    let observation_json = match observation_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Decode(e)),
+    };
+
+    match observation_json.get("properties") {
+        Some(properties) => Ok(serde_json::from_value(properties.to_owned())?),
+        None => Err(NwsError::MissingField("observation properties")),
+    }
+}
+
+// the filter narrows the request and is applied again to the response, NWS matches event names exactly
+pub async fn get_active_alerts(
+    client: reqwest::Client,
+    coordinates: Coordinates,
+    filter: &AlertFilter,
+) -> Result<Vec<AlertFeature>, NwsError> {
+    let alerts_url = format!(
+        "https://api.weather.gov/alerts/active?point={:.4},{:.4}",
+        coordinates.latitude, coordinates.longitude
+    );
+
+    let mut query = Vec::new();
+
+    if !filter.severity.is_empty() {
+        query.push(("severity", filter.severity.join(",")));
+    }
+    if !filter.event.is_empty() {
+        query.push(("event", filter.event.join(",")));
+    }
+
+    breaker::NWS.check()?;
+
+    let alerts_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(alerts_url).query(&query).headers(nws_headers()),
+        throttle::send,
+    )
+    .await;
+
+    let alerts_response = match alerts_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    let alerts_json_result = alerts_response.json::<serde_json::Value>().await;
+
+    let alerts_json = match alerts_json_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Decode(e)),
+    };
+
+    let features_json = match alerts_json.get("features") {
+        Some(features) => features.to_owned(),
+        None => return Err(NwsError::MissingField("alert features")),
+    };
+
+    let mut alerts: Vec<AlertFeature> = serde_json::from_value(features_json)?;
+
+    alerts.retain(|alert| filter.matches(&alert.properties));
+
+    Ok(alerts)
+}
+
+// the CAP 1.2 document behind an alert, which keeps the polygon, VTEC codes, and instruction text as issued
+pub async fn get_alert_cap(client: reqwest::Client, alert_id: &str) -> Result<String, NwsError> {
+    let cap_url = format!("https://api.weather.gov/alerts/{}", alert_id);
+
+    let mut headers = nws_headers();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/cap+xml"));
+
+    breaker::NWS.check()?;
+
+    let cap_response_result = http_util::send(
+        &breaker::NWS,
+        client.get(cap_url).headers(headers),
+        throttle::send,
+    )
+    .await
+    .and_then(|response| response.error_for_status());
+
+    let cap_response = match cap_response_result {
+        Ok(body) => body,
+        Err(e) => return Err(NwsError::Request(e)),
+    };
+
+    match cap_response.text().await {
+        Ok(body) => Ok(body),
+        Err(e) => Err(NwsError::Decode(e)),
+    }
+}
+
+fn nws_headers() -> HeaderMap {
+    let mut header_map = HeaderMap::new();
+    header_map.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/geojson"),
+    );
+    header_map.insert(
+        USER_AGENT,
+        HeaderValue::from_static("nws-forecast-summarizer - michael@michaelpeterswa.com"),
+    );
+
+    header_map
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::nws::{Measurement, Observation};
+
+const KILOMETERS_PER_MILE: f64 = 1.609344;
+const METERS_PER_MILE: f64 = 1609.344;
+const PASCALS_PER_INCH_OF_MERCURY: f64 = 3386.389;
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+// conditions struct, an observation in the requested units. temperatures are F or C, wind mph or km/h,
+// pressure inHg or hPa, and visibility miles or kilometers
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    pub description: String,
+    pub temperature: Option<i64>,
+    pub feels_like: Option<i64>,
+    pub dewpoint: Option<i64>,
+    pub relative_humidity: Option<i64>,
+    pub wind_speed: Option<i64>,
+    pub wind_gust: Option<i64>,
+    pub wind_direction: Option<String>,
+    pub pressure: Option<f64>,
+    pub visibility: Option<f64>,
+}
+
+// units is us or si, measurements in units the station shouldn't report are left out rather than guessed at
+pub fn conditions(observation: &Observation, units: &str) -> Conditions {
+    let wind_speed = speed(&observation.wind_speed, units);
+
+    // calm wind is reported with a direction of zero, which would read as north
+    let wind_direction = match wind_speed {
+        Some(0) => None,
+        _ => direction(&observation.wind_direction),
+    };
+
+    Conditions {
+        description: observation.text_description.to_owned(),
+        temperature: temperature(&observation.temperature, units),
+        // heat index and wind chill are only reported when they differ from the temperature
+        feels_like: temperature(&observation.heat_index, units)
+            .or(temperature(&observation.wind_chill, units)),
+        dewpoint: temperature(&observation.dewpoint, units),
+        relative_humidity: percent(&observation.relative_humidity),
+        wind_speed,
+        wind_gust: speed(&observation.wind_gust, units),
+        wind_direction,
+        pressure: pressure(&observation.barometric_pressure, units),
+        visibility: visibility(&observation.visibility, units),
+    }
+}
+
+fn temperature(measurement: &Measurement, units: &str) -> Option<i64> {
+    let celsius = match measurement.unit_code.as_str() {
+        "wmoUnit:degC" => measurement.value?,
+        "wmoUnit:degF" => (measurement.value? - 32.0) * 5.0 / 9.0,
+        _ => return None,
+    };
+
+    let temperature = match units {
+        "si" => celsius,
+        _ => celsius * 9.0 / 5.0 + 32.0,
+    };
+
+    Some(temperature.round() as i64)
+}
+
+fn speed(measurement: &Measurement, units: &str) -> Option<i64> {
+    let kilometers_per_hour = match measurement.unit_code.as_str() {
+        "wmoUnit:km_h-1" => measurement.value?,
+        "wmoUnit:m_s-1" => measurement.value? * 3.6,
+        _ => return None,
+    };
+
+    let speed = match units {
+        "si" => kilometers_per_hour,
+        _ => kilometers_per_hour / KILOMETERS_PER_MILE,
+    };
+
+    Some(speed.round() as i64)
+}
+
+fn percent(measurement: &Measurement) -> Option<i64> {
+    match measurement.unit_code.as_str() {
+        "wmoUnit:percent" => Some(measurement.value?.round() as i64),
+        _ => None,
+    }
+}
+
+fn direction(measurement: &Measurement) -> Option<String> {
+    let degrees = match measurement.unit_code.as_str() {
+        "wmoUnit:degree_(angle)" => measurement.value?,
+        _ => return None,
+    };
+
+    let point = (degrees.rem_euclid(360.0) / 22.5).round() as usize % COMPASS_POINTS.len();
+
+    Some(COMPASS_POINTS[point].to_string())
+}
+
+fn pressure(measurement: &Measurement, units: &str) -> Option<f64> {
+    let pascals = match measurement.unit_code.as_str() {
+        "wmoUnit:Pa" => measurement.value?,
+        _ => return None,
+    };
+
+    Some(match units {
+        "si" => round(pascals / 100.0, 1),
+        _ => round(pascals / PASCALS_PER_INCH_OF_MERCURY, 2),
+    })
+}
+
+fn visibility(measurement: &Measurement, units: &str) -> Option<f64> {
+    let meters = match measurement.unit_code.as_str() {
+        "wmoUnit:m" => measurement.value?,
+        _ => return None,
+    };
+
+    Some(match units {
+        "si" => round(meters / 1000.0, 1),
+        _ => round(meters / METERS_PER_MILE, 1),
+    })
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let scale = 10_f64.powi(places);
+
+    (value * scale).round() / scale
+}
@@ -1,7 +1,460 @@
-use ollama_rs::Ollama;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-pub fn connect(host: String, port: u16) -> Ollama {
-    let client = Ollama::new(host, port);
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use ollama_rs::{
+    error::OllamaError,
+    generation::{
+        chat::{request::ChatMessageRequest, ChatMessage},
+        completion::request::GenerationRequest,
+        options::GenerationOptions,
+        parameters::{FormatType, KeepAlive, TimeUnit},
+    },
+    Ollama,
+};
+use prometheus::{opts, register_gauge_vec, GaugeVec};
+use tracing::{debug, info, warn};
 
-    client
+use crate::{
+    breaker, http_util,
+    llm::{ChatTurn, LlmBackend, LlmError, Role, SummaryStream},
+};
+
+lazy_static! {
+    pub static ref OLLAMA_HOST_HEALTHY_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "ollama_host_healthy",
+            "whether an ollama host passed its last health check"
+        ),
+        &["host"]
+    )
+    .unwrap();
+}
+
+// ollama host struct, a single server and whether it is currently accepting requests
+struct OllamaHost {
+    connection: Ollama,
+    healthy: AtomicBool,
+}
+
+impl OllamaHost {
+    fn set_healthy(&self, healthy: bool) {
+        self.healthy.store(healthy, Ordering::Relaxed);
+
+        OLLAMA_HOST_HEALTHY_GAUGE
+            .with_label_values(&[self.connection.url_str()])
+            .set(if healthy { 1.0 } else { 0.0 });
+    }
+}
+
+// seconds to keep the model loaded, -1 keeps it loaded indefinitely and 0 unloads it after each request
+pub fn keep_alive(seconds: i64) -> KeepAlive {
+    match seconds {
+        seconds if seconds < 0 => KeepAlive::Indefinitely,
+        0 => KeepAlive::UnloadOnCompletion,
+        seconds => KeepAlive::Until {
+            time: seconds as u64,
+            unit: TimeUnit::Seconds,
+        },
+    }
+}
+
+// options left unset fall back to the model's defaults
+pub fn generation_options(
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    seed: Option<i32>,
+    num_predict: Option<i32>,
+) -> Option<GenerationOptions> {
+    if temperature.is_none() && top_p.is_none() && seed.is_none() && num_predict.is_none() {
+        return None;
+    }
+
+    let mut options = GenerationOptions::default();
+
+    if let Some(temperature) = temperature {
+        options = options.temperature(temperature);
+    }
+    if let Some(top_p) = top_p {
+        options = options.top_p(top_p);
+    }
+    if let Some(seed) = seed {
+        options = options.seed(seed);
+    }
+    if let Some(num_predict) = num_predict {
+        options = options.num_predict(num_predict);
+    }
+
+    Some(options)
+}
+
+pub fn connect(hosts: &[String], client: reqwest::Client) -> Vec<Ollama> {
+    hosts
+        .iter()
+        .map(|host| {
+            let url = reqwest::Url::parse(host)
+                .unwrap_or_else(|e| panic!("{} is not a valid ollama host: {}", host, e));
+            let port = url
+                .port_or_known_default()
+                .unwrap_or_else(|| panic!("{} is not a valid ollama host: no port", host));
+
+            Ollama::new_with_client(url, port, client.clone())
+        })
+        .collect()
+}
+
+// ollama backend, round-robins requests across one or more ollama servers
+pub struct OllamaBackend {
+    hosts: Arc<Vec<OllamaHost>>,
+    next: AtomicUsize,
+    model: String,
+    keep_alive: Option<KeepAlive>,
+    options: Option<GenerationOptions>,
+}
+
+impl OllamaBackend {
+    pub fn new(connections: Vec<Ollama>, model: String) -> Self {
+        let hosts = connections
+            .into_iter()
+            .map(|connection| {
+                let host = OllamaHost {
+                    connection,
+                    healthy: AtomicBool::new(true),
+                };
+                host.set_healthy(true);
+                host
+            })
+            .collect();
+
+        Self {
+            hosts: Arc::new(hosts),
+            next: AtomicUsize::new(0),
+            model,
+            keep_alive: None,
+            options: None,
+        }
+    }
+
+    pub fn with_keep_alive(mut self, keep_alive: Option<KeepAlive>) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn with_options(mut self, options: Option<GenerationOptions>) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn request(&self, model: &str, messages: Vec<ChatMessage>) -> ChatMessageRequest {
+        // logged so a summary can be reproduced with the same model and options
+        debug!(model = model, options = ?self.options, "sending ollama chat request");
+
+        let request = ChatMessageRequest::new(model.to_string(), messages);
+
+        match &self.options {
+            Some(options) => request.options(options.clone()),
+            None => request,
+        }
+    }
+
+    // loads the model on every healthy host so the first request doesn't wait on it
+    pub async fn warm_up(&self) {
+        for host in self.hosts.iter() {
+            if !host.healthy.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            info!(
+                "warming up model {} on ollama host {}",
+                self.model,
+                host.connection.url_str()
+            );
+
+            if let Err(e) = load_model(host, &self.model, self.keep_alive.clone()).await {
+                warn!(
+                    "error warming up ollama host {}: {}",
+                    host.connection.url_str(),
+                    e
+                );
+            }
+        }
+    }
+
+    // checks every reachable host has the models, pulling them when allowed, so a missing model fails at startup
+    pub async fn ensure_models(&self, models: &[String], pull: bool) -> Result<(), LlmError> {
+        let mut reachable = 0;
+        let mut last_error = None;
+
+        for host in self.hosts.iter() {
+            let url = host.connection.url_str();
+
+            let local_models = match host.connection.list_local_models().await {
+                Ok(local_models) => local_models,
+                Err(e) => {
+                    warn!("ollama host {} is unreachable: {}", url, e);
+                    host.set_healthy(false);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            reachable += 1;
+
+            for model in models {
+                if local_models
+                    .iter()
+                    .any(|local_model| model_matches(&local_model.name, model))
+                {
+                    continue;
+                }
+
+                if !pull {
+                    return Err(LlmError::ModelNotFound(format!(
+                        "{} on ollama host {}",
+                        model, url
+                    )));
+                }
+
+                info!("pulling model {} on ollama host {}", model, url);
+
+                host.connection.pull_model(model.clone(), false).await?;
+            }
+        }
+
+        match (reachable, last_error) {
+            (0, Some(e)) => Err(LlmError::Ollama(e)),
+            _ => Ok(()),
+        }
+    }
+
+    // periodically lists models on every host, failing hosts are skipped until they recover.
+    // chat requests can't carry keep_alive, so it is reapplied here to stop them resetting it
+    pub fn spawn_health_checks(&self, interval: Duration) {
+        let hosts = self.hosts.clone();
+        let model = self.model.clone();
+        let keep_alive = self.keep_alive.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                for host in hosts.iter() {
+                    match host.connection.list_local_models().await {
+                        Ok(_) => {
+                            host.set_healthy(true);
+
+                            if keep_alive.is_some() {
+                                if let Err(e) = load_model(host, &model, keep_alive.clone()).await {
+                                    warn!(
+                                        "error refreshing keep_alive on ollama host {}: {}",
+                                        host.connection.url_str(),
+                                        e
+                                    );
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            if host.healthy.load(Ordering::Relaxed) {
+                                warn!(
+                                    "ollama host {} failed health check: {}",
+                                    host.connection.url_str(),
+                                    e
+                                );
+                            }
+                            host.set_healthy(false);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // healthy hosts in round-robin order, falling back to every host when none are healthy
+    fn candidates(&self) -> Vec<&OllamaHost> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let count = self.hosts.len();
+
+        let ordered: Vec<&OllamaHost> = (0..count)
+            .map(|offset| &self.hosts[(start + offset) % count])
+            .collect();
+
+        let healthy: Vec<&OllamaHost> = ordered
+            .iter()
+            .copied()
+            .filter(|host| host.healthy.load(Ordering::Relaxed))
+            .collect();
+
+        match healthy.is_empty() {
+            true => ordered,
+            false => healthy,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OllamaBackend {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    // ready when any host answers with the model pulled, requests fail over to that one
+    async fn ready(&self) -> Result<(), LlmError> {
+        let mut last_error = None;
+
+        for host in self.hosts.iter() {
+            let url = host.connection.url_str();
+
+            match host.connection.list_local_models().await {
+                Ok(local_models)
+                    if local_models
+                        .iter()
+                        .any(|local_model| model_matches(&local_model.name, &self.model)) =>
+                {
+                    return Ok(())
+                }
+                Ok(_) => {
+                    last_error = Some(LlmError::ModelNotFound(format!(
+                        "{} on ollama host {}",
+                        self.model, url
+                    )))
+                }
+                Err(e) => last_error = Some(LlmError::Ollama(e)),
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Err(LlmError::Stream),
+        }
+    }
+
+    async fn chat_json(&self, model: &str, messages: Vec<ChatTurn>) -> Result<String, LlmError> {
+        let messages = chat_messages(messages);
+        let mut last_error = None;
+
+        breaker::OLLAMA.check()?;
+
+        for host in self.candidates() {
+            let result = http_util::retry(&breaker::OLLAMA, is_transient, || {
+                let request = self
+                    .request(model, messages.clone())
+                    .format(FormatType::Json);
+
+                host.connection.send_chat_messages(request)
+            })
+            .await;
+
+            match result {
+                Ok(chat) => return Ok(chat.message.content),
+                Err(e) => {
+                    warn!("ollama host {} failed: {}", host.connection.url_str(), e);
+                    host.set_healthy(false);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(LlmError::Ollama(e)),
+            None => Err(LlmError::Stream),
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<ChatTurn>,
+    ) -> Result<SummaryStream, LlmError> {
+        let messages = chat_messages(messages);
+        let mut last_error = None;
+
+        breaker::OLLAMA.check()?;
+
+        // failover only happens before the first token, a stream that breaks midway is an error
+        for host in self.candidates() {
+            let result = http_util::retry(&breaker::OLLAMA, is_transient, || {
+                host.connection
+                    .send_chat_messages_stream(self.request(model, messages.clone()))
+            })
+            .await;
+
+            match result {
+                Ok(stream) => {
+                    let tokens = stream.map(|response| match response {
+                        Ok(response) => Ok(response.message.content),
+                        Err(_) => Err(LlmError::Stream),
+                    });
+
+                    return Ok(Box::pin(tokens));
+                }
+                Err(e) => {
+                    warn!("ollama host {} failed: {}", host.connection.url_str(), e);
+                    host.set_healthy(false);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(LlmError::Ollama(e)),
+            None => Err(LlmError::Stream),
+        }
+    }
+}
+
+// ollama only passes through the reqwest error when the request didn't get a response
+fn is_transient<T>(result: &Result<T, OllamaError>) -> bool {
+    match result {
+        Err(OllamaError::ReqwestError(e)) => http_util::is_transient_error(e),
+        _ => false,
+    }
+}
+
+// an empty prompt loads the model without generating anything
+async fn load_model(
+    host: &OllamaHost,
+    model: &str,
+    keep_alive: Option<KeepAlive>,
+) -> Result<(), OllamaError> {
+    let mut request = GenerationRequest::new(model.to_string(), "");
+
+    if let Some(keep_alive) = keep_alive {
+        request = request.keep_alive(keep_alive);
+    }
+
+    host.connection.generate(request).await?;
+
+    Ok(())
+}
+
+// ollama reports names with a tag, so "llama3" matches "llama3:latest"
+fn model_matches(name: &str, model: &str) -> bool {
+    match model.contains(':') {
+        true => name == model,
+        false => name == model || name == format!("{}:latest", model),
+    }
+}
+
+fn chat_messages(messages: Vec<ChatTurn>) -> Vec<ChatMessage> {
+    messages
+        .into_iter()
+        .map(|message| match message.role {
+            Role::System => ChatMessage::system(message.content),
+            Role::User => ChatMessage::user(message.content),
+            Role::Assistant => ChatMessage::assistant(message.content),
+        })
+        .collect()
 }
@@ -0,0 +1,243 @@
+use async_stream::stream;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::llm::{self, ChatTurn, LlmBackend, LlmError, Role, SummaryStream};
+
+// openai backend, works with any server implementing the OpenAI chat completions API
+pub struct OpenAiBackend {
+    name: &'static str,
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    json_mode: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<Message>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    format_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletion {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: Delta,
+}
+
+#[derive(Debug, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+impl OpenAiBackend {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: String,
+        api_key: Option<String>,
+        model: String,
+    ) -> Self {
+        Self {
+            name: "openai",
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+            json_mode: true,
+        }
+    }
+
+    // local inference servers (vLLM, llama.cpp server, LM Studio) that speak the same API,
+    // not all of them accept a json_object response format so it can be turned off
+    pub fn compatible(
+        client: reqwest::Client,
+        base_url: String,
+        api_key: Option<String>,
+        model: String,
+        json_mode: bool,
+    ) -> Self {
+        Self {
+            name: "openai_compatible",
+            json_mode,
+            ..Self::new(client, base_url, api_key, model)
+        }
+    }
+
+    async fn send(&self, request: &ChatCompletionRequest) -> Result<reqwest::Response, LlmError> {
+        let mut builder = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(request);
+
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = builder.send().await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+
+            return Err(LlmError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiBackend {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    // compatible servers often list models under a different name than they answer to, so only
+    // the listing itself is checked
+    async fn ready(&self) -> Result<(), LlmError> {
+        let mut builder = self.client.get(format!("{}/models", self.base_url));
+
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = builder.send().await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+
+            return Err(LlmError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn chat_json(&self, model: &str, messages: Vec<ChatTurn>) -> Result<String, LlmError> {
+        let request = ChatCompletionRequest {
+            model: model.to_string(),
+            messages: chat_messages(messages),
+            stream: false,
+            response_format: self.json_mode.then(|| ResponseFormat {
+                format_type: "json_object".to_string(),
+            }),
+        };
+
+        let completion = self.send(&request).await?.json::<ChatCompletion>().await?;
+
+        match completion.choices.into_iter().next() {
+            Some(choice) => Ok(choice.message.content),
+            None => Err(LlmError::EmptySummary),
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<ChatTurn>,
+    ) -> Result<SummaryStream, LlmError> {
+        let request = ChatCompletionRequest {
+            model: model.to_string(),
+            messages: chat_messages(messages),
+            stream: true,
+            response_format: None,
+        };
+
+        let response = self.send(&request).await?;
+
+        let mut data = Box::pin(llm::sse_data(response.bytes_stream()));
+
+        // one JSON chunk per data line until [DONE]
+        let tokens = stream! {
+            while let Some(data) = data.next().await {
+                let data = match data {
+                    Ok(data) => data,
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                };
+
+                if data == "[DONE]" {
+                    return;
+                }
+
+                match serde_json::from_str::<ChatCompletionChunk>(&data) {
+                    Ok(chunk) => {
+                        let content = chunk
+                            .choices
+                            .into_iter()
+                            .next()
+                            .and_then(|choice| choice.delta.content);
+
+                        if let Some(content) = content {
+                            yield Ok(content);
+                        }
+                    }
+                    Err(e) => debug!("error decoding chat completion chunk: {}", e),
+                }
+            }
+        };
+
+        Ok(Box::pin(tokens))
+    }
+}
+
+fn chat_messages(messages: Vec<ChatTurn>) -> Vec<Message> {
+    messages
+        .into_iter()
+        .map(|message| Message {
+            role: match message.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            }
+            .to_string(),
+            content: message.content,
+        })
+        .collect()
+}
@@ -0,0 +1,612 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use tera::{Context, Tera};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::llm::NShotInOut;
+
+pub const FORECAST: &str = "forecast";
+pub const HOURLY_FORECAST: &str = "hourly_forecast";
+pub const ALERTS: &str = "alerts";
+pub const SHORT_FORECAST: &str = "short_forecast";
+pub const CURRENT: &str = "current";
+pub const AVIATION: &str = "aviation";
+pub const FIRE_WEATHER: &str = "fire_weather";
+pub const TROPICAL: &str = "tropical";
+pub const AIR_QUALITY: &str = "air_quality";
+pub const TIDES: &str = "tides";
+
+pub const SNOW_FOCUS: &str = "snow";
+pub const STARGAZING_FOCUS: &str = "stargazing";
+pub const RIVERS: &str = "rivers";
+
+pub const DEFAULT_STYLE: &str = "detailed";
+
+// tones are fixed rather than free text, so a request can't smuggle instructions in through them
+pub const TONES: &[(&str, &str)] = &[
+    ("neutral", ""),
+    (
+        "cheerful",
+        "Use an upbeat, cheerful tone, but keep bad weather clearly stated.",
+    ),
+    (
+        "deadpan",
+        "Use a dry, deadpan tone with understated wording.",
+    ),
+    (
+        "news-anchor",
+        "Write it the way a television news anchor would read the weather segment.",
+    ),
+];
+
+pub fn tone_instructions(name: &str) -> Option<&'static str> {
+    TONES
+        .iter()
+        .find(|(tone, _)| *tone == name)
+        .map(|(_, instructions)| *instructions)
+}
+
+// FNV-1a of the rendered prompt, stable across restarts so history can be grouped by prompt edits
+pub fn version(prompt: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in prompt.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{:016x}", hash)
+}
+
+const FORECAST_PROMPT: &str = "
+    You are a tool that can provide concise summaries of weather forecasts.
+    {% if alerts or convective or air_quality or daylight or normals or records or advisories or drought or \"stargazing\" in focus %}Input is a JSON object with \"alerts\", the active National Weather Service alerts,{% if convective %} \"convective_outlook\", the Storm Prediction Center severe thunderstorm risk covering the location for each outlook day,{% endif %}{% if air_quality %} \"air_quality\", the current air quality index and the pollutant responsible for it,{% endif %}{% if daylight %} \"daylight\", sunrise and sunset for each date,{% endif %}{% if normals %} \"normals\", the 1991-2020 normal high or low for each period's date and how far the forecast is from it,{% endif %}{% if advisories %} \"advisories\", hazards the forecast periods crossed a threshold for,{% endif %}{% if drought %} \"drought\", the US Drought Monitor category at the location,{% endif %}{% if records %} \"records\", the periods whose forecast high or low is near or past the record for its date,{% endif %}{% if \"stargazing\" in focus %} \"moon\", the moon phase and how much of it is lit each night,{% endif %} and \"periods\", an array with one entry per forecast period.{% else %}Input is a JSON array with one entry per forecast period.{% endif %}
+    {% if json %}Output is a JSON object with the key \"summary\" containing the overall forecast{% else %}Output is plain text containing the overall forecast{% endif %} in at most {{ sentences | default(value=4) }} sentences.
+    Each entry contains relavant weather information including a detailed text forecast.
+    Do not include any information that is not present in the input.
+    Do not comment twice on the same weather condition.
+    Use each period's when to say when weather happens, like tonight or tomorrow afternoon, rather than dates or period names.
+    A period's feels_like is the heat index or wind chill, mention it when it is much hotter or colder than the temperature, like: It will feel like 105F Saturday afternoon.
+    {% if alerts %}Start with the alerts, naming each one and when it ends, like: A Wind Advisory is in effect until 6 PM. An alert with applies_to_point false covers the area but not this exact location, so say it is nearby.{% endif %}
+    {% if advisories %}Mention every advisory with its period, even if the summary is short, like: Wind gusts could reach 50 mph Tuesday.{% endif %}
+    {% if convective %}Mention the highest severe thunderstorm risk and the day it is for, like: There is a Slight Risk of severe storms Thursday. Level 0 means general thunderstorms that aren't expected to be severe.{% endif %}
+    {% if daylight %}Mention sunrise or sunset only when it helps place the timing of the weather, like: Rain ends around sunset (7:42 PM).{% endif %}
+    {% if normals %}Compare temperatures to normal only when the departure is about 5 degrees or more, like: Highs about 8 degrees above normal for mid-June.{% endif %}
+    {% if records %}Call out each forecast high or low that approaches, ties, or breaks a record, with the record and the year it was set, like: The high of 94 would break the record of 92 set in 1991.{% endif %}
+    {% if frost %}Warn gardeners about each period with frost_risk true, like: Frost is possible Tuesday night with a low near 34F, so cover sensitive plants.{% endif %}
+    {% if drought %}Mention the drought when describing the precipitation, like: Thursday's rain will be welcome in this area of Severe Drought (D2).{% endif %}
+    {% if air_quality %}Mention the air quality category and the pollutant responsible, like: Air quality is Unhealthy for Sensitive Groups because of fine particulate matter.{% endif %}
+    {% if \"snow\" in focus %}Focus on snow: give accumulation amounts in {% if units == \"si\" %}centimeters{% else %}inches{% endif %} from snowfall and snow_accumulation, say when rain changes to snow or back, and describe how the snow will affect travel. Use the amounts rather than words like light or heavy.{% elif \"stargazing\" in focus %}Focus on stargazing: say which nights have clear or mostly clear skies and use the moon to judge how dark they will be, since a bright moon washes out fainter stars, like: Tonight is clear with only a thin crescent moon, good for stargazing. Leave out daytime weather unless it affects the night.{% elif focus %}Focus mainly on {{ focus | join(sep=\", \") }}.{% else %}Focus mainly on the daytime periods.{% endif %}
+    {% if units == \"si\" %}Give temperatures in degrees Celsius and wind speeds in kilometers per hour.{% endif %}
+    {% if locale %}Write the summary in the language and conventions of the {{ locale }} locale.{% endif %}
+    Avoid editorializing or making assumptions.
+    Make the output sound like a human wrote it, with concise but friendly language and complete sentences.
+    {% if format == \"emoji\" %}Start each sentence with one emoji that matches the weather it describes.{% endif %}
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const HOURLY_FORECAST_PROMPT: &str = "
+    You are a tool that can provide concise summaries of hourly weather forecasts.
+    Input is a JSON array with one entry per hour, in chronological order.
+    Output is a JSON object with the key \"summary\" containing the forecast for the covered hours in at most {{ sentences | default(value=4) }} sentences.
+    Describe how conditions change over the course of the period, naming approximate times of day for notable changes.
+    Use each hour's when, like this afternoon or tomorrow morning, to say when changes happen.
+    {% if focus %}Focus mainly on {{ focus | join(sep=\", \") }}.{% else %}Mention when precipitation is most likely and how temperatures rise and fall.{% endif %}
+    {% if units == \"si\" %}Give temperatures in degrees Celsius and wind speeds in kilometers per hour.{% endif %}
+    {% if locale %}Write the summary in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    Do not list every hour individually.
+    Avoid editorializing or making assumptions.
+    Make the output sound like a human wrote it, with concise but friendly language and complete sentences.
+    {% if format == \"emoji\" %}Start each sentence with one emoji that matches the weather it describes.{% endif %}
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const SHORT_FORECAST_PROMPT: &str = "
+    You are a tool that condenses weather forecasts into a single line for terminal users.
+    Input is a JSON array with the next two forecast periods.
+    Output is one line of plain text of at most 80 characters, like: Today: Sunny, 74F, light SW wind. Tonight: Clear, 51F.
+    Do not use markdown, quotes, or line breaks.
+    {% if units == \"si\" %}Give temperatures in degrees Celsius and wind speeds in kilometers per hour.{% endif %}
+    {% if locale %}Write the line in the language and conventions of the {{ locale }} locale.{% endif %}
+    {% if format == \"emoji\" %}Start each period with one emoji that matches its weather.{% endif %}
+    Do not include any information that is not present in the input.
+    ";
+
+const CURRENT_PROMPT: &str = "
+    You are a tool that describes the current weather in one sentence.
+    Input is a JSON object with the latest observation from the nearest weather station.
+    Output is a JSON object with the key \"summary\" containing one sentence, like: Right now it's 43F and raining with a light north wind.
+    {% if units == \"si\" %}Temperatures are in degrees Celsius and wind speeds in kilometers per hour.{% else %}Temperatures are in degrees Fahrenheit and wind speeds in miles per hour.{% endif %}
+    Only mention feels_like when it is present, and the gust when it is much stronger than the wind speed.
+    {% if locale %}Write the sentence in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    {% if format == \"emoji\" %}Start the sentence with one emoji that matches the weather.{% endif %}
+    {% if tone %}{{ tone }}{% endif %}
+    ";
+
+const AVIATION_PROMPT: &str = "
+    You are a flight instructor explaining airport weather reports to student pilots.
+    Input is a JSON object with \"metar\", the latest decoded observation, and \"taf\", the decoded terminal forecast, which is null when the airport has none.
+    Output is a JSON object with the key \"summary\" containing the explanation in at most {{ sentences | default(value=6) }} sentences.
+    Start with the current flight category and what it means for a VFR flight, then cover the wind, visibility, weather, and ceiling.
+    Describe how the forecast changes, giving times in UTC, and call out any period forecast to be MVFR, IFR, or LIFR.
+    Explain each abbreviation the first time it is used.
+    Wind speeds are in knots, visibility in statute miles, and cloud heights in feet above ground level.
+    {% if locale %}Write the explanation in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    Describe the conditions without telling the pilot whether to fly.
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const FIRE_WEATHER_PROMPT: &str = "
+    You are a tool that summarizes National Weather Service fire weather forecasts for land managers and fire crews.
+    Input is a JSON object with \"warnings\", the active Red Flag Warnings and Fire Weather Watches, and \"periods\", the fire weather zone forecast with one entry per period.
+    Output is a JSON object with the key \"summary\" containing the summary in at most {{ sentences | default(value=5) }} sentences.
+    {% if alerts %}Start with the warnings, naming each one and when it ends.{% endif %}
+    Focus on wind speed, direction, and gusts, minimum relative humidity, and any Haines index, lightning, or fire danger wording.
+    Say when the driest and windiest conditions are expected.
+    {% if units == \"si\" %}Give temperatures in degrees Celsius and wind speeds in kilometers per hour.{% endif %}
+    {% if locale %}Write the summary in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    Keep the tone factual and operational.
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const TROPICAL_PROMPT: &str = "
+    You are a tool that explains National Hurricane Center outlooks and advisories in plain language.
+    Input is a JSON object with \"outlook\", the tropical weather outlook for the basin, and \"storms\", the active tropical cyclones in the basin nearest first, each with its distance_miles from the location and the latest public advisory for nearby storms.
+    Output is a JSON object with the key \"summary\" containing the explanation in at most {{ sentences | default(value=5) }} sentences.
+    Lead with any storm that could affect the location and when its effects could arrive, then give the chances of new development from the outlook.
+    Say plainly when there are no active storms and nothing is expected to develop.
+    {% if locale %}Write the explanation in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    Keep the tone calm and factual and do not speculate beyond the advisories and the outlook.
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const AIR_QUALITY_PROMPT: &str = "
+    You are a tool that explains current air quality in plain language.
+    Input is a JSON object with the reporting area's air quality index (AQI), its category, the pollutant responsible, and the reading for each pollutant measured.
+    Output is a JSON object with the key \"summary\" containing the explanation in at most {{ sentences | default(value=2) }} sentences.
+    Name the category, the AQI, and the pollutant responsible.
+    For Unhealthy for Sensitive Groups say that sensitive groups should limit time outdoors, and for Unhealthy or worse say that everyone should.
+    {% if locale %}Write the explanation in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const TIDES_PROMPT: &str = "
+    You are a tool that describes the tides for people heading to the beach.
+    Input is a JSON object with the tide station, its distance_miles from the location, and \"predictions\", the high and low tides over the next 48 hours in the station's local time.
+    Output is a JSON object with the key \"summary\" containing the description in at most {{ sentences | default(value=1) }} sentences.
+    Give the times of the next low and high tides, and say when the lowest tide is if it's worth planning around.
+    {% if units == \"si\" %}Heights are in meters.{% else %}Heights are in feet.{% endif %}
+    {% if locale %}Write the description in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const RIVERS_PROMPT: &str = "
+    You are a tool that explains river conditions from National Water Prediction Service gauges.
+    Input is a JSON array of the river gauges near the location, nearest first, each with its observed and forecast stage, the flood category NWPS puts each in, and the stages where each flood category begins.
+    Output is a JSON object with the key \"summary\" containing the explanation in at most {{ sentences | default(value=3) }} sentences.
+    Start with any gauge where rising_to_flood is true, naming the river, the flood category it is forecast to reach, and when.
+    Compare each other river's observed stage to its minor flood stage, and say plainly when no river is near flooding.
+    {% if locale %}Write the explanation in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    Keep the tone calm and factual.
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+const ALERTS_PROMPT: &str = "
+    You are a tool that explains National Weather Service alerts in plain language.
+    Input is a JSON array with one entry per active watch, warning, or advisory.
+    Output is a JSON object with the key \"summary\" containing the explanation in at most {{ sentences | default(value=5) }} sentences.
+    Lead with the most severe alert and state what it is, where it applies, and when it is in effect.
+    Explain the difference between a watch and a warning when both are present.
+    An alert with applies_to_point false covers the area but not this exact location; say it is nearby rather than in effect here.
+    Include any recommended actions from the instruction text.
+    {% if locale %}Write the explanation in the language and conventions of the {{ locale }} locale.{% endif %}
+    Do not include any information that is not present in the input.
+    Avoid sensationalizing; keep the tone calm and factual.
+    {% if format == \"emoji\" %}Start each sentence with one emoji that matches the weather it describes.{% endif %}
+    {% if tone %}{{ tone }}{% endif %}
+    {% if style %}{{ style }}{% endif %}
+    ";
+
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("error reading prompt file {0}: {1}")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("error parsing prompt file {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("error parsing example file {0}: {1}")]
+    ParseExample(PathBuf, #[source] serde_json::Error),
+    #[error("error in prompt template: {0}")]
+    Template(#[from] tera::Error),
+}
+
+impl PromptError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PromptError::Read(_, _) => "read",
+            PromptError::Parse(_, _) => "parse",
+            PromptError::ParseExample(_, _) => "parse_example",
+            PromptError::Template(_) => "template",
+        }
+    }
+}
+
+// example file struct, input and output may be written as JSON values or as strings holding them
+#[derive(Debug, Deserialize)]
+struct ExampleFile {
+    input: serde_json::Value,
+    output: serde_json::Value,
+}
+
+// style struct, extra instructions appended to every template when the style is requested
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    pub instructions: String,
+    pub sentences: Option<usize>,
+}
+
+// prompts struct, the Tera templates for the system prompt of each kind of summary.
+// styles are added to the built in ones, a style with the same name replaces it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Prompts {
+    pub forecast: String,
+    pub hourly_forecast: String,
+    pub alerts: String,
+    pub short_forecast: String,
+    pub current: String,
+    pub aviation: String,
+    pub fire_weather: String,
+    pub tropical: String,
+    pub air_quality: String,
+    pub tides: String,
+    pub rivers: String,
+    pub styles: BTreeMap<String, Style>,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Self {
+            forecast: FORECAST_PROMPT.to_string(),
+            hourly_forecast: HOURLY_FORECAST_PROMPT.to_string(),
+            alerts: ALERTS_PROMPT.to_string(),
+            short_forecast: SHORT_FORECAST_PROMPT.to_string(),
+            current: CURRENT_PROMPT.to_string(),
+            aviation: AVIATION_PROMPT.to_string(),
+            fire_weather: FIRE_WEATHER_PROMPT.to_string(),
+            tropical: TROPICAL_PROMPT.to_string(),
+            air_quality: AIR_QUALITY_PROMPT.to_string(),
+            tides: TIDES_PROMPT.to_string(),
+            rivers: RIVERS_PROMPT.to_string(),
+            styles: BTreeMap::new(),
+        }
+    }
+}
+
+// prompt vars struct, per-request values interpolated into the templates
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PromptVars {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sentences: Option<usize>,
+    pub units: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    pub focus: Vec<String>,
+    pub format: String,
+    // set when active alerts are part of the forecast input
+    pub alerts: bool,
+    // set when the SPC convective outlook is part of the forecast input
+    pub convective: bool,
+    // set when the air quality is moderate or worse and part of the forecast input
+    pub air_quality: bool,
+    // set when sunrise and sunset are part of the forecast input
+    pub daylight: bool,
+    // set when the climate normals comparison is part of the forecast input
+    pub normals: bool,
+    // set when a forecast high or low near a record is part of the forecast input
+    pub records: bool,
+    // set when a forecast period has frost_risk
+    pub frost: bool,
+    // set when the rules engine found advisories for the forecast periods
+    pub advisories: bool,
+    // set when the location is in drought and precipitation is in the forecast
+    pub drought: bool,
+    #[serde(skip)]
+    pub tone: String,
+    #[serde(skip)]
+    pub style: Option<String>,
+}
+
+impl PromptVars {
+    // focus=snow also adds snowfall amounts to the forecast periods
+    pub fn snow(&self) -> bool {
+        self.focus.iter().any(|area| area == SNOW_FOCUS)
+    }
+
+    // focus=stargazing also adds the moon phase to the forecast input
+    pub fn stargazing(&self) -> bool {
+        self.focus.iter().any(|area| area == STARGAZING_FOCUS)
+    }
+
+    // identifies the rendered prompt in summary cache keys
+    pub fn cache_key(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}:{}",
+            self.sentences
+                .map(|sentences| sentences.to_string())
+                .unwrap_or_default(),
+            self.units,
+            self.locale.as_deref().unwrap_or_default(),
+            self.focus.join("+"),
+            self.format,
+            self.tone,
+            self.style.as_deref().unwrap_or(DEFAULT_STYLE)
+        )
+    }
+}
+
+// prompt set struct, compiled templates and the n-shot examples loaded alongside them
+pub struct PromptSet {
+    tera: Tera,
+    styles: BTreeMap<String, Style>,
+    pub forecast_examples: Vec<NShotInOut>,
+}
+
+impl PromptSet {
+    fn new(prompts: Prompts, forecast_examples: Vec<NShotInOut>) -> Result<Self, PromptError> {
+        let mut tera = Tera::default();
+
+        let mut styles = default_styles();
+        styles.extend(prompts.styles);
+
+        tera.add_raw_templates(vec![
+            (FORECAST, prompts.forecast),
+            (HOURLY_FORECAST, prompts.hourly_forecast),
+            (ALERTS, prompts.alerts),
+            (SHORT_FORECAST, prompts.short_forecast),
+            (CURRENT, prompts.current),
+            (AVIATION, prompts.aviation),
+            (FIRE_WEATHER, prompts.fire_weather),
+            (TROPICAL, prompts.tropical),
+            (AIR_QUALITY, prompts.air_quality),
+            (TIDES, prompts.tides),
+            (RIVERS, prompts.rivers),
+        ])?;
+
+        Ok(Self {
+            tera,
+            styles,
+            forecast_examples,
+        })
+    }
+
+    pub fn has_style(&self, name: &str) -> bool {
+        self.styles.contains_key(name)
+    }
+
+    pub fn style_names(&self) -> Vec<&str> {
+        self.styles.keys().map(|name| name.as_str()).collect()
+    }
+
+    // json selects between the JSON object and plain text output instructions.
+    // a style's sentence count applies unless the request sets its own
+    pub fn render(&self, name: &str, vars: &PromptVars, json: bool) -> Result<String, PromptError> {
+        let mut context = Context::from_serialize(vars)?;
+        context.insert("json", &json);
+        context.insert("tone", tone_instructions(&vars.tone).unwrap_or_default());
+
+        if let Some(style) = self
+            .styles
+            .get(vars.style.as_deref().unwrap_or(DEFAULT_STYLE))
+        {
+            context.insert("style", &style.instructions);
+
+            if let (None, Some(sentences)) = (vars.sentences, style.sentences) {
+                context.insert("sentences", &sentences);
+            }
+        }
+
+        Ok(self.tera.render(name, &context)?)
+    }
+}
+
+// prompt store, holds the current prompts and the files they can be reloaded from
+pub struct PromptStore {
+    path: Option<PathBuf>,
+    examples_dir: Option<PathBuf>,
+    prompts: RwLock<Arc<PromptSet>>,
+}
+
+impl PromptStore {
+    pub fn load(path: Option<String>, examples_dir: Option<String>) -> Result<Self, PromptError> {
+        let path = path.map(PathBuf::from);
+        let examples_dir = examples_dir.map(PathBuf::from);
+
+        let prompts = read_all(path.as_deref(), examples_dir.as_deref())?;
+
+        Ok(Self {
+            path,
+            examples_dir,
+            prompts: RwLock::new(Arc::new(prompts)),
+        })
+    }
+
+    pub fn get(&self) -> Arc<PromptSet> {
+        self.prompts.read().unwrap().clone()
+    }
+
+    // rereads the prompt file and examples, the current prompts are kept if they can't be loaded
+    pub fn reload(&self) -> Result<(), PromptError> {
+        if self.path.is_none() && self.examples_dir.is_none() {
+            return Ok(());
+        }
+
+        let prompts = read_all(self.path.as_deref(), self.examples_dir.as_deref())?;
+
+        *self.prompts.write().unwrap() = Arc::new(prompts);
+
+        info!("reloaded prompts");
+
+        Ok(())
+    }
+}
+
+// reloads the prompts whenever the process receives SIGHUP
+pub fn spawn_reload_on_hangup(prompt_store: Arc<PromptStore>) {
+    tokio::spawn(async move {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                error!("error listening for SIGHUP: {}", e);
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            if let Err(e) = prompt_store.reload() {
+                error!("{}", e);
+            }
+        }
+    });
+}
+
+fn read_all(path: Option<&Path>, examples_dir: Option<&Path>) -> Result<PromptSet, PromptError> {
+    let prompts = match path {
+        Some(path) => read(path)?,
+        None => Prompts::default(),
+    };
+
+    let forecast_examples = match examples_dir {
+        Some(examples_dir) => read_examples(examples_dir)?,
+        None => default_forecast_examples(),
+    };
+
+    PromptSet::new(prompts, forecast_examples)
+}
+
+// a .toml file can set any of the templates, any other file is the forecast template as plain text
+fn read(path: &Path) -> Result<Prompts, PromptError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return Err(PromptError::Read(path.to_path_buf(), e)),
+    };
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => match toml::from_str(&contents) {
+            Ok(prompts) => Ok(prompts),
+            Err(e) => Err(PromptError::Parse(path.to_path_buf(), e)),
+        },
+        _ => Ok(Prompts {
+            forecast: contents,
+            ..Prompts::default()
+        }),
+    }
+}
+
+// every .json file in the directory is one example, sent in file name order
+fn read_examples(examples_dir: &Path) -> Result<Vec<NShotInOut>, PromptError> {
+    let entries = match fs::read_dir(examples_dir) {
+        Ok(entries) => entries,
+        Err(e) => return Err(PromptError::Read(examples_dir.to_path_buf(), e)),
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+        .collect();
+
+    paths.sort();
+
+    let mut examples = Vec::new();
+
+    for path in paths {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => return Err(PromptError::Read(path, e)),
+        };
+
+        let example: ExampleFile = match serde_json::from_str(&contents) {
+            Ok(example) => example,
+            Err(e) => return Err(PromptError::ParseExample(path, e)),
+        };
+
+        examples.push(NShotInOut {
+            input: example_text(example.input),
+            output: example_text(example.output),
+        });
+    }
+
+    info!(
+        "loaded {} examples from {}",
+        examples.len(),
+        examples_dir.display()
+    );
+
+    Ok(examples)
+}
+
+fn example_text(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text,
+        value => value.to_string(),
+    }
+}
+
+fn default_styles() -> BTreeMap<String, Style> {
+    BTreeMap::from([
+        (
+            "brief".to_string(),
+            Style {
+                instructions: "Give only the single most important takeaway.".to_string(),
+                sentences: Some(1),
+            },
+        ),
+        (DEFAULT_STYLE.to_string(), Style::default()),
+        (
+            "kid_friendly".to_string(),
+            Style {
+                instructions: "Explain it so a five year old could understand, using simple words and comparisons to everyday things, and say what to wear or bring.".to_string(),
+                sentences: Some(4),
+            },
+        ),
+        (
+            "technical".to_string(),
+            Style {
+                instructions: "Write for a meteorologist instead of the general public, using precise meteorological terms, exact temperature ranges, wind speeds and directions, and precipitation chances, without friendly filler.".to_string(),
+                sentences: Some(6),
+            },
+        ),
+    ])
+}
+
+fn default_forecast_examples() -> Vec<NShotInOut> {
+    vec![NShotInOut {
+        input: "[{\"name\": \"Tonight\", \"start_time\": \"2024-06-08T20:00:00-07:00\", \"end_time\": \"2024-06-09T06:00:00-07:00\", \"temperature\": \"54F\", \"detailed_forecast\": \"Mostly cloudy, with a low around 54. East wind around 2 mph.\", \"relative_humidity\": \"80%\", \"wind_speed\": \"2 mph E\"}, {\"name\": \"Sunday\", \"start_time\": \"2024-06-09T06:00:00-07:00\", \"end_time\": \"2024-06-09T18:00:00-07:00\", \"temperature\": \"74F\", \"detailed_forecast\": \"Mostly sunny. High near 74, with temperatures falling to around 72 in the afternoon. Southwest wind 1 to 6 mph.\", \"relative_humidity\": \"79%\", \"wind_speed\": \"1 to 6 mph SW\"}, {\"name\": \"Sunday Night\", \"start_time\": \"2024-06-09T18:00:00-07:00\", \"end_time\": \"2024-06-10T06:00:00-07:00\", \"temperature\": \"51F\", \"detailed_forecast\": \"Mostly cloudy, with a low around 51. West wind 2 to 6 mph.\", \"relative_humidity\": \"85%\", \"wind_speed\": \"2 to 6 mph W\"}, {\"name\": \"Monday\", \"start_time\": \"2024-06-10T06:00:00-07:00\", \"end_time\": \"2024-06-10T18:00:00-07:00\", \"temperature\": \"71F\", \"detailed_forecast\": \"Mostly sunny, with a high near 71. Southwest wind around 3 mph.\", \"relative_humidity\": \"84%\", \"wind_speed\": \"3 mph SW\"}, {\"name\": \"Monday Night\", \"start_time\": \"2024-06-10T18:00:00-07:00\", \"end_time\": \"2024-06-11T06:00:00-07:00\", \"temperature\": \"52F\", \"detailed_forecast\": \"Partly cloudy, with a low around 52. North wind around 3 mph.\", \"relative_humidity\": \"80%\", \"wind_speed\": \"3 mph N\"}, {\"name\": \"Tuesday\", \"start_time\": \"2024-06-11T06:00:00-07:00\", \"end_time\": \"2024-06-11T18:00:00-07:00\", \"temperature\": \"69F\", \"detailed_forecast\": \"Partly sunny, with a high near 69.\", \"relative_humidity\": \"79%\", \"wind_speed\": \"2 to 7 mph SSW\"}, {\"name\": \"Tuesday Night\", \"start_time\": \"2024-06-11T18:00:00-07:00\", \"end_time\": \"2024-06-12T06:00:00-07:00\", \"temperature\": \"50F\", \"detailed_forecast\": \"Mostly cloudy, with a low around 50.\", \"relative_humidity\": \"83%\", \"wind_speed\": \"2 to 7 mph N\"}, {\"name\": \"Wednesday\", \"start_time\": \"2024-06-12T06:00:00-07:00\", \"end_time\": \"2024-06-12T18:00:00-07:00\", \"temperature\": \"67F\", \"detailed_forecast\": \"Mostly sunny, with a high near 67.\", \"relative_humidity\": \"82%\", \"wind_speed\": \"2 to 7 mph NNW\"}, {\"name\": \"Wednesday Night\", \"start_time\": \"2024-06-12T18:00:00-07:00\", \"end_time\": \"2024-06-13T06:00:00-07:00\", \"temperature\": \"47F\", \"detailed_forecast\": \"Mostly clear, with a low around 47.\", \"relative_humidity\": \"86%\", \"wind_speed\": \"1 to 7 mph N\"}, {\"name\": \"Thursday\", \"start_time\": \"2024-06-13T06:00:00-07:00\", \"end_time\": \"2024-06-13T18:00:00-07:00\", \"temperature\": \"69F\", \"detailed_forecast\": \"Mostly sunny, with a high near 69.\", \"relative_humidity\": \"84%\", \"wind_speed\": \"1 to 7 mph N\"}, {\"name\": \"Thursday Night\", \"start_time\": \"2024-06-13T18:00:00-07:00\", \"end_time\": \"2024-06-14T06:00:00-07:00\", \"temperature\": \"49F\", \"detailed_forecast\": \"Partly cloudy, with a low around 49.\", \"relative_humidity\": \"82%\", \"wind_speed\": \"2 to 7 mph NE\"}, {\"name\": \"Friday\", \"start_time\": \"2024-06-14T06:00:00-07:00\", \"end_time\": \"2024-06-14T18:00:00-07:00\", \"temperature\": \"67F\", \"detailed_forecast\": \"A chance of rain after 11am. Partly sunny, with a high near 67.\", \"relative_humidity\": \"81%\", \"wind_speed\": \"2 to 7 mph SW\"}, {\"name\": \"Friday Night\", \"start_time\": \"2024-06-14T18:00:00-07:00\", \"end_time\": \"2024-06-15T06:00:00-07:00\", \"temperature\": \"48F\", \"detailed_forecast\": \"A chance of rain. Mostly cloudy, with a low around 48.\", \"relative_humidity\": \"89%\", \"wind_speed\": \"3 to 7 mph SSW\"}, {\"name\": \"Saturday\", \"start_time\": \"2024-06-15T06:00:00-07:00\", \"end_time\": \"2024-06-15T18:00:00-07:00\", \"temperature\": \"61F\", \"detailed_forecast\": \"A chance of rain. Partly sunny, with a high near 61.\", \"relative_humidity\": \"89%\", \"wind_speed\": \"6 mph SW\"}]".to_string(),
+        output: "{\"summary\": \"This week will be mostly sunny and mild, with daytime high temperatures ranging from 61F to 74F. There might be some rain on Friday and Saturday, but it should be light. Humidity will be around 80% to 89%. Winds will be light, mostly from the south and west, up to 7mph.\"}".to_string(),
+    }]
+}
@@ -0,0 +1,163 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const ACIS_URL: &str = "https://data.rcc-acis.org/StnData";
+
+#[derive(Debug, Error)]
+pub enum RecordsError {
+    #[error("error requesting data.rcc-acis.org: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding data.rcc-acis.org response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("records request failed: {0}")]
+    Api(String),
+}
+
+// station data request struct, asks ACIS to reduce the station's whole period of record
+// to one value per calendar day instead of returning every observation
+#[derive(Debug, Clone, Serialize)]
+struct StationDataRequest<'a> {
+    sid: &'a str,
+    sdate: &'a str,
+    edate: &'a str,
+    elems: [Element<'a>; 2],
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Element<'a> {
+    name: &'a str,
+    interval: &'a str,
+    duration: &'a str,
+    smry: Summary<'a>,
+    smry_only: u8,
+    groupby: [&'a str; 3],
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Summary<'a> {
+    reduce: &'a str,
+    add: &'a str,
+}
+
+// each summary is a list of [value, date] pairs, one per calendar day
+#[derive(Debug, Clone, Deserialize)]
+struct RawStationData {
+    #[serde(default)]
+    smry: Vec<Vec<(String, String)>>,
+    error: Option<String>,
+}
+
+// record struct, a record temperature in fahrenheit and the year it was set
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub temperature: f64,
+    pub year: i32,
+}
+
+// daily record struct, the record high and record low for a day of the year like 06-15
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyRecord {
+    pub day: String,
+    pub high: Option<Record>,
+    pub low: Option<Record>,
+}
+
+// station ids are GHCN ids, like the ones climate normals use
+pub async fn get_daily_records(
+    client: reqwest::Client,
+    station_id: &str,
+) -> Result<Vec<DailyRecord>, RecordsError> {
+    let element = |name, reduce| Element {
+        name,
+        interval: "dly",
+        duration: "dly",
+        smry: Summary {
+            reduce,
+            add: "date",
+        },
+        smry_only: 1,
+        groupby: ["year", "01-01", "12-31"],
+    };
+
+    let response_result = client
+        .post(ACIS_URL)
+        .json(&StationDataRequest {
+            sid: station_id,
+            sdate: "por",
+            edate: "por",
+            elems: [element("maxt", "max"), element("mint", "min")],
+        })
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(RecordsError::Request(e)),
+    };
+
+    let raw = match response.json::<RawStationData>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(RecordsError::Decode(e)),
+    };
+
+    // errors come back as a 200 with a message instead of data
+    if let Some(error) = raw.error {
+        return Err(RecordsError::Api(error));
+    }
+
+    let mut summaries = raw.smry.into_iter();
+    let highs = summaries.next().unwrap_or_default();
+    let lows = summaries.next().unwrap_or_default();
+
+    let mut records: Vec<DailyRecord> = Vec::new();
+
+    for (value, date) in highs {
+        if let Some((day, record)) = record(&value, &date) {
+            records.push(DailyRecord {
+                day,
+                high: Some(record),
+                low: None,
+            });
+        }
+    }
+
+    for (value, date) in lows {
+        let (day, record) = match record(&value, &date) {
+            Some(record) => record,
+            None => continue,
+        };
+
+        match records.iter_mut().find(|daily| daily.day == day) {
+            Some(daily) => daily.low = Some(record),
+            None => records.push(DailyRecord {
+                day,
+                high: None,
+                low: Some(record),
+            }),
+        }
+    }
+
+    Ok(records)
+}
+
+// missing values are M, and the date a record was set gives the day it belongs to
+fn record(value: &str, date: &str) -> Option<(String, Record)> {
+    let temperature = value.trim().parse::<f64>().ok()?;
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+
+    Some((
+        date.format("%m-%d").to_string(),
+        Record {
+            temperature,
+            year: date.year(),
+        },
+    ))
+}
+
+pub fn for_date(records: &[DailyRecord], date: NaiveDate) -> Option<&DailyRecord> {
+    let day = date.format("%m-%d").to_string();
+
+    records.iter().find(|record| record.day == day)
+}
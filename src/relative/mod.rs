@@ -0,0 +1,94 @@
+use chrono::{DateTime, Datelike, Days, FixedOffset, NaiveDate, Timelike, Utc, Weekday};
+
+// NWS day and night periods are 12 hours, shorter ones like hourly periods are named by part of day
+const WHOLE_PERIOD_MIN_HOURS: i64 = 6;
+
+// the hours after midnight belong to the night before
+const MORNING_START_HOUR: u32 = 5;
+const AFTERNOON_START_HOUR: u32 = 12;
+const EVENING_START_HOUR: u32 = 17;
+const NIGHT_START_HOUR: u32 = 21;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Day,
+    Morning,
+    Afternoon,
+    Evening,
+    Night,
+}
+
+// a phrase like tonight, tomorrow afternoon, or Saturday this weekend, for when a period starts
+// relative to now in the location's time zone
+pub fn phrase(
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    now: DateTime<Utc>,
+) -> String {
+    let today = now.with_timezone(start.offset()).date_naive();
+
+    let part = part(start, end);
+    let date = match start.hour() < MORNING_START_HOUR {
+        true => start.date_naive() - Days::new(1),
+        false => start.date_naive(),
+    };
+
+    match ((date - today).num_days(), part) {
+        (-1, Part::Night) => "overnight".to_string(),
+        (0, Part::Day) => "today".to_string(),
+        (0, Part::Night) => "tonight".to_string(),
+        (0, part) => format!("this {}", part_name(part)),
+        (1, Part::Day) => "tomorrow".to_string(),
+        (1, part) => format!("tomorrow {}", part_name(part)),
+        (2..=6, part) if is_weekend(date) => format!("{} this weekend", day_phrase(date, part)),
+        (7.., part) => format!("next {}", day_phrase(date, part)),
+        (_, part) => day_phrase(date, part),
+    }
+}
+
+// the date the phrases are relative to, so summaries using them can be keyed on it
+pub fn today(start: DateTime<FixedOffset>, now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(start.offset()).date_naive()
+}
+
+fn part(start: DateTime<FixedOffset>, end: DateTime<FixedOffset>) -> Part {
+    let hour = start.hour();
+
+    if (end - start).num_hours() >= WHOLE_PERIOD_MIN_HOURS {
+        return match (MORNING_START_HOUR..EVENING_START_HOUR).contains(&hour) {
+            true => Part::Day,
+            false => Part::Night,
+        };
+    }
+
+    match hour {
+        hour if hour < MORNING_START_HOUR => Part::Night,
+        hour if hour < AFTERNOON_START_HOUR => Part::Morning,
+        hour if hour < EVENING_START_HOUR => Part::Afternoon,
+        hour if hour < NIGHT_START_HOUR => Part::Evening,
+        _ => Part::Night,
+    }
+}
+
+fn part_name(part: Part) -> &'static str {
+    match part {
+        Part::Day => "day",
+        Part::Morning => "morning",
+        Part::Afternoon => "afternoon",
+        Part::Evening => "evening",
+        Part::Night => "night",
+    }
+}
+
+fn day_phrase(date: NaiveDate, part: Part) -> String {
+    let day = date.format("%A");
+
+    match part {
+        Part::Day => day.to_string(),
+        part => format!("{} {}", day, part_name(part)),
+    }
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
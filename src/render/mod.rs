@@ -0,0 +1,203 @@
+use crate::geocode::Location;
+
+// output format struct, how a summary is returned to the client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Text,
+    Markdown,
+    Html,
+}
+
+impl OutputFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "json" => Some(OutputFormat::Json),
+            "text" => Some(OutputFormat::Text),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "html" => Some(OutputFormat::Html),
+            _ => None,
+        }
+    }
+
+    // first supported media type in an Accept header, quality values are ignored
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').find_map(|media_type| {
+            match media_type.split(';').next().unwrap_or_default().trim() {
+                "application/json" => Some(OutputFormat::Json),
+                "text/plain" => Some(OutputFormat::Text),
+                "text/markdown" => Some(OutputFormat::Markdown),
+                "text/html" => Some(OutputFormat::Html),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "application/json",
+            OutputFormat::Text => "text/plain; charset=utf-8",
+            OutputFormat::Markdown => "text/markdown; charset=utf-8",
+            OutputFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+// period row, a forecast period as a row of the table appended to markdown and html output
+pub trait PeriodRow {
+    fn headers() -> &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+pub fn text(summary: &str) -> String {
+    format!("{}\n", summary)
+}
+
+// periods are listed one per line under the summary, for plain text email bodies
+pub fn plain<T: PeriodRow>(summary: &str, location: &Location, periods: &[T]) -> String {
+    let mut plain = format!("Forecast for {}\n\n{}\n", title(location), summary);
+
+    if periods.is_empty() {
+        return plain;
+    }
+
+    plain.push('\n');
+
+    for period in periods {
+        let cells = period.cells();
+
+        match cells.split_first() {
+            Some((name, rest)) => plain.push_str(&format!("{}: {}\n", name, rest.join(", "))),
+            None => continue,
+        }
+    }
+
+    plain
+}
+
+pub fn markdown<T: PeriodRow>(summary: &str, location: &Location, periods: &[T]) -> String {
+    let mut markdown = format!("# Forecast for {}\n\n{}\n", title(location), summary);
+
+    if periods.is_empty() {
+        return markdown;
+    }
+
+    markdown.push_str(&format!("\n| {} |\n", T::headers().join(" | ")));
+    markdown.push_str(&format!("|{}\n", " --- |".repeat(T::headers().len())));
+
+    for period in periods {
+        let cells: Vec<String> = period
+            .cells()
+            .iter()
+            .map(|cell| cell.replace('|', "\\|"))
+            .collect();
+
+        markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+
+    markdown
+}
+
+pub fn html<T: PeriodRow>(summary: &str, location: &Location, periods: &[T]) -> String {
+    let title = escape(&format!("Forecast for {}", title(location)));
+
+    let mut rows = String::new();
+
+    for period in periods {
+        rows.push_str("<tr>");
+        for cell in period.cells() {
+            rows.push_str(&format!("<td>{}</td>", escape(&cell)));
+        }
+        rows.push_str("</tr>\n");
+    }
+
+    let headers: String = T::headers()
+        .iter()
+        .map(|header| format!("<th>{}</th>", escape(header)))
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; font-size: 0.9rem; }}
+th, td {{ border-bottom: 1px solid #ddd; padding: 0.4rem; text-align: left; vertical-align: top; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>{summary}</p>
+<table>
+<thead><tr>{headers}</tr></thead>
+<tbody>
+{rows}</tbody>
+</table>
+</body>
+</html>
+",
+        title = title,
+        summary = escape(summary),
+        headers = headers,
+        rows = rows,
+    )
+}
+
+// an emoji for NWS condition text like "Chance Rain Showers", the most impactful condition wins
+pub fn condition_emoji(forecast: &str) -> &'static str {
+    let forecast = forecast.to_lowercase();
+
+    let conditions: &[(&[&str], &str)] = &[
+        (&["thunder", "t-storm"], "⛈️"),
+        (&["snow", "flurr", "blizzard"], "❄️"),
+        (&["sleet", "freezing", "ice"], "🧊"),
+        (&["rain", "shower", "drizzle"], "🌧️"),
+        (&["fog", "haze", "smoke"], "🌫️"),
+        (&["wind", "breezy", "blustery"], "💨"),
+        (&["partly"], "⛅"),
+        (&["cloudy", "overcast"], "☁️"),
+        (&["sunny", "clear"], "☀️"),
+    ];
+
+    conditions
+        .iter()
+        .find(|(words, _)| words.iter().any(|word| forecast.contains(word)))
+        .map_or("🌡️", |(_, emoji)| emoji)
+}
+
+// the geocoded address when there is one, otherwise the coordinates
+pub fn title(location: &Location) -> String {
+    match &location.address {
+        Some(address) => address.to_owned(),
+        None => format!(
+            "{}, {}",
+            location.coordinates.latitude, location.coordinates.longitude
+        ),
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// one CSV record, fields with separators, quotes, or line breaks are quoted
+pub fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| match field.contains([',', '"', '\n', '\r']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.to_string(),
+        })
+        .collect();
+
+    format!("{}\r\n", fields.join(","))
+}
@@ -0,0 +1,121 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    airnow::{AirNowError, AirQuality},
+    error::AppError,
+    geocode::Location,
+    prompts,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param,
+    prepare::cached_air_quality,
+    prompt_vars, resolve_location, resolve_model,
+    summary::{cached_summary, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref AIR_QUALITY_COUNTER: Counter = register_counter!(opts!(
+        "air_quality_total",
+        "times the /api/v1/airquality endpoint was called"
+    ))
+    .unwrap();
+}
+
+// air quality response struct, the current AQI for the nearest AirNow reporting area
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirQualityResponse {
+    pub location: Location,
+    pub air_quality: AirQuality,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+pub async fn air_quality(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<AirQualityResponse>, AppError> {
+    AIR_QUALITY_COUNTER.inc();
+
+    let airnow = match &forecast_state.airnow {
+        Some(airnow) => airnow.clone(),
+        None => return Err(AppError::AirQualityFailed(AirNowError::NotConfigured)),
+    };
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let air_quality =
+        match cached_air_quality(&forecast_state, &airnow, location.coordinates, refresh).await? {
+            Some(air_quality) => air_quality,
+            None => {
+                return Err(AppError::NotFound(
+                    "no air quality observations are available for this location".to_string(),
+                ))
+            }
+        };
+
+    let (summary, verified) = match summarize {
+        true => {
+            let prompt =
+                forecast_state
+                    .prompts
+                    .get()
+                    .render(prompts::AIR_QUALITY, &prompt_vars, true)?;
+
+            let summary_key = format!(
+                "summary:{}:{}:air_quality:{}:{}:{}",
+                model,
+                prompt_vars.cache_key(),
+                air_quality.reporting_area,
+                air_quality.observed,
+                air_quality.aqi
+            );
+
+            let (summary, verified) = cached_summary(
+                &forecast_state,
+                summary_key,
+                refresh,
+                SummaryRequest {
+                    endpoint: "air_quality",
+                    location: Some(&location),
+                    model: &model,
+                    prompt: &prompt,
+                    training: &[],
+                    input: serde_json::to_string(&air_quality).unwrap(),
+                },
+            )
+            .await?;
+
+            (Some(summary), Some(verified))
+        }
+        false => (None, None),
+    };
+
+    Ok(Json(AirQualityResponse {
+        location,
+        air_quality,
+        model: summary.as_ref().map(|_| model),
+        summary,
+        verified,
+    }))
+}
@@ -0,0 +1,256 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Instant};
+use tracing::warn;
+
+use crate::{
+    cap::{self, CapDetails},
+    error::AppError,
+    geocode::Coordinates,
+    geometry,
+    nws::{self, AlertFeature, AlertFilter, AlertProperties},
+    prompts, sanitize,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, prompt_vars, resolve_location, resolve_model,
+    summary::{summarize, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref ALERTS_COUNTER: Counter = register_counter!(opts!(
+        "alerts_total",
+        "times the /api/v1/alerts endpoint was called"
+    ))
+    .unwrap();
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimplifiedAlert {
+    pub event: String,
+    pub severity: String,
+    pub urgency: String,
+    pub area: String,
+    pub onset: String,
+    pub ends: String,
+    pub headline: String,
+    pub description: String,
+    pub instruction: String,
+    pub applies_to_point: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoActiveAlerts {
+    pub summary: String,
+    pub active_alerts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertsResponse {
+    pub summary: String,
+    pub model: String,
+    // kept out of the summary, these are never rewritten by the llm
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub instructions: Vec<OfficialInstruction>,
+}
+
+// alerts body enum, what the alerts endpoint returns with and without active alerts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum AlertsBody {
+    NoActiveAlerts(NoActiveAlerts),
+    Alerts(AlertsResponse),
+}
+
+// official instruction struct, an alert's instructions exactly as NWS issued them
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfficialInstruction {
+    pub event: String,
+    pub instruction: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub vtec: Vec<String>,
+    pub applies_to_point: bool,
+}
+
+pub async fn alerts(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<AlertsBody>, AppError> {
+    ALERTS_COUNTER.inc();
+
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let filter = alert_filter(&params)?;
+    let use_cap = bool_param(&params, "cap", false)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let alerts =
+        nws::get_active_alerts(forecast_state.client.clone(), location.coordinates, &filter)
+            .await?;
+
+    if alerts.is_empty() {
+        let summary = match filter.is_empty() {
+            true => "There are no active alerts for this location.",
+            false => "There are no active alerts matching the filters for this location.",
+        };
+
+        let no_active_alerts = NoActiveAlerts {
+            summary: summary.to_string(),
+            active_alerts: 0,
+        };
+
+        return Ok(Json(AlertsBody::NoActiveAlerts(no_active_alerts)));
+    }
+
+    let details = futures_util::future::join_all(
+        alerts
+            .iter()
+            .map(|alert| alert_details(&forecast_state, &alert.properties, use_cap)),
+    )
+    .await;
+
+    let simplified_alerts: Vec<SimplifiedAlert> = alerts
+        .iter()
+        .zip(&details)
+        .map(|(alert, details)| simplified_alert(alert, details, location.coordinates))
+        .collect();
+
+    let instructions: Vec<OfficialInstruction> = alerts
+        .iter()
+        .zip(details)
+        .filter_map(|(alert, details)| {
+            Some(OfficialInstruction {
+                event: alert.properties.event.to_owned(),
+                applies_to_point: geometry::applies_to_point(
+                    alert.geometry.as_ref(),
+                    &details.polygons,
+                    location.coordinates,
+                ),
+                instruction: details.instruction?,
+                vtec: details.vtec,
+            })
+        })
+        .collect();
+
+    let simplified_alerts_json = serde_json::to_string(&simplified_alerts).unwrap();
+
+    let prompt = forecast_state
+        .prompts
+        .get()
+        .render(prompts::ALERTS, &prompt_vars, true)?;
+
+    let summary = summarize(
+        &forecast_state,
+        &SummaryRequest {
+            endpoint: "alerts",
+            location: Some(&location),
+            model: &model,
+            prompt: &prompt,
+            training: &[],
+            input: simplified_alerts_json,
+        },
+        Instant::now() + forecast_state.summary_deadline,
+    )
+    .await?;
+
+    Ok(Json(AlertsBody::Alerts(AlertsResponse {
+        summary,
+        model,
+        instructions,
+    })))
+}
+
+// severity and event are comma separated lists, like severity=severe,extreme
+fn alert_filter(params: &HashMap<String, String>) -> Result<AlertFilter, AppError> {
+    let list = |name: &str| -> Vec<String> {
+        params
+            .get(name)
+            .map(|value| value.split(',').map(|item| item.to_string()).collect())
+            .unwrap_or_default()
+    };
+
+    match AlertFilter::new(&list("severity"), &list("event")) {
+        Ok(filter) => Ok(filter),
+        Err(message) => Err(AppError::BadRequest(message)),
+    }
+}
+
+// CAP is only fetched when asked for, the JSON alert's instruction stands in when it isn't or can't be read
+pub(super) async fn alert_details(
+    forecast_state: &ForecastState,
+    properties: &AlertProperties,
+    use_cap: bool,
+) -> CapDetails {
+    let fallback = CapDetails {
+        instruction: properties
+            .instruction
+            .as_deref()
+            .map(|instruction| instruction.trim().to_string())
+            .filter(|instruction| !instruction.is_empty()),
+        ..CapDetails::default()
+    };
+
+    if !use_cap {
+        return fallback;
+    }
+
+    let xml = match nws::get_alert_cap(forecast_state.client.clone(), &properties.id).await {
+        Ok(xml) => xml,
+        Err(e) => {
+            warn!("error getting CAP alert {}: {}", properties.id, e);
+            return fallback;
+        }
+    };
+
+    match cap::parse(&xml) {
+        Ok(details) => CapDetails {
+            instruction: details.instruction.or(fallback.instruction),
+            ..details
+        },
+        Err(e) => {
+            warn!(
+                kind = e.kind(),
+                "error reading CAP alert {}: {}", properties.id, e
+            );
+            fallback
+        }
+    }
+}
+
+pub(super) fn simplified_alert(
+    alert: &AlertFeature,
+    details: &CapDetails,
+    coordinates: Coordinates,
+) -> SimplifiedAlert {
+    let properties = &alert.properties;
+
+    SimplifiedAlert {
+        event: sanitize::text(&properties.event),
+        severity: properties.severity.to_owned(),
+        urgency: properties.urgency.to_owned(),
+        area: sanitize::text(&properties.area_desc),
+        onset: properties.onset.to_owned().unwrap_or_default(),
+        ends: properties
+            .ends
+            .to_owned()
+            .unwrap_or(properties.expires.to_owned()),
+        headline: sanitize::text(properties.headline.as_deref().unwrap_or_default()),
+        description: sanitize::text(&properties.description),
+        instruction: sanitize::text(details.instruction.as_deref().unwrap_or_default()),
+        applies_to_point: geometry::applies_to_point(
+            alert.geometry.as_ref(),
+            &details.polygons,
+            coordinates,
+        ),
+    }
+}
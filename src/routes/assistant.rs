@@ -0,0 +1,146 @@
+use axum::{body::Bytes, extract::State, http::HeaderMap, Json};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use std::collections::HashMap;
+
+use crate::{
+    assistant::{
+        AlexaIntent, AlexaRequest, AlexaResponse, AssistantError, CERT_URL_HEADER, CITY_SLOT,
+        DAY_SLOT, FORECAST_INTENT, SIGNATURE_HEADER,
+    },
+    error::AppError,
+    render,
+};
+
+use std::sync::Arc;
+
+use super::{prepare::prepare_forecast, summary::forecast_summary, ForecastState};
+
+lazy_static! {
+    pub static ref ASSISTANT_COUNTER: Counter = register_counter!(opts!(
+        "assistant_total",
+        "times the /api/v1/assistant endpoint was called"
+    ))
+    .unwrap();
+}
+
+const ASSISTANT_HELP: &str =
+    "You can ask what the weather will be like today, tomorrow, or on a day this week.";
+
+const ASSISTANT_REPROMPT: &str = "What day would you like the forecast for?";
+
+// an alexa skill endpoint, the forecast intent speaks the summary or one day's NWS forecast
+pub async fn assistant(
+    State(forecast_state): State<Arc<ForecastState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<AlexaResponse>, AppError> {
+    ASSISTANT_COUNTER.inc();
+
+    let alexa = match &forecast_state.alexa {
+        Some(alexa) => alexa,
+        None => return Err(AppError::AssistantFailed(AssistantError::NotConfigured)),
+    };
+
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    };
+
+    alexa
+        .verify(header(CERT_URL_HEADER), header(SIGNATURE_HEADER), &body)
+        .await?;
+
+    let request: AlexaRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return Err(AppError::AssistantFailed(AssistantError::Parse(e))),
+    };
+
+    alexa.check(&request)?;
+
+    // launching the skill without asking anything gets the help prompt
+    let intent = match (request.request.kind.as_str(), &request.request.intent) {
+        ("SessionEndedRequest", _) => return Ok(Json(AlexaResponse::empty())),
+        ("IntentRequest", Some(intent)) => intent,
+        _ => return Ok(Json(AlexaResponse::ask(ASSISTANT_HELP, ASSISTANT_REPROMPT))),
+    };
+
+    match intent.name.as_str() {
+        FORECAST_INTENT => {}
+        "AMAZON.StopIntent" | "AMAZON.CancelIntent" => {
+            return Ok(Json(AlexaResponse::speak(&["Goodbye.".to_string()], None)))
+        }
+        _ => return Ok(Json(AlexaResponse::ask(ASSISTANT_HELP, ASSISTANT_REPROMPT))),
+    }
+
+    let address = match intent.slot(CITY_SLOT).or(alexa.default_address.as_deref()) {
+        Some(address) => address,
+        None => {
+            return Ok(Json(AlexaResponse::ask(
+                "Which city would you like the forecast for?",
+                "Which city?",
+            )))
+        }
+    };
+
+    // failures are spoken, alexa only says the skill didn't respond for an error status
+    match assistant_forecast(&forecast_state, address, intent).await {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            e.record();
+            Ok(Json(AlexaResponse::speak(
+                &[
+                    "Sorry, I couldn't get the forecast right now. Please try again later."
+                        .to_string(),
+                ],
+                None,
+            )))
+        }
+    }
+}
+
+async fn assistant_forecast(
+    forecast_state: &ForecastState,
+    address: &str,
+    intent: &AlexaIntent,
+) -> Result<AlexaResponse, AppError> {
+    let params = HashMap::from([("address".to_string(), address.to_string())]);
+
+    let prepared = prepare_forecast(forecast_state, &params, false).await?;
+
+    let title = format!("Forecast for {}", render::title(&prepared.location));
+
+    // week and weekend values of AMAZON.DATE fall back to the whole forecast's summary
+    let day = intent
+        .slot(DAY_SLOT)
+        .and_then(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").ok());
+
+    let day = match day {
+        Some(day) => day,
+        None => {
+            let summary = forecast_summary(forecast_state, &prepared, false)
+                .await?
+                .summary;
+            return Ok(AlexaResponse::speak(&[summary], Some(&title)));
+        }
+    };
+
+    // period start times are in the location's own offset, so their dates are the local day
+    let sentences: Vec<String> = prepared
+        .periods
+        .iter()
+        .filter(|period| period.start_time.date_naive() == day)
+        .map(|period| format!("{}: {}", period.name, period.detailed_forecast))
+        .collect();
+
+    if sentences.is_empty() {
+        return Ok(AlexaResponse::speak(
+            &["I don't have a forecast for that day yet.".to_string()],
+            None,
+        ));
+    }
+
+    Ok(AlexaResponse::speak(&sentences, Some(&title)))
+}
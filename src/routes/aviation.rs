@@ -0,0 +1,149 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    aviation::{self, Metar, Taf},
+    error::AppError,
+    geocode::Location,
+    prompts,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, prompt_vars, resolve_model,
+    summary::{cached_summary, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref AVIATION_COUNTER: Counter = register_counter!(opts!(
+        "aviation_total",
+        "times the /api/v1/aviation endpoint was called"
+    ))
+    .unwrap();
+}
+
+// aviation response struct, the decoded reports for an airport and their explanation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AviationResponse {
+    pub station: String,
+    pub metar: Metar,
+    pub taf: Option<Taf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+// aviation input struct, what the explanation is written from
+#[derive(Debug, Clone, Serialize)]
+struct AviationInput<'a> {
+    metar: &'a Metar,
+    taf: Option<&'a Taf>,
+}
+
+// reports aren't cached, specials are issued whenever conditions change and pilots need the latest one
+pub async fn aviation(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<AviationResponse>, AppError> {
+    AVIATION_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let station = match params.get("icao") {
+        Some(icao) if icao.len() == 4 && icao.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            icao.to_uppercase()
+        }
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "icao parameter must be a four character airport identifier".to_string(),
+            ))
+        }
+        None => {
+            return Err(AppError::BadRequest(
+                "icao parameter is required".to_string(),
+            ))
+        }
+    };
+
+    let (metar, taf) = tokio::try_join!(
+        aviation::get_metar(forecast_state.client.clone(), &station),
+        aviation::get_taf(forecast_state.client.clone(), &station),
+    )?;
+
+    let (summary, verified) = match summarize {
+        true => {
+            let prompt =
+                forecast_state
+                    .prompts
+                    .get()
+                    .render(prompts::AVIATION, &prompt_vars, true)?;
+
+            let reports = format!(
+                "{}\n{}",
+                metar.raw,
+                taf.as_ref().map(|taf| taf.raw.as_str()).unwrap_or_default()
+            );
+
+            let summary_key = format!(
+                "summary:{}:{}:aviation:{}:{}",
+                model,
+                prompt_vars.cache_key(),
+                station,
+                prompts::version(&reports)
+            );
+
+            // without the station's coordinates the summary is kept out of history, which is
+            // searched by location
+            let location = metar.coordinates.map(|coordinates| Location {
+                address: metar.name.to_owned().or(Some(station.clone())),
+                coordinates,
+            });
+
+            let (summary, verified) = cached_summary(
+                &forecast_state,
+                summary_key,
+                refresh,
+                SummaryRequest {
+                    endpoint: "aviation",
+                    location: location.as_ref(),
+                    model: &model,
+                    prompt: &prompt,
+                    training: &[],
+                    input: serde_json::to_string(&AviationInput {
+                        metar: &metar,
+                        taf: taf.as_ref(),
+                    })
+                    .unwrap(),
+                },
+            )
+            .await?;
+
+            (Some(summary), Some(verified))
+        }
+        false => (None, None),
+    };
+
+    Ok(Json(AviationResponse {
+        station,
+        metar,
+        taf,
+        model: summary.as_ref().map(|_| model),
+        summary,
+        verified,
+    }))
+}
@@ -0,0 +1,204 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::{
+    error::AppError,
+    geocode::{self, Coordinates, Location},
+    nws::{self, NwsError, Observation, Point, Station},
+    observation::{self, Conditions},
+    prompts,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, prompt_vars, resolve_location, resolve_model, resolve_point,
+    summary::{cached_summary, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref CURRENT_COUNTER: Counter = register_counter!(opts!(
+        "current_total",
+        "times the /api/v1/current endpoint was called"
+    ))
+    .unwrap();
+}
+
+// stations go offline or stop reporting temperature, so a few of the nearest are tried
+const CURRENT_STATION_ATTEMPTS: usize = 3;
+
+// current response struct, the latest observation from the nearest reporting station
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrentResponse {
+    pub location: Location,
+    pub station: Station,
+    pub distance_miles: f64,
+    pub observed_at: chrono::DateTime<chrono::Utc>,
+    pub units: String,
+    pub conditions: Conditions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+pub async fn current(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<CurrentResponse>, AppError> {
+    CURRENT_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+    let point = resolve_point(&forecast_state, location.coordinates).await?;
+
+    let (station, observation) =
+        nearest_observation(&forecast_state, &point, location.coordinates, refresh).await?;
+
+    let observed_at = match chrono::DateTime::parse_from_rfc3339(&observation.timestamp) {
+        Ok(observed_at) => observed_at.to_utc(),
+        Err(_) => {
+            return Err(AppError::NwsUnavailable(NwsError::MissingField(
+                "observation timestamp",
+            )))
+        }
+    };
+
+    let conditions = observation::conditions(&observation, &prompt_vars.units);
+
+    let (summary, verified) = match summarize {
+        true => {
+            let prompt =
+                forecast_state
+                    .prompts
+                    .get()
+                    .render(prompts::CURRENT, &prompt_vars, true)?;
+
+            let summary_key = format!(
+                "summary:{}:{}:current:{}:{}",
+                model,
+                prompt_vars.cache_key(),
+                station.id,
+                observation.timestamp
+            );
+
+            let (summary, verified) = cached_summary(
+                &forecast_state,
+                summary_key,
+                refresh,
+                SummaryRequest {
+                    endpoint: "current",
+                    location: Some(&location),
+                    model: &model,
+                    prompt: &prompt,
+                    training: &[],
+                    input: serde_json::to_string(&conditions).unwrap(),
+                },
+            )
+            .await?;
+
+            (Some(summary), Some(verified))
+        }
+        false => (None, None),
+    };
+
+    Ok(Json(CurrentResponse {
+        distance_miles: (geocode::distance_miles(location.coordinates, station.coordinates) * 10.0)
+            .round()
+            / 10.0,
+        location,
+        station,
+        observed_at,
+        units: prompt_vars.units,
+        conditions,
+        model: summary.as_ref().map(|_| model),
+        summary,
+        verified,
+    }))
+}
+
+// the nearest station with a current temperature, stations without one are usually offline
+async fn nearest_observation(
+    forecast_state: &ForecastState,
+    point: &Point,
+    coordinates: Coordinates,
+    refresh: bool,
+) -> Result<(Station, Observation), AppError> {
+    let stations_key = format!("stations:{}", point.gridpoint());
+
+    let mut stations = match forecast_state
+        .forecast_cache
+        .get::<Vec<Station>>(&stations_key)
+        .await
+    {
+        Some(stations) => stations,
+        None => {
+            let stations = nws::get_stations(
+                forecast_state.client.clone(),
+                point.observation_stations.to_owned(),
+            )
+            .await?;
+
+            forecast_state
+                .forecast_cache
+                .insert(stations_key, &stations)
+                .await;
+
+            stations
+        }
+    };
+
+    stations.sort_by(|a, b| {
+        geocode::distance_miles(coordinates, a.coordinates)
+            .total_cmp(&geocode::distance_miles(coordinates, b.coordinates))
+    });
+
+    for station in stations.into_iter().take(CURRENT_STATION_ATTEMPTS) {
+        let observation_key = format!("observation:{}", station.id);
+
+        if !refresh {
+            if let Some(observation) = forecast_state
+                .forecast_cache
+                .get::<Observation>(&observation_key)
+                .await
+            {
+                return Ok((station, observation));
+            }
+        }
+
+        match nws::get_latest_observation(forecast_state.client.clone(), &station.id).await {
+            Ok(observation) if observation.temperature.value.is_some() => {
+                forecast_state
+                    .forecast_cache
+                    .insert(observation_key, &observation)
+                    .await;
+
+                return Ok((station, observation));
+            }
+            Ok(_) => warn!("station {} has no current temperature", station.id),
+            Err(e) => warn!(
+                "error getting observation from station {}: {}",
+                station.id, e
+            ),
+        }
+    }
+
+    Err(AppError::NotFound(
+        "no current observations are available for this location".to_string(),
+    ))
+}
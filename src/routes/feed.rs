@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{
+    error::AppError,
+    feed::{self, FeedEntry, FeedLocation},
+    llm::{self, Priority},
+};
+
+use std::sync::Arc;
+
+use super::{prepare::prepare_forecast, summary::forecast_summary, ForecastState};
+
+lazy_static! {
+    pub static ref FEED_COUNTER: Counter = register_counter!(opts!(
+        "feed_total",
+        "times a /feed/{location}.xml feed was requested"
+    ))
+    .unwrap();
+}
+
+// serves /feed/{slug}.xml, entries are only added by the scheduled refresh
+pub async fn feed(
+    Path(file): Path<String>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    FEED_COUNTER.inc();
+
+    let slug = match file.strip_suffix(".xml") {
+        Some(slug) if forecast_state.feed.has_location(slug) => slug,
+        _ => {
+            return Err(AppError::NotFound(
+                "no feed exists for this location".to_string(),
+            ))
+        }
+    };
+
+    let atom = feed::atom(slug, &forecast_state.feed.entries(slug));
+
+    Ok((
+        [(CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        atom,
+    )
+        .into_response())
+}
+
+// summarizes every feed location on an interval, each refresh replaces that day's entry
+pub fn spawn_feed_refresh(forecast_state: Arc<ForecastState>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            for location in forecast_state.feed.locations() {
+                let refreshed = llm::with_priority(
+                    Priority::Scheduled,
+                    refresh_feed(&forecast_state, location),
+                )
+                .await;
+
+                if let Err(e) = refreshed {
+                    warn!("error refreshing feed {}: {}", location.slug, e);
+                }
+            }
+        }
+    });
+}
+
+async fn refresh_feed(
+    forecast_state: &ForecastState,
+    location: &FeedLocation,
+) -> Result<(), AppError> {
+    let prepared = prepare_forecast(forecast_state, &location.params, false).await?;
+
+    let summary = forecast_summary(forecast_state, &prepared, false)
+        .await?
+        .summary;
+
+    // NWS sends generated at in UTC, so it's moved into the periods' offset to get the local date.
+    // an evening summary would otherwise replace tomorrow's entry
+    let generated_at = chrono::DateTime::parse_from_rfc3339(&prepared.generated_at);
+
+    let date = match (generated_at, prepared.nws_periods.first()) {
+        (Ok(generated_at), Some(period)) => generated_at
+            .with_timezone(period.start_time.offset())
+            .date_naive()
+            .to_string(),
+        // RFC 3339, so the first ten characters are the date
+        _ => prepared
+            .generated_at
+            .get(..10)
+            .unwrap_or(&prepared.generated_at)
+            .to_string(),
+    };
+
+    forecast_state.feed.record(
+        &location.slug,
+        FeedEntry {
+            date,
+            updated: prepared.generated_at,
+            summary,
+        },
+    );
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    error::AppError,
+    geocode::Location,
+    llm,
+    nws::{self, AlertFilter, ZonePeriod},
+    prompts, sanitize,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, cached_forecast,
+    prepare::current_alerts,
+    prompt_vars, resolve_location, resolve_model, resolve_point,
+    summary::{budgeted_input, cached_summary, input_budget, SummaryRequest},
+    ForecastAlert, ForecastState,
+};
+
+lazy_static! {
+    pub static ref FIRE_WEATHER_COUNTER: Counter = register_counter!(opts!(
+        "fire_weather_total",
+        "times the /api/v1/fire endpoint was called"
+    ))
+    .unwrap();
+}
+
+const FIRE_WEATHER_EVENTS: [&str; 2] = ["Red Flag Warning", "Fire Weather Watch"];
+
+// fire weather response struct, a summary of the zone fire weather forecast and its warnings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FireWeatherResponse {
+    pub summary: String,
+    pub model: String,
+    pub verified: bool,
+    pub location: Location,
+    pub zone: String,
+    pub generated_at: String,
+    pub warnings: Vec<ForecastAlert>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub periods: Option<Vec<ZonePeriod>>,
+}
+
+// fire weather input struct, what the fire weather summary is written from
+#[derive(Debug, Clone, Serialize)]
+struct FireWeatherInput<'a> {
+    warnings: &'a [ForecastAlert],
+    periods: serde_json::Value,
+}
+
+// unlike forecast summaries, a failed warnings request fails the summary, leaving out a red flag warning isn't safe
+pub async fn fire_weather(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<FireWeatherResponse>, AppError> {
+    FIRE_WEATHER_COUNTER.inc();
+
+    let include_periods = bool_param(&params, "periods", false)?;
+    let refresh = bool_param(&params, "refresh", false)?;
+    let model = resolve_model(&forecast_state, &params)?;
+    let mut prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+    let point = resolve_point(&forecast_state, location.coordinates).await?;
+
+    let zone_url = match point.fire_weather_zone {
+        Some(zone_url) => zone_url,
+        None => {
+            return Err(AppError::NotFound(
+                "no fire weather forecast is issued for this location".to_string(),
+            ))
+        }
+    };
+
+    let zone = zone_url.rsplit('/').next().unwrap_or_default().to_string();
+
+    let filter = AlertFilter {
+        severity: Vec::new(),
+        event: FIRE_WEATHER_EVENTS
+            .iter()
+            .map(|event| event.to_string())
+            .collect(),
+    };
+
+    let forecast_key = format!("forecast_fire:{}", zone);
+
+    let (forecast, alerts) = tokio::try_join!(
+        cached_forecast::<ZonePeriod>(
+            &forecast_state,
+            &forecast_key,
+            format!("{}/forecast", zone_url),
+            refresh,
+        ),
+        async {
+            nws::get_active_alerts(forecast_state.client.clone(), location.coordinates, &filter)
+                .await
+                .map_err(AppError::from)
+        },
+    )?;
+
+    let warnings = current_alerts(&alerts, location.coordinates);
+    prompt_vars.alerts = !warnings.is_empty();
+
+    let periods: Vec<ZonePeriod> = forecast
+        .periods
+        .iter()
+        .map(|period| ZonePeriod {
+            number: period.number,
+            name: sanitize::text(&period.name),
+            detailed_forecast: sanitize::text(&period.detailed_forecast),
+        })
+        .collect();
+
+    let prompt = forecast_state
+        .prompts
+        .get()
+        .render(prompts::FIRE_WEATHER, &prompt_vars, true)?;
+
+    let warnings_json = serde_json::to_string(&warnings).unwrap();
+
+    let periods_json = budgeted_input(
+        "fire_weather",
+        &periods,
+        input_budget(&forecast_state, &prompt, &[])
+            .saturating_sub(llm::estimate_tokens(&warnings_json)),
+        |_| {},
+    );
+
+    let headlines: Vec<&str> = warnings
+        .iter()
+        .map(|warning| warning.headline.as_str())
+        .collect();
+
+    let summary_key = format!(
+        "summary:{}:{}:fire:{}:{}:{}",
+        model,
+        prompt_vars.cache_key(),
+        zone,
+        forecast.generated_at,
+        prompts::version(&headlines.join("\n"))
+    );
+
+    let (summary, verified) = cached_summary(
+        &forecast_state,
+        summary_key,
+        refresh,
+        SummaryRequest {
+            endpoint: "fire_weather",
+            location: Some(&location),
+            model: &model,
+            prompt: &prompt,
+            training: &[],
+            input: serde_json::to_string(&FireWeatherInput {
+                warnings: &warnings,
+                periods: serde_json::from_str(&periods_json).unwrap(),
+            })
+            .unwrap(),
+        },
+    )
+    .await?;
+
+    Ok(Json(FireWeatherResponse {
+        summary,
+        model,
+        verified,
+        location,
+        zone,
+        generated_at: forecast.generated_at,
+        warnings,
+        periods: include_periods.then_some(periods),
+    }))
+}
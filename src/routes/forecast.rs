@@ -0,0 +1,370 @@
+use axum::{
+    extract::{Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use futures_util::{Stream, StreamExt};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use std::{collections::HashMap, convert::Infallible};
+
+use crate::{
+    badge,
+    card::{CardError, CardPeriod, MAX_CARD_PERIODS},
+    error::AppError,
+    fallback,
+    ics::{self, CalendarEvent, EventTime},
+    render::{self, OutputFormat},
+    tts::{AudioFormat, TtsError},
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, forecast_response,
+    history::save_permalink,
+    output_format,
+    prepare::{frost_warning, prepare_forecast},
+    summary::{forecast_summary, short_summary, summary_events, summary_tokens, SummaryEvent},
+    ForecastResponse, ForecastState, ResponseMeta,
+};
+
+lazy_static! {
+    pub static ref FORECAST_COUNTER: Counter = register_counter!(opts!(
+        "forecast_total",
+        "times the /api/v1/forecast endpoint was called"
+    ))
+    .unwrap();
+    pub static ref FORECAST_STREAM_COUNTER: Counter = register_counter!(opts!(
+        "forecast_stream_total",
+        "times the /api/v1/forecast/stream endpoint was called"
+    ))
+    .unwrap();
+    pub static ref FORECAST_AUDIO_COUNTER: Counter = register_counter!(opts!(
+        "forecast_audio_total",
+        "times the /api/v1/forecast/audio endpoint was called"
+    ))
+    .unwrap();
+    pub static ref SHORT_FORECAST_COUNTER: Counter = register_counter!(opts!(
+        "short_forecast_total",
+        "times the /api/v1/forecast/short endpoint was called"
+    ))
+    .unwrap();
+    pub static ref FORECAST_ICS_COUNTER: Counter = register_counter!(opts!(
+        "forecast_ics_total",
+        "times the /api/v1/forecast.ics endpoint was called"
+    ))
+    .unwrap();
+    pub static ref FORECAST_CARD_COUNTER: Counter = register_counter!(opts!(
+        "forecast_card_total",
+        "times the /api/v1/forecast/card.png endpoint was called"
+    ))
+    .unwrap();
+    pub static ref BADGE_COUNTER: Counter =
+        register_counter!(opts!("badge_total", "times the /badge endpoint was called")).unwrap();
+}
+
+pub async fn forecast(
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    FORECAST_COUNTER.inc();
+
+    let output_format = output_format(&params, &headers);
+
+    let include_periods = bool_param(&params, "periods", false)?;
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    if !summarize {
+        return Ok(Json(prepared.periods).into_response());
+    }
+
+    let summary = forecast_summary(&forecast_state, &prepared, refresh).await?;
+
+    // with no summary but the periods' own text, the periods are the answer
+    let include_periods = include_periods || summary.level == fallback::Level::Periods;
+
+    let mut response = ForecastResponse {
+        summary: summary.summary,
+        model: prepared.model,
+        verified: summary.verified,
+        generated_by: summary.generated_by,
+        location: prepared.location,
+        generated_at: prepared.generated_at,
+        generated_at_local: prepared.generated_at_local,
+        time_zone: prepared.time_zone,
+        daylight: prepared.daylight,
+        moon: prepared.moon,
+        normals: prepared.normals,
+        records: prepared.records,
+        degree_days: prepared.degree_days,
+        advisories: prepared.advisories,
+        drought: prepared.drought,
+        periods: Some(prepared.periods),
+        permalink: None,
+        meta: Some(ResponseMeta {
+            summary_level: Some(summary.level),
+            ..prepared.meta
+        }),
+    };
+
+    response.permalink = save_permalink(
+        &forecast_state,
+        "forecast",
+        &prepared.summary_key,
+        &response,
+    )
+    .await;
+
+    Ok(forecast_response(output_format, response, include_periods))
+}
+
+pub async fn forecast_stream(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    FORECAST_STREAM_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    let (tokens, history) = summary_tokens(&forecast_state, &prepared, refresh).await?;
+
+    let frost_warning = frost_warning(&prepared);
+
+    let events = summary_events(
+        tokens,
+        forecast_state.summary_cache.clone(),
+        prepared.summary_key,
+        history,
+        frost_warning,
+    )
+    .map(|event| {
+        let event = match event {
+            SummaryEvent::Token(token) => Event::default().event("token").data(token),
+            SummaryEvent::Done(summary) => Event::default().event("done").data(summary),
+            SummaryEvent::Error(e) => Event::default().event("error").data(e.public_message()),
+        };
+
+        Ok(event)
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+// one event per forecast period, plus an all-day event on the first day holding the summary
+pub async fn forecast_ics(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    FORECAST_ICS_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    let coordinates = prepared.location.coordinates;
+    let uid = |start: &str| {
+        format!(
+            "{}-{:.4},{:.4}@nws-forecast-summarizer",
+            start, coordinates.latitude, coordinates.longitude
+        )
+    };
+
+    let mut events = Vec::new();
+
+    if summarize {
+        let first_day = prepared
+            .periods
+            .first()
+            .map(|period| period.start_time.date_naive());
+
+        if let Some(first_day) = first_day {
+            let summary = forecast_summary(&forecast_state, &prepared, refresh)
+                .await?
+                .summary;
+
+            events.push(CalendarEvent {
+                uid: uid(&first_day.to_string()),
+                time: EventTime::AllDay(first_day),
+                summary: "Forecast summary".to_string(),
+                description: Some(summary),
+            });
+        }
+    }
+
+    for period in &prepared.periods {
+        events.push(CalendarEvent {
+            uid: uid(&period.start_time.to_rfc3339()),
+            time: EventTime::Timed {
+                start: period.start_time.to_utc(),
+                end: period.end_time.to_utc(),
+            },
+            summary: format!(
+                "{}: {}, {}",
+                period.name, period.temperature, period.short_forecast
+            ),
+            description: Some(period.detailed_forecast.to_owned()),
+        });
+    }
+
+    let stamp = ics::parse_time(&prepared.generated_at).unwrap_or_else(chrono::Utc::now);
+
+    let calendar = ics::calendar(
+        &format!("Forecast for {}", render::title(&prepared.location)),
+        stamp,
+        &events,
+    );
+
+    Ok(([(CONTENT_TYPE, "text/calendar; charset=utf-8")], calendar).into_response())
+}
+
+// draws the summary and the next few periods as a shareable PNG
+pub async fn forecast_card(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    FORECAST_CARD_COUNTER.inc();
+
+    let card = match &forecast_state.card {
+        Some(card) => card.clone(),
+        None => return Err(AppError::CardFailed(CardError::NotConfigured)),
+    };
+
+    let refresh = bool_param(&params, "refresh", false)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    let summary = forecast_summary(&forecast_state, &prepared, refresh)
+        .await?
+        .summary;
+
+    let periods: Vec<CardPeriod> = prepared
+        .periods
+        .iter()
+        .take(MAX_CARD_PERIODS)
+        .map(|period| CardPeriod {
+            name: period.name.to_owned(),
+            temperature: period.temperature.to_owned(),
+            forecast: period.short_forecast.to_owned(),
+        })
+        .collect();
+
+    let title = render::title(&prepared.location);
+
+    // rasterizing is CPU bound, so it runs off the async workers
+    let png =
+        match tokio::task::spawn_blocking(move || card.render(&title, &summary, &periods)).await {
+            Ok(png) => png?,
+            Err(e) => return Err(AppError::CardFailed(CardError::Encode(e.to_string()))),
+        };
+
+    Ok(([(CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+// the current period as a shields.io style badge, no summary is generated so it stays cheap to embed
+pub async fn badge(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    BADGE_COUNTER.inc();
+
+    let prepared = prepare_forecast(&forecast_state, &params, false).await?;
+
+    let period = match prepared.periods.first() {
+        Some(period) => period,
+        None => {
+            return Err(AppError::NotFound(
+                "no forecast periods are available for this location".to_string(),
+            ))
+        }
+    };
+
+    let svg = badge::svg(
+        &period.name,
+        &format!("{} {}", period.temperature, period.short_forecast),
+        badge::color(badge::temperature(&period.temperature)),
+    );
+
+    Ok((
+        [
+            (CONTENT_TYPE, "image/svg+xml".to_string()),
+            (
+                CACHE_CONTROL,
+                format!("public, max-age={}", forecast_state.badge_max_age),
+            ),
+        ],
+        svg,
+    )
+        .into_response())
+}
+
+// speaks the forecast summary, the audio format defaults to the tts backend's native one
+pub async fn forecast_audio(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    FORECAST_AUDIO_COUNTER.inc();
+
+    let tts = match &forecast_state.tts {
+        Some(tts) => tts,
+        None => return Err(AppError::TtsFailed(TtsError::NotConfigured)),
+    };
+
+    let audio_format = match params.get("audio") {
+        Some(audio) => match AudioFormat::parse(audio) {
+            Some(audio_format) => audio_format,
+            None => {
+                return Err(AppError::BadRequest(
+                    "audio parameter must be mp3 or wav".to_string(),
+                ))
+            }
+        },
+        None => tts.default_format(),
+    };
+
+    let refresh = bool_param(&params, "refresh", false)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    let summary = forecast_summary(&forecast_state, &prepared, refresh)
+        .await?
+        .summary;
+
+    let audio = tts.synthesize(&summary, audio_format).await?;
+
+    Ok(([(CONTENT_TYPE, audio_format.content_type())], audio).into_response())
+}
+
+// a single plain text line for shell prompts and MOTD scripts, generated without JSON mode
+pub async fn forecast_short(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    SHORT_FORECAST_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    let line = short_summary(&forecast_state, &prepared, refresh).await?;
+
+    Ok((
+        [(CONTENT_TYPE, OutputFormat::Text.content_type())],
+        render::text(&line),
+    )
+        .into_response())
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{error::AppError, geocode::Location, gridpoint::GridData};
+
+use std::sync::Arc;
+
+use super::{bool_param, cached_grid_data, resolve_location, resolve_point, ForecastState};
+
+lazy_static! {
+    pub static ref GRIDPOINT_COUNTER: Counter = register_counter!(opts!(
+        "gridpoint_total",
+        "times the /api/v1/gridpoint endpoint was called"
+    ))
+    .unwrap();
+}
+
+// gridpoint response struct, the quantitative layers behind the text forecast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridpointResponse {
+    pub location: Location,
+    pub gridpoint: String,
+    #[serde(flatten)]
+    pub data: GridData,
+}
+
+pub async fn gridpoint(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<GridpointResponse>, AppError> {
+    GRIDPOINT_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+    let point = resolve_point(&forecast_state, location.coordinates).await?;
+
+    let data = cached_grid_data(&forecast_state, &point, refresh).await?;
+
+    Ok(Json(GridpointResponse {
+        location,
+        gridpoint: point.gridpoint(),
+        data,
+    }))
+}
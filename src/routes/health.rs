@@ -0,0 +1,61 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::health;
+
+use std::sync::Arc;
+
+use super::ForecastState;
+
+pub async fn root() -> &'static str {
+    "nws-forecast-summarizer"
+}
+
+// the process is up and serving, dependencies aren't checked so a down llm doesn't get it restarted
+pub async fn livez() -> &'static str {
+    "ok"
+}
+
+// 503 while any dependency is down, so orchestrators send traffic to instances that can answer
+pub async fn readyz(State(forecast_state): State<Arc<ForecastState>>) -> Response {
+    let readiness = health::readiness(
+        forecast_state.llm.as_ref(),
+        &[
+            &forecast_state.forecast_cache,
+            &forecast_state.geocode_cache,
+            &forecast_state.summary_cache,
+            &forecast_state.last_summary_cache,
+        ],
+        forecast_state.storage.as_deref(),
+    )
+    .await;
+
+    let status = match readiness.ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(readiness)).into_response()
+}
+
+// what each dependency looks like right now, always 200 since it's for people rather than
+// orchestrators
+pub async fn status(State(forecast_state): State<Arc<ForecastState>>) -> Json<health::Status> {
+    let status = health::status(
+        forecast_state.llm_pool.as_ref(),
+        &[
+            &forecast_state.forecast_cache,
+            &forecast_state.geocode_cache,
+            &forecast_state.summary_cache,
+            &forecast_state.last_summary_cache,
+        ],
+        forecast_state.storage.as_deref(),
+    )
+    .await;
+
+    Json(status)
+}
@@ -0,0 +1,344 @@
+use async_stream::stream;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::{
+    error::AppError,
+    geocode::{Coordinates, Location},
+    render,
+    storage::{self, HistoryEntry, HistoryFilter, Permalink, StorageError},
+};
+
+use std::sync::Arc;
+
+use super::{resolve_location, ForecastResponse, ForecastState};
+
+lazy_static! {
+    pub static ref PERMALINK_COUNTER: Counter = register_counter!(opts!(
+        "permalink_total",
+        "times a /s/{id} permalink was requested"
+    ))
+    .unwrap();
+    pub static ref HISTORY_COUNTER: Counter = register_counter!(opts!(
+        "history_total",
+        "times the /api/v1/history endpoint was called"
+    ))
+    .unwrap();
+    pub static ref HISTORY_EXPORT_COUNTER: Counter = register_counter!(opts!(
+        "history_export_total",
+        "times the /api/v1/history/export endpoint was called"
+    ))
+    .unwrap();
+}
+
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const MAX_HISTORY_LIMIT: usize = 100;
+const MAX_HISTORY_RADIUS_MILES: f64 = 100.0;
+const HISTORY_EXPORT_BATCH: usize = 500;
+const HISTORY_EXPORT_COLUMNS: [&str; 9] = [
+    "created_at",
+    "endpoint",
+    "address",
+    "latitude",
+    "longitude",
+    "model",
+    "prompt_version",
+    "latency_ms",
+    "summary",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryResponse {
+    pub location: Location,
+    pub summaries: Vec<HistoryEntry>,
+    pub next_cursor: Option<String>,
+}
+
+// permalinks are best effort, a summary is still returned when it can't be stored
+pub(super) async fn save_permalink<T: Serialize>(
+    forecast_state: &ForecastState,
+    endpoint: &str,
+    summary_key: &str,
+    response: &ForecastResponse<T>,
+) -> Option<String> {
+    let storage = forecast_state.storage.as_ref()?;
+
+    let permalink = Permalink {
+        id: storage::short_id(),
+        endpoint: endpoint.to_string(),
+        summary: response.summary.to_owned(),
+        model: response.model.to_owned(),
+        verified: response.verified,
+        location: response.location.clone(),
+        generated_at: response.generated_at.to_owned(),
+        periods: serde_json::to_value(&response.periods).unwrap_or_default(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    match storage.save_permalink(summary_key, &permalink).await {
+        Ok(id) => Some(format!("/s/{}", id)),
+        Err(e) => {
+            warn!("error saving permalink: {}", e);
+            None
+        }
+    }
+}
+
+pub async fn permalink(
+    Path(id): Path<String>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<Permalink>, AppError> {
+    PERMALINK_COUNTER.inc();
+
+    let not_found = || AppError::NotFound("no summary exists for this permalink".to_string());
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(not_found()),
+    };
+
+    match storage.permalink(&id).await? {
+        Some(permalink) => Ok(Json(permalink)),
+        None => Err(not_found()),
+    }
+}
+
+// lists the summaries generated for a location newest first, pass next_cursor back as cursor for older ones
+pub async fn history(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<HistoryResponse>, AppError> {
+    HISTORY_COUNTER.inc();
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    let limit = match params.get("limit") {
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(limit) if (1..=MAX_HISTORY_LIMIT).contains(&limit) => limit,
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "limit parameter must be between 1 and {}",
+                    MAX_HISTORY_LIMIT
+                )))
+            }
+        },
+        None => DEFAULT_HISTORY_LIMIT,
+    };
+
+    let cursor = match params.get("cursor") {
+        Some(cursor) => match cursor.parse::<i64>() {
+            Ok(cursor) if cursor > 0 => Some(cursor),
+            _ => {
+                return Err(AppError::BadRequest(
+                    "cursor parameter is invalid".to_string(),
+                ))
+            }
+        },
+        None => None,
+    };
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let filter = history_filter(&params, Some(location.coordinates))?;
+
+    let page = storage::search_history(storage.as_ref(), &filter, cursor, limit).await?;
+
+    Ok(Json(HistoryResponse {
+        location,
+        summaries: page.entries,
+        next_cursor: page.next_cursor.map(|cursor| cursor.to_string()),
+    }))
+}
+
+// streams every stored summary newest first, a location is optional here so the whole corpus can be pulled
+pub async fn history_export(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    HISTORY_EXPORT_COUNTER.inc();
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage.clone(),
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    let csv = match params.get("format").map(|format| format.as_str()) {
+        Some("csv") | None => true,
+        Some("jsonl") => false,
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "format parameter must be csv or jsonl".to_string(),
+            ))
+        }
+    };
+
+    let coordinates = match ["address", "lat", "lon"]
+        .iter()
+        .any(|name| params.contains_key(*name))
+    {
+        true => Some(
+            resolve_location(&forecast_state, &params)
+                .await?
+                .coordinates,
+        ),
+        false => None,
+    };
+
+    let filter = history_filter(&params, coordinates)?;
+
+    let rows = stream! {
+        if csv {
+            yield Ok::<String, StorageError>(render::csv_row(&HISTORY_EXPORT_COLUMNS));
+        }
+
+        let mut before = None;
+
+        loop {
+            let rows = match storage.history(&filter, before, HISTORY_EXPORT_BATCH).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    warn!("error exporting summary history: {}", e);
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            let exhausted = rows.len() < HISTORY_EXPORT_BATCH;
+            let mut chunk = String::new();
+
+            for (id, entry) in rows {
+                before = Some(id);
+
+                if !filter.within_radius(&entry) {
+                    continue;
+                }
+
+                match csv {
+                    true => chunk.push_str(&history_csv_row(&entry)),
+                    false => {
+                        chunk.push_str(&serde_json::to_string(&entry).unwrap());
+                        chunk.push('\n');
+                    }
+                }
+            }
+
+            if !chunk.is_empty() {
+                yield Ok(chunk);
+            }
+
+            if exhausted {
+                return;
+            }
+        }
+    };
+
+    let (content_type, file_name) = match csv {
+        true => ("text/csv; charset=utf-8", "summaries.csv"),
+        false => ("application/x-ndjson", "summaries.jsonl"),
+    };
+
+    Ok((
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        Body::from_stream(rows),
+    )
+        .into_response())
+}
+
+fn history_csv_row(entry: &HistoryEntry) -> String {
+    let latency_ms = entry.latency_ms.to_string();
+    let latitude = entry.location.coordinates.latitude.to_string();
+    let longitude = entry.location.coordinates.longitude.to_string();
+
+    render::csv_row(&[
+        &entry.created_at,
+        &entry.endpoint,
+        entry.location.address.as_deref().unwrap_or_default(),
+        &latitude,
+        &longitude,
+        &entry.model,
+        &entry.prompt_version,
+        &latency_ms,
+        &entry.summary,
+    ])
+}
+
+// radius, model, and date range filters shared by the history endpoints
+fn history_filter(
+    params: &HashMap<String, String>,
+    coordinates: Option<Coordinates>,
+) -> Result<HistoryFilter, AppError> {
+    let radius_miles = match params.get("radius") {
+        Some(radius) => match radius.parse::<f64>() {
+            Ok(radius) if radius > 0.0 && radius <= MAX_HISTORY_RADIUS_MILES => Some(radius),
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "radius parameter must be a number of miles up to {}",
+                    MAX_HISTORY_RADIUS_MILES
+                )))
+            }
+        },
+        None => None,
+    };
+
+    Ok(HistoryFilter {
+        coordinates,
+        radius_miles,
+        model: params.get("model").cloned(),
+        from: history_time(params, "from", false)?,
+        to: history_time(params, "to", true)?,
+    })
+}
+
+// accepts an RFC 3339 time or a date, a date as the end of a range includes that whole day
+fn history_time(
+    params: &HashMap<String, String>,
+    name: &str,
+    end_of_range: bool,
+) -> Result<Option<String>, AppError> {
+    let value = match params.get(name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(Some(time.with_timezone(&chrono::Utc).to_rfc3339()));
+    }
+
+    let date = match chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => date,
+        Err(_) => {
+            return Err(AppError::BadRequest(format!(
+                "{} parameter must be an RFC 3339 time or a YYYY-MM-DD date",
+                name
+            )))
+        }
+    };
+
+    let date = match end_of_range {
+        true => date.succ_opt().unwrap_or(date),
+        false => date,
+    };
+
+    Ok(date
+        .and_hms_opt(0, 0, 0)
+        .map(|time| time.and_utc().to_rfc3339()))
+}
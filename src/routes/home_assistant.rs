@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{error::AppError, render};
+
+use std::sync::Arc;
+
+use super::{bool_param, prepare::prepare_forecast, summary::short_summary, ForecastState};
+
+lazy_static! {
+    pub static ref HOME_ASSISTANT_COUNTER: Counter = register_counter!(opts!(
+        "home_assistant_total",
+        "times the /api/v1/forecast/ha endpoint was called"
+    ))
+    .unwrap();
+}
+
+// home assistant rejects sensor states longer than this
+const MAX_HOME_ASSISTANT_STATE_LENGTH: usize = 255;
+
+// home assistant sensor struct, flat so a RESTful sensor can read the state and list the rest as json_attributes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HomeAssistantSensor {
+    pub state: String,
+    pub location: String,
+    pub short_forecast: String,
+    pub temperature: i64,
+    pub temperature_unit: String,
+    pub high: Option<i64>,
+    pub low: Option<i64>,
+    pub precipitation_probability: Option<i64>,
+    pub wind_speed: String,
+    pub wind_direction: String,
+    pub updated_at: String,
+}
+
+// the short summary with the current period's numbers as attributes, for home assistant's RESTful sensor
+pub async fn forecast_home_assistant(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<HomeAssistantSensor>, AppError> {
+    HOME_ASSISTANT_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let prepared = prepare_forecast(&forecast_state, &params, refresh).await?;
+
+    let current = match prepared.nws_periods.first() {
+        Some(current) => current,
+        None => {
+            return Err(AppError::NotFound(
+                "no forecast periods are available for this location".to_string(),
+            ))
+        }
+    };
+
+    let state = match summarize {
+        true => short_summary(&forecast_state, &prepared, refresh).await?,
+        false => current.short_forecast.to_owned(),
+    };
+
+    let state = match state.chars().count() > MAX_HOME_ASSISTANT_STATE_LENGTH {
+        true => format!(
+            "{}…",
+            state
+                .chars()
+                .take(MAX_HOME_ASSISTANT_STATE_LENGTH - 1)
+                .collect::<String>()
+        ),
+        false => state,
+    };
+
+    // the next day and night periods, one of which is usually the current one
+    let high = prepared
+        .nws_periods
+        .iter()
+        .find(|period| period.is_daytime)
+        .map(|period| period.temperature);
+    let low = prepared
+        .nws_periods
+        .iter()
+        .find(|period| !period.is_daytime)
+        .map(|period| period.temperature);
+
+    Ok(Json(HomeAssistantSensor {
+        state,
+        location: render::title(&prepared.location),
+        short_forecast: current.short_forecast.to_owned(),
+        temperature: current.temperature,
+        temperature_unit: current.temperature_unit.to_owned(),
+        high,
+        low,
+        precipitation_probability: current.probability_of_precipitation.value,
+        wind_speed: current.wind_speed.to_owned(),
+        wind_direction: current.wind_direction.to_owned(),
+        updated_at: prepared.generated_at.to_owned(),
+    }))
+}
@@ -0,0 +1,250 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, FixedOffset};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    error::AppError,
+    nws::{self, HourlyPeriod},
+    prompts, relative,
+    render::PeriodRow,
+    sanitize, timezone, units,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, cached_forecast, forecast_response, forecast_units, generated_at_local,
+    history::save_permalink,
+    output_format,
+    prepare::{forecast_daylight, forecast_moon, local_dates},
+    prompt_vars, resolve_location, resolve_model, resolve_point, response_meta,
+    summary::{budgeted_input, cached_summary, input_budget, SummaryRequest},
+    ForecastResponse, ForecastState, GeneratedBy,
+};
+
+lazy_static! {
+    pub static ref HOURLY_FORECAST_COUNTER: Counter = register_counter!(opts!(
+        "hourly_forecast_total",
+        "times the /api/v1/forecast/hourly endpoint was called"
+    ))
+    .unwrap();
+}
+
+const DEFAULT_HOURLY_PERIODS: usize = 24;
+const MAX_HOURLY_PERIODS: usize = 48;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimplifiedHourlyForecastPeriod {
+    #[serde(with = "crate::timestamp")]
+    pub start_time: DateTime<FixedOffset>,
+    #[serde(with = "crate::timestamp")]
+    pub end_time: DateTime<FixedOffset>,
+    #[serde(default)]
+    pub time: String,
+    #[serde(default)]
+    pub when: String,
+    pub short_forecast: String,
+    pub temperature: String,
+    pub probability_of_precipitation: String,
+    pub relative_humidity: String,
+    pub wind_speed: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dewpoint: Option<String>,
+}
+
+impl PeriodRow for SimplifiedHourlyForecastPeriod {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "Start",
+            "Temperature",
+            "Precipitation",
+            "Humidity",
+            "Wind",
+            "Forecast",
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.start_time.to_rfc3339(),
+            self.temperature.to_owned(),
+            self.probability_of_precipitation.to_owned(),
+            self.relative_humidity.to_owned(),
+            self.wind_speed.to_owned(),
+            self.short_forecast.to_owned(),
+        ]
+    }
+}
+
+pub async fn hourly_forecast(
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Response, AppError> {
+    HOURLY_FORECAST_COUNTER.inc();
+
+    let output_format = output_format(&params, &headers);
+
+    let hours = match params.get("hours") {
+        Some(hours) => match hours.parse::<usize>() {
+            Ok(hours) if (1..=MAX_HOURLY_PERIODS).contains(&hours) => hours,
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "hours parameter must be between 1 and {}",
+                    MAX_HOURLY_PERIODS
+                )))
+            }
+        },
+        None => DEFAULT_HOURLY_PERIODS,
+    };
+
+    let include_periods = bool_param(&params, "periods", false)?;
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let point = resolve_point(&forecast_state, location.coordinates).await?;
+
+    let forecast_units = forecast_units(&forecast_state, &prompt_vars);
+    let forecast_key = format!("forecast_hourly:{}:{}", point.gridpoint(), forecast_units);
+
+    let forecast = cached_forecast::<HourlyPeriod>(
+        &forecast_state,
+        &forecast_key,
+        nws::forecast_url(&point.forecast_hourly, forecast_units),
+        refresh,
+    )
+    .await?;
+
+    let local_dates = local_dates(
+        forecast
+            .periods
+            .iter()
+            .take(hours)
+            .map(|period| period.start_time),
+    );
+    let daylight = forecast_daylight(location.coordinates, &local_dates);
+    let moon = forecast_moon(&local_dates);
+
+    let now = chrono::Utc::now();
+    let mut simplified_hourly_periods: Vec<SimplifiedHourlyForecastPeriod> = Vec::new();
+
+    for period in forecast.periods.iter().take(hours) {
+        let period = units::hourly_period(period, &prompt_vars.units);
+
+        simplified_hourly_periods.push(SimplifiedHourlyForecastPeriod {
+            time: timezone::period(
+                period.start_time,
+                period.end_time,
+                point.time_zone.as_deref(),
+            ),
+            when: relative::phrase(period.start_time, period.end_time, now),
+            start_time: period.start_time,
+            end_time: period.end_time,
+            short_forecast: sanitize::text(&period.short_forecast),
+            temperature: format!("{}{}", period.temperature, period.temperature_unit),
+            probability_of_precipitation: units::measurement(
+                period.probability_of_precipitation.value.unwrap_or(0),
+                &period.probability_of_precipitation.unit_code,
+            ),
+            relative_humidity: units::measurement(
+                period.relative_humidity.value,
+                &period.relative_humidity.unit_code,
+            ),
+            wind_speed: format!("{} {}", period.wind_speed, period.wind_direction),
+            dewpoint: units::dewpoint(&period.dewpoint, &prompt_vars.units),
+        });
+    }
+
+    if !summarize {
+        return Ok(Json(simplified_hourly_periods).into_response());
+    }
+
+    let prompt =
+        forecast_state
+            .prompts
+            .get()
+            .render(prompts::HOURLY_FORECAST, &prompt_vars, true)?;
+
+    // hourly periods have no long text to condense, so later hours are dropped instead
+    let simplified_hourly_json = budgeted_input(
+        "hourly_forecast",
+        &simplified_hourly_periods,
+        input_budget(&forecast_state, &prompt, &[]),
+        |_| {},
+    );
+
+    // the when phrases change at local midnight
+    let summary_key = format!(
+        "summary:{}:{}:{}:{}:{}:{}",
+        model,
+        prompt_vars.cache_key(),
+        forecast_key,
+        hours,
+        forecast.generated_at,
+        simplified_hourly_periods
+            .first()
+            .map(|period| relative::today(period.start_time, now).to_string())
+            .unwrap_or_default()
+    );
+
+    let (summary, verified) = cached_summary(
+        &forecast_state,
+        summary_key.clone(),
+        refresh,
+        SummaryRequest {
+            endpoint: "hourly_forecast",
+            location: Some(&location),
+            model: &model,
+            prompt: &prompt,
+            training: &[],
+            input: simplified_hourly_json,
+        },
+    )
+    .await?;
+
+    let meta = response_meta(&point, &location, &forecast, &model);
+
+    let mut response = ForecastResponse {
+        summary,
+        model,
+        verified,
+        generated_by: GeneratedBy::Llm,
+        location,
+        generated_at_local: generated_at_local(
+            &forecast.generated_at,
+            simplified_hourly_periods
+                .first()
+                .map(|period| period.start_time),
+            point.time_zone.as_deref(),
+        ),
+        generated_at: forecast.generated_at,
+        time_zone: point.time_zone,
+        daylight,
+        moon,
+        normals: None,
+        records: None,
+        degree_days: None,
+        advisories: Vec::new(),
+        drought: None,
+        periods: Some(simplified_hourly_periods),
+        permalink: None,
+        meta: Some(meta),
+    };
+
+    response.permalink =
+        save_permalink(&forecast_state, "hourly_forecast", &summary_key, &response).await;
+
+    Ok(forecast_response(output_format, response, include_periods))
+}
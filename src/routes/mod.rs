@@ -1,347 +1,768 @@
-use axum::extract::{Query, State};
-use lazy_static::lazy_static;
-use ollama_rs::{
-    generation::chat::{request::ChatMessageRequest, ChatMessage},
-    Ollama,
+use axum::{
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    Json,
 };
+use chrono::{DateTime, FixedOffset};
+use lazy_static::lazy_static;
 use prometheus::{opts, register_counter, Counter};
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::error::Error;
-use tracing::info;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    airnow::AirNowClient,
+    assistant::AlexaSkill,
+    cache::JsonCache,
+    card::CardRenderer,
+    derived::DegreeDays,
+    drought::DroughtCategory,
+    error::AppError,
+    fallback,
+    feed::FeedStore,
+    geocode::{self, Coordinates, Location},
+    gridpoint::GridData,
+    llm::{LlmBackend, WorkerPool},
+    moon::Moon,
+    normals::NormalsStation,
+    notify::ChannelTemplates,
+    nws::{self, ForecastData, ForecastPeriod, Point},
+    prompts::{self, PromptStore, PromptVars},
+    render::{self, OutputFormat, PeriodRow},
+    rules::{Advisory, RuleSet},
+    storage::Storage,
+    sun::Daylight,
+    timezone,
+    tts::TtsBackend,
+    units,
+};
 
 use std::sync::Arc;
 
+mod air_quality;
+mod alerts;
+mod assistant;
+mod aviation;
+mod current;
+mod feed;
+mod fire_weather;
+mod forecast;
+mod gridpoint;
+mod health;
+mod history;
+mod home_assistant;
+mod hourly;
+mod monitor;
+mod prepare;
+mod rivers;
+mod scheduler;
+mod socket;
+mod subscriptions;
+mod summary;
+mod tides;
+mod tropical;
+mod twilio;
+
+pub use self::air_quality::air_quality;
+pub use self::alerts::alerts;
+pub use self::assistant::assistant;
+pub use self::aviation::aviation;
+pub use self::current::current;
+pub use self::feed::{feed, spawn_feed_refresh};
+pub use self::fire_weather::fire_weather;
+pub use self::forecast::{
+    badge, forecast, forecast_audio, forecast_card, forecast_ics, forecast_short, forecast_stream,
+};
+pub use self::gridpoint::gridpoint;
+pub use self::health::{livez, readyz, root, status};
+pub use self::history::{history, history_export, permalink};
+pub use self::home_assistant::forecast_home_assistant;
+pub use self::hourly::hourly_forecast;
+pub use self::monitor::spawn_alert_monitor;
+pub use self::rivers::rivers;
+pub use self::scheduler::spawn_scheduler;
+pub use self::socket::forecast_ws;
+pub use self::subscriptions::{
+    create_subscription, delete_subscription, spawn_subscriptions, subscription, subscriptions,
+};
+pub use self::tides::tides;
+pub use self::tropical::tropical;
+pub use self::twilio::twilio_webhook;
+
 lazy_static! {
-    pub static ref FORECAST_COUNTER: Counter = register_counter!(opts!(
-        "forecast_total",
-        "times the /api/v1/forecast endpoint was called"
+    pub static ref STALE_FORECAST_REFETCH_COUNTER: Counter = register_counter!(opts!(
+        "stale_forecast_refetch_total",
+        "times a cached forecast was fetched again because it was stale"
     ))
     .unwrap();
 }
 
+const MAX_SENTENCES: usize = 8;
+const MAX_FOCUS_AREAS: usize = 4;
+// a stale forecast NWS hasn't updated either is only fetched again this often
+const STALE_REFETCH_MIN_INTERVAL_SECONDS: i64 = 300;
+
 #[derive(Clone)]
 pub struct ForecastState {
     pub client: reqwest::Client,
-    pub ollama_connection: Ollama,
-    pub ollama_model: String,
-}
-// coordinate struct
-#[derive(Deserialize)]
-struct Coordinates {
-    #[serde(rename = "y")]
-    latitude: f64,
-    #[serde(rename = "x")]
-    longitude: f64,
+    pub llm: Arc<dyn LlmBackend>,
+    // the same pool as llm, kept to report its queues
+    pub llm_pool: Arc<WorkerPool>,
+    pub model_allowlist: Vec<String>,
+    pub forecast_cache: JsonCache,
+    pub geocode_cache: JsonCache,
+    pub summary_cache: JsonCache,
+    // the last summary for each location, keyed without the forecast's generation time
+    pub last_summary_cache: JsonCache,
+    // what forecast summaries fall back to, in order, when the llm fails
+    pub summary_fallbacks: Vec<fallback::Level>,
+    pub summarize_by_default: bool,
+    pub include_alerts_by_default: bool,
+    // one gauge series per gridpoint forecast, so it's opt in
+    pub degree_day_metrics: bool,
+    pub max_input_tokens: usize,
+    pub max_summary_attempts: usize,
+    pub default_tone: String,
+    // us or si, used when a request doesn't ask for units
+    pub default_units: String,
+    // asks NWS for si forecasts, written by the forecaster in si, instead of converting us ones
+    pub nws_si_passthrough: bool,
+    pub tts: Option<Arc<dyn TtsBackend>>,
+    pub card: Option<Arc<CardRenderer>>,
+    pub airnow: Option<Arc<AirNowClient>>,
+    pub rules: Arc<RuleSet>,
+    pub badge_max_age: u64,
+    pub stale_forecast_age: chrono::TimeDelta,
+    // seconds until a cached forecast is checked with NWS again, with a conditional request
+    pub forecast_revalidate_after: i64,
+    // the most one summary can take before the request gives up on it
+    pub summary_deadline: Duration,
+    pub feed: Arc<FeedStore>,
+    pub storage: Option<Arc<dyn Storage>>,
+    pub channels: ChannelTemplates,
+    // listing every subscription exposes subscribers' addresses, so it needs this bearer token
+    pub subscriptions_admin_token: Option<String>,
+    // creating a subscription needs this or the admin token
+    pub subscriptions_create_token: Option<String>,
+    // delivers subscribers' notifications without following redirects
+    pub webhook_client: reqwest::Client,
+    pub alexa: Option<Arc<AlexaSkill>>,
+    // inbound texts are only trusted when signed with the token for the public url twilio calls
+    pub twilio_auth_token: Option<String>,
+    pub twilio_webhook_url: Option<String>,
+    pub prompts: Arc<PromptStore>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Period {
+pub struct SimplifiedForecastPeriod {
     pub detailed_forecast: String,
-    pub end_time: String,
-    pub icon: String,
-    pub is_daytime: bool,
-    pub name: String,
-    pub number: i64,
-    pub probability_of_precipitation: ProbabilityOfPrecipitation,
+    // only used for calendar events, the detailed forecast already covers it for the llm
+    #[serde(skip)]
     pub short_forecast: String,
-    pub start_time: String,
-    pub temperature: i64,
-    pub temperature_trend: Option<String>,
-    pub temperature_unit: String,
-    pub wind_direction: String,
+    #[serde(with = "crate::timestamp")]
+    pub end_time: DateTime<FixedOffset>,
+    pub name: String,
+    #[serde(with = "crate::timestamp")]
+    pub start_time: DateTime<FixedOffset>,
+    // the start and end in the location's time zone, like Tue 6 AM–6 PM PDT
+    #[serde(default)]
+    pub time: String,
+    // when the period starts relative to the request, like tonight or Saturday this weekend
+    #[serde(default)]
+    pub when: String,
+    pub temperature: String,
     pub wind_speed: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dewpoint: Option<String>,
+    // the heat index or wind chill, only when it differs from the temperature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feels_like: Option<String>,
+    // a nighttime low at or below 36F during the growing season
+    #[serde(default)]
+    pub frost_risk: bool,
+    // only filled in for focus=snow, the sentence NWS gives the amount in and the gridded total
+    // in inches or centimeters. the sentence survives condensing the detailed forecast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow_accumulation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snowfall: Option<f64>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Dewpoint {
-    pub unit_code: String,
-    pub value: f64,
+impl PeriodRow for SimplifiedForecastPeriod {
+    fn headers() -> &'static [&'static str] {
+        &["Period", "Temperature", "Wind", "Forecast"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            self.temperature.to_owned(),
+            self.wind_speed.to_owned(),
+            self.detailed_forecast.to_owned(),
+        ]
+    }
 }
 
+// forecast alert struct, the parts of an alert a forecast summary needs to lead with it
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ProbabilityOfPrecipitation {
-    pub unit_code: String,
-    pub value: Option<i64>,
+pub struct ForecastAlert {
+    pub event: String,
+    pub severity: String,
+    pub headline: String,
+    pub ends: String,
+    pub applies_to_point: bool,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct RelativeHumidity {
-    pub unit_code: String,
-    pub value: i64,
+// normals comparison struct, the 1991-2020 normals station nearest the point and each period against it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalsComparison {
+    pub station: NormalsStation,
+    pub distance_miles: f64,
+    pub periods: Vec<PeriodNormal>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct SimplifiedForecastPeriod {
-    pub detailed_forecast: String,
-    pub end_time: String,
+// period normal struct, kind is high for daytime periods and low for nighttime ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodNormal {
     pub name: String,
-    pub start_time: String,
-    pub temperature: String,
-    pub wind_speed: String,
+    pub kind: String,
+    pub temperature: i64,
+    pub normal: i64,
+    pub departure: i64,
+    pub unit: String,
+}
+
+// degree day forecast struct, fahrenheit degree days for each forecast date and their totals
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegreeDayForecast {
+    pub base: String,
+    pub heating_degree_days: f64,
+    pub cooling_degree_days: f64,
+    pub days: Vec<DailyDegreeDays>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NShotInOut {
-    pub input: String,
-    pub output: String,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyDegreeDays {
+    pub date: chrono::NaiveDate,
+    pub high: i64,
+    pub low: i64,
+    #[serde(flatten)]
+    pub degree_days: DegreeDays,
 }
 
-pub async fn root() -> &'static str {
-    "nws-forecast-summarizer"
+// records comparison struct, the periods near a record at the climate station nearest the point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordsComparison {
+    pub station: NormalsStation,
+    pub distance_miles: f64,
+    pub periods: Vec<PeriodRecord>,
 }
 
-pub async fn forecast(
-    Query(params): Query<HashMap<String, String>>,
-    State(forecast_state): State<Arc<ForecastState>>,
-) -> Result<String, &'static str> {
-    FORECAST_COUNTER.inc();
+// period record struct, status is approaches, ties, or breaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodRecord {
+    pub name: String,
+    pub kind: String,
+    pub temperature: i64,
+    pub record: i64,
+    pub record_year: i32,
+    pub unit: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForecastResponse<T> {
+    pub summary: String,
+    pub model: String,
+    pub verified: bool,
+    // responses stored before fallbacks were added were all written by the llm
+    #[serde(default)]
+    pub generated_by: GeneratedBy,
+    pub location: Location,
+    pub generated_at: String,
+    // generated_at in the location's time zone, like Wed 12:07 AM PDT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at_local: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    // sunrise and sunset for each local date the periods cover
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub daylight: Vec<Daylight>,
+    // the moon phase for each of those nights
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moon: Vec<Moon>,
+    // only filled in when normals are requested and a normals station is nearby
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normals: Option<NormalsComparison>,
+    // only filled in when records are requested and a forecast high or low comes close to one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub records: Option<RecordsComparison>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degree_days: Option<DegreeDayForecast>,
+    // periods that crossed a configured threshold, whether or not NWS issued an alert for it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub advisories: Vec<Advisory>,
+    // only looked up when precipitation is in the forecast, and only kept for D1 or worse
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drought: Option<DroughtCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub periods: Option<Vec<T>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permalink: Option<String>,
+    // responses stored before meta was added don't have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
+}
+
+// generated by enum, what wrote a summary. fallback summaries are built from the periods when the
+// llm is down, overloaded, or too slow, and nws ones are the periods' own text
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratedBy {
+    #[default]
+    Llm,
+    Fallback,
+    Nws,
+}
 
-    let address_result = match params.get("address") {
-        Some(address) => Ok(address.to_owned()),
-        None => Err("address parameter is required"),
+// response meta struct, where the forecast came from and how old it is, so consumers can judge staleness
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseMeta {
+    pub generated_at: String,
+    pub update_time: Option<String>,
+    pub office: String,
+    pub grid_x: i64,
+    pub grid_y: i64,
+    // none when the request gave coordinates
+    pub geocoder: Option<String>,
+    pub model: String,
+    // 0 when NWS was asked for this request, none for forecasts cached before it was tracked
+    pub forecast_age_seconds: Option<i64>,
+    // how far the summary fell back, none for responses without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_level: Option<fallback::Level>,
+}
+
+// sentences, units, locale, and focus are interpolated into the prompt templates
+fn prompt_vars(
+    forecast_state: &ForecastState,
+    params: &HashMap<String, String>,
+) -> Result<PromptVars, AppError> {
+    let sentences = match params.get("sentences") {
+        Some(sentences) => match sentences.parse::<usize>() {
+            Ok(sentences) if (1..=MAX_SENTENCES).contains(&sentences) => Some(sentences),
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "sentences parameter must be between 1 and {}",
+                    MAX_SENTENCES
+                )))
+            }
+        },
+        None => None,
     };
 
-    let address = match address_result {
-        Ok(address) => address,
-        Err(e) => return Err(e),
+    let units = match params.get("units") {
+        Some(units) => match units::parse(units) {
+            Some(units) => units.to_string(),
+            None => {
+                return Err(AppError::BadRequest(
+                    "units parameter must be us, si, imperial, or metric".to_string(),
+                ))
+            }
+        },
+        None => forecast_state.default_units.to_owned(),
     };
 
-    let coordinates_result = match geocode_address(forecast_state.client.clone(), address).await {
-        Ok(coordinates) => Ok(coordinates),
-        Err(e) => Err(e.to_string()),
+    // values end up in the prompt, so only allow plain words
+    let locale = match params.get("locale") {
+        Some(locale)
+            if locale.len() <= 16
+                && locale
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Some(locale.to_owned())
+        }
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "locale parameter must be a locale code like en-US".to_string(),
+            ))
+        }
+        None => None,
     };
 
-    let coordinates = match coordinates_result {
-        Ok(coordinates) => coordinates,
-        Err(_) => return Err("error geocoding address"),
+    let focus: Vec<String> = match params.get("focus") {
+        Some(focus) => focus
+            .split(',')
+            .map(|area| area.trim().to_lowercase())
+            .filter(|area| !area.is_empty())
+            .collect(),
+        None => Vec::new(),
     };
 
-    let forecast_url_result =
-        match forecast_address_from_coordinates(forecast_state.client.clone(), coordinates).await {
-            Ok(forecast_url) => Ok(forecast_url),
-            Err(e) => Err(e.to_string()),
-        };
+    if focus.len() > MAX_FOCUS_AREAS
+        || focus.iter().any(|area| {
+            area.len() > 32 || !area.chars().all(|c| c.is_ascii_alphabetic() || c == ' ')
+        })
+    {
+        return Err(AppError::BadRequest(format!(
+            "focus parameter must be at most {} comma separated words",
+            MAX_FOCUS_AREAS
+        )));
+    }
 
-    let forecast_url = match forecast_url_result {
-        Ok(forecast_url) => forecast_url,
-        Err(_) => return Err("error getting forecast URL"),
+    // emoji changes the prompt, the other formats only change how the response is rendered
+    let format = match params.get("format").map(|format| format.as_str()) {
+        Some("emoji") => "emoji".to_string(),
+        Some(format) if OutputFormat::parse(format).is_none() => {
+            return Err(AppError::BadRequest(
+                "format parameter must be json, text, markdown, html, or emoji".to_string(),
+            ))
+        }
+        _ => "text".to_string(),
     };
 
-    let periods_result =
-        match get_forecast_periods(forecast_state.client.clone(), forecast_url).await {
-            Ok(periods) => Ok(periods),
-            Err(e) => Err(e.to_string()),
-        };
-
-    let periods = match periods_result {
-        Ok(periods) => periods,
-        Err(e) => {
-            info!("error getting forecast periods: {}", e);
-            return Err("error forcast periods");
+    let tone = match params.get("tone") {
+        Some(tone) if prompts::tone_instructions(tone).is_some() => tone.to_owned(),
+        Some(_) => {
+            return Err(AppError::BadRequest(format!(
+                "tone parameter must be one of {}",
+                prompts::TONES
+                    .iter()
+                    .map(|(tone, _)| *tone)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )))
         }
+        None => forecast_state.default_tone.clone(),
     };
 
-    let mut simplified_forecast_periods: Vec<SimplifiedForecastPeriod> = Vec::new();
-
-    for period in periods {
-        simplified_forecast_periods.push(SimplifiedForecastPeriod {
-            detailed_forecast: period.detailed_forecast,
-            end_time: period.end_time,
-            name: period.name,
-            start_time: period.start_time,
-            temperature: format!("{}{}", period.temperature, period.temperature_unit),
-            wind_speed: format!("{} {}", period.wind_speed, period.wind_direction),
-        });
-    }
+    // styles come from the prompt file, so the names are checked against the loaded prompts
+    let style = match params.get("style") {
+        Some(style) => {
+            let prompts = forecast_state.prompts.get();
 
-    let simplified_forecast_json = serde_json::to_string(&simplified_forecast_periods).unwrap();
-
-    let prompt = "
-    You are a tool that can provide concise summaries of weather forecasts.
-    Input is a JSON array with one entry per forecast period.
-    Output is a JSON object with the key \"summary\" containing the overall forecast in at most four sentences.
-    Each entry contains relavant weather information including a detailed text forecast.
-    Do not include any information that is not present in the input.
-    Do not comment twice on the same weather condition.
-    Focus mainly on the daytime periods.
-    Avoid editorializing or making assumptions.
-    Make the output sound like a human wrote it, with concise but friendly language and complete sentences.
-    ";
-
-    let training = vec![NShotInOut {
-        input: "[{\"name\": \"Tonight\", \"start_time\": \"2024-06-08T20:00:00-07:00\", \"end_time\": \"2024-06-09T06:00:00-07:00\", \"temperature\": \"54F\", \"detailed_forecast\": \"Mostly cloudy, with a low around 54. East wind around 2 mph.\", \"relative_humidity\": \"80%\", \"wind_speed\": \"2 mph E\"}, {\"name\": \"Sunday\", \"start_time\": \"2024-06-09T06:00:00-07:00\", \"end_time\": \"2024-06-09T18:00:00-07:00\", \"temperature\": \"74F\", \"detailed_forecast\": \"Mostly sunny. High near 74, with temperatures falling to around 72 in the afternoon. Southwest wind 1 to 6 mph.\", \"relative_humidity\": \"79%\", \"wind_speed\": \"1 to 6 mph SW\"}, {\"name\": \"Sunday Night\", \"start_time\": \"2024-06-09T18:00:00-07:00\", \"end_time\": \"2024-06-10T06:00:00-07:00\", \"temperature\": \"51F\", \"detailed_forecast\": \"Mostly cloudy, with a low around 51. West wind 2 to 6 mph.\", \"relative_humidity\": \"85%\", \"wind_speed\": \"2 to 6 mph W\"}, {\"name\": \"Monday\", \"start_time\": \"2024-06-10T06:00:00-07:00\", \"end_time\": \"2024-06-10T18:00:00-07:00\", \"temperature\": \"71F\", \"detailed_forecast\": \"Mostly sunny, with a high near 71. Southwest wind around 3 mph.\", \"relative_humidity\": \"84%\", \"wind_speed\": \"3 mph SW\"}, {\"name\": \"Monday Night\", \"start_time\": \"2024-06-10T18:00:00-07:00\", \"end_time\": \"2024-06-11T06:00:00-07:00\", \"temperature\": \"52F\", \"detailed_forecast\": \"Partly cloudy, with a low around 52. North wind around 3 mph.\", \"relative_humidity\": \"80%\", \"wind_speed\": \"3 mph N\"}, {\"name\": \"Tuesday\", \"start_time\": \"2024-06-11T06:00:00-07:00\", \"end_time\": \"2024-06-11T18:00:00-07:00\", \"temperature\": \"69F\", \"detailed_forecast\": \"Partly sunny, with a high near 69.\", \"relative_humidity\": \"79%\", \"wind_speed\": \"2 to 7 mph SSW\"}, {\"name\": \"Tuesday Night\", \"start_time\": \"2024-06-11T18:00:00-07:00\", \"end_time\": \"2024-06-12T06:00:00-07:00\", \"temperature\": \"50F\", \"detailed_forecast\": \"Mostly cloudy, with a low around 50.\", \"relative_humidity\": \"83%\", \"wind_speed\": \"2 to 7 mph N\"}, {\"name\": \"Wednesday\", \"start_time\": \"2024-06-12T06:00:00-07:00\", \"end_time\": \"2024-06-12T18:00:00-07:00\", \"temperature\": \"67F\", \"detailed_forecast\": \"Mostly sunny, with a high near 67.\", \"relative_humidity\": \"82%\", \"wind_speed\": \"2 to 7 mph NNW\"}, {\"name\": \"Wednesday Night\", \"start_time\": \"2024-06-12T18:00:00-07:00\", \"end_time\": \"2024-06-13T06:00:00-07:00\", \"temperature\": \"47F\", \"detailed_forecast\": \"Mostly clear, with a low around 47.\", \"relative_humidity\": \"86%\", \"wind_speed\": \"1 to 7 mph N\"}, {\"name\": \"Thursday\", \"start_time\": \"2024-06-13T06:00:00-07:00\", \"end_time\": \"2024-06-13T18:00:00-07:00\", \"temperature\": \"69F\", \"detailed_forecast\": \"Mostly sunny, with a high near 69.\", \"relative_humidity\": \"84%\", \"wind_speed\": \"1 to 7 mph N\"}, {\"name\": \"Thursday Night\", \"start_time\": \"2024-06-13T18:00:00-07:00\", \"end_time\": \"2024-06-14T06:00:00-07:00\", \"temperature\": \"49F\", \"detailed_forecast\": \"Partly cloudy, with a low around 49.\", \"relative_humidity\": \"82%\", \"wind_speed\": \"2 to 7 mph NE\"}, {\"name\": \"Friday\", \"start_time\": \"2024-06-14T06:00:00-07:00\", \"end_time\": \"2024-06-14T18:00:00-07:00\", \"temperature\": \"67F\", \"detailed_forecast\": \"A chance of rain after 11am. Partly sunny, with a high near 67.\", \"relative_humidity\": \"81%\", \"wind_speed\": \"2 to 7 mph SW\"}, {\"name\": \"Friday Night\", \"start_time\": \"2024-06-14T18:00:00-07:00\", \"end_time\": \"2024-06-15T06:00:00-07:00\", \"temperature\": \"48F\", \"detailed_forecast\": \"A chance of rain. Mostly cloudy, with a low around 48.\", \"relative_humidity\": \"89%\", \"wind_speed\": \"3 to 7 mph SSW\"}, {\"name\": \"Saturday\", \"start_time\": \"2024-06-15T06:00:00-07:00\", \"end_time\": \"2024-06-15T18:00:00-07:00\", \"temperature\": \"61F\", \"detailed_forecast\": \"A chance of rain. Partly sunny, with a high near 61.\", \"relative_humidity\": \"89%\", \"wind_speed\": \"6 mph SW\"}]".to_string(),
-        output: "{\"summary\": \"This week will be mostly sunny and mild, with daytime high temperatures ranging from 61F to 74F. There might be some rain on Friday and Saturday, but it should be light. Humidity will be around 80% to 89%. Winds will be light, mostly from the south and west, up to 7mph.\"}".to_string(),
-    }];
-
-    let forecast_system_prompt = ChatMessage::system(prompt.to_string());
-
-    let training_cloned = training.clone();
-    let training_user = ChatMessage::user(training_cloned[0].input.to_owned());
-    let training_assistant = ChatMessage::assistant(training_cloned[0].output.to_owned());
-
-    let query = ChatMessage::user(simplified_forecast_json);
-
-    let chat = forecast_state
-        .ollama_connection
-        .send_chat_messages(
-            ChatMessageRequest::new(
-                forecast_state.ollama_model.clone(),
-                vec![
-                    forecast_system_prompt,
-                    training_user,
-                    training_assistant,
-                    query,
-                ],
-            )
-            .format(ollama_rs::generation::parameters::FormatType::Json),
-        )
-        .await
-        .unwrap();
-
-    let response = chat.message.unwrap().content;
-
-    Ok(response)
-}
+            if !prompts.has_style(style) {
+                return Err(AppError::BadRequest(format!(
+                    "style parameter must be one of {}",
+                    prompts.style_names().join(", ")
+                )));
+            }
 
-async fn geocode_address(
-    client: reqwest::Client,
-    address: String,
-) -> Result<Coordinates, Box<dyn Error>> {
-    let census_geocode_url = format!(
-        "https://geocoding.geo.census.gov/geocoder/locations/onelineaddress?address={}&benchmark=2020&format=json",
-        urlencoding::encode(&address)
-    );
+            Some(style.to_owned())
+        }
+        None => None,
+    };
 
-    let response_result = client.get(census_geocode_url).send().await;
+    Ok(PromptVars {
+        sentences,
+        units,
+        locale,
+        focus,
+        format,
+        alerts: false,
+        convective: false,
+        air_quality: false,
+        daylight: false,
+        normals: false,
+        records: false,
+        frost: false,
+        advisories: false,
+        drought: false,
+        tone,
+        style,
+    })
+}
 
-    let response = match response_result {
-        Ok(body) => body,
-        Err(e) => return Err(e.into()),
-    };
+// the format parameter takes precedence over the Accept header, anything unrecognized is JSON
+fn output_format(params: &HashMap<String, String>, headers: &HeaderMap) -> OutputFormat {
+    if let Some(output_format) = params
+        .get("format")
+        .and_then(|format| OutputFormat::parse(format))
+    {
+        return output_format;
+    }
 
-    let body_json_result = response.json::<serde_json::Value>().await;
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .and_then(OutputFormat::from_accept)
+        .unwrap_or(OutputFormat::Json)
+}
 
-    let body_json = match body_json_result {
-        Ok(body) => body,
-        Err(e) => return Err(e.into()),
+// the period table is always part of markdown and html output, JSON only includes periods when asked
+fn forecast_response<T: Serialize + PeriodRow>(
+    output_format: OutputFormat,
+    mut response: ForecastResponse<T>,
+    include_periods: bool,
+) -> Response {
+    let periods = response.periods.as_deref().unwrap_or_default();
+
+    let body = match output_format {
+        OutputFormat::Json => {
+            if !include_periods {
+                response.periods = None;
+            }
+            return Json(response).into_response();
+        }
+        OutputFormat::Text => render::text(&response.summary),
+        OutputFormat::Markdown => render::markdown(&response.summary, &response.location, periods),
+        OutputFormat::Html => render::html(&response.summary, &response.location, periods),
     };
 
-    let address_matches_option = body_json["result"]["addressMatches"].as_array();
+    ([(CONTENT_TYPE, output_format.content_type())], body).into_response()
+}
 
-    let address_matches = match address_matches_option {
-        Some(address_matches) => address_matches,
-        None => return Err("no address matches found".into()),
-    };
+// the default model is always allowed, others must be listed in LLM_MODEL_ALLOWLIST
+fn resolve_model(
+    forecast_state: &ForecastState,
+    params: &HashMap<String, String>,
+) -> Result<String, AppError> {
+    match params.get("model") {
+        Some(model) if forecast_state.model_allowlist.contains(model) => Ok(model.to_owned()),
+        Some(_) => Err(AppError::BadRequest(format!(
+            "model parameter must be one of {}",
+            forecast_state.model_allowlist.join(", ")
+        ))),
+        None => Ok(forecast_state.llm.model().to_string()),
+    }
+}
 
-    let coordinates_result: Result<Coordinates, serde_json::Error> =
-        serde_json::from_str(&address_matches[0]["coordinates"].to_string());
+fn bool_param(
+    params: &HashMap<String, String>,
+    name: &str,
+    default: bool,
+) -> Result<bool, AppError> {
+    match params.get(name) {
+        Some(value) => match value.parse::<bool>() {
+            Ok(value) => Ok(value),
+            Err(_) => Err(AppError::BadRequest(format!(
+                "{} parameter must be true or false",
+                name
+            ))),
+        },
+        None => Ok(default),
+    }
+}
 
-    let coordinates = match coordinates_result {
-        Ok(coordinates) => coordinates,
-        Err(e) => return Err(e.into()),
-    };
+async fn resolve_location(
+    forecast_state: &ForecastState,
+    params: &HashMap<String, String>,
+) -> Result<Location, AppError> {
+    match (params.get("lat"), params.get("lon")) {
+        (Some(lat), Some(lon)) => match coordinates_from_params(lat, lon) {
+            Some(coordinates) => Ok(Location {
+                address: None,
+                coordinates,
+            }),
+            None => Err(AppError::BadRequest(
+                "lat and lon parameters must be valid coordinates".to_string(),
+            )),
+        },
+        (Some(_), None) | (None, Some(_)) => Err(AppError::BadRequest(
+            "lat and lon parameters must be provided together".to_string(),
+        )),
+        (None, None) => {
+            let address = match params.get("address") {
+                Some(address) => address.to_owned(),
+                None => {
+                    return Err(AppError::BadRequest(
+                        "address or lat and lon parameters are required".to_string(),
+                    ))
+                }
+            };
+
+            let cache_key = format!("geocode:{}", normalize_address(&address));
+
+            if let Some(location) = forecast_state
+                .geocode_cache
+                .get::<Location>(&cache_key)
+                .await
+            {
+                return Ok(location);
+            }
+
+            let location = geocode::geocode_address(forecast_state.client.clone(), address).await?;
+
+            forecast_state
+                .geocode_cache
+                .insert(cache_key, &location)
+                .await;
+
+            Ok(location)
+        }
+    }
+}
 
-    Ok(coordinates)
+// lowercases and collapses whitespace and commas so trivially different spellings share a key
+fn normalize_address(address: &str) -> String {
+    address
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
 }
 
-async fn forecast_address_from_coordinates(
-    client: reqwest::Client,
+async fn resolve_point(
+    forecast_state: &ForecastState,
     coordinates: Coordinates,
-) -> Result<String, Box<dyn Error>> {
-    let point_url = format!(
-        "https://api.weather.gov/points/{:.5},{:.5}",
+) -> Result<Point, AppError> {
+    let cache_key = format!(
+        "point:{:.4},{:.4}",
         coordinates.latitude, coordinates.longitude
     );
 
-    let mut header_map = HeaderMap::new();
-    header_map.insert(
-        CONTENT_TYPE,
-        HeaderValue::from_static("application/geojson"),
-    );
-    header_map.insert(
-        USER_AGENT,
-        HeaderValue::from_static("nws-forecast-summarizer - michael@michaelpeterswa.com"),
-    );
+    if let Some(point) = forecast_state.forecast_cache.get::<Point>(&cache_key).await {
+        return Ok(point);
+    }
 
-    let point_response_result = client.get(point_url).headers(header_map).send().await;
+    let point = nws::get_point(forecast_state.client.clone(), coordinates).await?;
 
-    let point_response = match point_response_result {
-        Ok(body) => body,
-        Err(e) => return Err(e.into()),
-    };
+    forecast_state
+        .forecast_cache
+        .insert(cache_key, &point)
+        .await;
 
-    let point_json_result = point_response.json::<serde_json::Value>().await;
-
-    let point_json = match point_json_result {
-        Ok(body) => body,
-        Err(e) => return Err(e.into()),
-    };
+    Ok(point)
+}
 
-    let forecast_url = point_json["properties"]["forecast"].to_string();
+async fn cached_grid_data(
+    forecast_state: &ForecastState,
+    point: &Point,
+    refresh: bool,
+) -> Result<GridData, AppError> {
+    let cache_key = format!("grid_data:{}", point.gridpoint());
 
-    if forecast_url.is_empty() {
-        return Err("no forecast URL found".into());
+    if !refresh {
+        if let Some(data) = forecast_state.forecast_cache.get(&cache_key).await {
+            return Ok(data);
+        }
     }
 
-    // unwrap here is safe because the forecast URL is always a string
-    let forecast_url_cleaned = forecast_url
-        .strip_prefix("\"")
-        .unwrap()
-        .strip_suffix("\"")
-        .unwrap();
+    let properties = nws::get_grid_data(
+        forecast_state.client.clone(),
+        point.forecast_grid_data.to_owned(),
+    )
+    .await?;
 
-    info!("forecast URL: {}", forecast_url_cleaned.to_string());
+    let data = crate::gridpoint::parse(&properties)?;
 
-    Ok(forecast_url_cleaned.to_string())
-}
+    forecast_state.forecast_cache.insert(cache_key, &data).await;
 
-async fn get_forecast_periods(
-    client: reqwest::Client,
-    forecast_url: String,
-) -> Result<Vec<Period>, Box<dyn Error>> {
-    let mut header_map = HeaderMap::new();
-    header_map.insert(
-        CONTENT_TYPE,
-        HeaderValue::from_static("application/geojson"),
-    );
-    header_map.insert(
-        USER_AGENT,
-        HeaderValue::from_static("nws-forecast-summarizer - michael@michaelpeterswa.com"),
-    );
+    Ok(data)
+}
 
-    let forecast_response_result = client.get(forecast_url).headers(header_map).send().await;
+// the units to ask NWS for, us unless si passthrough is on and the request wants si
+fn forecast_units(forecast_state: &ForecastState, prompt_vars: &PromptVars) -> &'static str {
+    match forecast_state.nws_si_passthrough && prompt_vars.units == units::SI {
+        true => units::SI,
+        false => units::US,
+    }
+}
 
-    let forecast_response = match forecast_response_result {
-        Ok(body) => body,
-        Err(e) => return Err(e.into()),
-    };
+fn response_meta<T>(
+    point: &Point,
+    location: &Location,
+    forecast: &ForecastData<T>,
+    model: &str,
+) -> ResponseMeta {
+    ResponseMeta {
+        generated_at: forecast.generated_at.to_owned(),
+        update_time: forecast.update_time.to_owned(),
+        office: point.grid_id.to_owned(),
+        grid_x: point.grid_x,
+        grid_y: point.grid_y,
+        geocoder: location
+            .address
+            .as_ref()
+            .map(|_| geocode::GEOCODER.to_string()),
+        model: model.to_string(),
+        summary_level: None,
+        forecast_age_seconds: forecast
+            .fetched_at
+            .map(|fetched_at| (chrono::Utc::now() - fetched_at).num_seconds().max(0)),
+    }
+}
 
-    let forecast_json_result = forecast_response.json::<serde_json::Value>().await;
+// NWS gives generated_at in UTC, it's shown in the offset the periods are in
+fn generated_at_local(
+    generated_at: &str,
+    period_start: Option<DateTime<FixedOffset>>,
+    time_zone: Option<&str>,
+) -> Option<String> {
+    let generated_at = chrono::DateTime::parse_from_rfc3339(generated_at).ok()?;
+
+    Some(timezone::time(
+        generated_at.with_timezone(period_start?.offset()),
+        time_zone,
+    ))
+}
 
-    let forecast_json = match forecast_json_result {
-        Ok(body) => body,
-        Err(e) => return Err(e.into()),
+// stale forecasts are fetched again before they're summarized
+async fn cached_forecast<T: Serialize + DeserializeOwned + ForecastPeriod>(
+    forecast_state: &ForecastState,
+    cache_key: &str,
+    forecast_url: String,
+    refresh: bool,
+) -> Result<ForecastData<T>, AppError> {
+    // refreshes still send the cached copy's validators, NWS answers those with the latest either way
+    let cached = forecast_state
+        .forecast_cache
+        .get::<ForecastData<T>>(cache_key)
+        .await;
+
+    let cached = match cached {
+        Some(forecast) if !refresh => {
+            let now = chrono::Utc::now();
+            let fetched_within = |seconds| {
+                forecast.fetched_at.is_some_and(|fetched_at| {
+                    now - fetched_at < chrono::TimeDelta::seconds(seconds)
+                })
+            };
+
+            let stale = forecast.is_stale(forecast_state.stale_forecast_age, now)
+                && !fetched_within(STALE_REFETCH_MIN_INTERVAL_SECONDS);
+
+            // a 304 puts the forecast back in the cache, so busy gridpoints stay cached and are only
+            // downloaded again when NWS changes them
+            match (
+                stale,
+                fetched_within(forecast_state.forecast_revalidate_after),
+            ) {
+                (true, _) => STALE_FORECAST_REFETCH_COUNTER.inc(),
+                (false, true) => return Ok(forecast),
+                (false, false) => {}
+            }
+
+            Some(forecast)
+        }
+        cached => cached,
     };
 
-    let periods_json_string = forecast_json["properties"]["periods"].to_string();
-    let periods_json = periods_json_string.as_str();
+    let forecast =
+        nws::get_forecast::<T>(forecast_state.client.clone(), forecast_url, cached).await?;
 
-    if periods_json.is_empty() {
-        return Err("no forecast periods found".into());
-    }
+    forecast_state
+        .forecast_cache
+        .insert(cache_key.to_string(), &forecast)
+        .await;
 
-    let periods_result: Result<Vec<Period>, serde_json::Error> = serde_json::from_str(periods_json);
+    Ok(forecast)
+}
 
-    let periods = match periods_result {
-        Ok(periods) => periods,
-        Err(e) => return Err(e.into()),
-    };
+fn coordinates_from_params(lat: &str, lon: &str) -> Option<Coordinates> {
+    let latitude = lat.parse::<f64>().ok()?;
+    let longitude = lon.parse::<f64>().ok()?;
+
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
 
-    Ok(periods)
+    Some(Coordinates {
+        latitude,
+        longitude,
+    })
 }
@@ -0,0 +1,297 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+use crate::{
+    cap::CapDetails,
+    error::AppError,
+    geometry,
+    monitor::{self, ALERT_NOTIFICATIONS_COUNTER, ALERT_POLLS_COUNTER, NEW_ALERTS_COUNTER},
+    notify::{self, Notification},
+    nws::{self, AlertFeature, AlertFilter},
+    prompts,
+    storage::{Storage, StorageError, Subscription},
+    verify,
+};
+
+use std::sync::Arc;
+
+use super::{
+    alerts::{alert_details, simplified_alert},
+    prompt_vars, resolve_model,
+    subscriptions::subscriber_notifier,
+    summary::{summarize, SummaryRequest},
+    ForecastState,
+};
+
+// polls active alerts for every subscribed location, pushing each new warning to its subscribers once
+pub fn spawn_alert_monitor(forecast_state: Arc<ForecastState>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = poll_alerts(&forecast_state).await {
+                ALERT_POLLS_COUNTER.with_label_values(&["error"]).inc();
+                warn!("error polling alerts: {}", e);
+            }
+        }
+    });
+}
+
+// alert explanation struct, written once per alert and style in a poll however many subscribers share it
+#[derive(Debug, Clone)]
+struct AlertExplanation {
+    summary: String,
+    model: String,
+    verified: bool,
+}
+
+// alert poll cache struct, what a poll has already fetched or written for alerts shared by several subscribers
+#[derive(Debug, Default)]
+struct AlertPollCache {
+    explanations: HashMap<(String, Option<String>), AlertExplanation>,
+    details: HashMap<String, CapDetails>,
+}
+
+async fn poll_alerts(forecast_state: &ForecastState) -> Result<(), AppError> {
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    storage
+        .prune_alert_notifications(&chrono::Utc::now().to_rfc3339())
+        .await?;
+
+    let mut locations: BTreeMap<String, Vec<Subscription>> = BTreeMap::new();
+
+    for subscription in storage.subscriptions().await? {
+        locations
+            .entry(monitor::poll_key(subscription.location.coordinates))
+            .or_default()
+            .push(subscription);
+    }
+
+    let mut cache = AlertPollCache::default();
+
+    for subscriptions in locations.values() {
+        // every warning is fetched, each subscription's own filter is applied below
+        let alerts = match nws::get_active_alerts(
+            forecast_state.client.clone(),
+            subscriptions[0].location.coordinates,
+            &AlertFilter::default(),
+        )
+        .await
+        {
+            Ok(alerts) => alerts,
+            Err(e) => {
+                ALERT_POLLS_COUNTER.with_label_values(&["error"]).inc();
+                warn!("error polling alerts: {}", e);
+                continue;
+            }
+        };
+
+        ALERT_POLLS_COUNTER.with_label_values(&["success"]).inc();
+
+        for alert in alerts {
+            let properties = &alert.properties;
+
+            if !monitor::is_warning(properties) {
+                continue;
+            }
+
+            let mut new = false;
+
+            for subscription in subscriptions {
+                if !subscription.alerts.matches(properties) {
+                    continue;
+                }
+
+                // subscriptions share a poll by rounded coordinates, the polygon is checked against each one
+                if !geometry::applies_to_point(
+                    alert.geometry.as_ref(),
+                    &[],
+                    subscription.location.coordinates,
+                ) {
+                    continue;
+                }
+
+                match notify_alert(
+                    forecast_state,
+                    storage.as_ref(),
+                    &alert,
+                    subscription,
+                    &mut cache,
+                )
+                .await
+                {
+                    Ok(notified) => new |= notified,
+                    Err(e) => {
+                        ALERT_NOTIFICATIONS_COUNTER
+                            .with_label_values(&["error"])
+                            .inc();
+                        warn!(
+                            "error notifying subscription {} of alert {}: {}",
+                            subscription.id, properties.id, e
+                        );
+                    }
+                }
+            }
+
+            if new {
+                NEW_ALERTS_COUNTER
+                    .with_label_values(&[&properties.severity.to_lowercase()])
+                    .inc();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// false when the subscriber already has this alert, or the alert it updates.
+// nothing is recorded until the explanation is written, so a failed one is retried on the next poll
+async fn notify_alert(
+    forecast_state: &ForecastState,
+    storage: &dyn Storage,
+    alert: &AlertFeature,
+    subscription: &Subscription,
+    cache: &mut AlertPollCache,
+) -> Result<bool, AppError> {
+    let properties = &alert.properties;
+    let expires = monitor::expires_utc(properties);
+
+    if storage
+        .alert_notified(&properties.id, &subscription.id)
+        .await?
+    {
+        return Ok(false);
+    }
+
+    for reference in &properties.references {
+        if storage
+            .alert_notified(&reference.identifier, &subscription.id)
+            .await?
+        {
+            // updates get new ids, recording this one keeps it from being checked again
+            storage
+                .record_alert_notification(&properties.id, &subscription.id, &expires)
+                .await?;
+            return Ok(false);
+        }
+    }
+
+    let notifier = subscriber_notifier(forecast_state, subscription).await?;
+
+    let details = match cache.details.get(&properties.id) {
+        Some(details) => details.to_owned(),
+        None => {
+            let details = alert_details(forecast_state, properties, true).await;
+            cache
+                .details
+                .insert(properties.id.to_owned(), details.clone());
+            details
+        }
+    };
+
+    let key = (properties.id.to_owned(), subscription.style.to_owned());
+
+    let explanation = match cache.explanations.get(&key) {
+        Some(explanation) => explanation.to_owned(),
+        None => {
+            let explanation = explain_alert(forecast_state, alert, &details, subscription).await?;
+            cache.explanations.insert(key, explanation.clone());
+            explanation
+        }
+    };
+
+    // another replica may have notified the subscriber while the explanation was written
+    if !storage
+        .record_alert_notification(&properties.id, &subscription.id, &expires)
+        .await?
+    {
+        return Ok(false);
+    }
+
+    let notification = Notification {
+        event: "alert",
+        source: subscription.id.to_owned(),
+        summary: explanation.summary,
+        model: explanation.model,
+        verified: explanation.verified,
+        location: subscription.location.clone(),
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        permalink: None,
+        alert: Some(properties.event.to_owned()),
+        severity: Some(properties.severity.to_owned()),
+        instruction: details.instruction,
+        periods: Vec::new(),
+    };
+
+    tokio::spawn(async move {
+        let result = match notify::deliver(notifier.as_ref(), &notification).await {
+            true => "success",
+            false => "error",
+        };
+
+        ALERT_NOTIFICATIONS_COUNTER
+            .with_label_values(&[result])
+            .inc();
+    });
+
+    Ok(true)
+}
+
+async fn explain_alert(
+    forecast_state: &ForecastState,
+    alert: &AlertFeature,
+    details: &CapDetails,
+    subscription: &Subscription,
+) -> Result<AlertExplanation, AppError> {
+    let mut params = HashMap::new();
+
+    if let Some(style) = &subscription.style {
+        params.insert("style".to_string(), style.to_owned());
+    }
+
+    let model = resolve_model(forecast_state, &params)?;
+    let prompt_vars = prompt_vars(forecast_state, &params)?;
+
+    let input = serde_json::to_string(&[simplified_alert(
+        alert,
+        details,
+        subscription.location.coordinates,
+    )])
+    .unwrap();
+
+    let prompt = forecast_state
+        .prompts
+        .get()
+        .render(prompts::ALERTS, &prompt_vars, true)?;
+
+    let summary = summarize(
+        forecast_state,
+        &SummaryRequest {
+            endpoint: "alert_monitor",
+            location: Some(&subscription.location),
+            model: &model,
+            prompt: &prompt,
+            training: &[],
+            input: input.clone(),
+        },
+        Instant::now() + forecast_state.summary_deadline,
+    )
+    .await?;
+
+    let verified = verify::verify(&summary, &input).verified;
+
+    Ok(AlertExplanation {
+        summary,
+        model,
+        verified,
+    })
+}
@@ -0,0 +1,1184 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_gauge_vec, GaugeVec};
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::{
+    airnow::{AirNowClient, AirNowError, AirQuality},
+    derived,
+    drought::{self, DroughtArea, DroughtCategory, DroughtError, DroughtLookup},
+    error::AppError,
+    geocode::{Coordinates, Location},
+    geometry,
+    gridpoint::{self, GridData},
+    llm,
+    moon::{self, Moon},
+    normals::{self, DailyNormal, NormalsError, NormalsStation},
+    nws::{self, AlertFeature, AlertFilter, Period},
+    prompts::{self, PromptVars},
+    records::{self, DailyRecord, RecordsError},
+    relative,
+    rules::{Advisory, PeriodMetrics},
+    sanitize,
+    spc::{self, ConvectiveRisk, OutlookArea, SpcError},
+    sun::{self, Daylight},
+    timezone, units,
+};
+
+use super::{
+    bool_param, cached_forecast, cached_grid_data, forecast_units, generated_at_local, prompt_vars,
+    resolve_location, resolve_model, resolve_point, response_meta,
+    summary::{budgeted_input, condense_forecast_period},
+    DailyDegreeDays, DegreeDayForecast, ForecastAlert, ForecastState, NormalsComparison,
+    PeriodNormal, PeriodRecord, RecordsComparison, ResponseMeta, SimplifiedForecastPeriod,
+};
+
+lazy_static! {
+    pub static ref DEGREE_DAYS_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "forecast_degree_days",
+            "heating or cooling degree days over a gridpoint's forecast window"
+        ),
+        &["gridpoint", "kind"]
+    )
+    .unwrap();
+}
+
+// climate differs across a metro area, so a distant station's normals would mislead
+const NORMALS_STATION_MAX_DISTANCE_MILES: f64 = 30.0;
+// drought is only worth mentioning when the forecast has a real chance to ease it
+const DROUGHT_MIN_PRECIPITATION_CHANCE: i64 = 30;
+// a forecast this close to a record is worth calling out before it breaks one
+const RECORD_MARGIN_DEGREES_F: f64 = 3.0;
+
+// forecast input struct, what the summary is written from when alerts are included
+#[derive(Debug, Clone, Serialize)]
+struct ForecastInput<'a> {
+    alerts: &'a [ForecastAlert],
+    #[serde(skip_serializing_if = "<[ConvectiveRisk]>::is_empty")]
+    convective_outlook: &'a [ConvectiveRisk],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    air_quality: Option<&'a AirQuality>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    daylight: Vec<DaylightInput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    moon: Vec<MoonInput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    normals: Vec<NormalInput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    records: Vec<RecordInput>,
+    #[serde(skip_serializing_if = "<[Advisory]>::is_empty")]
+    advisories: &'a [Advisory],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    drought: Option<&'a DroughtCategory>,
+    periods: serde_json::Value,
+}
+
+// daylight input struct, times are written out so the model doesn't have to read offsets
+#[derive(Debug, Clone, Serialize)]
+struct DaylightInput {
+    date: String,
+    sunrise: Option<String>,
+    sunset: Option<String>,
+}
+
+// moon input struct, the phase for the night that starts on each date
+#[derive(Debug, Clone, Serialize)]
+struct MoonInput {
+    night: String,
+    phase: String,
+    illumination: String,
+}
+
+// normal input struct, the normal for the temperature a period gives and how far the forecast is from it
+#[derive(Debug, Clone, Serialize)]
+struct NormalInput {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normal_high: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normal_low: Option<String>,
+    departure: String,
+}
+
+// record input struct, the record a period's forecast comes close to and the year it was set
+#[derive(Debug, Clone, Serialize)]
+struct RecordInput {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    record_high: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    record_low: Option<String>,
+    status: String,
+}
+
+// prepared forecast struct, everything needed to summarize a 12-hour period forecast
+pub(super) struct PreparedForecast {
+    pub(super) location: Location,
+    pub(super) generated_at: String,
+    pub(super) generated_at_local: Option<String>,
+    pub(super) meta: ResponseMeta,
+    pub(super) time_zone: Option<String>,
+    pub(super) periods: Vec<SimplifiedForecastPeriod>,
+    // the periods as NWS returned them, for endpoints that need numbers instead of text
+    pub(super) nws_periods: Vec<Period>,
+    // only fetched when the request or config asks for alerts
+    alerts: Vec<ForecastAlert>,
+    // fetched along with alerts, empty outside the outlooks' coverage
+    convective_outlook: Vec<ConvectiveRisk>,
+    // only kept when AirNow is configured and the air is moderate or worse
+    air_quality: Option<AirQuality>,
+    pub(super) daylight: Vec<Daylight>,
+    pub(super) moon: Vec<Moon>,
+    // only looked up when the request asks for normals
+    pub(super) normals: Option<NormalsComparison>,
+    // only looked up when the request asks for records, and only kept when one is close
+    pub(super) records: Option<RecordsComparison>,
+    pub(super) degree_days: Option<DegreeDayForecast>,
+    pub(super) advisories: Vec<Advisory>,
+    pub(super) drought: Option<DroughtCategory>,
+    pub(super) model: String,
+    pub(super) prompt_vars: PromptVars,
+    pub(super) summary_key: String,
+    // summary_key without the parts that change from forecast to forecast
+    pub(super) last_summary_key: String,
+}
+
+pub(super) async fn prepare_forecast(
+    forecast_state: &ForecastState,
+    params: &HashMap<String, String>,
+    refresh: bool,
+) -> Result<PreparedForecast, AppError> {
+    let model = resolve_model(forecast_state, params)?;
+    let mut prompt_vars = prompt_vars(forecast_state, params)?;
+    let include_alerts = bool_param(params, "alerts", forecast_state.include_alerts_by_default)?;
+    prompt_vars.daylight = bool_param(params, "daylight", false)?;
+    let include_normals = bool_param(params, "normals", false)?;
+    let include_records = bool_param(params, "records", false)?;
+
+    let location = resolve_location(forecast_state, params).await?;
+
+    let point = resolve_point(forecast_state, location.coordinates).await?;
+
+    let forecast_units = forecast_units(forecast_state, &prompt_vars);
+    let forecast_key = format!("forecast:{}:{}", point.gridpoint(), forecast_units);
+
+    let forecast = cached_forecast::<Period>(
+        forecast_state,
+        &forecast_key,
+        nws::forecast_url(&point.forecast, forecast_units),
+        refresh,
+    )
+    .await?;
+
+    // a snow summary is still written from the text forecast when the grid can't be read
+    let grid_data = match prompt_vars.snow() {
+        true => match cached_grid_data(forecast_state, &point, refresh).await {
+            Ok(grid_data) => Some(grid_data),
+            Err(e) => {
+                warn!("error getting snowfall for forecast summary: {}", e);
+                None
+            }
+        },
+        false => None,
+    };
+
+    let local_dates = local_dates(forecast.periods.iter().map(|period| period.start_time));
+    let daylight = forecast_daylight(location.coordinates, &local_dates);
+    let moon = forecast_moon(&local_dates);
+
+    let degree_days = forecast_degree_days(&forecast.periods);
+
+    if forecast_state.degree_day_metrics {
+        if let Some(degree_days) = &degree_days {
+            let gridpoint = point.gridpoint();
+            DEGREE_DAYS_GAUGE
+                .with_label_values(&[&gridpoint, "heating"])
+                .set(degree_days.heating_degree_days);
+            DEGREE_DAYS_GAUGE
+                .with_label_values(&[&gridpoint, "cooling"])
+                .set(degree_days.cooling_degree_days);
+        }
+    }
+
+    // rule thresholds are us units, so they're tested before the periods are converted
+    let advisories: Vec<Advisory> = forecast
+        .periods
+        .iter()
+        .flat_map(|period| {
+            forecast_state
+                .rules
+                .evaluate(&sanitize::text(&period.name), &period_metrics(period))
+        })
+        .collect();
+
+    let periods: Vec<Period> = forecast
+        .periods
+        .iter()
+        .map(|period| units::period(period, &prompt_vars.units))
+        .collect();
+
+    let now = chrono::Utc::now();
+    let mut simplified_forecast_periods: Vec<SimplifiedForecastPeriod> = Vec::new();
+
+    for period in &periods {
+        let (snow_accumulation, snowfall) = match prompt_vars.snow() {
+            true => (
+                snow_accumulation(&period.detailed_forecast),
+                grid_data
+                    .as_ref()
+                    .and_then(|grid_data| period_snowfall(grid_data, period, &prompt_vars.units)),
+            ),
+            false => (None, None),
+        };
+
+        simplified_forecast_periods.push(SimplifiedForecastPeriod {
+            detailed_forecast: sanitize::text(&period.detailed_forecast),
+            short_forecast: period.short_forecast.to_owned(),
+            end_time: period.end_time,
+            name: sanitize::text(&period.name),
+            start_time: period.start_time,
+            time: timezone::period(
+                period.start_time,
+                period.end_time,
+                point.time_zone.as_deref(),
+            ),
+            when: relative::phrase(period.start_time, period.end_time, now),
+            temperature: format!("{}{}", period.temperature, period.temperature_unit),
+            wind_speed: format!("{} {}", period.wind_speed, period.wind_direction),
+            dewpoint: period
+                .dewpoint
+                .as_ref()
+                .and_then(|dewpoint| units::dewpoint(dewpoint, &prompt_vars.units)),
+            feels_like: feels_like(period),
+            frost_risk: frost_risk(period, location.coordinates.latitude),
+            snow_accumulation,
+            snowfall,
+        });
+    }
+
+    prompt_vars.frost = simplified_forecast_periods
+        .iter()
+        .any(|period| period.frost_risk);
+
+    let precipitation_expected = forecast.periods.iter().any(|period| {
+        period
+            .probability_of_precipitation
+            .value
+            .unwrap_or_default()
+            >= DROUGHT_MIN_PRECIPITATION_CHANCE
+    });
+
+    let ((alerts, convective_outlook), air_quality, normals, drought, records) = tokio::join!(
+        async {
+            match include_alerts {
+                true => tokio::join!(
+                    forecast_alerts(forecast_state, location.coordinates),
+                    convective_outlook(forecast_state, location.coordinates),
+                ),
+                false => (Vec::new(), Vec::new()),
+            }
+        },
+        forecast_air_quality(forecast_state, location.coordinates),
+        async {
+            match include_normals {
+                true => forecast_normals(forecast_state, location.coordinates, &periods).await,
+                false => None,
+            }
+        },
+        async {
+            match precipitation_expected {
+                true => forecast_drought(forecast_state, location.coordinates).await,
+                false => None,
+            }
+        },
+        async {
+            match include_records {
+                true => forecast_records(forecast_state, location.coordinates, &periods).await,
+                false => None,
+            }
+        },
+    );
+
+    let mut summary_key = format!(
+        "summary:{}:{}:{}:{}",
+        model,
+        prompt_vars.cache_key(),
+        forecast_key,
+        forecast.generated_at
+    );
+
+    // the when phrases change at local midnight
+    if let Some(today) = forecast
+        .periods
+        .first()
+        .map(|period| relative::today(period.start_time, now))
+    {
+        summary_key = format!("{}:{}", summary_key, today);
+    }
+
+    // alerts come and go between forecast updates, so the summary is keyed on which ones it mentions
+    if !alerts.is_empty() {
+        let headlines: Vec<&str> = alerts.iter().map(|alert| alert.headline.as_str()).collect();
+        summary_key = format!(
+            "{}:alerts:{}",
+            summary_key,
+            prompts::version(&headlines.join("\n"))
+        );
+        prompt_vars.alerts = true;
+    }
+
+    // outlooks are reissued through the day, usually without changing the risk at a given point
+    if !convective_outlook.is_empty() {
+        let risks: Vec<String> = convective_outlook
+            .iter()
+            .map(|risk| format!("{}:{}:{}", risk.day, risk.category, risk.valid))
+            .collect();
+        summary_key = format!(
+            "{}:convective:{}",
+            summary_key,
+            prompts::version(&risks.join("\n"))
+        );
+        prompt_vars.convective = true;
+    }
+
+    if prompt_vars.daylight {
+        summary_key = format!("{}:daylight", summary_key);
+    }
+
+    // the phase changes with the date, which the forecast key already covers
+    if prompt_vars.stargazing() {
+        summary_key = format!("{}:moon", summary_key);
+    }
+
+    // the grid updates on its own schedule, so snowfall amounts can change between forecasts
+    if let Some(grid_data) = &grid_data {
+        summary_key = format!("{}:snow:{}", summary_key, grid_data.updated.timestamp());
+    }
+
+    if let Some(air_quality) = &air_quality {
+        summary_key = format!(
+            "{}:air_quality:{}:{}",
+            summary_key, air_quality.observed, air_quality.aqi
+        );
+        prompt_vars.air_quality = true;
+    }
+
+    // normals only change every ten years, the forecast temperatures are already in the key
+    if let Some(normals) = &normals {
+        summary_key = format!("{}:normals:{}", summary_key, normals.station.id);
+        prompt_vars.normals = true;
+    }
+
+    // the rules can change between restarts, so the key covers what they found rather than the forecast alone
+    if !advisories.is_empty() {
+        let found: Vec<String> = advisories
+            .iter()
+            .map(|advisory| format!("{}:{}", advisory.period, advisory.message))
+            .collect();
+        summary_key = format!(
+            "{}:advisories:{}",
+            summary_key,
+            prompts::version(&found.join("\n"))
+        );
+        prompt_vars.advisories = true;
+    }
+
+    // the map changes weekly, so the summary is keyed on the category it mentions
+    if let Some(drought) = &drought {
+        summary_key = format!("{}:drought:{}", summary_key, drought.category);
+        prompt_vars.drought = true;
+    }
+
+    if let Some(records) = &records {
+        summary_key = format!("{}:records:{}", summary_key, records.station.id);
+        prompt_vars.records = true;
+    }
+
+    let last_summary_key = format!(
+        "last_summary:{}:{}:{}",
+        model,
+        prompt_vars.cache_key(),
+        forecast_key
+    );
+
+    let meta = response_meta(&point, &location, &forecast, &model);
+
+    Ok(PreparedForecast {
+        location,
+        summary_key,
+        last_summary_key,
+        model,
+        prompt_vars,
+        generated_at_local: generated_at_local(
+            &forecast.generated_at,
+            forecast.periods.first().map(|period| period.start_time),
+            point.time_zone.as_deref(),
+        ),
+        time_zone: point.time_zone.to_owned(),
+        meta,
+        generated_at: forecast.generated_at,
+        periods: simplified_forecast_periods,
+        nws_periods: forecast.periods,
+        alerts,
+        convective_outlook,
+        air_quality,
+        daylight,
+        moon,
+        normals,
+        records,
+        degree_days,
+        advisories,
+        drought,
+    })
+}
+
+// a summary is still written when alerts can't be fetched, it just can't mention them
+async fn forecast_alerts(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+) -> Vec<ForecastAlert> {
+    let alerts = match nws::get_active_alerts(
+        forecast_state.client.clone(),
+        coordinates,
+        &AlertFilter::default(),
+    )
+    .await
+    {
+        Ok(alerts) => alerts,
+        Err(e) => {
+            warn!("error getting alerts for forecast summary: {}", e);
+            return Vec::new();
+        }
+    };
+
+    current_alerts(&alerts, coordinates)
+}
+
+// one entry per local date the periods start on, each with the offset NWS gives that date's periods
+pub(super) fn local_dates(
+    start_times: impl Iterator<Item = DateTime<FixedOffset>>,
+) -> Vec<(NaiveDate, FixedOffset)> {
+    let mut dates: Vec<(NaiveDate, FixedOffset)> = Vec::new();
+
+    for start in start_times {
+        // periods are in order, so a date only needs checking against the last one
+        if dates
+            .last()
+            .is_some_and(|(date, _)| *date == start.date_naive())
+        {
+            continue;
+        }
+
+        dates.push((start.date_naive(), *start.offset()));
+    }
+
+    dates
+}
+
+pub(super) fn forecast_daylight(
+    coordinates: Coordinates,
+    dates: &[(NaiveDate, FixedOffset)],
+) -> Vec<Daylight> {
+    dates
+        .iter()
+        .map(|(date, offset)| sun::daylight(coordinates, *date, *offset))
+        .collect()
+}
+
+pub(super) fn forecast_moon(dates: &[(NaiveDate, FixedOffset)]) -> Vec<Moon> {
+    dates
+        .iter()
+        .map(|(date, offset)| moon::moon(*date, *offset))
+        .collect()
+}
+
+// a date counts once it has both a daytime high and a nighttime low, so a forecast
+// starting with tonight skips today
+fn forecast_degree_days(periods: &[Period]) -> Option<DegreeDayForecast> {
+    let mut days: Vec<DailyDegreeDays> = Vec::new();
+
+    for period in periods.iter().filter(|period| period.is_daytime) {
+        let date = period.start_time.date_naive();
+
+        let night = periods
+            .iter()
+            .find(|night| !night.is_daytime && night.start_time.date_naive() == date);
+
+        let night = match night {
+            Some(night) => night,
+            None => continue,
+        };
+
+        // si forecasts are whole degrees celsius, rounded so the days match the highs and lows shown
+        let high = fahrenheit(period.temperature, &period.temperature_unit).round();
+        let low = fahrenheit(night.temperature, &night.temperature_unit).round();
+
+        days.push(DailyDegreeDays {
+            date,
+            high: high as i64,
+            low: low as i64,
+            degree_days: derived::degree_days(high, low),
+        });
+    }
+
+    if days.is_empty() {
+        return None;
+    }
+
+    Some(DegreeDayForecast {
+        base: format!("{}F", derived::DEGREE_DAY_BASE_F),
+        heating_degree_days: days.iter().map(|day| day.degree_days.heating).sum(),
+        cooling_degree_days: days.iter().map(|day| day.degree_days.cooling).sum(),
+        days,
+    })
+}
+
+// what the rules engine tests, in the units its thresholds are written in
+fn period_metrics(period: &Period) -> PeriodMetrics {
+    let temperature = fahrenheit(period.temperature, &period.temperature_unit);
+
+    let apparent = derived::apparent_temperature(
+        temperature,
+        period
+            .relative_humidity
+            .as_ref()
+            .map(|relative_humidity| relative_humidity.value as f64),
+        derived::wind_speed_mph(&period.wind_speed),
+    );
+
+    PeriodMetrics {
+        temperature: Some(temperature),
+        wind_speed: derived::wind_speed_mph(&period.wind_speed),
+        wind_gust: derived::wind_gust_mph(&period.detailed_forecast),
+        precipitation_chance: period
+            .probability_of_precipitation
+            .value
+            .map(|value| value as f64),
+        heat_index: apparent.filter(|apparent| *apparent > temperature),
+        wind_chill: apparent.filter(|apparent| *apparent < temperature),
+        snowfall: snow_accumulation(&period.detailed_forecast)
+            .and_then(|accumulation| derived::accumulation_inches(&accumulation)),
+    }
+}
+
+pub(super) fn frost_risk(period: &Period, latitude: f64) -> bool {
+    if period.is_daytime
+        || fahrenheit(period.temperature, &period.temperature_unit) > derived::FROST_MAX_F
+    {
+        return false;
+    }
+
+    derived::growing_season(period.start_time.date_naive(), latitude)
+}
+
+// the warning for the first period at risk, added to summaries that don't already mention frost
+pub(super) fn frost_warning(prepared: &PreparedForecast) -> Option<String> {
+    let (period, simplified) = prepared
+        .nws_periods
+        .iter()
+        .zip(&prepared.periods)
+        .find(|(_, simplified)| simplified.frost_risk)?;
+
+    match fahrenheit(period.temperature, &period.temperature_unit) <= derived::FREEZE_MAX_F {
+        true => Some(format!(
+            "A freeze is possible {} with a low near {}, so protect or bring in sensitive plants.",
+            simplified.name, simplified.temperature
+        )),
+        false => Some(format!(
+            "Frost is possible {} with a low near {}, so cover sensitive plants.",
+            simplified.name, simplified.temperature
+        )),
+    }
+}
+
+// freezing rain and freezing fog aren't about frost, so only these count as a mention
+pub(super) fn mentions_frost(summary: &str) -> bool {
+    let summary = summary.to_lowercase();
+
+    ["frost", "freeze", "below freezing"]
+        .iter()
+        .any(|word| summary.contains(word))
+}
+
+pub(super) fn fahrenheit(temperature: i64, unit: &str) -> f64 {
+    match unit {
+        "C" => temperature as f64 * 9.0 / 5.0 + 32.0,
+        _ => temperature as f64,
+    }
+}
+
+// the formulas are fahrenheit, so celsius periods are converted there and back
+pub(super) fn feels_like(period: &Period) -> Option<String> {
+    let celsius = period.temperature_unit == "C";
+
+    let temperature_f = match celsius {
+        true => period.temperature as f64 * 9.0 / 5.0 + 32.0,
+        false => period.temperature as f64,
+    };
+
+    let apparent = derived::apparent_temperature(
+        temperature_f,
+        period
+            .relative_humidity
+            .as_ref()
+            .map(|relative_humidity| relative_humidity.value as f64),
+        derived::wind_speed_mph(&period.wind_speed),
+    )?;
+
+    let apparent = match celsius {
+        true => (apparent - 32.0) * 5.0 / 9.0,
+        false => apparent,
+    };
+
+    Some(format!(
+        "{}{}",
+        apparent.round() as i64,
+        period.temperature_unit
+    ))
+}
+
+// NWS puts amounts in their own sentence, like: New snow accumulation of 2 to 4 inches possible.
+pub(super) fn snow_accumulation(detailed_forecast: &str) -> Option<String> {
+    detailed_forecast
+        .split_inclusive(". ")
+        .find(|sentence| sentence.to_lowercase().contains("accumulation"))
+        .map(|sentence| sanitize::text(sentence.trim()))
+}
+
+// snowfallAmount is in millimeters of snow, not liquid equivalent
+fn period_snowfall(grid_data: &GridData, period: &Period, units: &str) -> Option<f64> {
+    if grid_data.snowfall_amount.unit_code != "wmoUnit:mm" {
+        return None;
+    }
+
+    let range = gridpoint::TimeRange {
+        start: period.start_time.to_utc(),
+        end: period.end_time.to_utc(),
+    };
+
+    let millimeters = gridpoint::total(&grid_data.snowfall_amount, range)?;
+
+    let snowfall = match units {
+        "si" => millimeters / 10.0,
+        _ => millimeters / 25.4,
+    };
+
+    Some((snowfall * 10.0).round() / 10.0)
+}
+
+// like alerts, the summary is still written without the risk when an outlook can't be fetched
+async fn convective_outlook(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+) -> Vec<ConvectiveRisk> {
+    let outlooks = futures_util::future::join_all(
+        spc::OUTLOOK_DAYS
+            .iter()
+            .map(|day| async move { (*day, cached_spc_outlook(forecast_state, *day).await) }),
+    )
+    .await;
+
+    outlooks
+        .into_iter()
+        .filter_map(|(day, areas)| match areas {
+            Ok(areas) => spc::risk_at(&areas, day, coordinates),
+            Err(e) => {
+                warn!("error getting day {} convective outlook: {}", day, e);
+                None
+            }
+        })
+        .collect()
+}
+
+// like alerts, the summary is still written without the drought status when the map can't be read
+async fn forecast_drought(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+) -> Option<DroughtCategory> {
+    match cached_drought_lookup(forecast_state, coordinates).await {
+        Ok(lookup) => lookup.category.filter(|category| category.is_drought()),
+        Err(e) => {
+            warn!("error getting drought status for forecast summary: {}", e);
+            None
+        }
+    }
+}
+
+// the map is megabytes of polygons, so each point's answer is cached on its own
+async fn cached_drought_lookup(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+) -> Result<DroughtLookup, DroughtError> {
+    let cache_key = format!(
+        "drought:{:.2},{:.2}",
+        coordinates.latitude, coordinates.longitude
+    );
+
+    if let Some(lookup) = forecast_state.forecast_cache.get(&cache_key).await {
+        return Ok(lookup);
+    }
+
+    let areas = cached_drought_map(forecast_state).await?;
+    let lookup = drought::lookup(&areas, coordinates);
+
+    forecast_state
+        .forecast_cache
+        .insert(cache_key, &lookup)
+        .await;
+
+    Ok(lookup)
+}
+
+// the map covers the whole country, so one copy is shared by every location
+async fn cached_drought_map(
+    forecast_state: &ForecastState,
+) -> Result<Vec<DroughtArea>, DroughtError> {
+    let cache_key = "drought_map";
+
+    if let Some(areas) = forecast_state.forecast_cache.get(cache_key).await {
+        return Ok(areas);
+    }
+
+    let areas = drought::get_drought_map(forecast_state.client.clone()).await?;
+
+    forecast_state
+        .forecast_cache
+        .insert(cache_key.to_string(), &areas)
+        .await;
+
+    Ok(areas)
+}
+
+// the outlooks cover the whole country, so one copy of each day is shared by every location
+async fn cached_spc_outlook(
+    forecast_state: &ForecastState,
+    day: u8,
+) -> Result<Vec<OutlookArea>, SpcError> {
+    let cache_key = format!("spc_outlook:day{}", day);
+
+    if let Some(areas) = forecast_state.forecast_cache.get(&cache_key).await {
+        return Ok(areas);
+    }
+
+    let areas = spc::get_outlook(forecast_state.client.clone(), day).await?;
+
+    forecast_state
+        .forecast_cache
+        .insert(cache_key, &areas)
+        .await;
+
+    Ok(areas)
+}
+
+// each period's high or low against the normal for its date, the summary is still written without them
+async fn forecast_normals(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+    periods: &[Period],
+) -> Option<NormalsComparison> {
+    let stations = match cached_normals_stations(forecast_state, coordinates).await {
+        Ok(stations) => stations,
+        Err(e) => {
+            warn!("error getting normals stations for forecast summary: {}", e);
+            return None;
+        }
+    };
+
+    let (station, distance_miles) = normals::nearest(&stations, coordinates)
+        .filter(|(_, distance_miles)| *distance_miles <= NORMALS_STATION_MAX_DISTANCE_MILES)?;
+
+    let daily_normals = match cached_daily_normals(forecast_state, &station.id).await {
+        Ok(daily_normals) => daily_normals,
+        Err(e) => {
+            warn!("error getting normals for station {}: {}", station.id, e);
+            return None;
+        }
+    };
+
+    let periods: Vec<PeriodNormal> = periods
+        .iter()
+        .filter_map(|period| {
+            let daily_normal = normals::for_date(&daily_normals, period.start_time.date_naive())?;
+
+            // daytime periods carry the high and nighttime periods the low
+            let (kind, normal) = match period.is_daytime {
+                true => ("high", daily_normal.high?),
+                false => ("low", daily_normal.low?),
+            };
+
+            // normals are fahrenheit, periods are celsius when units=si
+            let normal = match period.temperature_unit.as_str() {
+                "C" => (normal - 32.0) * 5.0 / 9.0,
+                _ => normal,
+            }
+            .round() as i64;
+
+            Some(PeriodNormal {
+                name: sanitize::text(&period.name),
+                kind: kind.to_string(),
+                temperature: period.temperature,
+                normal,
+                departure: period.temperature - normal,
+                unit: period.temperature_unit.to_owned(),
+            })
+        })
+        .collect();
+
+    if periods.is_empty() {
+        return None;
+    }
+
+    Some(NormalsComparison {
+        station: station.to_owned(),
+        distance_miles: (distance_miles * 10.0).round() / 10.0,
+        periods,
+    })
+}
+
+// the periods whose high or low approaches, ties, or breaks the record for its date
+async fn forecast_records(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+    periods: &[Period],
+) -> Option<RecordsComparison> {
+    let stations = match cached_normals_stations(forecast_state, coordinates).await {
+        Ok(stations) => stations,
+        Err(e) => {
+            warn!("error getting climate stations for forecast summary: {}", e);
+            return None;
+        }
+    };
+
+    let (station, distance_miles) = normals::nearest(&stations, coordinates)
+        .filter(|(_, distance_miles)| *distance_miles <= NORMALS_STATION_MAX_DISTANCE_MILES)?;
+
+    let daily_records = match cached_daily_records(forecast_state, &station.id).await {
+        Ok(daily_records) => daily_records,
+        Err(e) => {
+            warn!("error getting records for station {}: {}", station.id, e);
+            return None;
+        }
+    };
+
+    let periods: Vec<PeriodRecord> = periods
+        .iter()
+        .filter_map(|period| {
+            let daily_record = records::for_date(&daily_records, period.start_time.date_naive())?;
+
+            // daytime periods are compared to the record high and nighttime periods to the record low
+            let (kind, record) = match period.is_daytime {
+                true => ("high", daily_record.high?),
+                false => ("low", daily_record.low?),
+            };
+
+            // records are fahrenheit, periods are celsius when units=si
+            let (record_temperature, margin) = match period.temperature_unit.as_str() {
+                "C" => (
+                    (record.temperature - 32.0) * 5.0 / 9.0,
+                    RECORD_MARGIN_DEGREES_F * 5.0 / 9.0,
+                ),
+                _ => (record.temperature, RECORD_MARGIN_DEGREES_F),
+            };
+            let record_temperature = record_temperature.round() as i64;
+
+            // how far past the record the forecast goes, in the direction that would set a new one
+            let past = match period.is_daytime {
+                true => period.temperature - record_temperature,
+                false => record_temperature - period.temperature,
+            };
+
+            let status = match past {
+                past if past > 0 => "breaks",
+                0 => "ties",
+                past if past as f64 >= -margin => "approaches",
+                _ => return None,
+            };
+
+            Some(PeriodRecord {
+                name: sanitize::text(&period.name),
+                kind: kind.to_string(),
+                temperature: period.temperature,
+                record: record_temperature,
+                record_year: record.year,
+                unit: period.temperature_unit.to_owned(),
+                status: status.to_string(),
+            })
+        })
+        .collect();
+
+    if periods.is_empty() {
+        return None;
+    }
+
+    Some(RecordsComparison {
+        station: station.to_owned(),
+        distance_miles: (distance_miles * 10.0).round() / 10.0,
+        periods,
+    })
+}
+
+// records only change when one is broken, so they're kept as long as normals are
+async fn cached_daily_records(
+    forecast_state: &ForecastState,
+    station_id: &str,
+) -> Result<Vec<DailyRecord>, RecordsError> {
+    let cache_key = format!("records:{}", station_id);
+
+    if let Some(daily_records) = forecast_state.geocode_cache.get(&cache_key).await {
+        return Ok(daily_records);
+    }
+
+    let daily_records =
+        records::get_daily_records(forecast_state.client.clone(), station_id).await?;
+
+    forecast_state
+        .geocode_cache
+        .insert(cache_key, &daily_records)
+        .await;
+
+    Ok(daily_records)
+}
+
+// stations don't move, so nearby ones are kept as long as geocodes are
+async fn cached_normals_stations(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+) -> Result<Vec<NormalsStation>, NormalsError> {
+    let cache_key = format!(
+        "normals_stations:{:.2},{:.2}",
+        coordinates.latitude, coordinates.longitude
+    );
+
+    if let Some(stations) = forecast_state.geocode_cache.get(&cache_key).await {
+        return Ok(stations);
+    }
+
+    let stations = normals::get_stations(
+        forecast_state.client.clone(),
+        coordinates,
+        NORMALS_STATION_MAX_DISTANCE_MILES,
+    )
+    .await?;
+
+    forecast_state
+        .geocode_cache
+        .insert(cache_key, &stations)
+        .await;
+
+    Ok(stations)
+}
+
+async fn cached_daily_normals(
+    forecast_state: &ForecastState,
+    station_id: &str,
+) -> Result<Vec<DailyNormal>, NormalsError> {
+    let cache_key = format!("normals:{}", station_id);
+
+    if let Some(daily_normals) = forecast_state.geocode_cache.get(&cache_key).await {
+        return Ok(daily_normals);
+    }
+
+    let daily_normals =
+        normals::get_daily_normals(forecast_state.client.clone(), station_id).await?;
+
+    forecast_state
+        .geocode_cache
+        .insert(cache_key, &daily_normals)
+        .await;
+
+    Ok(daily_normals)
+}
+
+// like alerts, the summary is still written without air quality when AirNow can't be reached
+async fn forecast_air_quality(
+    forecast_state: &ForecastState,
+    coordinates: Coordinates,
+) -> Option<AirQuality> {
+    let airnow = forecast_state.airnow.as_ref()?;
+
+    match cached_air_quality(forecast_state, airnow, coordinates, false).await {
+        Ok(air_quality) => air_quality.filter(|air_quality| air_quality.is_notable()),
+        Err(e) => {
+            warn!("error getting air quality for forecast summary: {}", e);
+            None
+        }
+    }
+}
+
+// AirNow limits requests per hour per key, and reporting areas cover whole metro areas
+pub(super) async fn cached_air_quality(
+    forecast_state: &ForecastState,
+    airnow: &AirNowClient,
+    coordinates: Coordinates,
+    refresh: bool,
+) -> Result<Option<AirQuality>, AirNowError> {
+    let cache_key = format!(
+        "air_quality:{:.2},{:.2}",
+        coordinates.latitude, coordinates.longitude
+    );
+
+    if !refresh {
+        if let Some(air_quality) = forecast_state.forecast_cache.get(&cache_key).await {
+            return Ok(air_quality);
+        }
+    }
+
+    let air_quality = airnow.current(coordinates).await?;
+
+    forecast_state
+        .forecast_cache
+        .insert(cache_key, &air_quality)
+        .await;
+
+    Ok(air_quality)
+}
+
+// tests and cancellations aren't mentioned in summaries
+pub(super) fn current_alerts(
+    alerts: &[AlertFeature],
+    coordinates: Coordinates,
+) -> Vec<ForecastAlert> {
+    alerts
+        .iter()
+        .filter(|alert| {
+            alert.properties.status == "Actual" && alert.properties.message_type != "Cancel"
+        })
+        .map(|alert| ForecastAlert {
+            event: sanitize::text(&alert.properties.event),
+            severity: alert.properties.severity.to_owned(),
+            headline: sanitize::text(alert.properties.headline.as_deref().unwrap_or_default()),
+            ends: alert
+                .properties
+                .ends
+                .to_owned()
+                .unwrap_or(alert.properties.expires.to_owned()),
+            applies_to_point: geometry::applies_to_point(alert.geometry.as_ref(), &[], coordinates),
+        })
+        .collect()
+}
+
+// the periods fill whatever budget the alerts leave, alerts are never condensed away
+pub(super) fn forecast_input(endpoint: &str, prepared: &PreparedForecast, budget: usize) -> String {
+    if prepared.alerts.is_empty()
+        && prepared.convective_outlook.is_empty()
+        && prepared.air_quality.is_none()
+        && !prepared.prompt_vars.daylight
+        && !prepared.prompt_vars.stargazing()
+        && prepared.normals.is_none()
+        && prepared.records.is_none()
+        && prepared.advisories.is_empty()
+        && prepared.drought.is_none()
+    {
+        return budgeted_input(
+            endpoint,
+            &prepared.periods,
+            budget,
+            condense_forecast_period,
+        );
+    }
+
+    let alerts_json = serde_json::to_string(&prepared.alerts).unwrap();
+    let convective_json = serde_json::to_string(&prepared.convective_outlook).unwrap();
+    let air_quality_json = serde_json::to_string(&prepared.air_quality).unwrap();
+
+    let daylight: Vec<DaylightInput> = match prepared.prompt_vars.daylight {
+        true => prepared
+            .daylight
+            .iter()
+            .map(|day| DaylightInput {
+                date: day.date.format("%a %b %-d").to_string(),
+                sunrise: day
+                    .sunrise
+                    .map(|sunrise| sunrise.format("%-I:%M %p").to_string()),
+                sunset: day
+                    .sunset
+                    .map(|sunset| sunset.format("%-I:%M %p").to_string()),
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+    let daylight_json = serde_json::to_string(&daylight).unwrap();
+
+    let moon: Vec<MoonInput> = match prepared.prompt_vars.stargazing() {
+        true => prepared
+            .moon
+            .iter()
+            .map(|moon| MoonInput {
+                night: moon.date.format("%a %b %-d").to_string(),
+                phase: moon.phase.to_owned(),
+                illumination: format!("{}%", moon.illumination_percent),
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+    let moon_json = serde_json::to_string(&moon).unwrap();
+
+    let normals: Vec<NormalInput> = prepared
+        .normals
+        .iter()
+        .flat_map(|normals| normals.periods.iter())
+        .map(|period| {
+            let normal = format!("{}{}", period.normal, period.unit);
+
+            NormalInput {
+                name: period.name.to_owned(),
+                normal_high: (period.kind == "high").then(|| normal.to_owned()),
+                normal_low: (period.kind == "low").then(|| normal.to_owned()),
+                departure: format!("{:+}{}", period.departure, period.unit),
+            }
+        })
+        .collect();
+    let normals_json = serde_json::to_string(&normals).unwrap();
+
+    let records: Vec<RecordInput> = prepared
+        .records
+        .iter()
+        .flat_map(|records| records.periods.iter())
+        .map(|period| {
+            let record = format!("{}{} in {}", period.record, period.unit, period.record_year);
+
+            RecordInput {
+                name: period.name.to_owned(),
+                record_high: (period.kind == "high").then(|| record.to_owned()),
+                record_low: (period.kind == "low").then(|| record.to_owned()),
+                status: period.status.to_owned(),
+            }
+        })
+        .collect();
+    let records_json = serde_json::to_string(&records).unwrap();
+    let advisories_json = serde_json::to_string(&prepared.advisories).unwrap();
+    let drought_json = serde_json::to_string(&prepared.drought).unwrap();
+
+    let periods = budgeted_input(
+        endpoint,
+        &prepared.periods,
+        budget
+            .saturating_sub(llm::estimate_tokens(&alerts_json))
+            .saturating_sub(llm::estimate_tokens(&convective_json))
+            .saturating_sub(llm::estimate_tokens(&air_quality_json))
+            .saturating_sub(llm::estimate_tokens(&daylight_json))
+            .saturating_sub(llm::estimate_tokens(&moon_json))
+            .saturating_sub(llm::estimate_tokens(&normals_json))
+            .saturating_sub(llm::estimate_tokens(&records_json))
+            .saturating_sub(llm::estimate_tokens(&advisories_json))
+            .saturating_sub(llm::estimate_tokens(&drought_json)),
+        condense_forecast_period,
+    );
+
+    serde_json::to_string(&ForecastInput {
+        alerts: &prepared.alerts,
+        convective_outlook: &prepared.convective_outlook,
+        air_quality: prepared.air_quality.as_ref(),
+        daylight,
+        moon,
+        normals,
+        records,
+        advisories: &prepared.advisories,
+        drought: prepared.drought.as_ref(),
+        periods: serde_json::from_str(&periods).unwrap(),
+    })
+    .unwrap()
+}
@@ -0,0 +1,238 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::{
+    error::AppError,
+    geocode::{Coordinates, Location},
+    nwps::{self, FloodStages, Gauge, Reading},
+    prompts, sanitize,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, prompt_vars, resolve_location, resolve_model,
+    summary::{cached_summary, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref RIVERS_COUNTER: Counter = register_counter!(opts!(
+        "rivers_total",
+        "times the /api/v1/rivers endpoint was called"
+    ))
+    .unwrap();
+}
+
+const RIVER_GAUGE_RADIUS_MILES: f64 = 25.0;
+// each gauge's flood stages are a separate request, so only the nearest few are described
+const MAX_RIVER_GAUGES: usize = 5;
+
+// rivers response struct, the river gauges near the location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiversResponse {
+    pub location: Location,
+    pub gauges: Vec<RiverGauge>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+// river gauge struct, a gauge's stage compared to where flooding begins
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiverGauge {
+    pub id: String,
+    pub name: String,
+    pub coordinates: Coordinates,
+    pub distance_miles: f64,
+    pub observed: Option<Reading>,
+    pub forecast: Option<Reading>,
+    pub flood_stages: FloodStages,
+    pub rising_to_flood: bool,
+}
+
+pub async fn rivers(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<RiversResponse>, AppError> {
+    RIVERS_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let gauges_key = format!(
+        "river_gauges:{:.2},{:.2}",
+        location.coordinates.latitude, location.coordinates.longitude
+    );
+
+    let cached_gauges = match refresh {
+        true => None,
+        false => {
+            forecast_state
+                .forecast_cache
+                .get::<Vec<Gauge>>(&gauges_key)
+                .await
+        }
+    };
+
+    let gauges = match cached_gauges {
+        Some(gauges) => gauges,
+        None => {
+            let gauges = nwps::get_gauges(
+                forecast_state.client.clone(),
+                location.coordinates,
+                RIVER_GAUGE_RADIUS_MILES,
+            )
+            .await?;
+
+            forecast_state
+                .forecast_cache
+                .insert(gauges_key, &gauges)
+                .await;
+
+            gauges
+        }
+    };
+
+    let mut gauges: Vec<Gauge> = gauges
+        .into_iter()
+        .filter(|gauge| gauge.distance_miles(location.coordinates) <= RIVER_GAUGE_RADIUS_MILES)
+        .collect();
+
+    gauges.sort_by(|a, b| {
+        a.distance_miles(location.coordinates)
+            .total_cmp(&b.distance_miles(location.coordinates))
+    });
+    gauges.truncate(MAX_RIVER_GAUGES);
+
+    if gauges.is_empty() {
+        return Err(AppError::NotFound(
+            "no river gauges are near this location".to_string(),
+        ));
+    }
+
+    let flood_stages = futures_util::future::join_all(
+        gauges
+            .iter()
+            .map(|gauge| cached_flood_stages(&forecast_state, &gauge.id)),
+    )
+    .await;
+
+    let river_gauges: Vec<RiverGauge> = gauges
+        .into_iter()
+        .zip(flood_stages)
+        .map(|(gauge, flood_stages)| RiverGauge {
+            distance_miles: (gauge.distance_miles(location.coordinates) * 10.0).round() / 10.0,
+            rising_to_flood: gauge.rising_to_flood(),
+            id: gauge.id,
+            name: sanitize::text(&gauge.name),
+            coordinates: gauge.coordinates,
+            observed: gauge.observed,
+            forecast: gauge.forecast,
+            flood_stages,
+        })
+        .collect();
+
+    let (summary, verified) = match summarize {
+        true => {
+            let prompt =
+                forecast_state
+                    .prompts
+                    .get()
+                    .render(prompts::RIVERS, &prompt_vars, true)?;
+
+            let readings: Vec<String> = river_gauges
+                .iter()
+                .map(|gauge| {
+                    format!(
+                        "{}:{}:{}",
+                        gauge.id,
+                        gauge
+                            .observed
+                            .as_ref()
+                            .map(|reading| reading.time.as_str())
+                            .unwrap_or_default(),
+                        gauge
+                            .forecast
+                            .as_ref()
+                            .map(|reading| reading.time.as_str())
+                            .unwrap_or_default()
+                    )
+                })
+                .collect();
+
+            let summary_key = format!(
+                "summary:{}:{}:rivers:{}",
+                model,
+                prompt_vars.cache_key(),
+                prompts::version(&readings.join("\n"))
+            );
+
+            let (summary, verified) = cached_summary(
+                &forecast_state,
+                summary_key,
+                refresh,
+                SummaryRequest {
+                    endpoint: "rivers",
+                    location: Some(&location),
+                    model: &model,
+                    prompt: &prompt,
+                    training: &[],
+                    input: serde_json::to_string(&river_gauges).unwrap(),
+                },
+            )
+            .await?;
+
+            (Some(summary), Some(verified))
+        }
+        false => (None, None),
+    };
+
+    Ok(Json(RiversResponse {
+        location,
+        gauges: river_gauges,
+        model: summary.as_ref().map(|_| model),
+        summary,
+        verified,
+    }))
+}
+
+// flood stages are set when a gauge is installed and rarely revised, so they're kept as long as geocodes are.
+// a gauge is still reported without them when they can't be fetched
+async fn cached_flood_stages(forecast_state: &ForecastState, id: &str) -> FloodStages {
+    let cache_key = format!("flood_stages:{}", id);
+
+    if let Some(flood_stages) = forecast_state.geocode_cache.get(&cache_key).await {
+        return flood_stages;
+    }
+
+    let flood_stages = match nwps::get_flood_stages(forecast_state.client.clone(), id).await {
+        Ok(flood_stages) => flood_stages,
+        Err(e) => {
+            warn!("error getting flood stages for gauge {}: {}", id, e);
+            return FloodStages::default();
+        }
+    };
+
+    forecast_state
+        .geocode_cache
+        .insert(cache_key, &flood_stages)
+        .await;
+
+    flood_stages
+}
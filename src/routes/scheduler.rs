@@ -0,0 +1,147 @@
+use chrono::DateTime;
+use std::{collections::HashMap, time::Instant};
+use tracing::{info, warn};
+
+use crate::{
+    error::AppError,
+    llm::{self, Priority},
+    notify::{self, Notification},
+    scheduler::{
+        ScheduledJob, SCHEDULED_DURATION_GAUGE, SCHEDULED_LAST_SUCCESS_GAUGE,
+        SCHEDULED_RUNS_COUNTER,
+    },
+};
+
+use std::sync::Arc;
+
+use super::{
+    history::save_permalink, prepare::prepare_forecast, summary::forecast_summary,
+    ForecastResponse, ForecastState, ResponseMeta, SimplifiedForecastPeriod,
+};
+
+// one task per job, each sleeping until the job's next run
+pub fn spawn_scheduler(forecast_state: Arc<ForecastState>, jobs: Vec<ScheduledJob>) {
+    for job in jobs {
+        let forecast_state = forecast_state.clone();
+
+        tokio::spawn(async move {
+            // a sleep can end a moment before the wall clock reaches the minute it was for, so the
+            // next run is looked for after the last one rather than after now
+            let mut last_run: Option<DateTime<chrono::Local>> = None;
+
+            loop {
+                let after = match last_run {
+                    Some(last_run) => last_run.max(chrono::Local::now()),
+                    None => chrono::Local::now(),
+                };
+
+                let next = match job.schedule.next_after(after) {
+                    Some(next) => next,
+                    None => {
+                        warn!("scheduled job {} has no upcoming runs", job.name);
+                        return;
+                    }
+                };
+
+                let wait = (next - chrono::Local::now()).to_std().unwrap_or_default();
+
+                tokio::time::sleep(wait).await;
+
+                last_run = Some(next);
+
+                llm::with_priority(
+                    Priority::Scheduled,
+                    run_scheduled_job(&forecast_state, &job),
+                )
+                .await;
+            }
+        });
+    }
+}
+
+async fn run_scheduled_job(forecast_state: &ForecastState, job: &ScheduledJob) {
+    let started = Instant::now();
+
+    let result = scheduled_summary(forecast_state, &job.params).await;
+
+    SCHEDULED_DURATION_GAUGE
+        .with_label_values(&[&job.name])
+        .set(started.elapsed().as_secs_f64());
+
+    match result {
+        Ok(response) => {
+            notify::dispatch(
+                &job.notifiers,
+                Notification {
+                    event: "scheduled_summary",
+                    source: job.name.to_owned(),
+                    summary: response.summary,
+                    model: response.model,
+                    verified: response.verified,
+                    location: response.location,
+                    generated_at: response.generated_at,
+                    permalink: response.permalink,
+                    alert: None,
+                    severity: None,
+                    instruction: None,
+                    periods: response.periods.unwrap_or_default(),
+                },
+            );
+
+            SCHEDULED_RUNS_COUNTER
+                .with_label_values(&[&job.name, "success"])
+                .inc();
+            SCHEDULED_LAST_SUCCESS_GAUGE
+                .with_label_values(&[&job.name])
+                .set(chrono::Utc::now().timestamp() as f64);
+
+            info!(job = job.name, "generated scheduled summary");
+        }
+        Err(e) => {
+            SCHEDULED_RUNS_COUNTER
+                .with_label_values(&[&job.name, "error"])
+                .inc();
+
+            warn!("error running scheduled job {}: {}", job.name, e);
+        }
+    }
+}
+
+// generates the summary ahead of requests, which caches it and stores it when a database is configured
+pub(super) async fn scheduled_summary(
+    forecast_state: &ForecastState,
+    params: &HashMap<String, String>,
+) -> Result<ForecastResponse<SimplifiedForecastPeriod>, AppError> {
+    let prepared = prepare_forecast(forecast_state, params, false).await?;
+
+    let summary = forecast_summary(forecast_state, &prepared, false).await?;
+
+    let mut response = ForecastResponse {
+        summary: summary.summary,
+        model: prepared.model,
+        verified: summary.verified,
+        generated_by: summary.generated_by,
+        location: prepared.location,
+        generated_at: prepared.generated_at,
+        generated_at_local: prepared.generated_at_local,
+        time_zone: prepared.time_zone,
+        daylight: prepared.daylight,
+        moon: prepared.moon,
+        normals: prepared.normals,
+        records: prepared.records,
+        degree_days: prepared.degree_days,
+        advisories: prepared.advisories,
+        drought: prepared.drought,
+        periods: Some(prepared.periods),
+        permalink: None,
+        meta: Some(ResponseMeta {
+            summary_level: Some(summary.level),
+            ..prepared.meta
+        }),
+    };
+
+    response.permalink =
+        save_permalink(forecast_state, "forecast", &prepared.summary_key, &response).await;
+
+    Ok(response)
+}
@@ -0,0 +1,160 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{error::AppError, geocode::Location};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param,
+    prepare::{frost_warning, prepare_forecast},
+    summary::{summary_events, summary_tokens, SummaryEvent},
+    ForecastState, SimplifiedForecastPeriod,
+};
+
+lazy_static! {
+    pub static ref FORECAST_WS_COUNTER: Counter = register_counter!(opts!(
+        "forecast_ws_total",
+        "times a websocket connection was opened on /ws"
+    ))
+    .unwrap();
+}
+
+// socket message enum, sent to websocket clients as tagged JSON
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SocketMessage {
+    Location {
+        location: Location,
+    },
+    Periods {
+        generated_at: String,
+        periods: Vec<SimplifiedForecastPeriod>,
+    },
+    Token {
+        token: String,
+    },
+    Done {
+        summary: String,
+        model: String,
+    },
+    Error {
+        error: String,
+    },
+}
+
+pub async fn forecast_ws(
+    ws: WebSocketUpgrade,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Response {
+    FORECAST_WS_COUNTER.inc();
+
+    ws.on_upgrade(move |socket| handle_forecast_socket(socket, forecast_state))
+}
+
+// each text message is a forecast query, either a bare address or a JSON object of query parameters
+async fn handle_forecast_socket(mut socket: WebSocket, forecast_state: Arc<ForecastState>) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let query = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => return,
+            _ => continue,
+        };
+
+        let params = match serde_json::from_str::<HashMap<String, serde_json::Value>>(&query) {
+            Ok(object) => object
+                .into_iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(value) => (key, value),
+                    value => (key, value.to_string()),
+                })
+                .collect(),
+            Err(_) => HashMap::from([("address".to_string(), query.trim().to_string())]),
+        };
+
+        if let Err(e) = send_forecast_messages(&mut socket, &forecast_state, &params).await {
+            e.record();
+
+            let message = SocketMessage::Error {
+                error: e.public_message(),
+            };
+
+            if send_socket_message(&mut socket, &message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn send_forecast_messages(
+    socket: &mut WebSocket,
+    forecast_state: &ForecastState,
+    params: &HashMap<String, String>,
+) -> Result<(), AppError> {
+    let refresh = bool_param(params, "refresh", false)?;
+
+    let prepared = prepare_forecast(forecast_state, params, refresh).await?;
+
+    let location = SocketMessage::Location {
+        location: prepared.location.clone(),
+    };
+    let periods = SocketMessage::Periods {
+        generated_at: prepared.generated_at.clone(),
+        periods: prepared.periods.clone(),
+    };
+
+    for message in [location, periods] {
+        if send_socket_message(socket, &message).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    let (tokens, history) = summary_tokens(forecast_state, &prepared, refresh).await?;
+
+    let model = prepared.model.clone();
+    let frost_warning = frost_warning(&prepared);
+
+    let mut events = Box::pin(summary_events(
+        tokens,
+        forecast_state.summary_cache.clone(),
+        prepared.summary_key,
+        history,
+        frost_warning,
+    ));
+
+    while let Some(event) = events.next().await {
+        let message = match event {
+            SummaryEvent::Token(token) => SocketMessage::Token { token },
+            SummaryEvent::Done(summary) => SocketMessage::Done {
+                summary,
+                model: model.clone(),
+            },
+            SummaryEvent::Error(e) => return Err(e),
+        };
+
+        if send_socket_message(socket, &message).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_socket_message(
+    socket: &mut WebSocket,
+    message: &SocketMessage,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).unwrap();
+
+    socket.send(Message::Text(text)).await
+}
@@ -0,0 +1,369 @@
+use axum::{
+    extract::{Path, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DurationRound, NaiveDateTime};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::Deserialize;
+use std::collections::HashMap;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use crate::{
+    error::AppError,
+    llm::{self, Priority},
+    notify::{self, Notification, Notifier, NotifyError, SubscriptionChannel},
+    nws::AlertFilter,
+    scheduler::{Schedule, SCHEDULED_RUNS_COUNTER},
+    storage::{self, StorageError, Subscription},
+};
+
+use std::sync::Arc;
+
+use super::{prompt_vars, resolve_location, scheduler::scheduled_summary, ForecastState};
+
+lazy_static! {
+    pub static ref SUBSCRIPTIONS_COUNTER: Counter = register_counter!(opts!(
+        "subscriptions_total",
+        "times the /api/v1/subscriptions endpoints were called"
+    ))
+    .unwrap();
+}
+
+// subscription request struct, the location is an address or coordinates like the forecast parameters
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionRequest {
+    pub address: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub channel: SubscriptionChannel,
+    pub cron: Option<String>,
+    pub style: Option<String>,
+    #[serde(default)]
+    pub alerts: AlertFilter,
+}
+
+// runs the subscriptions due each minute, a subscription's run doesn't hold up the others
+pub fn spawn_subscriptions(forecast_state: Arc<ForecastState>) {
+    let storage = match forecast_state.storage.clone() {
+        Some(storage) => storage,
+        None => return,
+    };
+
+    tokio::spawn(async move {
+        let mut last_minute: Option<NaiveDateTime> = None;
+
+        loop {
+            let now = chrono::Local::now().naive_local();
+            let this_minute = now
+                .duration_trunc(chrono::TimeDelta::minutes(1))
+                .unwrap_or(now);
+
+            // the minute after the last one run, even when the clock still reads the last one
+            // after a sleep that ended a moment early. minutes missed while the process was
+            // suspended or the clock jumped ahead are skipped rather than all run at once
+            let minute = match last_minute {
+                Some(last_minute) => (last_minute + chrono::TimeDelta::minutes(1)).max(this_minute),
+                None => this_minute + chrono::TimeDelta::minutes(1),
+            };
+
+            tokio::time::sleep((minute - now).to_std().unwrap_or_default()).await;
+
+            last_minute = Some(minute);
+
+            let subscriptions = match storage.subscriptions().await {
+                Ok(subscriptions) => subscriptions,
+                Err(e) => {
+                    warn!("error reading subscriptions: {}", e);
+                    continue;
+                }
+            };
+
+            for subscription in subscriptions {
+                // cron expressions are checked when the subscription is created
+                let due = subscription
+                    .cron
+                    .as_deref()
+                    .and_then(|cron| Schedule::parse(cron).ok())
+                    .is_some_and(|schedule| schedule.matches(&minute));
+
+                if !due {
+                    continue;
+                }
+
+                let forecast_state = forecast_state.clone();
+
+                tokio::spawn(async move {
+                    llm::with_priority(
+                        Priority::Scheduled,
+                        run_subscription(&forecast_state, &subscription),
+                    )
+                    .await
+                });
+            }
+        }
+    });
+}
+
+// a subscriber's notifier, webhooks are checked for public addresses again before each delivery
+pub(super) async fn subscriber_notifier(
+    forecast_state: &ForecastState,
+    subscription: &Subscription,
+) -> Result<Arc<dyn Notifier>, NotifyError> {
+    if let SubscriptionChannel::Webhook { url, .. } = &subscription.channel {
+        notify::check_webhook_host(url).await?;
+    }
+
+    forecast_state.channels.build(
+        forecast_state.webhook_client.clone(),
+        forecast_state.storage.clone(),
+        format!("subscription_{}", subscription.channel.name()),
+        &subscription.channel,
+    )
+}
+
+async fn run_subscription(forecast_state: &ForecastState, subscription: &Subscription) {
+    // one label for every subscription, ids would make a series per subscriber
+    let job = "subscriptions";
+
+    let notifier = match subscriber_notifier(forecast_state, subscription).await {
+        Ok(notifier) => notifier,
+        Err(e) => {
+            SCHEDULED_RUNS_COUNTER
+                .with_label_values(&[job, "error"])
+                .inc();
+            warn!(
+                "error building notifier for subscription {}: {}",
+                subscription.id, e
+            );
+            return;
+        }
+    };
+
+    let mut params = HashMap::from([
+        (
+            "lat".to_string(),
+            subscription.location.coordinates.latitude.to_string(),
+        ),
+        (
+            "lon".to_string(),
+            subscription.location.coordinates.longitude.to_string(),
+        ),
+    ]);
+
+    // texts are billed by the segment, so they're brief unless the subscriber asked for a style
+    match (&subscription.style, &subscription.channel) {
+        (Some(style), _) => {
+            params.insert("style".to_string(), style.to_owned());
+        }
+        (None, SubscriptionChannel::Sms { .. }) => {
+            params.insert("style".to_string(), "brief".to_string());
+        }
+        (None, _) => {}
+    }
+
+    match scheduled_summary(forecast_state, &params).await {
+        Ok(response) => {
+            notify::dispatch(
+                &[notifier],
+                Notification {
+                    event: "scheduled_summary",
+                    source: subscription.id.to_owned(),
+                    summary: response.summary,
+                    model: response.model,
+                    verified: response.verified,
+                    // the subscriber's own address reads better than the coordinates it resolved to
+                    location: subscription.location.clone(),
+                    generated_at: response.generated_at,
+                    permalink: response.permalink,
+                    alert: None,
+                    severity: None,
+                    instruction: None,
+                    periods: response.periods.unwrap_or_default(),
+                },
+            );
+
+            SCHEDULED_RUNS_COUNTER
+                .with_label_values(&[job, "success"])
+                .inc();
+        }
+        Err(e) => {
+            SCHEDULED_RUNS_COUNTER
+                .with_label_values(&[job, "error"])
+                .inc();
+
+            warn!("error running subscription {}: {}", subscription.id, e);
+        }
+    }
+}
+
+// registers a subscription, the returned id is what the subscriber uses to look it up or delete it
+pub async fn create_subscription(
+    State(forecast_state): State<Arc<ForecastState>>,
+    headers: HeaderMap,
+    Json(request): Json<SubscriptionRequest>,
+) -> Result<(StatusCode, Json<Subscription>), AppError> {
+    SUBSCRIPTIONS_COUNTER.inc();
+
+    let tokens = [
+        &forecast_state.subscriptions_create_token,
+        &forecast_state.subscriptions_admin_token,
+    ];
+
+    if !bearer_authorized(&headers, &tokens) {
+        return Err(AppError::Forbidden(
+            "creating subscriptions needs the create or admin token".to_string(),
+        ));
+    }
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    if let Err(message) = forecast_state.channels.validate(&request.channel) {
+        return Err(AppError::BadRequest(message));
+    }
+
+    if let SubscriptionChannel::Webhook { url, .. } = &request.channel {
+        if let Err(e) = notify::check_webhook_host(url).await {
+            return Err(AppError::BadRequest(e.to_string()));
+        }
+    }
+
+    if !forecast_state.channels.supports(&request.channel) {
+        return Err(AppError::BadRequest(format!(
+            "{} subscriptions are not enabled",
+            request.channel.name()
+        )));
+    }
+
+    if let Some(cron) = &request.cron {
+        match Schedule::parse(cron) {
+            Ok(schedule) if schedule.next_after(chrono::Local::now()).is_some() => {}
+            Ok(_) => {
+                return Err(AppError::BadRequest(format!(
+                    "cron {} never matches a date",
+                    cron
+                )))
+            }
+            Err(e) => return Err(AppError::BadRequest(format!("cron is invalid: {}", e))),
+        }
+    }
+
+    let mut params = HashMap::new();
+
+    if let Some(address) = &request.address {
+        params.insert("address".to_string(), address.to_owned());
+    }
+    if let Some(lat) = request.lat {
+        params.insert("lat".to_string(), lat.to_string());
+    }
+    if let Some(lon) = request.lon {
+        params.insert("lon".to_string(), lon.to_string());
+    }
+    if let Some(style) = &request.style {
+        params.insert("style".to_string(), style.to_owned());
+    }
+
+    prompt_vars(&forecast_state, &params)?;
+
+    let alerts = match AlertFilter::new(&request.alerts.severity, &request.alerts.event) {
+        Ok(alerts) => alerts,
+        Err(message) => return Err(AppError::BadRequest(message)),
+    };
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let subscription = Subscription {
+        id: storage::short_id(),
+        location,
+        channel: request.channel,
+        cron: request.cron,
+        style: request.style,
+        alerts,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    storage.save_subscription(&subscription).await?;
+
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+pub async fn subscription(
+    Path(id): Path<String>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<Subscription>, AppError> {
+    SUBSCRIPTIONS_COUNTER.inc();
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    match storage.subscription(&id).await? {
+        Some(subscription) => Ok(Json(subscription)),
+        None => Err(AppError::NotFound(
+            "no subscription exists with this id".to_string(),
+        )),
+    }
+}
+
+pub async fn delete_subscription(
+    Path(id): Path<String>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<StatusCode, AppError> {
+    SUBSCRIPTIONS_COUNTER.inc();
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    match storage.delete_subscription(&id).await? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(AppError::NotFound(
+            "no subscription exists with this id".to_string(),
+        )),
+    }
+}
+
+pub async fn subscriptions(
+    State(forecast_state): State<Arc<ForecastState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Subscription>>, AppError> {
+    SUBSCRIPTIONS_COUNTER.inc();
+
+    if !bearer_authorized(&headers, &[&forecast_state.subscriptions_admin_token]) {
+        return Err(AppError::Forbidden(
+            "listing subscriptions needs the admin token".to_string(),
+        ));
+    }
+
+    let storage = match &forecast_state.storage {
+        Some(storage) => storage,
+        None => return Err(AppError::StorageFailed(StorageError::NotConfigured)),
+    };
+
+    Ok(Json(storage.subscriptions().await?))
+}
+
+// whether the request's bearer token is one of the tokens, unset tokens match nothing. compared in
+// constant time so the response time doesn't give away how much of a token was right
+fn bearer_authorized(headers: &HeaderMap, tokens: &[&Option<String>]) -> bool {
+    let token = match headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        Some(token) => token,
+        None => return false,
+    };
+
+    tokens
+        .iter()
+        .filter_map(|expected| expected.as_deref())
+        .any(|expected| bool::from(expected.as_bytes().ct_eq(token.as_bytes())))
+}
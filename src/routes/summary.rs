@@ -0,0 +1,574 @@
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+use serde::Serialize;
+use std::time::Instant;
+use tracing::warn;
+
+use crate::{
+    cache::JsonCache,
+    error::AppError,
+    fallback,
+    geocode::Location,
+    llm::{self, LlmError, NShotInOut, SummaryStream},
+    nws::Period,
+    prompts,
+    storage::{HistoryEntry, Storage},
+    units,
+    verify::{self, UNVERIFIED_SUMMARIES_COUNTER},
+};
+
+use std::sync::Arc;
+
+use super::{
+    prepare::{forecast_input, frost_warning, mentions_frost, PreparedForecast},
+    ForecastState, GeneratedBy, SimplifiedForecastPeriod,
+};
+
+lazy_static! {
+    pub static ref INPUT_TRUNCATED_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "input_truncated_total",
+            "summary inputs shortened to fit the token budget"
+        ),
+        &["endpoint", "strategy"]
+    )
+    .unwrap();
+    pub static ref FALLBACK_SUMMARIES_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "fallback_summaries_total",
+            "forecast summaries that fell back because the llm failed"
+        ),
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref SUMMARY_LEVEL_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "forecast_summary_level_total",
+            "forecast summaries served at each fallback level"
+        ),
+        &["level"]
+    )
+    .unwrap();
+}
+
+const SHORT_FORECAST_PERIODS: usize = 2;
+
+// summary event enum, the stages of a streamed summary
+pub(super) enum SummaryEvent {
+    Token(String),
+    Done(String),
+    Error(AppError),
+}
+
+// pending history struct, a streamed summary waiting to be recorded once it completes
+pub(super) struct PendingHistory {
+    storage: Arc<dyn Storage>,
+    entry: HistoryEntry,
+    started: Instant,
+}
+
+// returns the cached summary as a single token, or a live stream from the llm backend.
+// only live streams come with pending history, cached summaries were recorded when generated
+pub(super) async fn summary_tokens(
+    forecast_state: &ForecastState,
+    prepared: &PreparedForecast,
+    refresh: bool,
+) -> Result<(SummaryStream, Option<PendingHistory>), AppError> {
+    if !refresh {
+        if let Some(summary) = forecast_state
+            .summary_cache
+            .get::<String>(&prepared.summary_key)
+            .await
+        {
+            return Ok((
+                futures_util::stream::once(async { Ok(summary) }).boxed(),
+                None,
+            ));
+        }
+    }
+
+    let prompts = forecast_state.prompts.get();
+    let prompt = prompts.render(prompts::FORECAST, &prepared.prompt_vars, false)?;
+    let training = prompts.forecast_examples.clone();
+
+    let input = forecast_input(
+        "forecast_stream",
+        prepared,
+        input_budget(forecast_state, &prompt, &training),
+    );
+
+    let started = Instant::now();
+
+    let tokens = llm::summarize_stream(
+        forecast_state.llm.as_ref(),
+        &prepared.model,
+        &prompt,
+        &training,
+        input,
+    )
+    .await?;
+
+    let history = forecast_state
+        .storage
+        .clone()
+        .map(|storage| PendingHistory {
+            storage,
+            entry: history_entry(
+                "forecast_stream",
+                &prepared.location,
+                &prepared.model,
+                &prompt,
+            ),
+            started,
+        });
+
+    Ok((tokens, history))
+}
+
+// forwards tokens and caches the complete summary once the stream finishes.
+// a frost warning the model left out is sent as one last token
+pub(super) fn summary_events(
+    mut tokens: SummaryStream,
+    summary_cache: JsonCache,
+    summary_key: String,
+    history: Option<PendingHistory>,
+    frost_warning: Option<String>,
+) -> impl Stream<Item = SummaryEvent> {
+    stream! {
+        let mut summary = String::new();
+
+        while let Some(token) = tokens.next().await {
+            match token {
+                Ok(token) => {
+                    summary.push_str(&token);
+                    yield SummaryEvent::Token(token);
+                }
+                Err(e) => {
+                    let e = AppError::from(e);
+                    e.record();
+                    yield SummaryEvent::Error(e);
+                    return;
+                }
+            }
+        }
+
+        let mut summary = summary.trim().to_string();
+
+        if let Some(warning) = frost_warning.filter(|_| !summary.is_empty() && !mentions_frost(&summary)) {
+            let token = format!(" {}", warning);
+            summary.push_str(&token);
+            yield SummaryEvent::Token(token);
+        }
+
+        if !summary.is_empty() {
+            summary_cache.insert(summary_key, &summary).await;
+
+            if let Some(history) = history {
+                record_summary(history.storage.as_ref(), history.entry, history.started, &summary).await;
+            }
+        }
+
+        yield SummaryEvent::Done(summary);
+    }
+}
+
+// the one line summary of the next two periods, shared by the short and home assistant endpoints
+pub(super) async fn short_summary(
+    forecast_state: &ForecastState,
+    prepared: &PreparedForecast,
+    refresh: bool,
+) -> Result<String, AppError> {
+    let cache_key = format!("short:{}", prepared.summary_key);
+
+    if !refresh {
+        if let Some(line) = forecast_state.summary_cache.get::<String>(&cache_key).await {
+            return Ok(line);
+        }
+    }
+
+    let prompt = forecast_state.prompts.get().render(
+        prompts::SHORT_FORECAST,
+        &prepared.prompt_vars,
+        false,
+    )?;
+
+    let periods: Vec<&SimplifiedForecastPeriod> = prepared
+        .periods
+        .iter()
+        .take(SHORT_FORECAST_PERIODS)
+        .collect();
+
+    let started = Instant::now();
+
+    let mut tokens = llm::summarize_stream(
+        forecast_state.llm.as_ref(),
+        &prepared.model,
+        &prompt,
+        &[],
+        serde_json::to_string(&periods).unwrap(),
+    )
+    .await?;
+
+    let mut line = String::new();
+
+    while let Some(token) = tokens.next().await {
+        line.push_str(&token?);
+    }
+
+    // models sometimes wrap the line anyway, so it is joined back together
+    let line = line.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if line.is_empty() {
+        return Err(AppError::LlmFailed(LlmError::EmptySummary));
+    }
+
+    if let Some(storage) = &forecast_state.storage {
+        record_summary(
+            storage.as_ref(),
+            history_entry(
+                "short_forecast",
+                &prepared.location,
+                &prepared.model,
+                &prompt,
+            ),
+            started,
+            &line,
+        )
+        .await;
+    }
+
+    forecast_state.summary_cache.insert(cache_key, &line).await;
+
+    Ok(line)
+}
+
+// forecast summary struct, the summary, whether every number in it was found in the forecast, and
+// what wrote it at which level
+pub(super) struct ForecastSummary {
+    pub(super) summary: String,
+    pub(super) verified: bool,
+    pub(super) generated_by: GeneratedBy,
+    pub(super) level: fallback::Level,
+}
+
+pub(super) async fn forecast_summary(
+    forecast_state: &ForecastState,
+    prepared: &PreparedForecast,
+    refresh: bool,
+) -> Result<ForecastSummary, AppError> {
+    let prompts = forecast_state.prompts.get();
+    let prompt = prompts.render(prompts::FORECAST, &prepared.prompt_vars, true)?;
+    let training = prompts.forecast_examples.clone();
+
+    let simplified_forecast_json = forecast_input(
+        "forecast",
+        prepared,
+        input_budget(forecast_state, &prompt, &training),
+    );
+
+    let summary = cached_summary(
+        forecast_state,
+        prepared.summary_key.clone(),
+        refresh,
+        SummaryRequest {
+            endpoint: "forecast",
+            location: Some(&prepared.location),
+            model: &prepared.model,
+            prompt: &prompt,
+            training: &training,
+            input: simplified_forecast_json.clone(),
+        },
+    )
+    .await;
+
+    let e = match summary {
+        Ok((summary, verified)) => {
+            // stored on every request, so a location that's asked for often keeps one
+            forecast_state
+                .last_summary_cache
+                .insert(prepared.last_summary_key.clone(), &summary)
+                .await;
+
+            return Ok(finish_summary(
+                prepared,
+                ForecastSummary {
+                    summary,
+                    verified,
+                    generated_by: GeneratedBy::Llm,
+                    level: fallback::Level::Full,
+                },
+            ));
+        }
+        // an unknown model is the request's mistake, not the llm failing
+        Err(AppError::LlmFailed(e)) if !matches!(e, LlmError::ModelNotFound(_)) => e,
+        Err(e) => return Err(e),
+    };
+
+    for level in &forecast_state.summary_fallbacks {
+        let summary =
+            match degraded_summary(forecast_state, prepared, *level, &simplified_forecast_json)
+                .await
+            {
+                Some(summary) => summary,
+                None => continue,
+            };
+
+        warn!(
+            "llm failed, falling back to a {} summary: {}",
+            level.label(),
+            e
+        );
+        FALLBACK_SUMMARIES_COUNTER
+            .with_label_values(&[e.kind()])
+            .inc();
+
+        return Ok(finish_summary(prepared, summary));
+    }
+
+    Err(AppError::LlmFailed(e))
+}
+
+// none when the level has nothing to offer, like a location that was never summarized
+async fn degraded_summary(
+    forecast_state: &ForecastState,
+    prepared: &PreparedForecast,
+    level: fallback::Level,
+    input: &str,
+) -> Option<ForecastSummary> {
+    let periods: Vec<Period> = prepared
+        .nws_periods
+        .iter()
+        .map(|period| units::period(period, &prepared.prompt_vars.units))
+        .collect();
+
+    // the template and the periods only use numbers from the periods, so they're verified
+    let (summary, verified, generated_by) = match level {
+        fallback::Level::Full => return None,
+        fallback::Level::Cached => {
+            let summary = forecast_state
+                .last_summary_cache
+                .get::<String>(&prepared.last_summary_key)
+                .await?;
+            let verified = verify::verify(&summary, input).verified;
+
+            (summary, verified, GeneratedBy::Llm)
+        }
+        fallback::Level::Template => (fallback::summary(&periods)?, true, GeneratedBy::Fallback),
+        fallback::Level::Periods => (fallback::periods(&periods)?, true, GeneratedBy::Nws),
+    };
+
+    Some(ForecastSummary {
+        summary,
+        verified,
+        generated_by,
+        level,
+    })
+}
+
+// counted at the level it's served at, and gardeners are warned whether or not the summary
+// thought frost was worth mentioning
+fn finish_summary(prepared: &PreparedForecast, summary: ForecastSummary) -> ForecastSummary {
+    SUMMARY_LEVEL_COUNTER
+        .with_label_values(&[summary.level.label()])
+        .inc();
+
+    match frost_warning(prepared) {
+        Some(warning) if !mentions_frost(&summary.summary) => ForecastSummary {
+            summary: format!("{} {}", summary.summary, warning),
+            ..summary
+        },
+        _ => summary,
+    }
+}
+
+// summary request struct, what a summary is generated from and the endpoint and location it is recorded against
+pub(super) struct SummaryRequest<'a> {
+    pub(super) endpoint: &'static str,
+    // none keeps the summary out of history, for reports without a location to search by
+    pub(super) location: Option<&'a Location>,
+    pub(super) model: &'a str,
+    pub(super) prompt: &'a str,
+    pub(super) training: &'a [NShotInOut],
+    pub(super) input: String,
+}
+
+// summaries are keyed on the forecast generation time, so identical forecast data is only summarized once.
+// the returned flag is whether every number in the summary was found in the input
+pub(super) async fn cached_summary(
+    forecast_state: &ForecastState,
+    cache_key: String,
+    refresh: bool,
+    request: SummaryRequest<'_>,
+) -> Result<(String, bool), AppError> {
+    if !refresh {
+        if let Some(summary) = forecast_state.summary_cache.get::<String>(&cache_key).await {
+            let verified = verify::verify(&summary, &request.input).verified;
+            return Ok((summary, verified));
+        }
+    }
+
+    // the summary and its regeneration share one deadline, so a retry can't double how long it takes
+    let deadline = Instant::now() + forecast_state.summary_deadline;
+
+    let mut summary = summarize(forecast_state, &request, deadline).await?;
+    let mut verification = verify::verify(&summary, &request.input);
+
+    // a summary with numbers that aren't in the forecast is regenerated once before being flagged.
+    // only the retry failing keeps the first summary, flagged, rather than failing the request
+    if !verification.verified && Instant::now() >= deadline {
+        warn!(
+            unsupported = ?verification.unsupported,
+            "summary has unsupported numbers and no time is left to regenerate it"
+        );
+    } else if !verification.verified {
+        warn!(
+            unsupported = ?verification.unsupported,
+            "regenerating summary with unsupported numbers"
+        );
+
+        match summarize(forecast_state, &request, deadline).await {
+            Ok(regenerated) => {
+                let regenerated_verification = verify::verify(&regenerated, &request.input);
+
+                if regenerated_verification.verified {
+                    summary = regenerated;
+                    verification = regenerated_verification;
+                }
+            }
+            Err(e) => warn!("error regenerating summary, keeping the first one: {}", e),
+        }
+    }
+
+    if !verification.verified {
+        UNVERIFIED_SUMMARIES_COUNTER.inc();
+    }
+
+    forecast_state
+        .summary_cache
+        .insert(cache_key, &summary)
+        .await;
+
+    Ok((summary, verification.verified))
+}
+
+// gives up at the deadline, which is the summary deadline from when the summary was first asked for
+pub(super) async fn summarize(
+    forecast_state: &ForecastState,
+    request: &SummaryRequest<'_>,
+    deadline: Instant,
+) -> Result<String, AppError> {
+    let started = Instant::now();
+
+    let summary = tokio::time::timeout_at(
+        deadline.into(),
+        llm::summarize(
+            forecast_state.llm.as_ref(),
+            request.model,
+            request.prompt,
+            request.training,
+            request.input.clone(),
+            forecast_state.max_summary_attempts,
+        ),
+    )
+    .await
+    .map_err(|_| LlmError::Timeout(forecast_state.summary_deadline.as_secs()))??;
+
+    if let (Some(storage), Some(location)) = (&forecast_state.storage, request.location) {
+        record_summary(
+            storage.as_ref(),
+            history_entry(request.endpoint, location, request.model, request.prompt),
+            started,
+            &summary,
+        )
+        .await;
+    }
+
+    Ok(summary)
+}
+
+// history entry with everything known before generation, the summary and timings are filled in by record_summary
+fn history_entry(endpoint: &str, location: &Location, model: &str, prompt: &str) -> HistoryEntry {
+    HistoryEntry {
+        endpoint: endpoint.to_string(),
+        location: location.clone(),
+        model: model.to_string(),
+        prompt_version: prompts::version(prompt),
+        latency_ms: 0,
+        summary: String::new(),
+        created_at: String::new(),
+    }
+}
+
+// history is best effort, a failed write never fails the request
+async fn record_summary(
+    storage: &dyn Storage,
+    mut entry: HistoryEntry,
+    started: Instant,
+    summary: &str,
+) {
+    entry.latency_ms = started.elapsed().as_millis() as u64;
+    entry.summary = summary.to_string();
+    entry.created_at = chrono::Utc::now().to_rfc3339();
+
+    if let Err(e) = storage.record_summary(&entry).await {
+        warn!("error recording summary history: {}", e);
+    }
+}
+
+// tokens left for forecast input once the prompt and examples are accounted for
+pub(super) fn input_budget(
+    forecast_state: &ForecastState,
+    prompt: &str,
+    training: &[NShotInOut],
+) -> usize {
+    forecast_state
+        .max_input_tokens
+        .saturating_sub(llm::context_tokens(prompt, training))
+}
+
+// serializes periods for the llm, condensing the latest periods first and then dropping them until the input fits
+pub(super) fn budgeted_input<T: Serialize + Clone>(
+    endpoint: &str,
+    periods: &[T],
+    budget: usize,
+    condense: fn(&mut T),
+) -> String {
+    let mut periods = periods.to_vec();
+    let mut input = serde_json::to_string(&periods).unwrap();
+
+    if llm::estimate_tokens(&input) <= budget {
+        return input;
+    }
+
+    for index in (0..periods.len()).rev() {
+        condense(&mut periods[index]);
+        input = serde_json::to_string(&periods).unwrap();
+
+        if llm::estimate_tokens(&input) <= budget {
+            INPUT_TRUNCATED_COUNTER
+                .with_label_values(&[endpoint, "condensed"])
+                .inc();
+            return input;
+        }
+    }
+
+    while periods.len() > 1 && llm::estimate_tokens(&input) > budget {
+        periods.pop();
+        input = serde_json::to_string(&periods).unwrap();
+    }
+
+    INPUT_TRUNCATED_COUNTER
+        .with_label_values(&[endpoint, "dropped"])
+        .inc();
+
+    input
+}
+
+// keeps only the first sentence of the detailed forecast
+pub(super) fn condense_forecast_period(period: &mut SimplifiedForecastPeriod) {
+    if let Some(end) = period.detailed_forecast.find(". ") {
+        period.detailed_forecast.truncate(end + 1);
+    }
+}
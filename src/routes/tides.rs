@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::DurationRound;
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    error::AppError,
+    geocode::Location,
+    prompts,
+    tides::{self, TideKind, TidePrediction, TideStation},
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, prompt_vars, resolve_location, resolve_model,
+    summary::{cached_summary, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref TIDES_COUNTER: Counter = register_counter!(opts!(
+        "tides_total",
+        "times the /api/v1/tides endpoint was called"
+    ))
+    .unwrap();
+}
+
+// inland points are closer to some tide station too, but its tides don't describe them
+const TIDE_STATION_MAX_DISTANCE_MILES: f64 = 50.0;
+const TIDE_PREDICTION_HOURS: u32 = 48;
+
+// tides response struct, high and low tide predictions from the nearest tide station
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TidesResponse {
+    pub location: Location,
+    pub station: TideStation,
+    pub distance_miles: f64,
+    pub units: String,
+    pub predictions: Vec<TidePrediction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+// tides input struct, times are written out so the model doesn't have to read offsets
+#[derive(Debug, Clone, Serialize)]
+struct TidesInput<'a> {
+    station: &'a str,
+    distance_miles: f64,
+    predictions: Vec<TideInputPrediction>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TideInputPrediction {
+    time: String,
+    kind: TideKind,
+    height: f64,
+}
+
+pub async fn tides(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<TidesResponse>, AppError> {
+    TIDES_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let summarize = bool_param(&params, "summarize", forecast_state.summarize_by_default)?;
+
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let stations = cached_tide_stations(&forecast_state).await?;
+
+    let (station, distance_miles) = match tides::nearest(&stations, location.coordinates) {
+        Some((station, distance_miles)) if distance_miles <= TIDE_STATION_MAX_DISTANCE_MILES => {
+            (station.to_owned(), distance_miles)
+        }
+        _ => {
+            return Err(AppError::NotFound(
+                "no tide station is near this location".to_string(),
+            ))
+        }
+    };
+
+    // the window starts on the hour so predictions can be cached for the rest of it
+    let now = chrono::Utc::now();
+    let begin = now
+        .duration_trunc(chrono::TimeDelta::hours(1))
+        .unwrap_or(now);
+
+    let predictions_key = format!(
+        "tides:{}:{}:{}",
+        station.id,
+        prompt_vars.units,
+        begin.timestamp()
+    );
+
+    let cached_predictions = match refresh {
+        true => None,
+        false => {
+            forecast_state
+                .forecast_cache
+                .get::<Vec<TidePrediction>>(&predictions_key)
+                .await
+        }
+    };
+
+    let predictions = match cached_predictions {
+        Some(predictions) => predictions,
+        None => {
+            let predictions = tides::get_predictions(
+                forecast_state.client.clone(),
+                &station,
+                begin,
+                TIDE_PREDICTION_HOURS,
+                &prompt_vars.units,
+            )
+            .await?;
+
+            forecast_state
+                .forecast_cache
+                .insert(predictions_key.to_owned(), &predictions)
+                .await;
+
+            predictions
+        }
+    };
+
+    let distance_miles = (distance_miles * 10.0).round() / 10.0;
+
+    let (summary, verified) = match summarize {
+        true => {
+            let prompt = forecast_state
+                .prompts
+                .get()
+                .render(prompts::TIDES, &prompt_vars, true)?;
+
+            let summary_key = format!(
+                "summary:{}:{}:{}",
+                model,
+                prompt_vars.cache_key(),
+                predictions_key
+            );
+
+            let input = TidesInput {
+                station: &station.name,
+                distance_miles,
+                predictions: predictions
+                    .iter()
+                    .map(|prediction| TideInputPrediction {
+                        time: prediction.time.format("%a %b %-d %-I:%M %p").to_string(),
+                        kind: prediction.kind,
+                        height: prediction.height,
+                    })
+                    .collect(),
+            };
+
+            let (summary, verified) = cached_summary(
+                &forecast_state,
+                summary_key,
+                refresh,
+                SummaryRequest {
+                    endpoint: "tides",
+                    location: Some(&location),
+                    model: &model,
+                    prompt: &prompt,
+                    training: &[],
+                    input: serde_json::to_string(&input).unwrap(),
+                },
+            )
+            .await?;
+
+            (Some(summary), Some(verified))
+        }
+        false => (None, None),
+    };
+
+    Ok(Json(TidesResponse {
+        location,
+        station,
+        distance_miles,
+        units: prompt_vars.units,
+        predictions,
+        model: summary.as_ref().map(|_| model),
+        summary,
+        verified,
+    }))
+}
+
+// the station list rarely changes, so it's kept as long as geocodes are
+async fn cached_tide_stations(
+    forecast_state: &ForecastState,
+) -> Result<Vec<TideStation>, AppError> {
+    let cache_key = "tide_stations";
+
+    if let Some(stations) = forecast_state.geocode_cache.get(cache_key).await {
+        return Ok(stations);
+    }
+
+    let stations = tides::get_stations(forecast_state.client.clone()).await?;
+
+    forecast_state
+        .geocode_cache
+        .insert(cache_key.to_string(), &stations)
+        .await;
+
+    Ok(stations)
+}
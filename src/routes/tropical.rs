@@ -0,0 +1,199 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::{
+    error::AppError,
+    geocode::Location,
+    nhc::{self, Basin, Storm},
+    prompts, sanitize,
+};
+
+use std::sync::Arc;
+
+use super::{
+    bool_param, prompt_vars, resolve_location, resolve_model,
+    summary::{cached_summary, SummaryRequest},
+    ForecastState,
+};
+
+lazy_static! {
+    pub static ref TROPICAL_COUNTER: Counter = register_counter!(opts!(
+        "tropical_total",
+        "times the /api/v1/tropical endpoint was called"
+    ))
+    .unwrap();
+}
+
+// advisories are long, they're only read for storms close enough to matter
+const TROPICAL_ADVISORY_RADIUS_MILES: f64 = 1000.0;
+
+// tropical response struct, the outlook and active storms for the location's basin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TropicalResponse {
+    pub summary: String,
+    pub model: String,
+    pub verified: bool,
+    pub location: Location,
+    pub basin: Basin,
+    pub outlook_issued: String,
+    pub storms: Vec<TropicalStorm>,
+}
+
+// tropical storm struct, an active storm with its distance from the location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TropicalStorm {
+    pub name: String,
+    pub classification: String,
+    pub max_wind_knots: Option<i64>,
+    pub pressure_mb: Option<i64>,
+    pub distance_miles: f64,
+    pub movement_direction: Option<i64>,
+    pub movement_speed_mph: Option<i64>,
+    pub last_update: String,
+    pub advisory_number: Option<String>,
+}
+
+// tropical input struct, what the tropical explanation is written from
+#[derive(Debug, Clone, Serialize)]
+struct TropicalInput<'a> {
+    outlook: String,
+    storms: Vec<TropicalStormInput<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TropicalStormInput<'a> {
+    #[serde(flatten)]
+    storm: &'a TropicalStorm,
+    advisory: Option<String>,
+}
+
+pub async fn tropical(
+    Query(params): Query<HashMap<String, String>>,
+    State(forecast_state): State<Arc<ForecastState>>,
+) -> Result<Json<TropicalResponse>, AppError> {
+    TROPICAL_COUNTER.inc();
+
+    let refresh = bool_param(&params, "refresh", false)?;
+    let model = resolve_model(&forecast_state, &params)?;
+    let prompt_vars = prompt_vars(&forecast_state, &params)?;
+
+    let location = resolve_location(&forecast_state, &params).await?;
+
+    let basin = nhc::basin(location.coordinates);
+
+    let (outlook, storms) = tokio::try_join!(
+        nhc::get_outlook(forecast_state.client.clone(), basin),
+        nhc::get_active_storms(forecast_state.client.clone()),
+    )?;
+
+    let mut storms: Vec<Storm> = storms
+        .into_iter()
+        .filter(|storm| storm.basin() == Some(basin))
+        .collect();
+
+    storms.sort_by(|a, b| {
+        a.distance_miles(location.coordinates)
+            .total_cmp(&b.distance_miles(location.coordinates))
+    });
+
+    // a storm is still described from its position and intensity when its advisory can't be read
+    let advisories = futures_util::future::join_all(storms.iter().map(|storm| async {
+        let advisory = storm.public_advisory.as_ref()?;
+
+        if storm.distance_miles(location.coordinates) > TROPICAL_ADVISORY_RADIUS_MILES {
+            return None;
+        }
+
+        match nhc::get_advisory_text(forecast_state.client.clone(), advisory).await {
+            Ok(text) => Some(sanitize::text(&text)),
+            Err(e) => {
+                warn!("error getting advisory for {}: {}", storm.id, e);
+                None
+            }
+        }
+    }))
+    .await;
+
+    let tropical_storms: Vec<TropicalStorm> = storms
+        .iter()
+        .map(|storm| TropicalStorm {
+            name: sanitize::text(&storm.name),
+            classification: storm.classification_name().to_string(),
+            max_wind_knots: storm.intensity.trim().parse().ok(),
+            pressure_mb: storm.pressure.trim().parse().ok(),
+            distance_miles: storm.distance_miles(location.coordinates).round(),
+            movement_direction: storm.movement_dir,
+            movement_speed_mph: storm.movement_speed,
+            last_update: storm.last_update.to_owned(),
+            advisory_number: storm
+                .public_advisory
+                .as_ref()
+                .map(|advisory| advisory.adv_num.to_owned()),
+        })
+        .collect();
+
+    let prompt = forecast_state
+        .prompts
+        .get()
+        .render(prompts::TROPICAL, &prompt_vars, true)?;
+
+    // the outlook and each storm are updated on their own schedules
+    let updates: Vec<String> = std::iter::once(outlook.issued.to_owned())
+        .chain(
+            storms
+                .iter()
+                .map(|storm| format!("{}:{}", storm.id, storm.last_update)),
+        )
+        .collect();
+
+    // storm distances are part of the input, so only nearby locations share a summary
+    let summary_key = format!(
+        "summary:{}:{}:tropical:{}:{:.1},{:.1}:{}",
+        model,
+        prompt_vars.cache_key(),
+        basin.name(),
+        location.coordinates.latitude,
+        location.coordinates.longitude,
+        prompts::version(&updates.join("\n"))
+    );
+
+    let (summary, verified) = cached_summary(
+        &forecast_state,
+        summary_key,
+        refresh,
+        SummaryRequest {
+            endpoint: "tropical",
+            location: Some(&location),
+            model: &model,
+            prompt: &prompt,
+            training: &[],
+            input: serde_json::to_string(&TropicalInput {
+                outlook: sanitize::text(&outlook.text),
+                storms: tropical_storms
+                    .iter()
+                    .zip(advisories)
+                    .map(|(storm, advisory)| TropicalStormInput { storm, advisory })
+                    .collect(),
+            })
+            .unwrap(),
+        },
+    )
+    .await?;
+
+    Ok(Json(TropicalResponse {
+        summary,
+        model,
+        verified,
+        location,
+        basin,
+        outlook_issued: outlook.issued,
+        storms: tropical_storms,
+    }))
+}
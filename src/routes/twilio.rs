@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Form, State},
+    http::{header::CONTENT_TYPE, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+use std::collections::BTreeMap;
+
+use crate::{
+    error::AppError,
+    notify::{self, NotifyError, SmsKeyword, TWILIO_SIGNATURE_HEADER},
+    render,
+};
+
+use std::sync::Arc;
+
+use super::ForecastState;
+
+lazy_static! {
+    pub static ref TWILIO_WEBHOOK_COUNTER: Counter = register_counter!(opts!(
+        "twilio_webhook_total",
+        "times the /api/v1/sms/twilio webhook was called"
+    ))
+    .unwrap();
+}
+
+// twilio's incoming message webhook, opt-out keywords stop sms notifications to the sender
+pub async fn twilio_webhook(
+    State(forecast_state): State<Arc<ForecastState>>,
+    headers: HeaderMap,
+    Form(params): Form<BTreeMap<String, String>>,
+) -> Result<Response, AppError> {
+    TWILIO_WEBHOOK_COUNTER.inc();
+
+    let (auth_token, webhook_url, storage) = match (
+        &forecast_state.twilio_auth_token,
+        &forecast_state.twilio_webhook_url,
+        &forecast_state.storage,
+    ) {
+        (Some(auth_token), Some(webhook_url), Some(storage)) => (auth_token, webhook_url, storage),
+        _ => return Err(AppError::NotifyFailed(NotifyError::NotConfigured)),
+    };
+
+    let signature = headers
+        .get(TWILIO_SIGNATURE_HEADER)
+        .and_then(|signature| signature.to_str().ok())
+        .unwrap_or_default();
+
+    if !notify::twilio_signature_valid(auth_token, webhook_url, &params, signature) {
+        return Err(AppError::Forbidden(
+            "request signature is invalid".to_string(),
+        ));
+    }
+
+    let from = match params.get("From") {
+        Some(from) => from,
+        None => {
+            return Err(AppError::BadRequest(
+                "From parameter is required".to_string(),
+            ))
+        }
+    };
+
+    let keyword = SmsKeyword::parse(params.get("Body").map_or("", |body| body.as_str()));
+
+    match keyword {
+        Some(SmsKeyword::OptOut) => storage.set_sms_opt_out(from, true).await?,
+        Some(SmsKeyword::OptIn) => storage.set_sms_opt_out(from, false).await?,
+        Some(SmsKeyword::Help) | None => {}
+    }
+
+    // an empty response sends no reply to anything that isn't a keyword
+    let twiml = match keyword {
+        Some(keyword) => format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response><Message>{}</Message></Response>",
+            render::escape(keyword.reply())
+        ),
+        None => "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response/>".to_string(),
+    };
+
+    Ok(([(CONTENT_TYPE, "text/xml")], twiml).into_response())
+}
@@ -0,0 +1,188 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RulesError {
+    #[error("error reading rules file {0}: {1}")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("error parsing rules file {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("invalid rule {0}: {1}")]
+    InvalidRule(String, String),
+}
+
+// metric enum, the per-period values a rule can test. speeds are mph, temperatures F,
+// snowfall inches, and precipitation chance percent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Temperature,
+    WindSpeed,
+    WindGust,
+    PrecipitationChance,
+    HeatIndex,
+    WindChill,
+    Snowfall,
+}
+
+// rule struct, tags a period when its metric is above or below a threshold. message can use
+// {value} for the period's value, rounded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub tag: String,
+    pub metric: Metric,
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<Rule>,
+}
+
+// period metrics struct, what a forecast period says about each metric, none when it doesn't say
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct PeriodMetrics {
+    pub temperature: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub wind_gust: Option<f64>,
+    pub precipitation_chance: Option<f64>,
+    pub heat_index: Option<f64>,
+    pub wind_chill: Option<f64>,
+    pub snowfall: Option<f64>,
+}
+
+// advisory struct, a rule a forecast period matched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    pub tag: String,
+    pub period: String,
+    pub message: String,
+}
+
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+// thresholds near where NWS issues advisories, used when no rules file is configured
+impl Default for RuleSet {
+    fn default() -> Self {
+        let rule = |tag: &str, metric, above, below, message: &str| Rule {
+            tag: tag.to_string(),
+            metric,
+            above,
+            below,
+            message: message.to_string(),
+        };
+
+        Self {
+            rules: vec![
+                rule(
+                    "strong_wind_gusts",
+                    Metric::WindGust,
+                    Some(45.0),
+                    None,
+                    "Wind gusts could reach {value} mph",
+                ),
+                rule(
+                    "dangerous_heat",
+                    Metric::HeatIndex,
+                    Some(103.0),
+                    None,
+                    "The heat index could reach {value}F",
+                ),
+                rule(
+                    "dangerous_cold",
+                    Metric::WindChill,
+                    None,
+                    Some(-10.0),
+                    "Wind chills could drop to {value}F",
+                ),
+                rule(
+                    "heavy_snow",
+                    Metric::Snowfall,
+                    Some(6.0),
+                    None,
+                    "Snowfall could total {value} inches",
+                ),
+                rule(
+                    "rain_likely",
+                    Metric::PrecipitationChance,
+                    Some(80.0),
+                    None,
+                    "Precipitation is likely, with a {value}% chance",
+                ),
+            ],
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Result<Self, RulesError> {
+        for rule in &rules {
+            if rule.above.is_some() == rule.below.is_some() {
+                return Err(RulesError::InvalidRule(
+                    rule.tag.to_owned(),
+                    "exactly one of above or below must be set".to_string(),
+                ));
+            }
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn evaluate(&self, period: &str, metrics: &PeriodMetrics) -> Vec<Advisory> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let value = match rule.metric {
+                    Metric::Temperature => metrics.temperature,
+                    Metric::WindSpeed => metrics.wind_speed,
+                    Metric::WindGust => metrics.wind_gust,
+                    Metric::PrecipitationChance => metrics.precipitation_chance,
+                    Metric::HeatIndex => metrics.heat_index,
+                    Metric::WindChill => metrics.wind_chill,
+                    Metric::Snowfall => metrics.snowfall,
+                }?;
+
+                let matched = match (rule.above, rule.below) {
+                    (Some(above), _) => value > above,
+                    (_, Some(below)) => value < below,
+                    _ => false,
+                };
+
+                match matched {
+                    true => Some(Advisory {
+                        tag: rule.tag.to_owned(),
+                        period: period.to_string(),
+                        message: rule
+                            .message
+                            .replace("{value}", &(value.round() as i64).to_string()),
+                    }),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn load(path: &Path) -> Result<RuleSet, RulesError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return Err(RulesError::Read(path.to_path_buf(), e)),
+    };
+
+    let file: RulesFile = match toml::from_str(&contents) {
+        Ok(file) => file,
+        Err(e) => return Err(RulesError::Parse(path.to_path_buf(), e)),
+    };
+
+    RuleSet::new(file.rules)
+}
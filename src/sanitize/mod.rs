@@ -0,0 +1,167 @@
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+
+lazy_static! {
+    pub static ref INPUT_SANITIZED_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "input_sanitized_total",
+            "forecast text altered before being sent to the llm"
+        ),
+        &["reason"]
+    )
+    .unwrap();
+}
+
+// longest text kept from a single field, NWS alert descriptions are the longest legitimate input
+const MAX_TEXT_LENGTH: usize = 4000;
+
+// chat template tokens and role prefixes a model may treat as the start of a new message
+const ROLE_MARKERS: &[&str] = &[
+    "<|im_start|>",
+    "<|im_end|>",
+    "<|system|>",
+    "<|user|>",
+    "<|assistant|>",
+    "<|endoftext|>",
+    "<|eot_id|>",
+    "<|start_header_id|>",
+    "<|end_header_id|>",
+    "[inst]",
+    "[/inst]",
+    "<<sys>>",
+    "<</sys>>",
+    "system:",
+    "assistant:",
+    "user:",
+    "human:",
+];
+
+// phrases that only make sense as instructions to a model, sentences containing them are dropped
+const INSTRUCTION_PHRASES: &[&str] = &[
+    "ignore previous",
+    "ignore all previous",
+    "ignore the previous",
+    "ignore prior",
+    "ignore the above",
+    "ignore all instructions",
+    "disregard previous",
+    "disregard the above",
+    "disregard all",
+    "forget previous",
+    "forget your instructions",
+    "new instructions",
+    "system prompt",
+    "you are now",
+    "output the following",
+];
+
+// cleans third-party text before it is placed in a prompt
+pub fn text(input: &str) -> String {
+    let mut text = strip_control_characters(input);
+
+    text = strip_role_markers(&text);
+    text = strip_instructions(&text);
+    text = strip_structural_characters(&text);
+
+    let mut text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if text.len() > MAX_TEXT_LENGTH {
+        let mut end = MAX_TEXT_LENGTH;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        INPUT_SANITIZED_COUNTER
+            .with_label_values(&["too_long"])
+            .inc();
+    }
+
+    text
+}
+
+// newlines and tabs become spaces, other control characters are removed
+fn strip_control_characters(input: &str) -> String {
+    input
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+fn strip_role_markers(input: &str) -> String {
+    let mut text = input.to_string();
+
+    for marker in ROLE_MARKERS {
+        let mut from = 0;
+
+        while let Some(offset) = find_ignore_case(&text[from..], marker) {
+            let start = from + offset;
+
+            // role prefixes only count at the start of a word, so "superuser:" is left alone
+            let word_start = !text[..start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric());
+
+            if !word_start {
+                from = start + marker.len();
+                continue;
+            }
+
+            text.replace_range(start..start + marker.len(), " ");
+            from = start + 1;
+
+            INPUT_SANITIZED_COUNTER
+                .with_label_values(&["role_marker"])
+                .inc();
+        }
+    }
+
+    text
+}
+
+fn strip_instructions(input: &str) -> String {
+    let mut sentences = Vec::new();
+
+    for sentence in input.split_inclusive(['.', '!', '?']) {
+        let lowercase = sentence.to_lowercase();
+
+        if INSTRUCTION_PHRASES
+            .iter()
+            .any(|phrase| lowercase.contains(phrase))
+        {
+            INPUT_SANITIZED_COUNTER
+                .with_label_values(&["instruction"])
+                .inc();
+            continue;
+        }
+
+        sentences.push(sentence);
+    }
+
+    sentences.concat()
+}
+
+// braces, brackets, and backticks could be read as the start of JSON or markup in the output
+fn strip_structural_characters(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| match c {
+            '{' | '[' | '<' => '(',
+            '}' | ']' | '>' => ')',
+            '"' | '`' => '\'',
+            c => c,
+        })
+        .collect()
+}
+
+// markers are ascii, so a match always starts and ends on a character boundary
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
@@ -0,0 +1,528 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeDelta, TimeZone, Timelike};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, register_gauge_vec, CounterVec, GaugeVec};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    notify::{self, ChannelTemplates, Notifier, NotifierConfig, NotifierKind, NotifyError},
+    storage::Storage,
+};
+
+lazy_static! {
+    pub static ref SCHEDULED_RUNS_COUNTER: CounterVec = register_counter_vec!(
+        opts!("scheduled_runs_total", "scheduled summary jobs run"),
+        &["job", "result"]
+    )
+    .unwrap();
+    pub static ref SCHEDULED_LAST_SUCCESS_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "scheduled_last_success_timestamp_seconds",
+            "unix time a scheduled summary job last succeeded"
+        ),
+        &["job"]
+    )
+    .unwrap();
+    pub static ref SCHEDULED_DURATION_GAUGE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "scheduled_last_duration_seconds",
+            "how long a scheduled summary job's last run took"
+        ),
+        &["job"]
+    )
+    .unwrap();
+}
+
+// long enough to reach the next February 29th, anything that can match at all matches within this
+const MAX_SEARCH_DAYS: i64 = 5 * 366;
+
+#[derive(Debug, Error)]
+pub enum ScheduleError {
+    #[error("error reading schedule file {0}: {1}")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("error parsing schedule file {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("invalid schedule for job {0}: {1}")]
+    InvalidCron(String, String),
+    #[error("job {0} is defined more than once")]
+    DuplicateJob(String),
+    #[error("notifier {0} is defined more than once")]
+    DuplicateNotifier(String),
+    #[error("job {0} notifies {1}, which isn't defined")]
+    UnknownNotifier(String, String),
+    #[error("invalid notifier {0}: {1}")]
+    InvalidNotifier(String, #[source] NotifyError),
+    #[error("the {0} channel's notifier {1} isn't defined or isn't a {2} notifier")]
+    InvalidChannel(&'static str, String, &'static str),
+}
+
+// schedule struct, a five field cron expression (minute hour day-of-month month day-of-week)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    minutes: BTreeSet<u32>,
+    hours: BTreeSet<u32>,
+    days_of_month: BTreeSet<u32>,
+    months: BTreeSet<u32>,
+    days_of_week: BTreeSet<u32>,
+    // cron matches either day field when both are restricted, and both when either starts with *
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+
+        if fields.len() != 5 {
+            return Err(format!(
+                "expected 5 fields (minute hour day-of-month month day-of-week), found {}",
+                fields.len()
+            ));
+        }
+
+        // 7 is accepted as Sunday alongside 0
+        let mut days_of_week = field(fields[4], 0, 7, "day-of-week")?;
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+
+        Ok(Self {
+            minutes: field(fields[0], 0, 59, "minute")?,
+            hours: field(fields[1], 0, 23, "hour")?,
+            days_of_month: field(fields[2], 1, 31, "day-of-month")?,
+            months: field(fields[3], 1, 12, "month")?,
+            days_of_week,
+            any_day_of_month: fields[2].starts_with('*'),
+            any_day_of_week: fields[4].starts_with('*'),
+        })
+    }
+
+    // the first matching minute after the given time, local times skipped by DST changes never match
+    pub fn next_after<Tz: TimeZone>(&self, after: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+
+        let mut candidate = start;
+
+        while candidate - start < TimeDelta::days(MAX_SEARCH_DAYS) {
+            if !self.months.contains(&candidate.month()) || !self.matches_day(&candidate) {
+                candidate = (candidate.date() + TimeDelta::days(1)).and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+
+            if !self.hours.contains(&candidate.hour()) {
+                candidate = candidate.with_minute(0)? + TimeDelta::hours(1);
+                continue;
+            }
+
+            if !self.minutes.contains(&candidate.minute()) {
+                candidate += TimeDelta::minutes(1);
+                continue;
+            }
+
+            if let Some(next) = timezone.from_local_datetime(&candidate).earliest() {
+                return Some(next);
+            }
+
+            candidate += TimeDelta::minutes(1);
+        }
+
+        None
+    }
+
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        self.months.contains(&time.month())
+            && self.matches_day(time)
+            && self.hours.contains(&time.hour())
+            && self.minutes.contains(&time.minute())
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day_of_month = self.days_of_month.contains(&time.day());
+        let day_of_week = self
+            .days_of_week
+            .contains(&time.weekday().num_days_from_sunday());
+
+        // a stepped * like */2 still restricts its field
+        match self.any_day_of_month || self.any_day_of_week {
+            true => day_of_month && day_of_week,
+            false => day_of_month || day_of_week,
+        }
+    }
+}
+
+// one cron field, a comma separated list of *, n, or a-b, each optionally stepped with /n
+fn field(field: &str, min: u32, max: u32, name: &str) -> Result<BTreeSet<u32>, String> {
+    let mut values = BTreeSet::new();
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("invalid {} step in {}", name, part)),
+            },
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start, min, max, name)?, value(end, min, max, name)?),
+                None => {
+                    let start = value(range, min, max, name)?;
+                    // a single value with a step runs from the value to the end, like in vixie cron
+                    match step > 1 {
+                        true => (start, max),
+                        false => (start, start),
+                    }
+                }
+            },
+        };
+
+        if start > end {
+            return Err(format!("invalid {} range {}", name, range));
+        }
+
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    Ok(values)
+}
+
+fn value(value: &str, min: u32, max: u32, name: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(value) if (min..=max).contains(&value) => Ok(value),
+        _ => Err(format!(
+            "{} must be between {} and {}, found {}",
+            name, min, max, value
+        )),
+    }
+}
+
+// job file struct, the [[jobs]] tables of the schedule file
+#[derive(Debug, Deserialize)]
+struct ScheduleFile {
+    #[serde(default)]
+    jobs: Vec<JobConfig>,
+    #[serde(default)]
+    notifiers: Vec<NotifierConfig>,
+    #[serde(default)]
+    channels: ChannelsConfig,
+}
+
+// channels config struct, the notifiers whose servers deliver subscriptions, like email = "mail",
+// and the topic prefix mqtt subscriptions publish under
+#[derive(Debug, Default, Deserialize)]
+struct ChannelsConfig {
+    email: Option<String>,
+    sms: Option<String>,
+    mqtt: Option<String>,
+    mqtt_topic_prefix: Option<String>,
+}
+
+// every key besides name, cron, and notify is passed through as a forecast query parameter
+#[derive(Debug, Deserialize)]
+struct JobConfig {
+    name: String,
+    cron: String,
+    #[serde(default)]
+    notify: Vec<String>,
+    #[serde(flatten)]
+    params: HashMap<String, toml::Value>,
+}
+
+// scheduled job struct, a location and options summarized on a cron schedule and pushed to its notifiers
+#[derive(Clone)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub cron: String,
+    pub schedule: Schedule,
+    pub params: HashMap<String, String>,
+    pub notifiers: Vec<Arc<dyn Notifier>>,
+}
+
+// loaded schedule struct, the file's jobs and the channel templates subscriptions are delivered with
+pub struct LoadedSchedule {
+    pub jobs: Vec<ScheduledJob>,
+    pub channels: ChannelTemplates,
+}
+
+pub fn load(
+    client: reqwest::Client,
+    storage: Option<Arc<dyn Storage>>,
+    path: &Path,
+) -> Result<LoadedSchedule, ScheduleError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return Err(ScheduleError::Read(path.to_path_buf(), e)),
+    };
+
+    let file: ScheduleFile = match toml::from_str(&contents) {
+        Ok(file) => file,
+        Err(e) => return Err(ScheduleError::Parse(path.to_path_buf(), e)),
+    };
+
+    let mut notifiers: HashMap<String, Arc<dyn Notifier>> = HashMap::new();
+    let mut kinds: HashMap<String, NotifierKind> = HashMap::new();
+
+    for notifier in file.notifiers {
+        if notifiers.contains_key(&notifier.name) {
+            return Err(ScheduleError::DuplicateNotifier(notifier.name));
+        }
+
+        let name = notifier.name.clone();
+        kinds.insert(name.clone(), notifier.kind.clone());
+
+        match notify::build(client.clone(), storage.clone(), notifier) {
+            Ok(notifier) => notifiers.insert(name, notifier),
+            Err(e) => return Err(ScheduleError::InvalidNotifier(name, e)),
+        };
+    }
+
+    let mut jobs: Vec<ScheduledJob> = Vec::new();
+
+    for job in file.jobs {
+        if jobs.iter().any(|existing| existing.name == job.name) {
+            return Err(ScheduleError::DuplicateJob(job.name));
+        }
+
+        let schedule = match Schedule::parse(&job.cron) {
+            Ok(schedule) => schedule,
+            Err(e) => return Err(ScheduleError::InvalidCron(job.name, e)),
+        };
+
+        // an expression like "0 0 31 4 *" parses but never runs
+        if schedule.next_after(Local::now()).is_none() {
+            return Err(ScheduleError::InvalidCron(
+                job.name,
+                format!("{} never matches a date", job.cron),
+            ));
+        }
+
+        // toml numbers and booleans are written the way they'd appear in a query string
+        let params = job
+            .params
+            .into_iter()
+            .map(|(key, value)| match value {
+                toml::Value::String(value) => (key, value),
+                value => (key, value.to_string()),
+            })
+            .collect();
+
+        let mut job_notifiers = Vec::new();
+
+        for name in job.notify {
+            match notifiers.get(&name) {
+                Some(notifier) => job_notifiers.push(notifier.clone()),
+                None => return Err(ScheduleError::UnknownNotifier(job.name, name)),
+            }
+        }
+
+        jobs.push(ScheduledJob {
+            name: job.name,
+            cron: job.cron,
+            schedule,
+            params,
+            notifiers: job_notifiers,
+        });
+    }
+
+    let channel = |channel: &'static str, name: Option<String>, expected: &'static str| {
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        match kinds.get(&name) {
+            Some(kind @ NotifierKind::Email { .. }) if expected == "email" => {
+                Ok(Some(kind.clone()))
+            }
+            Some(kind @ NotifierKind::Twilio { .. }) if expected == "twilio" => {
+                Ok(Some(kind.clone()))
+            }
+            Some(kind @ NotifierKind::Mqtt { .. }) if expected == "mqtt" => Ok(Some(kind.clone())),
+            _ => Err(ScheduleError::InvalidChannel(channel, name, expected)),
+        }
+    };
+
+    let channels = ChannelTemplates {
+        email: channel("email", file.channels.email, "email")?,
+        sms: channel("sms", file.channels.sms, "twilio")?,
+        mqtt: channel("mqtt", file.channels.mqtt, "mqtt")?,
+        mqtt_topic_prefix: file.channels.mqtt_topic_prefix,
+    };
+
+    Ok(LoadedSchedule { jobs, channels })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::{America::New_York, Tz};
+
+    use super::*;
+
+    fn time(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        New_York
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Tz>) -> DateTime<Tz> {
+        Schedule::parse(expression)
+            .unwrap()
+            .next_after(after)
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_either_restricted_day_field() {
+        // the 20th or any friday, 2026-10-14 is a wednesday
+        let schedule = "0 9 20 * 5";
+
+        assert_eq!(
+            next(schedule, time(2026, 10, 14, 0, 0)),
+            time(2026, 10, 16, 9, 0)
+        );
+        assert_eq!(
+            next(schedule, time(2026, 10, 16, 9, 0)),
+            time(2026, 10, 20, 9, 0)
+        );
+        assert_eq!(
+            next(schedule, time(2026, 10, 20, 9, 0)),
+            time(2026, 10, 23, 9, 0)
+        );
+
+        assert_eq!(
+            next("0 9 20 * *", time(2026, 10, 14, 0, 0)),
+            time(2026, 10, 20, 9, 0)
+        );
+        assert_eq!(
+            next("0 9 * * 5", time(2026, 10, 16, 9, 0)),
+            time(2026, 10, 23, 9, 0)
+        );
+    }
+
+    #[test]
+    fn steps_through_fields() {
+        assert_eq!(
+            next("*/15 * * * *", time(2026, 10, 14, 10, 7)),
+            time(2026, 10, 14, 10, 15)
+        );
+        assert_eq!(
+            next("*/15 * * * *", time(2026, 10, 14, 10, 45)),
+            time(2026, 10, 14, 11, 0)
+        );
+        assert_eq!(
+            next("0 */6 * * *", time(2026, 10, 14, 13, 0)),
+            time(2026, 10, 14, 18, 0)
+        );
+        assert_eq!(
+            next("5/20 * * * *", time(2026, 10, 14, 10, 30)),
+            time(2026, 10, 14, 10, 45)
+        );
+        assert_eq!(
+            next("0 0 1-10/3 * *", time(2026, 10, 5, 0, 0)),
+            time(2026, 10, 7, 0, 0)
+        );
+
+        // a stepped * restricts its day field, so both day fields have to match
+        assert_eq!(
+            next("0 0 */2 * *", time(2026, 10, 14, 0, 0)),
+            time(2026, 10, 15, 0, 0)
+        );
+        assert_eq!(
+            next("0 0 */2 * 1", time(2026, 10, 14, 0, 0)),
+            time(2026, 10, 19, 0, 0)
+        );
+        assert_eq!(
+            next("0 0 */2 * 1", time(2026, 10, 19, 0, 0)),
+            time(2026, 11, 9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn accepts_seven_as_sunday() {
+        assert_eq!(Schedule::parse("0 8 * * 7"), Schedule::parse("0 8 * * 0"));
+        assert_eq!(
+            next("0 8 * * 7", time(2026, 10, 14, 0, 0)),
+            time(2026, 10, 18, 8, 0)
+        );
+        assert_eq!(
+            next("0 8 * * 6-7", time(2026, 10, 17, 8, 0)),
+            time(2026, 10, 18, 8, 0)
+        );
+        assert_eq!(Schedule::parse("@weekly"), Schedule::parse("0 0 * * 7"));
+    }
+
+    #[test]
+    fn finds_the_next_february_29th() {
+        assert_eq!(
+            next("0 0 29 2 *", time(2026, 10, 14, 0, 0)),
+            time(2028, 2, 29, 0, 0)
+        );
+        assert_eq!(
+            next("0 0 29 2 *", time(2028, 2, 29, 0, 0)),
+            time(2032, 2, 29, 0, 0)
+        );
+    }
+
+    #[test]
+    fn skips_times_in_a_dst_gap() {
+        // 2:00 to 3:00 doesn't happen on 2026-03-08 in new york
+        assert_eq!(
+            next("30 2 * * *", time(2026, 3, 7, 12, 0)),
+            time(2026, 3, 9, 2, 30)
+        );
+        assert_eq!(
+            next("30 3 * * *", time(2026, 3, 7, 12, 0)),
+            time(2026, 3, 8, 3, 30)
+        );
+
+        // 1:00 to 2:00 happens twice on 2026-11-01, only the first one runs
+        assert_eq!(
+            next("30 1 * * *", time(2026, 10, 31, 12, 0)).to_rfc3339(),
+            "2026-11-01T01:30:00-04:00"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "0 0 0 * *",
+            "0 0 * 13 *",
+            "0 0 * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "a * * * *",
+        ] {
+            assert!(Schedule::parse(expression).is_err(), "{expression}");
+        }
+    }
+
+    #[test]
+    fn never_matches_impossible_dates() {
+        let schedule = Schedule::parse("0 0 30 2 *").unwrap();
+
+        assert_eq!(schedule.next_after(time(2026, 10, 14, 0, 0)), None);
+    }
+}
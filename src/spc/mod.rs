@@ -0,0 +1,145 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{geocode::Coordinates, geometry, throttle};
+
+const SPC_URL: &str = "https://www.spc.noaa.gov";
+
+// categorical outlooks are issued for the next three days, days four through eight are probabilistic only
+pub const OUTLOOK_DAYS: [u8; 3] = [1, 2, 3];
+
+#[derive(Debug, Error)]
+pub enum SpcError {
+    #[error("error requesting spc.noaa.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding spc.noaa.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("invalid outlook time {0}")]
+    InvalidTime(String),
+}
+
+// outlook collection struct, the categorical outlook GeoJSON with one feature per risk category
+#[derive(Debug, Clone, Deserialize)]
+struct OutlookCollection {
+    #[serde(default)]
+    features: Vec<OutlookFeature>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OutlookFeature {
+    geometry: Option<serde_json::Value>,
+    properties: OutlookProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct OutlookProperties {
+    label: String,
+    label2: String,
+    valid: String,
+    expire: String,
+}
+
+// outlook area struct, one risk category's polygons
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlookArea {
+    pub category: String,
+    pub risk: String,
+    pub level: u8,
+    pub valid: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+    pub geometry: serde_json::Value,
+}
+
+// convective risk struct, the highest risk category covering a point on one outlook day.
+// level is SPC's 1 (marginal) to 5 (high) scale, general thunderstorms are level 0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvectiveRisk {
+    pub day: u8,
+    pub category: String,
+    pub risk: String,
+    pub level: u8,
+    pub valid: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+// labels are TSTM, MRGL, SLGT, ENH, MDT, and HIGH
+fn level(category: &str) -> Option<u8> {
+    match category {
+        "TSTM" => Some(0),
+        "MRGL" => Some(1),
+        "SLGT" => Some(2),
+        "ENH" => Some(3),
+        "MDT" => Some(4),
+        "HIGH" => Some(5),
+        _ => None,
+    }
+}
+
+// outlook times are UTC, like 202610141200
+fn parse_time(time: &str) -> Result<DateTime<Utc>, SpcError> {
+    match NaiveDateTime::parse_from_str(time, "%Y%m%d%H%M") {
+        Ok(time) => Ok(time.and_utc()),
+        Err(_) => Err(SpcError::InvalidTime(time.to_string())),
+    }
+}
+
+pub async fn get_outlook(client: reqwest::Client, day: u8) -> Result<Vec<OutlookArea>, SpcError> {
+    let response_result = throttle::send(client.get(format!(
+        "{}/products/outlook/day{}otlk_cat.nolyr.geojson",
+        SPC_URL, day
+    )))
+    .await
+    .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(SpcError::Request(e)),
+    };
+
+    let collection = match response.json::<OutlookCollection>().await {
+        Ok(collection) => collection,
+        Err(e) => return Err(SpcError::Decode(e)),
+    };
+
+    let mut areas: Vec<OutlookArea> = Vec::new();
+
+    // an outlook with no risk anywhere still has a feature, just without a geometry
+    for feature in collection.features {
+        let (level, geometry) = match (level(&feature.properties.label), feature.geometry) {
+            (Some(level), Some(geometry)) => (level, geometry),
+            _ => continue,
+        };
+
+        areas.push(OutlookArea {
+            category: feature.properties.label,
+            risk: feature.properties.label2,
+            level,
+            valid: parse_time(&feature.properties.valid)?,
+            expires: parse_time(&feature.properties.expire)?,
+            geometry,
+        });
+    }
+
+    Ok(areas)
+}
+
+// risk areas are nested, so the point is in the highest category whose polygon contains it
+pub fn risk_at(areas: &[OutlookArea], day: u8, coordinates: Coordinates) -> Option<ConvectiveRisk> {
+    areas
+        .iter()
+        .filter(|area| {
+            geometry::from_geojson(&area.geometry)
+                .is_some_and(|polygons| geometry::contains(&polygons, coordinates))
+        })
+        .max_by_key(|area| area.level)
+        .map(|area| ConvectiveRisk {
+            day,
+            category: area.category.to_owned(),
+            risk: area.risk.to_owned(),
+            level: area.level,
+            valid: area.valid,
+            expires: area.expires,
+        })
+}
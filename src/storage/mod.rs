@@ -0,0 +1,910 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    ColumnIndex, Database, Decode, Encode, PgPool, Postgres, QueryBuilder, Row, Sqlite, SqlitePool,
+    Type,
+};
+use thiserror::Error;
+
+use crate::{
+    geocode::{self, Coordinates, Location},
+    notify::SubscriptionChannel,
+    nws::AlertFilter,
+};
+
+const ID_LENGTH: usize = 10;
+const ID_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// miles per degree of latitude, and of longitude at the equator
+const MILES_PER_DEGREE: f64 = 69.0;
+
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("error running migrations: {0}")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error("error encoding stored value: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("unsupported database url {0}, expected sqlite: or postgres:")]
+    UnsupportedUrl(String),
+    #[error("no database is configured")]
+    NotConfigured,
+}
+
+impl StorageError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StorageError::Database(_) => "database",
+            StorageError::Migrate(_) => "migrate",
+            StorageError::Encode(_) => "encode",
+            StorageError::UnsupportedUrl(_) => "unsupported_url",
+            StorageError::NotConfigured => "not_configured",
+        }
+    }
+}
+
+// permalink struct, a generated summary and what it was generated from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Permalink {
+    pub id: String,
+    pub endpoint: String,
+    pub summary: String,
+    pub model: String,
+    pub verified: bool,
+    pub location: Location,
+    pub generated_at: String,
+    pub periods: serde_json::Value,
+    pub created_at: String,
+}
+
+// history entry struct, one generated summary with how long it took and which prompt produced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub endpoint: String,
+    pub location: Location,
+    pub model: String,
+    pub prompt_version: String,
+    pub latency_ms: u64,
+    pub summary: String,
+    pub created_at: String,
+}
+
+// history filter struct, without a radius only summaries for the exact (rounded) location match
+// and without coordinates every location matches.
+// from is inclusive and to is exclusive, both RFC 3339 in UTC to compare with created_at
+#[derive(Debug, Clone)]
+pub struct HistoryFilter {
+    pub coordinates: Option<Coordinates>,
+    pub radius_miles: Option<f64>,
+    pub model: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl HistoryFilter {
+    // the exact radius check for entries inside the query's bounding box
+    pub fn within_radius(&self, entry: &HistoryEntry) -> bool {
+        match (self.coordinates, self.radius_miles) {
+            (Some(coordinates), Some(radius_miles)) => {
+                geocode::distance_miles(coordinates, entry.location.coordinates) <= radius_miles
+            }
+            _ => true,
+        }
+    }
+}
+
+// subscription struct, a location summarized on the subscriber's schedule and delivered to their channel.
+// without a cron expression the subscription only gets alerts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    pub location: Location,
+    pub channel: SubscriptionChannel,
+    pub cron: Option<String>,
+    pub style: Option<String>,
+    // which alerts the alert monitor sends, every warning when empty
+    #[serde(default)]
+    pub alerts: AlertFilter,
+    pub created_at: String,
+}
+
+// history page struct, the cursor is set when older summaries match the filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub next_cursor: Option<i64>,
+}
+
+// short random base62 id, hashed from the time and a counter with a per-process random key
+pub fn short_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default(),
+    );
+    hasher.write_u64(ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+
+    let mut value = hasher.finish();
+    let mut id = String::with_capacity(ID_LENGTH);
+
+    for _ in 0..ID_LENGTH {
+        id.push(ID_ALPHABET[(value % ID_ALPHABET.len() as u64) as usize] as char);
+        value /= ID_ALPHABET.len() as u64;
+    }
+
+    id
+}
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // a query that touches no tables, for readiness checks
+    async fn ping(&self) -> Result<(), StorageError>;
+
+    // a summary keeps the permalink it was first saved with, so the existing id is returned on conflict
+    async fn save_permalink(
+        &self,
+        summary_key: &str,
+        permalink: &Permalink,
+    ) -> Result<String, StorageError>;
+
+    async fn permalink(&self, id: &str) -> Result<Option<Permalink>, StorageError>;
+
+    async fn record_summary(&self, entry: &HistoryEntry) -> Result<(), StorageError>;
+
+    // newest first with their ids, a radius filter only narrows to a bounding box here
+    async fn history(
+        &self,
+        filter: &HistoryFilter,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<(i64, HistoryEntry)>, StorageError>;
+
+    // opted out numbers are skipped by sms notifiers until they opt back in
+    async fn set_sms_opt_out(&self, phone: &str, opted_out: bool) -> Result<(), StorageError>;
+
+    async fn sms_opted_out(&self, phone: &str) -> Result<bool, StorageError>;
+
+    async fn save_subscription(&self, subscription: &Subscription) -> Result<(), StorageError>;
+
+    async fn subscription(&self, id: &str) -> Result<Option<Subscription>, StorageError>;
+
+    // oldest first, the scheduler checks every subscription each minute
+    async fn subscriptions(&self) -> Result<Vec<Subscription>, StorageError>;
+
+    // false when there was no such subscription
+    async fn delete_subscription(&self, id: &str) -> Result<bool, StorageError>;
+
+    // false when the subscription was already notified of this alert
+    async fn record_alert_notification(
+        &self,
+        alert_id: &str,
+        subscription_id: &str,
+        expires: &str,
+    ) -> Result<bool, StorageError>;
+
+    async fn alert_notified(
+        &self,
+        alert_id: &str,
+        subscription_id: &str,
+    ) -> Result<bool, StorageError>;
+
+    // expiry times are stored in UTC, so they compare as strings
+    async fn prune_alert_notifications(&self, expired_before: &str) -> Result<u64, StorageError>;
+}
+
+// pages through history newest first, fetching past summaries in the bounding box corners until the page is full
+pub async fn search_history(
+    storage: &dyn Storage,
+    filter: &HistoryFilter,
+    cursor: Option<i64>,
+    limit: usize,
+) -> Result<HistoryPage, StorageError> {
+    let mut matches: Vec<(i64, HistoryEntry)> = Vec::new();
+    let mut before = cursor;
+
+    loop {
+        let rows = storage.history(filter, before, limit + 1).await?;
+        let exhausted = rows.len() <= limit;
+
+        for (id, entry) in rows {
+            before = Some(id);
+
+            if filter.within_radius(&entry) {
+                matches.push((id, entry));
+            }
+
+            if matches.len() > limit {
+                break;
+            }
+        }
+
+        if exhausted || matches.len() > limit {
+            break;
+        }
+    }
+
+    let next_cursor = match matches.len() > limit {
+        true => {
+            matches.truncate(limit);
+            matches.last().map(|(id, _)| *id)
+        }
+        false => None,
+    };
+
+    Ok(HistoryPage {
+        entries: matches.into_iter().map(|(_, entry)| entry).collect(),
+        next_cursor,
+    })
+}
+
+// picks the backend from the url scheme, both are migrated on connect
+pub async fn connect(url: &str) -> Result<Arc<dyn Storage>, StorageError> {
+    if url.starts_with("sqlite:") {
+        return Ok(Arc::new(SqliteStorage::connect(url).await?));
+    }
+
+    if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+        return Ok(Arc::new(PostgresStorage::connect(url).await?));
+    }
+
+    Err(StorageError::UnsupportedUrl(
+        url.split(':').next().unwrap_or_default().to_string(),
+    ))
+}
+
+// sqlite storage, the database file is created if it doesn't exist
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    pub async fn connect(url: &str) -> Result<Self, StorageError> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+
+        sqlx::migrate!("migrations/sqlite").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn ping(&self) -> Result<(), StorageError> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn save_permalink(
+        &self,
+        summary_key: &str,
+        permalink: &Permalink,
+    ) -> Result<String, StorageError> {
+        sqlx::query(
+            "INSERT INTO permalinks
+                (id, summary_key, endpoint, summary, model, verified, location, generated_at, periods, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (summary_key) DO NOTHING",
+        )
+        .bind(&permalink.id)
+        .bind(summary_key)
+        .bind(&permalink.endpoint)
+        .bind(&permalink.summary)
+        .bind(&permalink.model)
+        .bind(permalink.verified)
+        .bind(serde_json::to_string(&permalink.location)?)
+        .bind(&permalink.generated_at)
+        .bind(serde_json::to_string(&permalink.periods)?)
+        .bind(&permalink.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        let row = sqlx::query("SELECT id FROM permalinks WHERE summary_key = ?")
+            .bind(summary_key)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.try_get("id")?)
+    }
+
+    async fn permalink(&self, id: &str) -> Result<Option<Permalink>, StorageError> {
+        let row = sqlx::query(
+            "SELECT id, endpoint, summary, model, verified, location, generated_at, periods, created_at
+            FROM permalinks WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Permalink {
+            id: row.try_get("id")?,
+            endpoint: row.try_get("endpoint")?,
+            summary: row.try_get("summary")?,
+            model: row.try_get("model")?,
+            verified: row.try_get("verified")?,
+            location: serde_json::from_str(row.try_get("location")?)?,
+            generated_at: row.try_get("generated_at")?,
+            periods: serde_json::from_str(row.try_get("periods")?)?,
+            created_at: row.try_get("created_at")?,
+        }))
+    }
+
+    async fn record_summary(&self, entry: &HistoryEntry) -> Result<(), StorageError> {
+        let (latitude, longitude) = rounded(entry.location.coordinates);
+
+        sqlx::query(
+            "INSERT INTO summaries
+                (endpoint, location, latitude, longitude, model, prompt_version, latency_ms, summary, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&entry.endpoint)
+        .bind(serde_json::to_string(&entry.location)?)
+        .bind(latitude)
+        .bind(longitude)
+        .bind(&entry.model)
+        .bind(&entry.prompt_version)
+        .bind(entry.latency_ms as i64)
+        .bind(&entry.summary)
+        .bind(&entry.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn history(
+        &self,
+        filter: &HistoryFilter,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<(i64, HistoryEntry)>, StorageError> {
+        let rows = history_query::<Sqlite>(filter, before, limit)
+            .build()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries = Vec::new();
+
+        for row in rows {
+            entries.push((
+                row.try_get("id")?,
+                HistoryEntry {
+                    endpoint: row.try_get("endpoint")?,
+                    location: serde_json::from_str(row.try_get("location")?)?,
+                    model: row.try_get("model")?,
+                    prompt_version: row.try_get("prompt_version")?,
+                    latency_ms: row.try_get::<i64, _>("latency_ms")? as u64,
+                    summary: row.try_get("summary")?,
+                    created_at: row.try_get("created_at")?,
+                },
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    async fn set_sms_opt_out(&self, phone: &str, opted_out: bool) -> Result<(), StorageError> {
+        match opted_out {
+            true => sqlx::query(
+                "INSERT INTO sms_opt_outs (phone, created_at) VALUES (?, ?) ON CONFLICT (phone) DO NOTHING",
+            )
+            .bind(phone)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?,
+            false => sqlx::query("DELETE FROM sms_opt_outs WHERE phone = ?")
+                .bind(phone)
+                .execute(&self.pool)
+                .await?,
+        };
+
+        Ok(())
+    }
+
+    async fn sms_opted_out(&self, phone: &str) -> Result<bool, StorageError> {
+        let row = sqlx::query("SELECT phone FROM sms_opt_outs WHERE phone = ?")
+            .bind(phone)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn save_subscription(&self, subscription: &Subscription) -> Result<(), StorageError> {
+        sqlx::query(
+            "INSERT INTO subscriptions (id, location, channel, cron, style, alert_filter, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&subscription.id)
+        .bind(serde_json::to_string(&subscription.location)?)
+        .bind(serde_json::to_string(&subscription.channel)?)
+        .bind(&subscription.cron)
+        .bind(&subscription.style)
+        .bind(alert_filter(&subscription.alerts)?)
+        .bind(&subscription.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn subscription(&self, id: &str) -> Result<Option<Subscription>, StorageError> {
+        let row = sqlx::query(
+            "SELECT id, location, channel, cron, style, alert_filter, created_at FROM subscriptions WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(subscription_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn subscriptions(&self) -> Result<Vec<Subscription>, StorageError> {
+        let rows = sqlx::query(
+            "SELECT id, location, channel, cron, style, alert_filter, created_at FROM subscriptions ORDER BY created_at",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(subscription_row).collect()
+    }
+
+    async fn delete_subscription(&self, id: &str) -> Result<bool, StorageError> {
+        let result = sqlx::query("DELETE FROM subscriptions WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_alert_notification(
+        &self,
+        alert_id: &str,
+        subscription_id: &str,
+        expires: &str,
+    ) -> Result<bool, StorageError> {
+        let result = sqlx::query(
+            "INSERT INTO alert_notifications (alert_id, subscription_id, expires, created_at)
+            VALUES (?, ?, ?, ?) ON CONFLICT (alert_id, subscription_id) DO NOTHING",
+        )
+        .bind(alert_id)
+        .bind(subscription_id)
+        .bind(expires)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn alert_notified(
+        &self,
+        alert_id: &str,
+        subscription_id: &str,
+    ) -> Result<bool, StorageError> {
+        let row = sqlx::query(
+            "SELECT alert_id FROM alert_notifications WHERE alert_id = ? AND subscription_id = ?",
+        )
+        .bind(alert_id)
+        .bind(subscription_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn prune_alert_notifications(&self, expired_before: &str) -> Result<u64, StorageError> {
+        let result = sqlx::query("DELETE FROM alert_notifications WHERE expires < ?")
+            .bind(expired_before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// postgres storage, lets several replicas share permalinks and history
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    pub async fn connect(url: &str) -> Result<Self, StorageError> {
+        let pool = PgPoolOptions::new().connect(url).await?;
+
+        sqlx::migrate!("migrations/postgres").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn ping(&self) -> Result<(), StorageError> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn save_permalink(
+        &self,
+        summary_key: &str,
+        permalink: &Permalink,
+    ) -> Result<String, StorageError> {
+        sqlx::query(
+            "INSERT INTO permalinks
+                (id, summary_key, endpoint, summary, model, verified, location, generated_at, periods, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (summary_key) DO NOTHING",
+        )
+        .bind(&permalink.id)
+        .bind(summary_key)
+        .bind(&permalink.endpoint)
+        .bind(&permalink.summary)
+        .bind(&permalink.model)
+        .bind(permalink.verified)
+        .bind(serde_json::to_string(&permalink.location)?)
+        .bind(&permalink.generated_at)
+        .bind(serde_json::to_string(&permalink.periods)?)
+        .bind(&permalink.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        let row = sqlx::query("SELECT id FROM permalinks WHERE summary_key = $1")
+            .bind(summary_key)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.try_get("id")?)
+    }
+
+    async fn permalink(&self, id: &str) -> Result<Option<Permalink>, StorageError> {
+        let row = sqlx::query(
+            "SELECT id, endpoint, summary, model, verified, location, generated_at, periods, created_at
+            FROM permalinks WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Permalink {
+            id: row.try_get("id")?,
+            endpoint: row.try_get("endpoint")?,
+            summary: row.try_get("summary")?,
+            model: row.try_get("model")?,
+            verified: row.try_get("verified")?,
+            location: serde_json::from_str(row.try_get("location")?)?,
+            generated_at: row.try_get("generated_at")?,
+            periods: serde_json::from_str(row.try_get("periods")?)?,
+            created_at: row.try_get("created_at")?,
+        }))
+    }
+
+    async fn record_summary(&self, entry: &HistoryEntry) -> Result<(), StorageError> {
+        let (latitude, longitude) = rounded(entry.location.coordinates);
+
+        sqlx::query(
+            "INSERT INTO summaries
+                (endpoint, location, latitude, longitude, model, prompt_version, latency_ms, summary, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(&entry.endpoint)
+        .bind(serde_json::to_string(&entry.location)?)
+        .bind(latitude)
+        .bind(longitude)
+        .bind(&entry.model)
+        .bind(&entry.prompt_version)
+        .bind(entry.latency_ms as i64)
+        .bind(&entry.summary)
+        .bind(&entry.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn history(
+        &self,
+        filter: &HistoryFilter,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<(i64, HistoryEntry)>, StorageError> {
+        let rows = history_query::<Postgres>(filter, before, limit)
+            .build()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries = Vec::new();
+
+        for row in rows {
+            entries.push((
+                row.try_get("id")?,
+                HistoryEntry {
+                    endpoint: row.try_get("endpoint")?,
+                    location: serde_json::from_str(row.try_get("location")?)?,
+                    model: row.try_get("model")?,
+                    prompt_version: row.try_get("prompt_version")?,
+                    latency_ms: row.try_get::<i64, _>("latency_ms")? as u64,
+                    summary: row.try_get("summary")?,
+                    created_at: row.try_get("created_at")?,
+                },
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    async fn set_sms_opt_out(&self, phone: &str, opted_out: bool) -> Result<(), StorageError> {
+        match opted_out {
+            true => sqlx::query(
+                "INSERT INTO sms_opt_outs (phone, created_at) VALUES ($1, $2) ON CONFLICT (phone) DO NOTHING",
+            )
+            .bind(phone)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?,
+            false => sqlx::query("DELETE FROM sms_opt_outs WHERE phone = $1")
+                .bind(phone)
+                .execute(&self.pool)
+                .await?,
+        };
+
+        Ok(())
+    }
+
+    async fn sms_opted_out(&self, phone: &str) -> Result<bool, StorageError> {
+        let row = sqlx::query("SELECT phone FROM sms_opt_outs WHERE phone = $1")
+            .bind(phone)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn save_subscription(&self, subscription: &Subscription) -> Result<(), StorageError> {
+        sqlx::query(
+            "INSERT INTO subscriptions (id, location, channel, cron, style, alert_filter, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&subscription.id)
+        .bind(serde_json::to_string(&subscription.location)?)
+        .bind(serde_json::to_string(&subscription.channel)?)
+        .bind(&subscription.cron)
+        .bind(&subscription.style)
+        .bind(alert_filter(&subscription.alerts)?)
+        .bind(&subscription.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn subscription(&self, id: &str) -> Result<Option<Subscription>, StorageError> {
+        let row = sqlx::query(
+            "SELECT id, location, channel, cron, style, alert_filter, created_at FROM subscriptions WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(subscription_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn subscriptions(&self) -> Result<Vec<Subscription>, StorageError> {
+        let rows = sqlx::query(
+            "SELECT id, location, channel, cron, style, alert_filter, created_at FROM subscriptions ORDER BY created_at",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(subscription_row).collect()
+    }
+
+    async fn delete_subscription(&self, id: &str) -> Result<bool, StorageError> {
+        let result = sqlx::query("DELETE FROM subscriptions WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_alert_notification(
+        &self,
+        alert_id: &str,
+        subscription_id: &str,
+        expires: &str,
+    ) -> Result<bool, StorageError> {
+        let result = sqlx::query(
+            "INSERT INTO alert_notifications (alert_id, subscription_id, expires, created_at)
+            VALUES ($1, $2, $3, $4) ON CONFLICT (alert_id, subscription_id) DO NOTHING",
+        )
+        .bind(alert_id)
+        .bind(subscription_id)
+        .bind(expires)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn alert_notified(
+        &self,
+        alert_id: &str,
+        subscription_id: &str,
+    ) -> Result<bool, StorageError> {
+        let row = sqlx::query(
+            "SELECT alert_id FROM alert_notifications WHERE alert_id = $1 AND subscription_id = $2",
+        )
+        .bind(alert_id)
+        .bind(subscription_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn prune_alert_notifications(&self, expired_before: &str) -> Result<u64, StorageError> {
+        let result = sqlx::query("DELETE FROM alert_notifications WHERE expires < $1")
+            .bind(expired_before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// an empty filter is stored as NULL, like subscriptions made before filters existed
+fn alert_filter(filter: &AlertFilter) -> Result<Option<String>, StorageError> {
+    match filter.is_empty() {
+        true => Ok(None),
+        false => Ok(Some(serde_json::to_string(filter)?)),
+    }
+}
+
+// subscription rows are read the same way from either backend
+fn subscription_row<R: Row>(row: &R) -> Result<Subscription, StorageError>
+where
+    for<'r> &'r str: ColumnIndex<R> + Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> String: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> Option<String>: Decode<'r, R::Database> + Type<R::Database>,
+{
+    Ok(Subscription {
+        id: row.try_get("id")?,
+        location: serde_json::from_str(row.try_get("location")?)?,
+        channel: serde_json::from_str(row.try_get("channel")?)?,
+        cron: row.try_get("cron")?,
+        style: row.try_get("style")?,
+        alerts: match row.try_get::<Option<String>, _>("alert_filter")? {
+            Some(alert_filter) => serde_json::from_str(&alert_filter)?,
+            None => AlertFilter::default(),
+        },
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+// the same query for both backends, QueryBuilder takes care of the placeholder style
+fn history_query<'a, DB: Database>(
+    filter: &'a HistoryFilter,
+    before: Option<i64>,
+    limit: usize,
+) -> QueryBuilder<'a, DB>
+where
+    f64: Encode<'a, DB> + Type<DB>,
+    i64: Encode<'a, DB> + Type<DB>,
+    &'a str: Encode<'a, DB> + Type<DB>,
+{
+    let mut query = QueryBuilder::new(
+        "SELECT id, endpoint, location, model, prompt_version, latency_ms, summary, created_at
+        FROM summaries WHERE 1 = 1",
+    );
+
+    let (latitude, longitude) = match filter.coordinates {
+        Some(coordinates) => rounded(coordinates),
+        None => (0.0, 0.0),
+    };
+
+    match (filter.coordinates, filter.radius_miles) {
+        (None, _) => {}
+        (Some(_), Some(radius_miles)) => {
+            let latitude_delta = radius_miles / MILES_PER_DEGREE;
+            let longitude_delta =
+                radius_miles / (MILES_PER_DEGREE * latitude.to_radians().cos().max(0.01));
+
+            query
+                .push(" AND latitude BETWEEN ")
+                .push_bind(latitude - latitude_delta)
+                .push(" AND ")
+                .push_bind(latitude + latitude_delta)
+                .push(" AND longitude BETWEEN ")
+                .push_bind(longitude - longitude_delta)
+                .push(" AND ")
+                .push_bind(longitude + longitude_delta);
+        }
+        (Some(_), None) => {
+            query
+                .push(" AND latitude = ")
+                .push_bind(latitude)
+                .push(" AND longitude = ")
+                .push_bind(longitude);
+        }
+    }
+
+    if let Some(model) = &filter.model {
+        query.push(" AND model = ").push_bind(model.as_str());
+    }
+
+    if let Some(from) = &filter.from {
+        query.push(" AND created_at >= ").push_bind(from.as_str());
+    }
+
+    if let Some(to) = &filter.to {
+        query.push(" AND created_at < ").push_bind(to.as_str());
+    }
+
+    if let Some(before) = before {
+        query.push(" AND id < ").push_bind(before);
+    }
+
+    query
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(limit as i64);
+
+    query
+}
+
+fn rounded(coordinates: Coordinates) -> (f64, f64) {
+    (
+        (coordinates.latitude * 10_000.0).round() / 10_000.0,
+        (coordinates.longitude * 10_000.0).round() / 10_000.0,
+    )
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::geocode::Coordinates;
+
+// julian dates of the J2000 epoch and the unix epoch
+pub(crate) const J2000: f64 = 2451545.0;
+pub(crate) const UNIX_EPOCH_JULIAN: f64 = 2440587.5;
+pub(crate) const SECONDS_PER_DAY: f64 = 86400.0;
+
+const AXIAL_TILT_DEGREES: f64 = 23.4397;
+// the sun's upper limb touches the horizon when its center is this far below it, after refraction
+const SUNRISE_ALTITUDE_DEGREES: f64 = -0.833;
+
+// daylight struct, sunrise and sunset on a local date. both are none when the sun doesn't rise or set,
+// the day length tells polar day from polar night
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Daylight {
+    pub date: NaiveDate,
+    pub sunrise: Option<DateTime<FixedOffset>>,
+    pub sunset: Option<DateTime<FixedOffset>>,
+    pub day_length_minutes: i64,
+}
+
+// the sunrise equation, accurate to about a minute away from the poles
+pub fn daylight(coordinates: Coordinates, date: NaiveDate, offset: FixedOffset) -> Daylight {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    let day = (date - epoch).num_days() as f64;
+
+    // solar noon drifts a day's fraction for each degree of longitude
+    let mean_solar_time = day - coordinates.longitude / 360.0;
+
+    let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let anomaly = mean_anomaly.to_radians();
+
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+
+    let ecliptic_longitude = (mean_anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+
+    let transit = J2000 + mean_solar_time + 0.0053 * anomaly.sin()
+        - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * AXIAL_TILT_DEGREES.to_radians().sin()).asin();
+    let latitude = coordinates.latitude.to_radians();
+
+    let cos_hour_angle = (SUNRISE_ALTITUDE_DEGREES.to_radians().sin()
+        - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+
+    // below -1 the sun stays up all day, above 1 it never rises
+    if cos_hour_angle < -1.0 {
+        return Daylight {
+            date,
+            sunrise: None,
+            sunset: None,
+            day_length_minutes: 24 * 60,
+        };
+    }
+
+    if cos_hour_angle > 1.0 {
+        return Daylight {
+            date,
+            sunrise: None,
+            sunset: None,
+            day_length_minutes: 0,
+        };
+    }
+
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let sunrise = julian_time(transit - hour_angle / 360.0, offset);
+    let sunset = julian_time(transit + hour_angle / 360.0, offset);
+
+    Daylight {
+        date,
+        day_length_minutes: match (sunrise, sunset) {
+            (Some(sunrise), Some(sunset)) => (sunset - sunrise).num_minutes(),
+            _ => 0,
+        },
+        sunrise,
+        sunset,
+    }
+}
+
+fn julian_time(julian: f64, offset: FixedOffset) -> Option<DateTime<FixedOffset>> {
+    let seconds = ((julian - UNIX_EPOCH_JULIAN) * SECONDS_PER_DAY).round() as i64;
+
+    DateTime::from_timestamp(seconds, 0).map(|time| time.with_timezone(&offset))
+}
@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter_vec, CounterVec};
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
+use tokio::time::Instant;
+use tracing::warn;
+
+lazy_static! {
+    pub static ref THROTTLE_WAITS_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "outbound_throttle_waits_total",
+            "outbound requests that waited for the rate limiter"
+        ),
+        &["host"]
+    )
+    .unwrap();
+    pub static ref RATE_LIMITED_COUNTER: CounterVec = register_counter_vec!(
+        opts!(
+            "outbound_rate_limited_total",
+            "outbound requests answered with 429 Too Many Requests"
+        ),
+        &["host"]
+    )
+    .unwrap();
+}
+
+// a 429 without a Retry-After waits this long, and a long one is capped so requests aren't held
+// for minutes
+const DEFAULT_RETRY_AFTER_SECONDS: u64 = 5;
+const MAX_RETRY_AFTER_SECONDS: u64 = 60;
+
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+// token bucket struct, refills at rate tokens a second up to burst. tokens go negative as requests
+// are promised later slots, and updated moves into the future while the bucket is paused
+struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(rate: f32, burst: u32) -> TokenBucket {
+        let burst = burst.max(1) as f64;
+
+        TokenBucket {
+            rate: rate as f64,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+            paused_until: None,
+        }
+    }
+
+    // takes a token and returns when it can be used, a rate of 0 is unlimited
+    fn reserve(&mut self, now: Instant) -> Instant {
+        if self.rate <= 0.0 {
+            return self.paused_until.unwrap_or(now).max(now);
+        }
+
+        if now > self.updated {
+            let elapsed = (now - self.updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+            self.updated = now;
+        }
+
+        self.tokens -= 1.0;
+
+        match self.tokens >= 0.0 {
+            true => self.updated,
+            false => self.updated + Duration::from_secs_f64(-self.tokens / self.rate),
+        }
+    }
+
+    // nothing goes out until then, and one request at a time after at the bucket's rate
+    fn pause(&mut self, until: Instant) {
+        if self
+            .paused_until
+            .is_some_and(|paused_until| paused_until >= until)
+        {
+            return;
+        }
+
+        self.paused_until = Some(until);
+        self.tokens = 1.0;
+        self.updated = until;
+    }
+
+    fn is_paused(&self, now: Instant) -> bool {
+        self.paused_until
+            .is_some_and(|paused_until| paused_until > now)
+    }
+}
+
+// throttle struct, a token bucket per host plus one shared by every host
+pub struct Throttle {
+    global: Mutex<TokenBucket>,
+    hosts: Mutex<HashMap<String, TokenBucket>>,
+    host_rate: f32,
+    host_burst: u32,
+}
+
+impl Throttle {
+    pub fn new(host_rate: f32, host_burst: u32, global_rate: f32, global_burst: u32) -> Throttle {
+        Throttle {
+            global: Mutex::new(TokenBucket::new(global_rate, global_burst)),
+            hosts: Mutex::new(HashMap::new()),
+            host_rate,
+            host_burst,
+        }
+    }
+
+    fn reserve(&self, host: &str, now: Instant) -> Instant {
+        let global_ready = self.global.lock().unwrap().reserve(now);
+
+        let host_ready = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| TokenBucket::new(self.host_rate, self.host_burst))
+            .reserve(now);
+
+        global_ready.max(host_ready)
+    }
+
+    fn is_paused(&self, host: &str, now: Instant) -> bool {
+        self.hosts
+            .lock()
+            .unwrap()
+            .get(host)
+            .is_some_and(|bucket| bucket.is_paused(now))
+    }
+
+    fn pause(&self, host: &str, until: Instant) {
+        self.hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| TokenBucket::new(self.host_rate, self.host_burst))
+            .pause(until);
+    }
+
+    // waits for a slot, and again if the host was paused by a 429 while waiting for it
+    async fn acquire(&self, host: &str) {
+        loop {
+            let ready = self.reserve(host, Instant::now());
+
+            if ready > Instant::now() {
+                THROTTLE_WAITS_COUNTER.with_label_values(&[host]).inc();
+                tokio::time::sleep_until(ready).await;
+            }
+
+            if !self.is_paused(host, Instant::now()) {
+                return;
+            }
+        }
+    }
+}
+
+// the limits every throttled request shares, set once at startup
+pub fn configure(throttle: Throttle) {
+    if THROTTLE.set(throttle).is_err() {
+        warn!("outbound throttle is already configured");
+    }
+}
+
+// unlimited until configured
+fn throttle() -> &'static Throttle {
+    THROTTLE.get_or_init(|| Throttle::new(0.0, 1, 0.0, 1))
+}
+
+// sends the request once its host and the global bucket allow it. a 429 pauses the host for
+// its Retry-After, then the request is sent once more
+pub async fn send(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    let (client, request) = request.build_split();
+    let request = request?;
+
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let retry = request.try_clone();
+    let throttle = throttle();
+
+    throttle.acquire(&host).await;
+
+    let response = client.execute(request).await?;
+
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return Ok(response);
+    }
+
+    RATE_LIMITED_COUNTER.with_label_values(&[&host]).inc();
+
+    let delay = retry_after(&response, Utc::now());
+    warn!(
+        "{} rate limited the request, waiting {} seconds",
+        host,
+        delay.as_secs()
+    );
+    throttle.pause(&host, Instant::now() + delay);
+
+    let retry = match retry {
+        Some(retry) => retry,
+        None => return Ok(response),
+    };
+
+    throttle.acquire(&host).await;
+
+    client.execute(retry).await
+}
+
+// Retry-After is either seconds or an HTTP date
+fn retry_after(response: &Response, now: DateTime<Utc>) -> Duration {
+    let value = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim());
+
+    let seconds = match value {
+        Some(value) => match value.parse::<u64>() {
+            Ok(seconds) => seconds,
+            Err(_) => match DateTime::parse_from_rfc2822(value) {
+                Ok(date) => (date.with_timezone(&Utc) - now).num_seconds().max(0) as u64,
+                Err(_) => DEFAULT_RETRY_AFTER_SECONDS,
+            },
+        },
+        None => DEFAULT_RETRY_AFTER_SECONDS,
+    };
+
+    Duration::from_secs(seconds.min(MAX_RETRY_AFTER_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(retry_after: Option<&str>) -> Response {
+        let mut response = axum::http::Response::builder().status(StatusCode::TOO_MANY_REQUESTS);
+
+        if let Some(retry_after) = retry_after {
+            response = response.header(RETRY_AFTER, retry_after);
+        }
+
+        Response::from(response.body("").unwrap())
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2015-10-21T07:27:15Z".parse().unwrap()
+    }
+
+    #[test]
+    fn reads_retry_after_seconds() {
+        assert_eq!(
+            retry_after(&response(Some("30")), now()),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            retry_after(&response(Some(" 0 ")), now()),
+            Duration::from_secs(0)
+        );
+        assert_eq!(
+            retry_after(&response(Some("3600")), now()),
+            Duration::from_secs(MAX_RETRY_AFTER_SECONDS)
+        );
+    }
+
+    #[test]
+    fn reads_retry_after_dates() {
+        assert_eq!(
+            retry_after(&response(Some("Wed, 21 Oct 2015 07:28:00 GMT")), now()),
+            Duration::from_secs(45)
+        );
+        // a date that has already passed means retry now
+        assert_eq!(
+            retry_after(&response(Some("Wed, 21 Oct 2015 07:00:00 GMT")), now()),
+            Duration::from_secs(0)
+        );
+        assert_eq!(
+            retry_after(&response(Some("Thu, 22 Oct 2015 07:28:00 GMT")), now()),
+            Duration::from_secs(MAX_RETRY_AFTER_SECONDS)
+        );
+    }
+
+    #[test]
+    fn defaults_malformed_retry_after() {
+        for value in ["soon", "-5", "1.5", "", "2015-10-21T07:28:00Z"] {
+            assert_eq!(
+                retry_after(&response(Some(value)), now()),
+                Duration::from_secs(DEFAULT_RETRY_AFTER_SECONDS),
+                "{value}"
+            );
+        }
+        assert_eq!(
+            retry_after(&response(None), now()),
+            Duration::from_secs(DEFAULT_RETRY_AFTER_SECONDS)
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::geocode::{self, Coordinates};
+
+const STATIONS_URL: &str = "https://api.tidesandcurrents.noaa.gov/mdapi/prod/webapi/stations.json";
+const PREDICTIONS_URL: &str = "https://api.tidesandcurrents.noaa.gov/api/prod/datagetter";
+
+// co-ops asks callers to identify themselves
+const APPLICATION: &str = "nws-forecast-summarizer";
+
+#[derive(Debug, Error)]
+pub enum TidesError {
+    #[error("error requesting tidesandcurrents.noaa.gov: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("error decoding tidesandcurrents.noaa.gov response: {0}")]
+    Decode(#[source] reqwest::Error),
+    #[error("tide predictions failed: {0}")]
+    Api(String),
+    #[error("invalid tide prediction {0}")]
+    InvalidPrediction(String),
+}
+
+impl TidesError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TidesError::Request(_) => "request",
+            TidesError::Decode(_) => "decode",
+            TidesError::Api(_) => "api",
+            TidesError::InvalidPrediction(_) => "invalid_prediction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawStations {
+    #[serde(default)]
+    stations: Vec<RawStation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawStation {
+    id: String,
+    name: String,
+    #[serde(default)]
+    state: String,
+    lat: f64,
+    lng: f64,
+    timezonecorr: Option<f64>,
+    #[serde(default)]
+    observedst: bool,
+}
+
+// tide station struct, a station with tide predictions. utc_offset is its standard time offset in hours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TideStation {
+    pub id: String,
+    pub name: String,
+    pub state: String,
+    pub coordinates: Coordinates,
+    pub utc_offset: f64,
+    pub observes_dst: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawPredictions {
+    #[serde(default)]
+    predictions: Vec<RawPrediction>,
+    error: Option<RawApiError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawPrediction {
+    t: String,
+    v: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawApiError {
+    message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TideKind {
+    High,
+    Low,
+}
+
+// tide prediction struct, a high or low tide in the station's local time. height is feet or meters above MLLW
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TidePrediction {
+    pub time: DateTime<FixedOffset>,
+    pub kind: TideKind,
+    pub height: f64,
+}
+
+pub async fn get_stations(client: reqwest::Client) -> Result<Vec<TideStation>, TidesError> {
+    let response_result = client
+        .get(STATIONS_URL)
+        .query(&[("type", "tidepredictions")])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(TidesError::Request(e)),
+    };
+
+    let raw = match response.json::<RawStations>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(TidesError::Decode(e)),
+    };
+
+    Ok(raw
+        .stations
+        .into_iter()
+        .map(|station| TideStation {
+            id: station.id,
+            name: station.name,
+            state: station.state,
+            coordinates: Coordinates {
+                latitude: station.lat,
+                longitude: station.lng,
+            },
+            utc_offset: station.timezonecorr.unwrap_or_default(),
+            observes_dst: station.observedst,
+        })
+        .collect())
+}
+
+pub fn nearest(stations: &[TideStation], coordinates: Coordinates) -> Option<(&TideStation, f64)> {
+    geocode::nearest(stations, coordinates, |station| station.coordinates)
+}
+
+// predictions are requested in gmt so the window starts now wherever the station is,
+// then shifted to the station's local time
+pub async fn get_predictions(
+    client: reqwest::Client,
+    station: &TideStation,
+    begin: DateTime<Utc>,
+    hours: u32,
+    units: &str,
+) -> Result<Vec<TidePrediction>, TidesError> {
+    let response_result = client
+        .get(PREDICTIONS_URL)
+        .query(&[
+            ("product", "predictions".to_string()),
+            ("application", APPLICATION.to_string()),
+            ("station", station.id.to_owned()),
+            ("begin_date", begin.format("%Y%m%d %H:%M").to_string()),
+            ("range", hours.to_string()),
+            ("datum", "MLLW".to_string()),
+            ("time_zone", "gmt".to_string()),
+            ("interval", "hilo".to_string()),
+            (
+                "units",
+                match units {
+                    "si" => "metric".to_string(),
+                    _ => "english".to_string(),
+                },
+            ),
+            ("format", "json".to_string()),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let response = match response_result {
+        Ok(body) => body,
+        Err(e) => return Err(TidesError::Request(e)),
+    };
+
+    let raw = match response.json::<RawPredictions>().await {
+        Ok(raw) => raw,
+        Err(e) => return Err(TidesError::Decode(e)),
+    };
+
+    // errors come back as a 200 with a message instead of predictions
+    if let Some(error) = raw.error {
+        return Err(TidesError::Api(error.message));
+    }
+
+    raw.predictions
+        .into_iter()
+        .map(|prediction| {
+            let invalid =
+                || TidesError::InvalidPrediction(format!("{} {}", prediction.t, prediction.v));
+
+            let time = match NaiveDateTime::parse_from_str(&prediction.t, "%Y-%m-%d %H:%M") {
+                Ok(time) => time.and_utc(),
+                Err(_) => return Err(invalid()),
+            };
+
+            let height = match prediction.v.trim().parse::<f64>() {
+                Ok(height) => height,
+                Err(_) => return Err(invalid()),
+            };
+
+            // higher highs and lower lows are HH and LL
+            let kind = match prediction.kind.chars().next() {
+                Some('H') => TideKind::High,
+                Some('L') => TideKind::Low,
+                _ => return Err(invalid()),
+            };
+
+            Ok(TidePrediction {
+                time: time.with_timezone(&local_offset(station, time)),
+                kind,
+                height,
+            })
+        })
+        .collect()
+}
+
+// stations only report their standard offset and whether they observe daylight saving time,
+// which in the US runs from 2 AM on the second Sunday in March to 2 AM on the first Sunday in November
+fn local_offset(station: &TideStation, time: DateTime<Utc>) -> FixedOffset {
+    let standard_seconds = (station.utc_offset * 3600.0).round() as i32;
+
+    let standard = time.naive_utc() + TimeDelta::seconds(standard_seconds as i64);
+
+    let daylight = station.observes_dst && {
+        let year = standard.year();
+
+        match (
+            NaiveDate::from_weekday_of_month_opt(year, 3, Weekday::Sun, 2),
+            NaiveDate::from_weekday_of_month_opt(year, 11, Weekday::Sun, 1),
+        ) {
+            (Some(start), Some(end)) => {
+                // 2 AM daylight time in november is 1 AM standard time
+                standard >= start.and_hms_opt(2, 0, 0).unwrap_or_default()
+                    && standard < end.and_hms_opt(1, 0, 0).unwrap_or_default()
+            }
+            _ => false,
+        }
+    };
+
+    let seconds = match daylight {
+        true => standard_seconds + 3600,
+        false => standard_seconds,
+    };
+
+    FixedOffset::east_opt(seconds).unwrap_or(FixedOffset::east_opt(0).unwrap())
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Deserializer, Serializer};
+
+// serde for times kept as RFC 3339 strings like 2026-10-14T06:00:00-07:00, the format NWS sends
+// and responses have always used. the offset is kept, it's the location's own
+pub fn serialize<S: Serializer>(
+    time: &DateTime<FixedOffset>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<FixedOffset>, D::Error> {
+    let time = String::deserialize(deserializer)?;
+
+    DateTime::parse_from_rfc3339(&time).map_err(serde::de::Error::custom)
+}
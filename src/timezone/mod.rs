@@ -0,0 +1,69 @@
+use chrono::{DateTime, FixedOffset, Timelike};
+use chrono_tz::Tz;
+
+const SECONDS_PER_HOUR: i32 = 3600;
+
+// like PDT for the point's zone at that time, or UTC-7 for points cached without one
+pub fn abbreviation(time_zone: Option<&str>, time: DateTime<FixedOffset>) -> String {
+    if let Some(time_zone) = time_zone.and_then(|time_zone| time_zone.parse::<Tz>().ok()) {
+        return time.with_timezone(&time_zone).format("%Z").to_string();
+    }
+
+    let seconds = time.offset().local_minus_utc();
+    let sign = match seconds < 0 {
+        true => "-",
+        false => "+",
+    };
+    let hours = seconds.abs() / SECONDS_PER_HOUR;
+
+    match seconds.abs() / 60 % 60 {
+        0 => format!("UTC{}{}", sign, hours),
+        minutes => format!("UTC{}{}:{:02}", sign, hours, minutes),
+    }
+}
+
+// 6 AM, or 6:30 AM when it isn't on the hour
+fn clock(time: DateTime<FixedOffset>) -> String {
+    match time.minute() {
+        0 => time.format("%-I %p").to_string(),
+        _ => time.format("%-I:%M %p").to_string(),
+    }
+}
+
+// a time like Wed 12:07 AM PDT
+pub fn time(time: DateTime<FixedOffset>, time_zone: Option<&str>) -> String {
+    format!(
+        "{} {} {}",
+        time.format("%a"),
+        clock(time),
+        abbreviation(time_zone, time)
+    )
+}
+
+// a period like Tue 6 AM–6 PM PDT, with the end day named when it ends on another day and
+// both zones named when daylight saving time starts or ends during it
+pub fn period(
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    time_zone: Option<&str>,
+) -> String {
+    let start_abbreviation = abbreviation(time_zone, start);
+    let end_abbreviation = abbreviation(time_zone, end);
+
+    let start_text = match start_abbreviation == end_abbreviation {
+        true => format!("{} {}", start.format("%a"), clock(start)),
+        false => format!(
+            "{} {} {}",
+            start.format("%a"),
+            clock(start),
+            start_abbreviation
+        ),
+    };
+
+    let end_text = match start.date_naive() == end.date_naive() {
+        true => clock(end),
+        false => format!("{} {}", end.format("%a"), clock(end)),
+    };
+
+    format!("{}–{} {}", start_text, end_text, end_abbreviation)
+}
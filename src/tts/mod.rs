@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TtsError {
+    #[error("error sending request to tts backend: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("tts backend returned {status}: {message}")]
+    Api { status: u16, message: String },
+    #[error("{0} audio is not supported by this tts backend")]
+    UnsupportedFormat(&'static str),
+    #[error("no tts backend is configured")]
+    NotConfigured,
+}
+
+impl TtsError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TtsError::Http(_) => "request",
+            TtsError::Api { .. } => "api",
+            TtsError::UnsupportedFormat(_) => "unsupported_format",
+            TtsError::NotConfigured => "not_configured",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioFormat {
+    Mpeg,
+    Wav,
+}
+
+impl AudioFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "mp3" | "mpeg" => Some(AudioFormat::Mpeg),
+            "wav" => Some(AudioFormat::Wav),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Mpeg => "mp3",
+            AudioFormat::Wav => "wav",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Mpeg => "audio/mpeg",
+            AudioFormat::Wav => "audio/wav",
+        }
+    }
+}
+
+// tts backend, turns summary text into audio
+#[async_trait]
+pub trait TtsBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // format used when a request doesn't ask for one
+    fn default_format(&self) -> AudioFormat;
+
+    async fn synthesize(&self, text: &str, format: AudioFormat) -> Result<Vec<u8>, TtsError>;
+}
+
+// piper backend, talks to the piper HTTP server which only produces wav
+pub struct PiperBackend {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PiperRequest<'a> {
+    text: &'a str,
+}
+
+impl PiperBackend {
+    pub fn new(client: reqwest::Client, base_url: String) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl TtsBackend for PiperBackend {
+    fn name(&self) -> &'static str {
+        "piper"
+    }
+
+    fn default_format(&self) -> AudioFormat {
+        AudioFormat::Wav
+    }
+
+    async fn synthesize(&self, text: &str, format: AudioFormat) -> Result<Vec<u8>, TtsError> {
+        if format != AudioFormat::Wav {
+            return Err(TtsError::UnsupportedFormat(format.name()));
+        }
+
+        let response = self
+            .client
+            .post(&self.base_url)
+            .json(&PiperRequest { text })
+            .send()
+            .await?;
+
+        audio(response).await
+    }
+}
+
+// openai speech backend, works with any server implementing the OpenAI audio speech API
+pub struct OpenAiSpeechBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    voice: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'static str,
+}
+
+impl OpenAiSpeechBackend {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: String,
+        api_key: Option<String>,
+        model: String,
+        voice: String,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+            voice,
+        }
+    }
+}
+
+#[async_trait]
+impl TtsBackend for OpenAiSpeechBackend {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn default_format(&self) -> AudioFormat {
+        AudioFormat::Mpeg
+    }
+
+    async fn synthesize(&self, text: &str, format: AudioFormat) -> Result<Vec<u8>, TtsError> {
+        let mut builder = self
+            .client
+            .post(format!("{}/audio/speech", self.base_url))
+            .json(&SpeechRequest {
+                model: &self.model,
+                input: text,
+                voice: &self.voice,
+                response_format: format.name(),
+            });
+
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = builder.send().await?;
+
+        audio(response).await
+    }
+}
+
+async fn audio(response: reqwest::Response) -> Result<Vec<u8>, TtsError> {
+    let status = response.status();
+
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_default();
+
+        return Err(TtsError::Api {
+            status: status.as_u16(),
+            message,
+        });
+    }
+
+    Ok(response.bytes().await?.to_vec())
+}
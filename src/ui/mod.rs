@@ -0,0 +1,17 @@
+use axum::response::Html;
+use lazy_static::lazy_static;
+use prometheus::{opts, register_counter, Counter};
+
+lazy_static! {
+    pub static ref UI_COUNTER: Counter =
+        register_counter!(opts!("ui_total", "times the /ui page was loaded")).unwrap();
+}
+
+// compiled into the binary so the UI ships with the API
+const INDEX: &str = include_str!("index.html");
+
+pub async fn index() -> Html<&'static str> {
+    UI_COUNTER.inc();
+
+    Html(INDEX)
+}
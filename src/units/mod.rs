@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use crate::nws::{Dewpoint, HourlyPeriod, Period};
+
+// us is fahrenheit, mph, and inches, si is celsius, km/h, and centimeters
+pub const US: &str = "us";
+pub const SI: &str = "si";
+
+const KILOMETERS_PER_MILE: f64 = 1.609344;
+const CENTIMETERS_PER_INCH: f64 = 2.54;
+
+// a number this many words after one of these is a temperature, like "highs 78 to 84"
+// or "temperatures falling to around 72"
+const TEMPERATURE_WORDS: [&str; 6] = [
+    "high",
+    "highs",
+    "low",
+    "lows",
+    "temperature",
+    "temperatures",
+];
+const TEMPERATURE_LOOKBACK_WORDS: usize = 4;
+
+// metric and imperial are accepted too, since that's what most people call them
+pub fn parse(units: &str) -> Option<&'static str> {
+    match units {
+        "us" | "imperial" => Some(US),
+        "si" | "metric" => Some(SI),
+        _ => None,
+    }
+}
+
+// a whole degree temperature in F or C, in the requested units
+pub fn temperature(value: i64, unit: &str, units: &str) -> (i64, String) {
+    let value = value as f64;
+
+    let (value, unit) = match (unit, units) {
+        ("F", SI) => ((value - 32.0) * 5.0 / 9.0, "C"),
+        ("C", US) => (value * 9.0 / 5.0 + 32.0, "F"),
+        (unit, _) => (value, unit),
+    };
+
+    (value.round() as i64, unit.to_string())
+}
+
+// NWS measurements carry a WMO unit code like wmoUnit:degC, this is how each one is written
+pub fn label(unit_code: &str) -> Option<&'static str> {
+    match unit_code {
+        "wmoUnit:degC" => Some("C"),
+        "wmoUnit:degF" => Some("F"),
+        "wmoUnit:percent" => Some("%"),
+        "wmoUnit:km_h-1" => Some(" km/h"),
+        "wmoUnit:m_s-1" => Some(" m/s"),
+        "wmoUnit:mm" => Some(" mm"),
+        "wmoUnit:m" => Some(" m"),
+        "wmoUnit:Pa" => Some(" Pa"),
+        _ => None,
+    }
+}
+
+// a measurement with its unit, like 65%. codes without a label are left off rather than guessed at
+pub fn measurement(value: impl Display, unit_code: &str) -> String {
+    format!("{}{}", value, label(unit_code).unwrap_or_default())
+}
+
+pub fn dewpoint(dewpoint: &Dewpoint, units: &str) -> Option<String> {
+    let celsius = match label(&dewpoint.unit_code)? {
+        "C" => dewpoint.value,
+        "F" => (dewpoint.value - 32.0) * 5.0 / 9.0,
+        _ => return None,
+    };
+
+    match units {
+        SI => Some(format!("{}C", celsius.round() as i64)),
+        _ => Some(format!("{}F", (celsius * 9.0 / 5.0 + 32.0).round() as i64)),
+    }
+}
+
+// the period with its temperature, wind, and text forecast in the requested units. forecasts
+// NWS already wrote in si keep their text, only us text can be rewritten
+pub fn period(period: &Period, units: &str) -> Period {
+    let text_units = text_units(&period.temperature_unit, units);
+    let (temperature, temperature_unit) =
+        temperature(period.temperature, &period.temperature_unit, units);
+
+    Period {
+        detailed_forecast: text(&period.detailed_forecast, text_units),
+        temperature,
+        temperature_unit,
+        wind_speed: text(&period.wind_speed, text_units),
+        ..period.clone()
+    }
+}
+
+pub fn hourly_period(period: &HourlyPeriod, units: &str) -> HourlyPeriod {
+    let text_units = text_units(&period.temperature_unit, units);
+    let (temperature, temperature_unit) =
+        temperature(period.temperature, &period.temperature_unit, units);
+
+    HourlyPeriod {
+        temperature,
+        temperature_unit,
+        wind_speed: text(&period.wind_speed, text_units),
+        ..period.clone()
+    }
+}
+
+fn text_units<'a>(temperature_unit: &str, units: &'a str) -> &'a str {
+    match temperature_unit {
+        "F" => units,
+        _ => US,
+    }
+}
+
+// NWS text is always us units, so si rewrites the speeds, amounts, and temperatures in it,
+// like "10 to 25 mph" to "16 to 40 km/h" and "High near 62." to "High near 17."
+pub fn text(text: &str, units: &str) -> String {
+    if units != SI {
+        return text.to_string();
+    }
+
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut converted: Vec<String> = Vec::with_capacity(words.len());
+    // the unit word to rewrite after the number that was converted
+    let mut pending_unit: Option<usize> = None;
+
+    for (index, word) in words.iter().enumerate() {
+        if pending_unit == Some(index) {
+            pending_unit = None;
+            converted.push(convert_unit_word(word));
+            continue;
+        }
+
+        let (value, rest) = match number(word) {
+            Some(number) => number,
+            None => {
+                converted.push(word.to_string());
+                continue;
+            }
+        };
+
+        let unit_index = match (words.get(index + 1), words.get(index + 2)) {
+            (Some(&"to"), Some(next)) if number(next).is_some() => index + 3,
+            _ => index + 1,
+        };
+
+        let value = match words.get(unit_index).and_then(|word| unit(word)) {
+            Some(Unit::Speed) => {
+                if unit_index == index + 1 {
+                    pending_unit = Some(unit_index);
+                }
+                value * KILOMETERS_PER_MILE
+            }
+            Some(Unit::Length) => {
+                if unit_index == index + 1 {
+                    pending_unit = Some(unit_index);
+                }
+                (value * CENTIMETERS_PER_INCH).max(1.0)
+            }
+            None if is_temperature(&words[..index]) => (value - 32.0) * 5.0 / 9.0,
+            None => {
+                converted.push(word.to_string());
+                continue;
+            }
+        };
+
+        converted.push(format!("{}{}", value.round() as i64, rest));
+    }
+
+    converted.join(" ")
+}
+
+enum Unit {
+    Speed,
+    Length,
+}
+
+fn unit(word: &str) -> Option<Unit> {
+    match word.trim_end_matches(['.', ',', ';']) {
+        "mph" => Some(Unit::Speed),
+        "inch" | "inches" => Some(Unit::Length),
+        _ => None,
+    }
+}
+
+fn convert_unit_word(word: &str) -> String {
+    let bare = word.trim_end_matches(['.', ',', ';']);
+    let punctuation = &word[bare.len()..];
+
+    match bare {
+        "mph" => format!("km/h{}", punctuation),
+        "inch" | "inches" => format!("cm{}", punctuation),
+        _ => word.to_string(),
+    }
+}
+
+// a number with nothing after it but punctuation, so times like 11am are left alone
+fn number(word: &str) -> Option<(f64, &str)> {
+    let bare = word.trim_end_matches(['.', ',', ';']);
+    let value = bare.parse::<f64>().ok()?;
+
+    Some((value, &word[bare.len()..]))
+}
+
+// looks back within the sentence for a word that says the number is a temperature
+fn is_temperature(before: &[&str]) -> bool {
+    before
+        .iter()
+        .rev()
+        .take(TEMPERATURE_LOOKBACK_WORDS)
+        .take_while(|word| !word.ends_with('.'))
+        .any(|word| TEMPERATURE_WORDS.contains(&word.trim_end_matches(',').to_lowercase().as_str()))
+}
@@ -0,0 +1,181 @@
+use lazy_static::lazy_static;
+use prometheus::{register_counter, Counter};
+
+lazy_static! {
+    pub static ref UNVERIFIED_SUMMARIES_COUNTER: Counter = register_counter!(
+        "unverified_summaries_total",
+        "summaries mentioning numbers missing from the forecast"
+    )
+    .unwrap();
+}
+
+const TEMPERATURE_TOLERANCE: f64 = 2.0;
+const WIND_TOLERANCE: f64 = 2.0;
+const PRECIPITATION_TOLERANCE: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Temperature,
+    Wind,
+    Precipitation,
+}
+
+// quantity struct, a number or range found in text, normalized to fahrenheit, mph, and percent
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Quantity {
+    kind: Kind,
+    low: f64,
+    high: f64,
+}
+
+// verification struct, whether every number in a summary is backed by the source forecast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub verified: bool,
+    pub unsupported: Vec<String>,
+}
+
+// checks temperatures, wind speeds, and precipitation chances in the summary against the source input
+pub fn verify(summary: &str, source: &str) -> Verification {
+    let facts = quantities(source);
+
+    let mut unsupported = Vec::new();
+
+    for claim in quantities(summary) {
+        for value in [claim.low, claim.high] {
+            if !facts.iter().any(|fact| supports(fact, claim.kind, value)) {
+                unsupported.push(format!("{:?} {}", claim.kind, value).to_lowercase());
+            }
+        }
+    }
+
+    unsupported.dedup();
+
+    Verification {
+        verified: unsupported.is_empty(),
+        unsupported,
+    }
+}
+
+fn supports(fact: &Quantity, kind: Kind, value: f64) -> bool {
+    if fact.kind != kind {
+        return false;
+    }
+
+    let tolerance = match kind {
+        Kind::Temperature => TEMPERATURE_TOLERANCE,
+        Kind::Wind => WIND_TOLERANCE,
+        Kind::Precipitation => PRECIPITATION_TOLERANCE,
+    };
+
+    value >= fact.low - tolerance && value <= fact.high + tolerance
+}
+
+// finds numbers followed by a unit, "5 to 10 mph" and "5-10 mph" become a single range
+fn quantities(text: &str) -> Vec<Quantity> {
+    let text = text.to_lowercase();
+    let numbers = numbers(&text);
+
+    let mut quantities = Vec::new();
+    let mut index = 0;
+
+    while index < numbers.len() {
+        let (_, end, value) = numbers[index];
+
+        if let Some((kind, converted)) = unit(&text[end..], value) {
+            quantities.push(Quantity {
+                kind,
+                low: converted,
+                high: converted,
+            });
+            index += 1;
+            continue;
+        }
+
+        if let Some(&(next_start, next_end, next_value)) = numbers.get(index + 1) {
+            let between = text[end..next_start].trim();
+
+            if between == "to" || between == "-" || between == "and" {
+                if let Some((kind, high)) = unit(&text[next_end..], next_value) {
+                    let low = unit(&text[next_end..], value).map_or(value, |(_, low)| low);
+
+                    quantities.push(Quantity {
+                        kind,
+                        low: low.min(high),
+                        high: low.max(high),
+                    });
+                    index += 2;
+                    continue;
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    quantities
+}
+
+// start, end, and value of each number not attached to a word, like the 5 in I-5
+fn numbers(text: &str) -> Vec<(usize, usize, f64)> {
+    let bytes = text.as_bytes();
+    let mut numbers = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if !bytes[index].is_ascii_digit() {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+
+        while index < bytes.len() && (bytes[index].is_ascii_digit() || bytes[index] == b'.') {
+            index += 1;
+        }
+
+        let attached = start > 0 && bytes[start - 1].is_ascii_alphabetic();
+
+        let digits = text[start..index].trim_end_matches('.');
+
+        if let (false, Ok(value)) = (attached, digits.parse::<f64>()) {
+            numbers.push((start, start + digits.len(), value));
+        }
+    }
+
+    numbers
+}
+
+// the unit directly after a number, with the value converted to fahrenheit or mph
+fn unit(rest: &str, value: f64) -> Option<(Kind, f64)> {
+    let rest = rest.trim_start();
+    let word_end = |unit: &str| {
+        rest.starts_with(unit)
+            && !rest[unit.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic())
+    };
+
+    if rest.starts_with('%') || word_end("percent") {
+        return Some((Kind::Precipitation, value));
+    }
+
+    if word_end("mph") || word_end("miles per hour") {
+        return Some((Kind::Wind, value));
+    }
+
+    if word_end("km/h") || word_end("kph") || word_end("kilometers per hour") {
+        return Some((Kind::Wind, value / 1.609));
+    }
+
+    if word_end("°c") || word_end("c") || word_end("degrees c") || word_end("degrees celsius") {
+        return Some((Kind::Temperature, value * 9.0 / 5.0 + 32.0));
+    }
+
+    if word_end("°f") || word_end("°") || word_end("f") || word_end("degrees") {
+        return Some((Kind::Temperature, value));
+    }
+
+    None
+}